@@ -0,0 +1,93 @@
+//! Benchmarks for the combat and movement core, sized like a busy late wave.
+//! Run with `cargo bench --features bevy-demo`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+// Only some of each module is benchmarked
+#[allow(unused)]
+#[path = "../src/config.rs"]
+mod config;
+#[allow(unused)]
+#[path = "../src/combat.rs"]
+mod combat;
+
+use combat::{compute_damage, nearest_in_range};
+use config::{schedule_wave, AttackType, DefenseType, UnitSpawn, UnitType, Wave};
+
+const TOWERS: usize = 50;
+const ENEMIES: usize = 300;
+
+/// Spread `count` points over a 30x20 tile map, deterministically
+fn positions(count: usize, seed: usize) -> Vec<(f32, f32)> {
+    (0..count)
+        .map(|i| {
+            let n = i * 7919 + seed * 104729;
+            ((n % 960) as f32, (n / 960 % 640) as f32)
+        })
+        .collect()
+}
+
+fn unit(id: &str) -> UnitType {
+    UnitType {
+        id: id.to_string(),
+        name: id.to_string(),
+        base_health: 100.0,
+        base_speed: 60.0,
+        damage_to_base: 1,
+        gold_reward: 5,
+        ..Default::default()
+    }
+}
+
+fn bench_compute_damage(c: &mut Criterion) {
+    let defenses: Vec<DefenseType> = (0..ENEMIES).map(|i| DefenseType::ALL[i % 3]).collect();
+    c.bench_function("compute_damage 300 enemies x 3 attacks", |b| {
+        b.iter(|| {
+            let mut total = 0.0;
+            for attack in AttackType::ALL {
+                for defense in &defenses {
+                    total += compute_damage(black_box(25.0), attack, *defense, Some(AttackType::Divine));
+                }
+            }
+            total
+        })
+    });
+}
+
+fn bench_targeting(c: &mut Criterion) {
+    let towers = positions(TOWERS, 1);
+    let enemies = positions(ENEMIES, 2);
+    c.bench_function("nearest_in_range 50 towers x 300 enemies", |b| {
+        b.iter(|| {
+            towers
+                .iter()
+                .filter_map(|tower| {
+                    nearest_in_range(*tower, black_box(256.0), enemies.iter().copied().enumerate())
+                })
+                .count()
+        })
+    });
+}
+
+fn bench_schedule_wave(c: &mut Criterion) {
+    let units: Vec<UnitType> = (0..10).map(|i| unit(&format!("unit_{i}"))).collect();
+    let wave = Wave {
+        wave_number: 30,
+        spawn_interval: 0.5,
+        spawns: (0..10)
+            .map(|i| UnitSpawn {
+                unit_id: format!("unit_{i}"),
+                count: (ENEMIES / 10) as i32,
+                health_multiplier: 1.0 + i as f32 * 0.1,
+                ..Default::default()
+            })
+            .collect(),
+        ..Default::default()
+    };
+    c.bench_function("schedule_wave 300 enemies", |b| {
+        b.iter(|| schedule_wave(black_box(&wave), &units))
+    });
+}
+
+criterion_group!(benches, bench_compute_damage, bench_targeting, bench_schedule_wave);
+criterion_main!(benches);
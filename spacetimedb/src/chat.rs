@@ -0,0 +1,75 @@
+//! Chat rules shared by the reducers and views. Clients sort by the same key
+//! so every view of the chat agrees on the order.
+use spacetimedb::Identity;
+
+use crate::Message;
+
+/// Whispers are only visible to their sender and recipient
+pub fn is_visible_to(message: &Message, viewer: Identity) -> bool {
+    match message.to {
+        None => true,
+        Some(recipient) => recipient == viewer || message.sender == viewer,
+    }
+}
+
+/// Sort messages oldest first by `sent`, the time the server received them.
+/// The sort is stable, so messages with the same timestamp keep their
+/// insertion order.
+pub fn sort_chronologically(messages: &mut [Message]) {
+    messages.sort_by_key(|message| message.sent);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spacetimedb::Timestamp;
+
+    fn identity(id: u8) -> Identity {
+        Identity::from_byte_array([id; 32])
+    }
+
+    fn message(sender: u8, sent: i64, text: &str) -> Message {
+        Message {
+            sender: identity(sender),
+            sent: Timestamp::from_micros_since_unix_epoch(sent),
+            text: text.to_string(),
+            to: None,
+        }
+    }
+
+    fn texts(messages: &[Message]) -> Vec<&str> {
+        messages.iter().map(|message| message.text.as_str()).collect()
+    }
+
+    #[test]
+    fn messages_from_different_senders_sort_chronologically() {
+        // Grouped by sender, as iterating the table might return them
+        let mut messages = vec![
+            message(1, 300, "third"),
+            message(1, 100, "first"),
+            message(2, 400, "fourth"),
+            message(2, 200, "second"),
+        ];
+        sort_chronologically(&mut messages);
+        assert_eq!(texts(&messages), ["first", "second", "third", "fourth"]);
+    }
+
+    #[test]
+    fn messages_with_the_same_timestamp_keep_their_order() {
+        let mut messages = vec![message(2, 100, "a"), message(1, 100, "b")];
+        sort_chronologically(&mut messages);
+        assert_eq!(texts(&messages), ["a", "b"]);
+    }
+
+    #[test]
+    fn whispers_are_only_visible_to_sender_and_recipient() {
+        let whisper = Message {
+            to: Some(identity(2)),
+            ..message(1, 100, "psst")
+        };
+        assert!(is_visible_to(&whisper, identity(1)));
+        assert!(is_visible_to(&whisper, identity(2)));
+        assert!(!is_visible_to(&whisper, identity(3)));
+        assert!(is_visible_to(&message(1, 100, "hi all"), identity(3)));
+    }
+}
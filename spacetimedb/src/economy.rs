@@ -0,0 +1,347 @@
+//! Gold rules shared by the reducers. Costs and rewards come from the same
+//! config files the client loads, compiled into the module so clients can't
+//! change them.
+use serde::Deserialize;
+use spacetimedb::{TimeDuration, Timestamp};
+
+use crate::GoldClaims;
+
+/// Gold and lives when `starting_resources.toml` is broken (match the
+/// client's `StartingResources` default)
+const DEFAULT_STARTING_GOLD: i32 = 100;
+const DEFAULT_STARTING_LIVES: i32 = 20;
+/// Bounds on the starting resources (match the client's `MAX_STARTING_*`)
+const MAX_STARTING_GOLD: i32 = 100_000;
+const MAX_STARTING_LIVES: i32 = 1_000;
+
+/// Largest gold income a single `claim_gold` call may report
+const MAX_SINGLE_INCOME: i32 = 100;
+/// Window over which a player's `claim_gold` income is capped
+const INCOME_WINDOW_MICROS: i64 = 10_000_000;
+/// Most gold a player may claim per window: a bounty wave's worth of kills,
+/// but not an endless stream of claims
+const MAX_INCOME_PER_WINDOW: i32 = 5_000;
+
+/// Most kills one `record_stats` call may report; clients report about once
+/// a second
+const MAX_REPORTED_KILLS: u32 = 200;
+/// Most wood or meat one `record_stats` call may report
+const MAX_REPORTED_RESOURCES: u32 = 50;
+
+/// Reward multiplier for enemies of a bounty wave (matches the client's
+/// `BOUNTY_GOLD_MULTIPLIER`)
+const BOUNTY_GOLD_MULTIPLIER: i32 = 5;
+
+const TOWERS_TOML: &str = include_str!("../../towers.toml");
+const UNITS_TOML: &str = include_str!("../../units.toml");
+const STARTING_RESOURCES_TOML: &str = include_str!("../../starting_resources.toml");
+
+#[derive(Deserialize)]
+struct TowersFile {
+    move_cost: i32,
+    wall_cost: i32,
+    towers: Vec<TowerEntry>,
+}
+
+#[derive(Deserialize)]
+struct TowerEntry {
+    id: String,
+    cost: i32,
+}
+
+#[derive(Deserialize)]
+struct UnitsFile {
+    units: Vec<UnitEntry>,
+}
+
+#[derive(Deserialize)]
+struct UnitEntry {
+    gold_reward: i32,
+    damage_to_base: i32,
+    #[serde(default)]
+    gold_steal: i32,
+}
+
+#[derive(Deserialize)]
+struct StartingResourcesFile {
+    standard: StartingEntry,
+}
+
+/// Wood and meat only live on the clients, so the server reads just these
+#[derive(Deserialize)]
+struct StartingEntry {
+    gold: i32,
+    lives: i32,
+}
+
+/// Starting gold and lives of the standard mode, the one online games use.
+/// Out-of-bounds values fall back to the defaults rather than starting a
+/// game without lives.
+fn starting_resources() -> (i32, i32) {
+    toml::from_str::<StartingResourcesFile>(STARTING_RESOURCES_TOML)
+        .ok()
+        .map(|file| file.standard)
+        .filter(|start| {
+            (0..=MAX_STARTING_GOLD).contains(&start.gold) && (1..=MAX_STARTING_LIVES).contains(&start.lives)
+        })
+        .map_or((DEFAULT_STARTING_GOLD, DEFAULT_STARTING_LIVES), |start| (start.gold, start.lives))
+}
+
+/// Gold every player starts a game with
+pub fn starting_gold() -> i32 {
+    starting_resources().0
+}
+
+/// Lives the players share at the start of a game
+pub fn starting_lives() -> i32 {
+    starting_resources().1
+}
+
+/// Cost of a tower type from `towers.toml`
+pub fn tower_cost(tower_type_id: &str) -> Option<i32> {
+    let file: TowersFile = toml::from_str(TOWERS_TOML).ok()?;
+    file.towers.into_iter().find(|t| t.id == tower_type_id).map(|t| t.cost)
+}
+
+/// Cost of moving a tower from `towers.toml`
+pub fn move_tower_cost() -> Option<i32> {
+    toml::from_str::<TowersFile>(TOWERS_TOML).ok().map(|file| file.move_cost)
+}
+
+/// Cost of walling off a road tile from `towers.toml`
+pub fn wall_cost() -> Option<i32> {
+    toml::from_str::<TowersFile>(TOWERS_TOML).ok().map(|file| file.wall_cost)
+}
+
+/// Largest kill reward of any unit in `units.toml`, as paid in a bounty wave
+fn max_kill_reward() -> i32 {
+    toml::from_str::<UnitsFile>(UNITS_TOML)
+        .map(|file| file.units.iter().map(|u| u.gold_reward).max().unwrap_or(0))
+        .unwrap_or(0)
+        * BOUNTY_GOLD_MULTIPLIER
+}
+
+/// Gold left after paying `cost` from the server-side balance `gold`
+pub fn charge(gold: i32, cost: i32) -> Result<i32, String> {
+    if cost < 0 {
+        return Err("Invalid cost".to_string());
+    }
+    if gold < cost {
+        return Err(format!("Not enough gold: have {}, need {}", gold, cost));
+    }
+    Ok(gold - cost)
+}
+
+/// Check one income report from a client (a kill reward or gathered gold).
+/// Enemies still run on the clients, so the server can only bound the amount.
+pub fn validate_income(amount: i32) -> Result<i32, String> {
+    let max = max_kill_reward().max(MAX_SINGLE_INCOME);
+    if amount <= 0 || amount > max {
+        return Err(format!("Invalid gold income {}", amount));
+    }
+    Ok(amount)
+}
+
+/// The caller's claims after taking `amount` more at `now`. The first claim
+/// after a window ran out opens the next one.
+pub fn budget_income(claims: GoldClaims, amount: i32, now: Timestamp) -> Result<GoldClaims, String> {
+    let window_over = now >= claims.window_started + TimeDuration::from_micros(INCOME_WINDOW_MICROS);
+    let (window_started, claimed) = if window_over {
+        (now, 0)
+    } else {
+        (claims.window_started, claims.claimed)
+    };
+    if claimed + amount > MAX_INCOME_PER_WINDOW {
+        return Err(format!("Too much gold claimed: {} already this window", claimed));
+    }
+    Ok(GoldClaims {
+        window_started,
+        claimed: claimed + amount,
+        ..claims
+    })
+}
+
+/// Check one leak report from a client against the hardest-hitting unit in
+/// `units.toml`
+pub fn validate_leak_damage(damage: i32) -> Result<i32, String> {
+    let max = toml::from_str::<UnitsFile>(UNITS_TOML)
+        .map(|file| file.units.iter().map(|u| u.damage_to_base).max().unwrap_or(0))
+        .unwrap_or(0);
+    if damage <= 0 || damage > max {
+        return Err(format!("Invalid leak damage {}", damage));
+    }
+    Ok(damage)
+}
+
+/// Shared lives left after the enemy `spawn` (wave number, spawn index)
+/// reached the castle, or None if one of the `reported` leaks already was
+/// that enemy: every client simulates it and sends its own report.
+pub fn apply_leak(
+    lives: i32,
+    damage: i32,
+    spawn: (i32, i32),
+    mut reported: impl Iterator<Item = (i32, i32)>,
+) -> Result<Option<i32>, String> {
+    let damage = validate_leak_damage(damage)?;
+    if reported.any(|leak| leak == spawn) {
+        return Ok(None);
+    }
+    Ok(Some((lives - damage).max(0)))
+}
+
+/// Gold left after a thief took up to `amount` from `gold`. The amount is
+/// checked against the greediest thief in `units.toml`.
+pub fn steal(gold: i32, amount: i32) -> Result<i32, String> {
+    let max = toml::from_str::<UnitsFile>(UNITS_TOML)
+        .map(|file| file.units.iter().map(|u| u.gold_steal).max().unwrap_or(0))
+        .unwrap_or(0);
+    if amount <= 0 || amount > max {
+        return Err(format!("Invalid theft {}", amount));
+    }
+    Ok(gold - amount.min(gold.max(0)))
+}
+
+/// Check one stats report from a client. Like income, the server can only
+/// bound what the clients count.
+pub fn validate_stats(kills: u32, wood: u32, meat: u32) -> Result<(), String> {
+    if kills > MAX_REPORTED_KILLS {
+        return Err(format!("Invalid kill count {}", kills));
+    }
+    if wood > MAX_REPORTED_RESOURCES || meat > MAX_REPORTED_RESOURCES {
+        return Err(format!("Invalid resource counts {} wood, {} meat", wood, meat));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spacetimedb::Identity;
+
+    #[test]
+    fn tower_costs_come_from_the_config() {
+        assert_eq!(tower_cost("archer"), Some(50));
+        assert_eq!(tower_cost("holy"), Some(300));
+        assert_eq!(tower_cost("no_such_tower"), None);
+        assert_eq!(move_tower_cost(), Some(10));
+        assert_eq!(wall_cost(), Some(15));
+    }
+
+    #[test]
+    fn starting_resources_come_from_the_config() {
+        assert_eq!(starting_gold(), 100);
+        assert_eq!(starting_lives(), 20);
+    }
+
+    #[test]
+    fn charge_deducts_the_cost() {
+        assert_eq!(charge(100, 50), Ok(50));
+        assert_eq!(charge(50, 50), Ok(0));
+    }
+
+    #[test]
+    fn insufficient_server_gold_is_rejected() {
+        // The client may believe it has 500 gold; only the server balance counts
+        let server_gold = 40;
+        let cost = tower_cost("archer").unwrap();
+        assert!(charge(server_gold, cost).is_err());
+    }
+
+    #[test]
+    fn negative_costs_are_rejected() {
+        assert!(charge(100, -10).is_err());
+    }
+
+    #[test]
+    fn income_is_bounded() {
+        assert_eq!(validate_income(10), Ok(10));
+        assert!(validate_income(0).is_err());
+        assert!(validate_income(-5).is_err());
+        assert!(validate_income(1_000_000).is_err());
+    }
+
+    #[test]
+    fn bounty_rewards_are_accepted() {
+        // The monk (25) and the ogre (50) from `units.toml`, paid 5x in a bounty wave
+        assert_eq!(validate_income(125), Ok(125));
+        assert_eq!(validate_income(250), Ok(250));
+        assert!(validate_income(251).is_err());
+    }
+
+    fn at(secs: i64) -> Timestamp {
+        Timestamp::from_micros_since_unix_epoch(secs * 1_000_000)
+    }
+
+    fn claims(window_started: Timestamp, claimed: i32) -> GoldClaims {
+        GoldClaims {
+            identity: Identity::from_byte_array([1; 32]),
+            window_started,
+            claimed,
+        }
+    }
+
+    #[test]
+    fn claims_add_up_within_a_window() {
+        let after = budget_income(claims(at(100), 200), 50, at(105)).unwrap();
+        assert_eq!(after.window_started, at(100));
+        assert_eq!(after.claimed, 250);
+    }
+
+    #[test]
+    fn claims_past_the_budget_are_rejected() {
+        assert!(budget_income(claims(at(100), MAX_INCOME_PER_WINDOW - 10), 10, at(105)).is_ok());
+        assert!(budget_income(claims(at(100), MAX_INCOME_PER_WINDOW - 10), 11, at(105)).is_err());
+    }
+
+    #[test]
+    fn a_new_window_resets_the_budget() {
+        let after = budget_income(claims(at(100), MAX_INCOME_PER_WINDOW), 50, at(110)).unwrap();
+        assert_eq!(after.window_started, at(110));
+        assert_eq!(after.claimed, 50);
+    }
+
+    #[test]
+    fn stats_reports_are_bounded() {
+        assert_eq!(validate_stats(3, 1, 0), Ok(()));
+        assert!(validate_stats(10_000, 0, 0).is_err());
+        assert!(validate_stats(0, 0, 10_000).is_err());
+    }
+
+    #[test]
+    fn each_leak_is_taken_once() {
+        let mut reported = Vec::new();
+        assert_eq!(apply_leak(20, 3, (2, 5), reported.iter().copied()), Ok(Some(17)));
+        reported.push((2, 5));
+        // The same enemy reported by a second player's client
+        assert_eq!(apply_leak(17, 3, (2, 5), reported.iter().copied()), Ok(None));
+        assert_eq!(apply_leak(17, 3, (2, 6), reported.iter().copied()), Ok(Some(14)));
+    }
+
+    #[test]
+    fn leaks_never_take_lives_below_zero() {
+        assert_eq!(apply_leak(2, 5, (1, 0), std::iter::empty()), Ok(Some(0)));
+        assert!(apply_leak(20, 100, (1, 0), std::iter::empty()).is_err());
+    }
+
+    #[test]
+    fn thieves_take_at_most_the_balance() {
+        // The thief in `units.toml` steals 15
+        assert_eq!(steal(100, 15), Ok(85));
+        assert_eq!(steal(10, 15), Ok(0));
+        assert_eq!(steal(0, 15), Ok(0));
+    }
+
+    #[test]
+    fn thefts_are_bounded_by_the_units() {
+        assert!(steal(100, 0).is_err());
+        assert!(steal(100, 16).is_err());
+    }
+
+    #[test]
+    fn leak_damage_is_bounded_by_the_units() {
+        assert_eq!(validate_leak_damage(1), Ok(1));
+        assert_eq!(validate_leak_damage(3), Ok(3));
+        assert!(validate_leak_damage(0).is_err());
+        assert!(validate_leak_damage(100).is_err());
+    }
+}
@@ -1,5 +1,11 @@
+mod chat;
+mod economy;
+mod map;
+mod players;
+mod towers;
+
 use log::info;
-use spacetimedb::{Identity, JwtClaims, ReducerContext, SpacetimeType, Table, Timestamp, ViewContext};
+use spacetimedb::{Identity, JwtClaims, ReducerContext, ScheduleAt, SpacetimeType, Table, TimeDuration, Timestamp, ViewContext};
 use serde::{Deserialize, Serialize};
 
 #[spacetimedb::table(name = user, public)]
@@ -9,6 +15,11 @@ pub struct User {
     name: Option<String>,
     color: Color,
     online: bool,
+    /// Playing rather than sitting in the menus; only these players vote on
+    /// waves and keep a game going
+    in_game: bool,
+    /// Set while the player composes a chat message; stale once passed
+    typing_until: Option<Timestamp>,
 }
 
 #[derive(SpacetimeType, Debug, Clone)]
@@ -18,11 +29,306 @@ pub enum Color {
     Purple,
     Black
 }
-#[spacetimedb::table(name = message, public)]
+/// Private: clients read messages through the `visible_message` view,
+/// which hides other players' whispers
+#[spacetimedb::table(name = message)]
 pub struct Message {
     sender: Identity,
+    /// When the server received the message. Sort by this, not table order
     sent: Timestamp,
     text: String,
+    /// Recipient of a whisper; None for messages to everyone
+    to: Option<Identity>,
+}
+
+/// A player's vote to start the next wave now
+#[spacetimedb::table(name = wave_vote, public)]
+pub struct WaveVote {
+    #[primary_key]
+    voter: Identity,
+    cast: Timestamp,
+}
+
+/// Single row, bumped every time a wave start vote passes.
+/// Clients start the wave when they see it change. Created with count 0 by
+/// `start_game`, so a passing vote is always an update: the insert clients
+/// get when subscribing must not start a wave.
+#[spacetimedb::table(name = wave_start, public)]
+pub struct WaveStart {
+    #[primary_key]
+    id: u32,
+    count: u32,
+    started: Timestamp,
+}
+
+const WAVE_START_ID: u32 = 0;
+
+fn ensure_wave_start(ctx: &ReducerContext) {
+    if ctx.db.wave_start().id().find(WAVE_START_ID).is_none() {
+        ctx.db.wave_start().insert(WaveStart {
+            id: WAVE_START_ID,
+            count: 0,
+            started: ctx.timestamp,
+        });
+    }
+}
+
+/// Single row holding when the day/night cycle started. Clients derive the
+/// time of day from it, so everyone shares the cycle without the server ticking.
+#[spacetimedb::table(name = game_clock, public)]
+pub struct GameClock {
+    #[primary_key]
+    id: u32,
+    day_started: Timestamp,
+}
+
+const GAME_CLOCK_ID: u32 = 0;
+
+fn ensure_game_clock(ctx: &ReducerContext) {
+    if ctx.db.game_clock().id().find(GAME_CLOCK_ID).is_none() {
+        ctx.db.game_clock().insert(GameClock {
+            id: GAME_CLOCK_ID,
+            day_started: ctx.timestamp,
+        });
+    }
+}
+
+#[derive(SpacetimeType, Debug, Clone, Copy, PartialEq)]
+pub enum WeatherKind {
+    Clear,
+    Rain,
+    Fog,
+}
+
+/// Single row with the weather every player sees
+#[spacetimedb::table(name = weather, public)]
+pub struct Weather {
+    #[primary_key]
+    id: u32,
+    kind: WeatherKind,
+    since: Timestamp,
+}
+
+/// Next run of `change_weather`, which schedules the one after from `tick_config`
+#[spacetimedb::table(name = weather_schedule, scheduled(change_weather))]
+pub struct WeatherSchedule {
+    #[primary_key]
+    #[auto_inc]
+    scheduled_id: u64,
+    scheduled_at: ScheduleAt,
+}
+
+const WEATHER_ID: u32 = 0;
+const WEATHER_CHANGE_MICROS: i64 = 90_000_000;
+
+/// Intervals of the server's scheduled reducers. Operators can tune them with
+/// `spacetime sql`; each reducer reads its interval when it reschedules itself.
+#[spacetimedb::table(name = tick_config, public)]
+pub struct TickConfig {
+    #[primary_key]
+    id: u32,
+    weather_change_micros: i64,
+}
+
+const TICK_CONFIG_ID: u32 = 0;
+/// Intervals below this are treated as a typo and raised to it
+const MIN_TICK_MICROS: i64 = 1_000_000;
+
+fn ensure_tick_config(ctx: &ReducerContext) {
+    if ctx.db.tick_config().id().find(TICK_CONFIG_ID).is_none() {
+        ctx.db.tick_config().insert(TickConfig {
+            id: TICK_CONFIG_ID,
+            weather_change_micros: WEATHER_CHANGE_MICROS,
+        });
+    }
+}
+
+/// A configured interval, raised to `MIN_TICK_MICROS` if set too low
+fn tick_interval(name: &str, micros: i64) -> TimeDuration {
+    if micros < MIN_TICK_MICROS {
+        log::warn!("tick_config.{} = {} is below the minimum, using {}", name, micros, MIN_TICK_MICROS);
+        return TimeDuration::from_micros(MIN_TICK_MICROS);
+    }
+    TimeDuration::from_micros(micros)
+}
+
+fn schedule_weather_change(ctx: &ReducerContext) {
+    let micros = ctx
+        .db
+        .tick_config()
+        .id()
+        .find(TICK_CONFIG_ID)
+        .map_or(WEATHER_CHANGE_MICROS, |config| config.weather_change_micros);
+    let interval = tick_interval("weather_change_micros", micros);
+    ctx.db.weather_schedule().insert(WeatherSchedule {
+        scheduled_id: 0,
+        scheduled_at: ScheduleAt::Time(ctx.timestamp + interval),
+    });
+}
+
+fn ensure_weather(ctx: &ReducerContext) {
+    if ctx.db.weather().id().find(WEATHER_ID).is_none() {
+        ctx.db.weather().insert(Weather {
+            id: WEATHER_ID,
+            kind: WeatherKind::Clear,
+            since: ctx.timestamp,
+        });
+    }
+    if ctx.db.weather_schedule().count() == 0 {
+        schedule_weather_change(ctx);
+    }
+}
+
+/// Roll the next weather: mostly clear, sometimes rain, rarely fog
+#[spacetimedb::reducer]
+pub fn change_weather(ctx: &ReducerContext, schedule: WeatherSchedule) -> Result<(), String> {
+    if ctx.sender != ctx.identity() {
+        return Err("Only the server changes the weather".to_string());
+    }
+    // One-shot rows are removed after running; older databases still have a
+    // repeating row, which is replaced here so the configured interval applies
+    ctx.db.weather_schedule().scheduled_id().delete(schedule.scheduled_id);
+    schedule_weather_change(ctx);
+
+    let kind = match ctx.random::<u32>() % 100 {
+        0..60 => WeatherKind::Clear,
+        60..85 => WeatherKind::Rain,
+        _ => WeatherKind::Fog,
+    };
+    let Some(weather) = ctx.db.weather().id().find(WEATHER_ID) else {
+        return Err("Weather row missing".to_string());
+    };
+    if weather.kind != kind {
+        log::info!("Weather changes to {:?}", kind);
+        ctx.db.weather().id().update(Weather {
+            kind,
+            since: ctx.timestamp,
+            ..weather
+        });
+    }
+    Ok(())
+}
+
+/// How gold is kept in a game, picked by whoever starts a fresh one
+#[derive(SpacetimeType, Debug, Clone, Copy, PartialEq)]
+pub enum EconomyMode {
+    /// Every player earns into and spends from their own `player_state`
+    Split,
+    /// Every player earns into and spends from `game_status.shared_gold`
+    Shared,
+}
+
+/// Lives every player shares. Enemies still run on the clients, which
+/// report the ones reaching the castle with `report_leak`; once lives run out
+/// the game is over for everyone.
+#[spacetimedb::table(name = game_status, public)]
+pub struct GameStatus {
+    #[primary_key]
+    id: u32,
+    lives: i32,
+    max_lives: i32,
+    game_over: bool,
+    economy_mode: EconomyMode,
+    /// The gold pool of a shared economy; unused in a split one
+    shared_gold: i32,
+    /// Player whose reducer last changed `shared_gold`, so clients can tell
+    /// their own changes from their teammates'
+    gold_changed_by: Option<Identity>,
+}
+
+const GAME_STATUS_ID: u32 = 0;
+
+fn fresh_game_status(economy_mode: EconomyMode) -> GameStatus {
+    let lives = economy::starting_lives();
+    GameStatus {
+        id: GAME_STATUS_ID,
+        lives,
+        max_lives: lives,
+        game_over: false,
+        economy_mode,
+        shared_gold: economy::starting_gold(),
+        gold_changed_by: None,
+    }
+}
+
+/// Server-side gold balance. Clients show their own prediction and
+/// reconcile with this row.
+#[spacetimedb::table(name = player_state, public)]
+pub struct PlayerState {
+    #[primary_key]
+    identity: Identity,
+    gold: i32,
+}
+
+/// What each player did this game, for the end-game breakdown. Towers and
+/// gold are counted by the reducers that pay for and credit them; kills and
+/// gathered resources are reported by the client with `record_stats`.
+#[spacetimedb::table(name = player_stats, public)]
+pub struct PlayerStats {
+    #[primary_key]
+    identity: Identity,
+    towers_built: u32,
+    kills: u32,
+    gold_earned: u32,
+    wood_gathered: u32,
+    meat_gathered: u32,
+}
+
+/// Road tiles walled off by players. Every client routes its enemies around
+/// all of them, so everyone's enemies take the same way.
+#[spacetimedb::table(name = wall, public)]
+pub struct Wall {
+    #[primary_key]
+    #[auto_inc]
+    id: u64,
+    owner: Identity,
+    tile_x: i32,
+    tile_y: i32,
+}
+
+/// Enemies whose leak was already taken from the shared lives this game.
+/// Every client reports the same leak, so only the first report counts.
+#[spacetimedb::table(name = leak_report)]
+pub struct LeakReport {
+    #[primary_key]
+    #[auto_inc]
+    id: u64,
+    wave: i32,
+    spawn_index: i32,
+}
+
+/// Thieves that already took gold from the shared pool this game. Every
+/// client reports the same theft, so only the first report counts.
+#[spacetimedb::table(name = theft_report)]
+pub struct TheftReport {
+    #[primary_key]
+    #[auto_inc]
+    id: u64,
+    wave: i32,
+    spawn_index: i32,
+}
+
+/// Gold a player claimed with `claim_gold` in the current window. Kills
+/// still happen on the clients, so the server caps how fast they pay out.
+#[spacetimedb::table(name = gold_claims)]
+pub struct GoldClaims {
+    #[primary_key]
+    identity: Identity,
+    window_started: Timestamp,
+    claimed: i32,
+}
+
+#[spacetimedb::table(name = tower, public)]
+pub struct Tower {
+    #[primary_key]
+    #[auto_inc]
+    id: u64,
+    owner: Identity,
+    tower_type_id: String,
+    x: f32,
+    y: f32,
+    /// When it was placed, for the undo grace period
+    placed_at: Timestamp,
 }
 
 #[spacetimedb::view(name = my_user, public)]
@@ -30,17 +336,19 @@ fn my_user(ctx: &ViewContext) -> Option<User> {
     ctx.db.user().identity().find(ctx.sender)
 }
 
-fn validate_name(name: String) -> Result<String, String> {
-    if name.is_empty() {
-        Err("Names must not be empty".to_string())
-    } else {
-        Ok(name)
-    }
+/// Public messages plus the whispers the caller sent or received
+#[spacetimedb::view(name = visible_message, public)]
+fn visible_message(ctx: &ViewContext) -> Vec<Message> {
+    ctx.db
+        .message()
+        .iter()
+        .filter(|message| chat::is_visible_to(message, ctx.sender))
+        .collect()
 }
 
 #[spacetimedb::reducer]
 pub fn set_name(ctx: &ReducerContext, name: String) -> Result<(), String> {
-    let name = validate_name(name)?;
+    let name = players::validate_name(name)?;
     if let Some(user) = ctx.db.user().identity().find(ctx.sender) {
         log::info!("User {} sets name to {name}", ctx.sender);
         ctx.db.user().identity().update(User {
@@ -68,6 +376,14 @@ pub fn set_color(ctx: &ReducerContext, color: Color) -> Result<(), String> {
     }
 }
 
+/// How long a `set_typing(true)` call shows the player as typing. Clients
+/// refresh it while typing, so a crashed client's indicator still expires.
+const TYPING_TIMEOUT_MICROS: i64 = 5_000_000;
+
+/// Messages kept in the `message` table; older ones are pruned so late
+/// joiners don't have to download the whole history
+const MAX_MESSAGES: usize = 200;
+
 fn validate_message(text: String) -> Result<String, String> {
     if text.is_empty() {
         Err("Messages must not be empty".to_string())
@@ -84,13 +400,471 @@ pub fn send_message(ctx: &ReducerContext, text: String) -> Result<(), String> {
         sender: ctx.sender,
         text,
         sent: ctx.timestamp,
+        to: None,
+    });
+    clear_typing(ctx);
+    prune_messages(ctx);
+    Ok(())
+}
+
+/// Send a message only the recipient (and the sender) can see
+#[spacetimedb::reducer]
+pub fn send_private_message(ctx: &ReducerContext, recipient: Identity, text: String) -> Result<(), String> {
+    let text = validate_message(text)?;
+    if recipient == ctx.sender {
+        return Err("Cannot whisper to yourself".to_string());
+    }
+    if ctx.db.user().identity().find(recipient).is_none() {
+        return Err("Unknown recipient".to_string());
+    }
+
+    log::info!("User {} whispers to {}", ctx.sender, recipient);
+    ctx.db.message().insert(Message {
+        sender: ctx.sender,
+        text,
+        sent: ctx.timestamp,
+        to: Some(recipient),
     });
+    clear_typing(ctx);
+    prune_messages(ctx);
     Ok(())
 }
 
+/// Show or hide the caller's typing indicator
+#[spacetimedb::reducer]
+pub fn set_typing(ctx: &ReducerContext, typing: bool) -> Result<(), String> {
+    let Some(user) = ctx.db.user().identity().find(ctx.sender) else {
+        return Err("Cannot set typing for unknown user".to_string());
+    };
+    let typing_until = typing.then(|| ctx.timestamp + TimeDuration::from_micros(TYPING_TIMEOUT_MICROS));
+    ctx.db.user().identity().update(User { typing_until, ..user });
+    Ok(())
+}
+
+fn clear_typing(ctx: &ReducerContext) {
+    if let Some(user) = ctx.db.user().identity().find(ctx.sender) {
+        if user.typing_until.is_some() {
+            ctx.db.user().identity().update(User { typing_until: None, ..user });
+        }
+    }
+}
+
+/// Delete the oldest messages beyond the most recent `MAX_MESSAGES`
+fn prune_messages(ctx: &ReducerContext) {
+    let count = ctx.db.message().count() as usize;
+    if count <= MAX_MESSAGES {
+        return;
+    }
+
+    let mut messages: Vec<Message> = ctx.db.message().iter().collect();
+    chat::sort_chronologically(&mut messages);
+    for message in messages.into_iter().take(count - MAX_MESSAGES) {
+        ctx.db.message().delete(message);
+    }
+}
+
+#[spacetimedb::reducer]
+pub fn request_start_wave(ctx: &ReducerContext) -> Result<(), String> {
+    match ctx.db.user().identity().find(ctx.sender) {
+        Some(user) if user.online && user.in_game => {}
+        _ => return Err("Only players in the game can vote to start the wave".to_string()),
+    }
+
+    if ctx.db.wave_vote().voter().find(ctx.sender).is_none() {
+        log::info!("User {} is ready for the next wave", ctx.sender);
+        ctx.db.wave_vote().insert(WaveVote {
+            voter: ctx.sender,
+            cast: ctx.timestamp,
+        });
+    }
+    try_start_wave(ctx);
+    Ok(())
+}
+
+/// Drop the caller's vote once their wave started without the vote passing
+/// (the prep timer ran out), so it doesn't carry over to the next wave
+#[spacetimedb::reducer]
+pub fn withdraw_wave_vote(ctx: &ReducerContext) {
+    ctx.db.wave_vote().voter().delete(ctx.sender);
+}
+
+/// Start the wave once every player in the game has voted, then clear the votes
+fn try_start_wave(ctx: &ReducerContext) {
+    let playing: Vec<Identity> = ctx
+        .db
+        .user()
+        .iter()
+        .filter(|u| u.online && u.in_game)
+        .map(|u| u.identity)
+        .collect();
+    let votes = playing
+        .iter()
+        .filter(|identity| ctx.db.wave_vote().voter().find(**identity).is_some())
+        .count();
+    if playing.is_empty() || votes < playing.len() {
+        return;
+    }
+
+    let voters: Vec<Identity> = ctx.db.wave_vote().iter().map(|vote| vote.voter).collect();
+    for voter in voters {
+        ctx.db.wave_vote().voter().delete(voter);
+    }
+    match ctx.db.wave_start().id().find(WAVE_START_ID) {
+        Some(start) => {
+            ctx.db.wave_start().id().update(WaveStart {
+                count: start.count + 1,
+                started: ctx.timestamp,
+                ..start
+            });
+        }
+        None => {
+            ctx.db.wave_start().insert(WaveStart {
+                id: WAVE_START_ID,
+                count: 1,
+                started: ctx.timestamp,
+            });
+        }
+    }
+    log::info!("All {} players ready, starting wave", playing.len());
+}
+
+fn player_state_or_default(ctx: &ReducerContext) -> PlayerState {
+    ctx.db.player_state().identity().find(ctx.sender).unwrap_or(PlayerState {
+        identity: ctx.sender,
+        gold: economy::starting_gold(),
+    })
+}
+
+fn save_player_state(ctx: &ReducerContext, state: PlayerState) {
+    if ctx.db.player_state().identity().find(state.identity).is_some() {
+        ctx.db.player_state().identity().update(state);
+    } else {
+        ctx.db.player_state().insert(state);
+    }
+}
+
+/// Gold the caller earns into and spends from: the shared pool in a shared
+/// economy, their own `player_state` otherwise. The pool is handed out as a
+/// `PlayerState` too, so the economy rules work the same on both.
+fn purse_or_default(ctx: &ReducerContext) -> PlayerState {
+    match ctx.db.game_status().id().find(GAME_STATUS_ID) {
+        Some(status) if status.economy_mode == EconomyMode::Shared => PlayerState {
+            identity: ctx.sender,
+            gold: status.shared_gold,
+        },
+        _ => player_state_or_default(ctx),
+    }
+}
+
+/// Write back a balance from `purse_or_default` to where it came from
+fn save_purse(ctx: &ReducerContext, purse: PlayerState) {
+    match ctx.db.game_status().id().find(GAME_STATUS_ID) {
+        Some(status) if status.economy_mode == EconomyMode::Shared => {
+            ctx.db.game_status().id().update(GameStatus {
+                shared_gold: purse.gold,
+                gold_changed_by: Some(ctx.sender),
+                ..status
+            });
+        }
+        _ => save_player_state(ctx, purse),
+    }
+}
+
+fn player_stats_or_default(ctx: &ReducerContext) -> PlayerStats {
+    ctx.db.player_stats().identity().find(ctx.sender).unwrap_or(PlayerStats {
+        identity: ctx.sender,
+        towers_built: 0,
+        kills: 0,
+        gold_earned: 0,
+        wood_gathered: 0,
+        meat_gathered: 0,
+    })
+}
+
+fn save_player_stats(ctx: &ReducerContext, stats: PlayerStats) {
+    if ctx.db.player_stats().identity().find(stats.identity).is_some() {
+        ctx.db.player_stats().identity().update(stats);
+    } else {
+        ctx.db.player_stats().insert(stats);
+    }
+}
+
+/// Place a tower on a free buildable tile, paying for it from the caller's
+/// server-side gold
+#[spacetimedb::reducer]
+pub fn place_tower(ctx: &ReducerContext, tower_type_id: String, x: f32, y: f32) -> Result<(), String> {
+    towers::check_site(x, y, ctx.db.tower().iter().map(|tower| (tower.x, tower.y)))?;
+    let (state, stats, tower) = towers::place(
+        purse_or_default(ctx),
+        player_stats_or_default(ctx),
+        tower_type_id,
+        x,
+        y,
+        ctx.timestamp,
+    )?;
+    save_purse(ctx, state);
+    save_player_stats(ctx, stats);
+    ctx.db.tower().insert(tower);
+    Ok(())
+}
+
+/// Move one of the caller's towers to a free buildable tile, paying the
+/// move fee from their server-side gold
+#[spacetimedb::reducer]
+pub fn move_tower(ctx: &ReducerContext, tower_id: u64, x: f32, y: f32) -> Result<(), String> {
+    let tower = ctx.db.tower().id().find(tower_id).ok_or("Unknown tower")?;
+    let others = ctx
+        .db
+        .tower()
+        .iter()
+        .filter(|other| other.id != tower_id)
+        .map(|other| (other.x, other.y));
+    let (state, tower) = towers::relocate(purse_or_default(ctx), tower, x, y, others)?;
+    save_purse(ctx, state);
+    ctx.db.tower().id().update(tower);
+    Ok(())
+}
+
+/// Take back the caller's most recently placed tower for a full refund,
+/// if they placed it within the undo grace period
+#[spacetimedb::reducer]
+pub fn undo_tower_placement(ctx: &ReducerContext) -> Result<(), String> {
+    let tower = ctx
+        .db
+        .tower()
+        .iter()
+        .filter(|tower| tower.owner == ctx.sender)
+        .max_by_key(|tower| tower.placed_at)
+        .ok_or("No tower to undo")?;
+    let (state, stats) = towers::undo(
+        purse_or_default(ctx),
+        player_stats_or_default(ctx),
+        &tower,
+        ctx.timestamp,
+    )?;
+    save_purse(ctx, state);
+    save_player_stats(ctx, stats);
+    ctx.db.tower().id().delete(tower.id);
+    Ok(())
+}
+
+/// Wall off a road tile, paying from the caller's server-side gold. Walls
+/// that would leave enemies no way to the castle are refused.
+#[spacetimedb::reducer]
+pub fn place_wall(ctx: &ReducerContext, tile_x: i32, tile_y: i32) -> Result<(), String> {
+    map::check_wall_site((tile_x, tile_y), ctx.db.wall().iter().map(|wall| (wall.tile_x, wall.tile_y)))?;
+    let cost = economy::wall_cost().ok_or("Walls aren't configured")?;
+    let state = purse_or_default(ctx);
+    let gold = economy::charge(state.gold, cost)?;
+    save_purse(ctx, PlayerState { gold, ..state });
+    ctx.db.wall().insert(Wall {
+        id: 0,
+        owner: ctx.sender,
+        tile_x,
+        tile_y,
+    });
+    Ok(())
+}
+
+/// Credit gold earned on the client (kill rewards, gathered gold)
+#[spacetimedb::reducer]
+pub fn claim_gold(ctx: &ReducerContext, amount: i32) -> Result<(), String> {
+    let amount = economy::validate_income(amount)?;
+    let claims = ctx.db.gold_claims().identity().find(ctx.sender).unwrap_or(GoldClaims {
+        identity: ctx.sender,
+        window_started: ctx.timestamp,
+        claimed: 0,
+    });
+    let claims = economy::budget_income(claims, amount, ctx.timestamp)?;
+    if ctx.db.gold_claims().identity().find(ctx.sender).is_some() {
+        ctx.db.gold_claims().identity().update(claims);
+    } else {
+        ctx.db.gold_claims().insert(claims);
+    }
+    let state = purse_or_default(ctx);
+    save_purse(ctx, PlayerState {
+        gold: state.gold + amount,
+        ..state
+    });
+    let stats = player_stats_or_default(ctx);
+    save_player_stats(ctx, PlayerStats {
+        gold_earned: stats.gold_earned + amount as u32,
+        ..stats
+    });
+    Ok(())
+}
+
+/// Add kills and gathered resources counted on the client since the last call
+#[spacetimedb::reducer]
+pub fn record_stats(ctx: &ReducerContext, kills: u32, wood: u32, meat: u32) -> Result<(), String> {
+    economy::validate_stats(kills, wood, meat)?;
+    let stats = player_stats_or_default(ctx);
+    save_player_stats(ctx, PlayerStats {
+        kills: stats.kills + kills,
+        wood_gathered: stats.wood_gathered + wood,
+        meat_gathered: stats.meat_gathered + meat,
+        ..stats
+    });
+    Ok(())
+}
+
+/// Pay for anything other than a tower (exploring, walls, workers...)
+#[spacetimedb::reducer]
+pub fn spend_gold(ctx: &ReducerContext, amount: i32) -> Result<(), String> {
+    let state = purse_or_default(ctx);
+    let gold = economy::charge(state.gold, amount)?;
+    save_purse(ctx, PlayerState { gold, ..state });
+    Ok(())
+}
+
+/// Take lives for an enemy that reached the castle on the caller's client,
+/// named by its wave and spawn index. Later reports of the same enemy from
+/// the other players' clients are ignored.
+#[spacetimedb::reducer]
+pub fn report_leak(ctx: &ReducerContext, wave: i32, spawn_index: i32, damage: i32) -> Result<(), String> {
+    if ctx.db.player_state().identity().find(ctx.sender).is_none() {
+        return Err("Only players in a game can report leaks".to_string());
+    }
+    let status = ctx
+        .db
+        .game_status()
+        .id()
+        .find(GAME_STATUS_ID)
+        .unwrap_or_else(|| fresh_game_status(EconomyMode::Split));
+    if status.game_over {
+        return Ok(());
+    }
+    let reported = ctx.db.leak_report().iter().map(|report| (report.wave, report.spawn_index));
+    let Some(lives) = economy::apply_leak(status.lives, damage, (wave, spawn_index), reported)? else {
+        return Ok(());
+    };
+    ctx.db.leak_report().insert(LeakReport { id: 0, wave, spawn_index });
+    if lives == 0 {
+        log::info!("Castle destroyed, game over");
+    }
+    let status = GameStatus {
+        lives,
+        game_over: lives == 0,
+        ..status
+    };
+    if ctx.db.game_status().id().find(GAME_STATUS_ID).is_some() {
+        ctx.db.game_status().id().update(status);
+    } else {
+        ctx.db.game_status().insert(status);
+    }
+    Ok(())
+}
+
+/// Take gold for a thief that reached the castle on the caller's client,
+/// never more than the balance holds. In a split economy each player loses
+/// their own gold; a shared pool is robbed once however many players report
+/// the thief.
+#[spacetimedb::reducer]
+pub fn report_theft(ctx: &ReducerContext, wave: i32, spawn_index: i32, amount: i32) -> Result<(), String> {
+    if ctx.db.player_state().identity().find(ctx.sender).is_none() {
+        return Err("Only players in a game can report thefts".to_string());
+    }
+    let shared = ctx
+        .db
+        .game_status()
+        .id()
+        .find(GAME_STATUS_ID)
+        .is_some_and(|status| status.economy_mode == EconomyMode::Shared);
+    if shared {
+        let reported = ctx
+            .db
+            .theft_report()
+            .iter()
+            .any(|report| report.wave == wave && report.spawn_index == spawn_index);
+        if reported {
+            return Ok(());
+        }
+    }
+    let state = purse_or_default(ctx);
+    let gold = economy::steal(state.gold, amount)?;
+    if shared {
+        ctx.db.theft_report().insert(TheftReport { id: 0, wave, spawn_index });
+    }
+    save_purse(ctx, PlayerState { gold, ..state });
+    Ok(())
+}
+
+/// Reset the caller's gold, towers and stats, and the shared lives if no
+/// game is running, when they start a new game. A fresh game uses the
+/// caller's `economy_mode`; joining a running one keeps its mode.
+#[spacetimedb::reducer]
+pub fn start_game(ctx: &ReducerContext, economy_mode: EconomyMode) {
+    // Databases published before the clock existed get it on the first game
+    ensure_game_clock(ctx);
+    ensure_wave_start(ctx);
+    ensure_tick_config(ctx);
+    ensure_weather(ctx);
+    save_player_state(ctx, PlayerState {
+        identity: ctx.sender,
+        gold: economy::starting_gold(),
+    });
+    ctx.db.player_stats().identity().delete(ctx.sender);
+    // Shared lives only reset once the last game ended or nobody else is
+    // still playing, so joining doesn't heal the castle
+    let others_playing = ctx
+        .db
+        .user()
+        .iter()
+        .any(|u| u.online && u.in_game && u.identity != ctx.sender);
+    let status = ctx.db.game_status().id().find(GAME_STATUS_ID);
+    if status.as_ref().is_none_or(|status| status.game_over || !others_playing) {
+        ctx.db.game_status().id().delete(GAME_STATUS_ID);
+        ctx.db.game_status().insert(fresh_game_status(economy_mode));
+        // Wave numbers start over, so last game's reports would match new enemies
+        let leaks: Vec<u64> = ctx.db.leak_report().iter().map(|report| report.id).collect();
+        for id in leaks {
+            ctx.db.leak_report().id().delete(id);
+        }
+        let thefts: Vec<u64> = ctx.db.theft_report().iter().map(|report| report.id).collect();
+        for id in thefts {
+            ctx.db.theft_report().id().delete(id);
+        }
+        // A new game starts on an open road
+        let walls: Vec<u64> = ctx.db.wall().iter().map(|wall| wall.id).collect();
+        for id in walls {
+            ctx.db.wall().id().delete(id);
+        }
+    }
+    remove_towers(ctx, ctx.sender);
+    if let Some(user) = ctx.db.user().identity().find(ctx.sender) {
+        ctx.db.user().identity().update(User { in_game: true, ..user });
+    }
+}
+
+/// Take the caller out of the game and back to the menus, staying
+/// connected. Their towers go, and the others no longer wait on their vote.
+#[spacetimedb::reducer]
+pub fn leave_game(ctx: &ReducerContext) {
+    let Some(user) = ctx.db.user().identity().find(ctx.sender) else { return };
+    log::info!("User {} left the game", ctx.sender);
+    ctx.db.user().identity().update(User { in_game: false, ..user });
+    remove_towers(ctx, ctx.sender);
+    ctx.db.wave_vote().voter().delete(ctx.sender);
+    if ctx.db.wave_vote().count() > 0 {
+        try_start_wave(ctx);
+    }
+}
+
+fn remove_towers(ctx: &ReducerContext, owner: Identity) {
+    let towers: Vec<u64> = ctx.db.tower().iter().filter(|t| t.owner == owner).map(|t| t.id).collect();
+    for id in towers {
+        ctx.db.tower().id().delete(id);
+    }
+}
+
 #[spacetimedb::reducer(init)]
 // Called when the module is initially published
-pub fn init(_ctx: &ReducerContext) {}
+pub fn init(ctx: &ReducerContext) {
+    ensure_game_clock(ctx);
+    ensure_wave_start(ctx);
+    ensure_tick_config(ctx);
+    ensure_weather(ctx);
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 struct UserProfile {
@@ -138,33 +912,25 @@ pub fn identity_connected(ctx: &ReducerContext) {
         None
     };
 
-    if let Some(user) = ctx.db.user().identity().find(ctx.sender) {
-        // Returning user - update online status
-        ctx.db.user().identity().update(User {
-            online: true,
-            // Keep existing data if already set, otherwise use JWT data
-            name: user.name.or(name),
-            color: user.color,
-            ..user
-        });
-    } else {
-        // New user
-        ctx.db.user().insert(User {
-            identity: ctx.sender,
-            name,
-            color: Color::Purple,
-            online: true,
-        });
+    match ctx.db.user().identity().find(ctx.sender) {
+        Some(user) => {
+            ctx.db.user().identity().update(players::connected(Some(user), ctx.sender, name));
+        }
+        None => {
+            ctx.db.user().insert(players::connected(None, ctx.sender, name));
+        }
     }
 }
 
 #[spacetimedb::reducer(client_disconnected)]
 pub fn identity_disconnected(ctx: &ReducerContext) {
     if let Some(user) = ctx.db.user().identity().find(ctx.sender) {
-        ctx.db.user().identity().update(User {
-            online: false,
-            ..user
-        });
+        ctx.db.user().identity().update(players::disconnected(user));
+        // Drop their vote; the remaining players may now all be ready
+        ctx.db.wave_vote().voter().delete(ctx.sender);
+        if ctx.db.wave_vote().count() > 0 {
+            try_start_wave(ctx);
+        }
     } else {
         // This branch should be unreachable,
         // as it doesn't make sense for a client to disconnect without connecting first.
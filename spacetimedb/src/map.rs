@@ -0,0 +1,161 @@
+//! The map as far as the server needs it to check where towers and walls go.
+//! The client builds the same road from `map::PATH_CORNERS`; keep the two in step.
+use std::collections::{HashSet, VecDeque};
+
+/// Size of a map tile in world units (matches the client's `SCALED_TILE_SIZE`)
+pub const TILE_SIZE: f32 = 32.0;
+/// Map size in tiles (matches the client's `MAP_WIDTH`/`MAP_HEIGHT`)
+const MAP_WIDTH: i32 = 30;
+const MAP_HEIGHT: i32 = 20;
+
+/// Corners of the road from the enemy spawn to the castle (matches the
+/// client's `PATH_CORNERS`)
+const PATH_CORNERS: [(i32, i32); 10] = [
+    (0, 10),
+    (5, 10),
+    (5, 4),
+    (11, 4),
+    (11, 16),
+    (17, 16),
+    (17, 4),
+    (23, 4),
+    (23, 11),
+    (26, 11),
+];
+
+/// Center and half-size in tiles of the castle
+const CASTLE_CENTER: (i32, i32) = (27, 10);
+const CASTLE_HALF_SIZE: i32 = 2;
+
+/// Tile under world position (x, y), like the client's `world_to_tile`
+pub fn tile_at(x: f32, y: f32) -> (i32, i32) {
+    let tile_x = ((x + 480.0) / TILE_SIZE).floor() as i32;
+    let tile_y = MAP_HEIGHT - 1 - ((y + 320.0) / TILE_SIZE).floor() as i32;
+    (tile_x, tile_y)
+}
+
+/// World position of a tile's center, like the client's `tile_to_world`
+fn tile_center(tile_x: i32, tile_y: i32) -> (f32, f32) {
+    let x = -480.0 + tile_x as f32 * TILE_SIZE + TILE_SIZE / 2.0;
+    let y = -320.0 + (MAP_HEIGHT - 1 - tile_y) as f32 * TILE_SIZE + TILE_SIZE / 2.0;
+    (x, y)
+}
+
+pub fn in_bounds(tile_x: i32, tile_y: i32) -> bool {
+    (0..MAP_WIDTH).contains(&tile_x) && (0..MAP_HEIGHT).contains(&tile_y)
+}
+
+/// Road segments between the corners as (x range, y range), one tile wide
+fn segments() -> impl Iterator<Item = ((i32, i32), (i32, i32))> {
+    PATH_CORNERS.windows(2).map(|pair| {
+        let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+        ((x1.min(x2), x1.max(x2)), (y1.min(y2), y1.max(y2)))
+    })
+}
+
+/// Whether a tile is road (three tiles wide) or castle, where nothing can be built
+pub fn is_blocked(tile_x: i32, tile_y: i32) -> bool {
+    let on_road = segments().any(|((min_x, max_x), (min_y, max_y))| {
+        let (min_x, max_x, min_y, max_y) = if min_x == max_x {
+            (min_x - 1, max_x + 1, min_y, max_y)
+        } else {
+            (min_x, max_x, min_y - 1, max_y + 1)
+        };
+        (min_x..=max_x).contains(&tile_x) && (min_y..=max_y).contains(&tile_y)
+    });
+    on_road || is_castle(tile_x, tile_y)
+}
+
+fn is_castle(tile_x: i32, tile_y: i32) -> bool {
+    (tile_x - CASTLE_CENTER.0).abs() <= CASTLE_HALF_SIZE && (tile_y - CASTLE_CENTER.1).abs() <= CASTLE_HALF_SIZE
+}
+
+/// Whether enemies can still get from the spawn to the castle along the road
+/// with `walls` built. The client's A* walks the same tiles, so whenever this
+/// holds it finds a path too.
+fn path_exists(walls: &HashSet<(i32, i32)>) -> bool {
+    let start = PATH_CORNERS[0];
+    let goal = PATH_CORNERS[PATH_CORNERS.len() - 1];
+    let walkable = |(x, y): (i32, i32)| is_blocked(x, y) && !walls.contains(&(x, y));
+
+    let mut seen = HashSet::from([start]);
+    let mut open = VecDeque::from([start]);
+    while let Some((x, y)) = open.pop_front() {
+        if (x, y) == goal {
+            return true;
+        }
+        for neighbor in [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)] {
+            if walkable(neighbor) && seen.insert(neighbor) {
+                open.push_back(neighbor);
+            }
+        }
+    }
+    false
+}
+
+/// Check a wall on `tile` next to the `walls` already built. Walls go on
+/// road tiles other than the spawn, and never cut the road off entirely.
+pub fn check_wall_site(tile: (i32, i32), walls: impl Iterator<Item = (i32, i32)>) -> Result<(), String> {
+    let (tile_x, tile_y) = tile;
+    if !in_bounds(tile_x, tile_y) || !is_blocked(tile_x, tile_y) || is_castle(tile_x, tile_y) {
+        return Err("Walls can only go on the road".to_string());
+    }
+    if tile == PATH_CORNERS[0] {
+        return Err("Can't wall off the enemy spawn".to_string());
+    }
+    let mut walls: HashSet<(i32, i32)> = walls.collect();
+    if !walls.insert(tile) {
+        return Err("There is already a wall there".to_string());
+    }
+    if !path_exists(&walls) {
+        return Err("A wall there would block the path".to_string());
+    }
+    Ok(())
+}
+
+/// Whether a tower on grid point (x, y) would stand on the road's center
+/// lane, the route enemies take unless walls divert them. Like the client's
+/// `overlaps_enemy_path`, a tower covers the four tiles around its point.
+pub fn overlaps_enemy_path(x: f32, y: f32) -> bool {
+    segments().any(|((min_x, max_x), (min_y, max_y))| {
+        (min_x..=max_x).any(|tile_x| {
+            (min_y..=max_y).any(|tile_y| {
+                let (center_x, center_y) = tile_center(tile_x, tile_y);
+                (center_x - x).abs() < TILE_SIZE && (center_y - y).abs() < TILE_SIZE
+            })
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn walls_go_on_free_road_tiles() {
+        assert_eq!(check_wall_site((2, 10), std::iter::empty()), Ok(()));
+        // The side lanes are road too
+        assert_eq!(check_wall_site((2, 9), std::iter::empty()), Ok(()));
+    }
+
+    #[test]
+    fn walls_stay_off_grass_castle_and_spawn() {
+        assert!(check_wall_site((2, 2), std::iter::empty()).is_err());
+        assert!(check_wall_site((27, 10), std::iter::empty()).is_err());
+        assert!(check_wall_site((0, 10), std::iter::empty()).is_err());
+        assert!(check_wall_site((-1, 10), std::iter::empty()).is_err());
+    }
+
+    #[test]
+    fn walls_are_not_stacked() {
+        assert!(check_wall_site((2, 10), [(2, 10)].into_iter()).is_err());
+    }
+
+    #[test]
+    fn walls_never_block_the_road_entirely() {
+        // The first segment is three tiles wide; two walls still leave a lane
+        let walls = [(2, 9), (2, 10)];
+        assert!(check_wall_site((4, 9), walls.into_iter()).is_ok());
+        assert!(check_wall_site((2, 11), walls.into_iter()).is_err());
+    }
+}
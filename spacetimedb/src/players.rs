@@ -0,0 +1,99 @@
+//! Player rows as the profile and connection reducers change them, kept
+//! apart from the reducers so the rules can be tested without a database.
+use spacetimedb::Identity;
+
+use crate::{Color, User};
+
+pub fn validate_name(name: String) -> Result<String, String> {
+    if name.is_empty() {
+        Err("Names must not be empty".to_string())
+    } else {
+        Ok(name)
+    }
+}
+
+/// Row for a player who just connected. Returning players keep the name
+/// they chose and only take `jwt_name` if they never set one; new players
+/// start purple. Either way they arrive in the menus, not in a game.
+pub fn connected(existing: Option<User>, identity: Identity, jwt_name: Option<String>) -> User {
+    match existing {
+        Some(user) => User {
+            online: true,
+            in_game: false,
+            name: user.name.or(jwt_name),
+            ..user
+        },
+        None => User {
+            identity,
+            name: jwt_name,
+            color: Color::Purple,
+            online: true,
+            in_game: false,
+            typing_until: None,
+        },
+    }
+}
+
+/// Row for a player who just disconnected; they stop showing as typing
+/// and drop out of the game
+pub fn disconnected(user: User) -> User {
+    User {
+        online: false,
+        in_game: false,
+        typing_until: None,
+        ..user
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spacetimedb::Timestamp;
+
+    fn identity(id: u8) -> Identity {
+        Identity::from_byte_array([id; 32])
+    }
+
+    #[test]
+    fn empty_names_are_rejected() {
+        assert_eq!(validate_name("Max".to_string()), Ok("Max".to_string()));
+        assert!(validate_name(String::new()).is_err());
+    }
+
+    #[test]
+    fn new_players_come_online_with_their_jwt_name() {
+        let user = connected(None, identity(1), Some("Max".to_string()));
+        assert_eq!(user.identity, identity(1));
+        assert_eq!(user.name.as_deref(), Some("Max"));
+        assert!(user.online);
+    }
+
+    #[test]
+    fn returning_players_keep_their_chosen_name() {
+        let offline = User {
+            identity: identity(1),
+            name: Some("Chosen".to_string()),
+            color: Color::Blue,
+            online: false,
+            in_game: false,
+            typing_until: None,
+        };
+        let user = connected(Some(offline), identity(1), Some("From JWT".to_string()));
+        assert_eq!(user.name.as_deref(), Some("Chosen"));
+        assert!(matches!(user.color, Color::Blue));
+        assert!(user.online);
+    }
+
+    #[test]
+    fn disconnecting_goes_offline_and_stops_typing() {
+        let online = User {
+            in_game: true,
+            typing_until: Some(Timestamp::from_micros_since_unix_epoch(1_000)),
+            ..connected(None, identity(1), None)
+        };
+        let user = disconnected(online);
+        assert!(!user.online);
+        assert!(!user.in_game);
+        assert!(user.typing_until.is_none());
+    }
+}
@@ -0,0 +1,241 @@
+//! Tower placement as `place_tower` applies it, kept apart from the reducer
+//! so it can be tested without a database.
+use spacetimedb::{TimeDuration, Timestamp};
+
+use crate::{economy, map, PlayerState, PlayerStats, Tower};
+
+/// How long after placing a tower its owner can still undo it for a full
+/// refund (matches the client's `TOWER_UNDO_GRACE_SECS`)
+pub const UNDO_GRACE_MICROS: i64 = 10_000_000;
+
+/// Check that a tower can stand on grid point (x, y): a tile corner on the
+/// map, off the road and castle, clear of the enemy path and of the towers
+/// at `others`. Fog is only tracked on the clients, so it isn't checked.
+pub fn check_site(x: f32, y: f32, mut others: impl Iterator<Item = (f32, f32)>) -> Result<(), String> {
+    let on_grid = |v: f32| (v / map::TILE_SIZE).round() * map::TILE_SIZE == v;
+    if !on_grid(x) || !on_grid(y) {
+        return Err(format!("({}, {}) is not on the tower grid", x, y));
+    }
+    let (tile_x, tile_y) = map::tile_at(x, y);
+    if !map::in_bounds(tile_x, tile_y) {
+        return Err(format!("({}, {}) is outside the map", x, y));
+    }
+    if map::is_blocked(tile_x, tile_y) || map::overlaps_enemy_path(x, y) {
+        return Err(format!("({}, {}) is on the road", x, y));
+    }
+    if others.any(|(other_x, other_y)| (other_x - x).hypot(other_y - y) < map::TILE_SIZE / 2.0) {
+        return Err(format!("({}, {}) already has a tower", x, y));
+    }
+    Ok(())
+}
+
+/// The caller's gold and stats after buying a tower, and the tower row to
+/// insert. Nothing changes if the type is unknown or the gold is short.
+pub fn place(
+    state: PlayerState,
+    stats: PlayerStats,
+    tower_type_id: String,
+    x: f32,
+    y: f32,
+    now: Timestamp,
+) -> Result<(PlayerState, PlayerStats, Tower), String> {
+    let cost = economy::tower_cost(&tower_type_id)
+        .ok_or_else(|| format!("Unknown tower type '{}'", tower_type_id))?;
+    let gold = economy::charge(state.gold, cost)?;
+
+    let tower = Tower {
+        id: 0,
+        owner: state.identity,
+        tower_type_id,
+        x,
+        y,
+        placed_at: now,
+    };
+    let stats = PlayerStats {
+        towers_built: stats.towers_built + 1,
+        ..stats
+    };
+    Ok((PlayerState { gold, ..state }, stats, tower))
+}
+
+/// The caller's gold after paying to move `tower` to grid point (x, y), and
+/// the moved row. Only its owner can, and only onto a site `check_site`
+/// accepts.
+pub fn relocate(
+    state: PlayerState,
+    tower: Tower,
+    x: f32,
+    y: f32,
+    others: impl Iterator<Item = (f32, f32)>,
+) -> Result<(PlayerState, Tower), String> {
+    if tower.owner != state.identity {
+        return Err("Only the player who placed a tower can move it".to_string());
+    }
+    check_site(x, y, others)?;
+    let cost = economy::move_tower_cost().ok_or("Moving towers isn't configured")?;
+    let gold = economy::charge(state.gold, cost)?;
+    Ok((PlayerState { gold, ..state }, Tower { x, y, ..tower }))
+}
+
+/// The caller's gold and stats after taking back `tower`, with its whole
+/// cost refunded. Only its owner can, and only within `UNDO_GRACE_MICROS`
+/// of placing it.
+pub fn undo(
+    state: PlayerState,
+    stats: PlayerStats,
+    tower: &Tower,
+    now: Timestamp,
+) -> Result<(PlayerState, PlayerStats), String> {
+    if tower.owner != state.identity {
+        return Err("Only the player who placed a tower can undo it".to_string());
+    }
+    if now > tower.placed_at + TimeDuration::from_micros(UNDO_GRACE_MICROS) {
+        return Err("Too late to undo placing this tower".to_string());
+    }
+    let cost = economy::tower_cost(&tower.tower_type_id)
+        .ok_or_else(|| format!("Unknown tower type '{}'", tower.tower_type_id))?;
+
+    let stats = PlayerStats {
+        towers_built: stats.towers_built.saturating_sub(1),
+        ..stats
+    };
+    Ok((PlayerState { gold: state.gold + cost, ..state }, stats))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spacetimedb::Identity;
+
+    fn at(secs: i64) -> Timestamp {
+        Timestamp::from_micros_since_unix_epoch(secs * 1_000_000)
+    }
+
+    fn player(gold: i32) -> (PlayerState, PlayerStats) {
+        let identity = Identity::from_byte_array([1; 32]);
+        let state = PlayerState { identity, gold };
+        let stats = PlayerStats {
+            identity,
+            towers_built: 0,
+            kills: 0,
+            gold_earned: 0,
+            wood_gathered: 0,
+            meat_gathered: 0,
+        };
+        (state, stats)
+    }
+
+    #[test]
+    fn placing_deducts_gold_and_creates_the_tower() {
+        let (state, stats) = player(economy::starting_gold());
+        let cost = economy::tower_cost("archer").unwrap();
+        let (state, stats, tower) = place(state, stats, "archer".to_string(), 96.0, 160.0, at(0)).unwrap();
+
+        assert_eq!(state.gold, economy::starting_gold() - cost);
+        assert_eq!(stats.towers_built, 1);
+        assert_eq!(tower.owner, state.identity);
+        assert_eq!(tower.tower_type_id, "archer");
+        assert_eq!((tower.x, tower.y), (96.0, 160.0));
+    }
+
+    #[test]
+    fn unknown_tower_types_are_rejected() {
+        let (state, stats) = player(1_000);
+        assert!(place(state, stats, "no_such_tower".to_string(), 0.0, 0.0, at(0)).is_err());
+    }
+
+    #[test]
+    fn placing_without_enough_gold_is_rejected() {
+        let (state, stats) = player(10);
+        assert!(place(state, stats, "archer".to_string(), 0.0, 0.0, at(0)).is_err());
+    }
+
+    #[test]
+    fn towers_can_stand_on_free_grid_points() {
+        assert_eq!(check_site(-416.0, 256.0, std::iter::empty()), Ok(()));
+    }
+
+    #[test]
+    fn sites_off_the_grid_or_map_are_rejected() {
+        assert!(check_site(-400.0, 256.0, std::iter::empty()).is_err());
+        assert!(check_site(f32::NAN, 256.0, std::iter::empty()).is_err());
+        assert!(check_site(-1024.0, 256.0, std::iter::empty()).is_err());
+    }
+
+    #[test]
+    fn sites_on_the_road_are_rejected() {
+        assert!(map::overlaps_enemy_path(-448.0, -32.0));
+        assert!(check_site(-448.0, -32.0, std::iter::empty()).is_err());
+    }
+
+    #[test]
+    fn occupied_sites_are_rejected() {
+        assert!(check_site(-416.0, 256.0, [(-416.0, 256.0)].into_iter()).is_err());
+        assert_eq!(check_site(-416.0, 256.0, [(-384.0, 256.0)].into_iter()), Ok(()));
+    }
+
+    #[test]
+    fn moving_charges_the_fee_and_updates_the_row() {
+        let (state, stats) = player(economy::starting_gold());
+        let (state, _, tower) = place(state, stats, "archer".to_string(), -416.0, 256.0, at(0)).unwrap();
+        let gold = state.gold;
+        let (state, tower) = relocate(state, tower, -384.0, 256.0, std::iter::empty()).unwrap();
+
+        assert_eq!(state.gold, gold - economy::move_tower_cost().unwrap());
+        assert_eq!((tower.x, tower.y), (-384.0, 256.0));
+    }
+
+    #[test]
+    fn moving_onto_a_taken_or_blocked_site_is_rejected() {
+        let placed = || {
+            let (state, stats) = player(economy::starting_gold());
+            let (state, _, tower) = place(state, stats, "archer".to_string(), -416.0, 256.0, at(0)).unwrap();
+            (state, tower)
+        };
+        let (state, tower) = placed();
+        assert!(relocate(state, tower, -384.0, 256.0, [(-384.0, 256.0)].into_iter()).is_err());
+        let (state, tower) = placed();
+        assert!(relocate(state, tower, -448.0, -32.0, std::iter::empty()).is_err());
+    }
+
+    #[test]
+    fn only_the_owner_can_move() {
+        let (state, stats) = player(economy::starting_gold());
+        let (_, _, tower) = place(state, stats, "archer".to_string(), -416.0, 256.0, at(0)).unwrap();
+        let other = PlayerState {
+            identity: Identity::from_byte_array([2; 32]),
+            gold: 1_000,
+        };
+        assert!(relocate(other, tower, -384.0, 256.0, std::iter::empty()).is_err());
+    }
+
+    #[test]
+    fn undoing_within_the_grace_period_refunds_everything() {
+        let (state, stats) = player(economy::starting_gold());
+        let (state, stats, tower) = place(state, stats, "archer".to_string(), 0.0, 0.0, at(100)).unwrap();
+        let (state, stats) = undo(state, stats, &tower, at(105)).unwrap();
+
+        assert_eq!(state.gold, economy::starting_gold());
+        assert_eq!(stats.towers_built, 0);
+    }
+
+    #[test]
+    fn undoing_after_the_grace_period_is_rejected() {
+        let (state, stats) = player(economy::starting_gold());
+        let (state, stats, tower) = place(state, stats, "archer".to_string(), 0.0, 0.0, at(100)).unwrap();
+        let late = at(100) + TimeDuration::from_micros(UNDO_GRACE_MICROS + 1);
+        assert!(undo(state, stats, &tower, late).is_err());
+    }
+
+    #[test]
+    fn only_the_owner_can_undo() {
+        let (state, stats) = player(economy::starting_gold());
+        let (_, _, tower) = place(state, stats, "archer".to_string(), 0.0, 0.0, at(100)).unwrap();
+        let other = PlayerState {
+            identity: Identity::from_byte_array([2; 32]),
+            gold: 0,
+        };
+        let (_, other_stats) = player(0);
+        assert!(undo(other, other_stats, &tower, at(101)).is_err());
+    }
+}
@@ -573,7 +573,7 @@ pub fn handle_anonymous_button(
     for interaction in &interaction_query {
         if *interaction == Interaction::Pressed {
             info!("Playing anonymously...");
-            next_state.set(AppState::InGame);
+            next_state.set(AppState::Loading);
         }
     }
 }
@@ -713,10 +713,44 @@ const TOKEN_FILE: &str = ".spacetimedb_token";
 /// Load the access token from file if it exists
 pub fn load_token_from_file() -> Option<String> {
     match std::fs::read_to_string(TOKEN_FILE) {
-        Ok(token) if !token.is_empty() => {
+        Ok(token) if !token.trim().is_empty() => {
+            let token = token.trim().to_string();
+            if !is_well_formed_token(&token) {
+                warn!("Ignoring {}: it does not contain a valid token", TOKEN_FILE);
+                return None;
+            }
             info!("Loaded token from {}", TOKEN_FILE);
-            Some(token.trim().to_string())
+            Some(token)
         }
         _ => None,
     }
 }
+
+/// Delete the saved token so the next launch doesn't try it again
+pub fn discard_saved_token() {
+    match std::fs::remove_file(TOKEN_FILE) {
+        Ok(()) => warn!("Discarded saved token in {}", TOKEN_FILE),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => error!("Failed to remove {}: {}", TOKEN_FILE, e),
+    }
+}
+
+/// Basic JWT shape check: three base64url segments, header and payload being
+/// JSON objects. Catches truncated or garbled tokens before they reach the
+/// server and fail with an opaque connection error.
+pub fn is_well_formed_token(token: &str) -> bool {
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+    let parts: Vec<&str> = token.split('.').collect();
+    if parts.len() != 3 || parts.iter().any(|part| part.is_empty()) {
+        return false;
+    }
+
+    parts[..2].iter().all(|part| {
+        URL_SAFE_NO_PAD
+            .decode(part.trim_end_matches('='))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<serde_json::Value>(&bytes).ok())
+            .is_some_and(|json| json.is_object())
+    })
+}
@@ -1,15 +1,22 @@
 use bevy::prelude::*;
 use bevy_ecs_tiled::prelude::*;
 
+use crate::user_settings::Settings;
+
 /// Plugin that configures native Bevy plugins with project-specific settings
 pub struct BevyPlugin;
 
 impl Plugin for BevyPlugin {
     fn build(&self, app: &mut App) {
+        let settings = app.world().get_resource::<Settings>().cloned().unwrap_or_default();
+        let [width, height] = settings.graphics.resolution;
+
         app.add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
                 title: "Tower Defense MMO".to_string(),
-                resolution: (1024u32, 768u32).into(),
+                resolution: (width, height).into(),
+                mode: settings.window_mode(),
+                present_mode: settings.present_mode(),
                 ..default()
             }),
             ..default()
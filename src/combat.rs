@@ -0,0 +1,110 @@
+//! Combat math shared by the game and the balance simulation, kept free of
+//! Bevy systems so it can be benchmarked on its own (see `benches/combat.rs`).
+
+use crate::config::{get_damage_multiplier, AttackType, DefenseType};
+
+/// Damage a hit of `damage` deals to an enemy with `defense`; nothing if the
+/// enemy is immune to `attack`
+pub fn compute_damage(damage: f32, attack: AttackType, defense: DefenseType, immune_to: Option<AttackType>) -> f32 {
+    if immune_to == Some(attack) {
+        0.0
+    } else {
+        damage * get_damage_multiplier(attack, defense)
+    }
+}
+
+/// Id of the candidate closest to `origin` within `range`, the first one
+/// listed on ties
+pub fn nearest_in_range<T>(
+    origin: (f32, f32),
+    range: f32,
+    candidates: impl IntoIterator<Item = (T, (f32, f32))>,
+) -> Option<T> {
+    let range_squared = range * range;
+    candidates
+        .into_iter()
+        .map(|(id, (x, y))| (id, (x - origin.0).powi(2) + (y - origin.1).powi(2)))
+        .filter(|(_, distance_squared)| *distance_squared <= range_squared)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(id, _)| id)
+}
+
+/// Spread `share` of the damage each linked enemy took across the other
+/// living members of its group. `members` holds each member's (health when
+/// damage was last shared, health now); only the current health changes.
+/// Dead members neither pass damage on nor receive it, and spread damage
+/// never takes a member below `min_health`, so each dies to its own hits.
+pub fn share_linked_damage(members: &mut [(f32, f32)], share: f32, min_health: f32) {
+    let living = members.iter().filter(|(_, health)| *health > 0.0).count();
+    if living < 2 {
+        return;
+    }
+
+    let shared: Vec<f32> = members
+        .iter()
+        .map(|&(before, health)| if health > 0.0 { (before - health).max(0.0) * share } else { 0.0 })
+        .collect();
+    let total: f32 = shared.iter().sum();
+    for ((_, health), own) in members.iter_mut().zip(shared) {
+        if *health <= 0.0 {
+            continue;
+        }
+        let incoming = (total - own) / (living - 1) as f32;
+        *health = (*health + own - incoming).max(min_health.min(*health + own));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Damage multipliers by attack (blunt, pierce, divine) and defense
+    /// (armor, agility, mystical). Written out rather than derived, so a
+    /// balance change has to update this on purpose.
+    const EXPECTED: [[f32; 3]; 3] = [
+        [1.25, 0.85, 1.10],
+        [0.80, 1.25, 0.90],
+        [1.00, 0.90, 1.30],
+    ];
+
+    #[test]
+    fn damage_follows_the_type_matrix() {
+        for (attack, row) in AttackType::ALL.into_iter().zip(EXPECTED) {
+            for (defense, multiplier) in DefenseType::ALL.into_iter().zip(row) {
+                let damage = compute_damage(100.0, attack, defense, None);
+                assert!((damage - 100.0 * multiplier).abs() < 1e-3, "{attack:?} vs {defense:?}: {damage}");
+            }
+        }
+    }
+
+    #[test]
+    fn immune_enemies_take_no_damage_from_that_type_only() {
+        for defense in DefenseType::ALL {
+            assert_eq!(compute_damage(100.0, AttackType::Pierce, defense, Some(AttackType::Pierce)), 0.0);
+            assert!(compute_damage(100.0, AttackType::Blunt, defense, Some(AttackType::Pierce)) > 0.0);
+        }
+    }
+
+    #[test]
+    fn linked_damage_is_spread_over_the_living_members() {
+        // The first member took 40: it keeps 20 and the other living member takes 20
+        let mut members = [(100.0, 60.0), (100.0, 100.0), (100.0, 0.0)];
+        share_linked_damage(&mut members, 0.5, 1.0);
+        assert_eq!(members.map(|(_, health)| health), [80.0, 80.0, 0.0]);
+
+        // Spread damage can't kill, and a lone survivor keeps its damage
+        let mut members = [(100.0, 20.0), (1.0, 1.0)];
+        share_linked_damage(&mut members, 0.5, 1.0);
+        assert_eq!(members.map(|(_, health)| health), [60.0, 1.0]);
+        let mut members = [(100.0, 10.0), (100.0, -5.0)];
+        share_linked_damage(&mut members, 0.5, 1.0);
+        assert_eq!(members.map(|(_, health)| health), [10.0, -5.0]);
+    }
+
+    #[test]
+    fn nearest_enemy_in_range_is_targeted() {
+        let enemies = [("far", (90.0, 0.0)), ("near", (30.0, 40.0)), ("outside", (200.0, 0.0))];
+        assert_eq!(nearest_in_range((0.0, 0.0), 100.0, enemies), Some("near"));
+        assert_eq!(nearest_in_range((0.0, 0.0), 10.0, enemies), None);
+    }
+}
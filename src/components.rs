@@ -1,78 +1,44 @@
 use bevy::prelude::*;
+use spacetimedb_sdk::Identity;
 
-// ==================== Combat Type System ====================
-
-/// Attack types for towers/projectiles
-#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
-pub enum AttackType {
-    #[default]
-    Blunt,   // Hammers, catapults, rocks
-    Pierce,  // Arrows, spears, bolts
-    Divine,  // Holy/magical damage
-}
-
-/// Defense types for enemies
-#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
-pub enum DefenseType {
-    #[default]
-    Armor,    // Heavy armor - weak to blunt, strong vs pierce
-    Agility,  // Dodgy/fast - weak to pierce, strong vs blunt
-    Mystical, // Magical creatures - weak to divine
-}
-
-impl AttackType {
-    pub fn from_str(s: &str) -> Self {
-        match s.to_lowercase().as_str() {
-            "blunt" => AttackType::Blunt,
-            "pierce" => AttackType::Pierce,
-            "divine" => AttackType::Divine,
-            _ => AttackType::Blunt,
-        }
-    }
-}
-
-impl DefenseType {
-    pub fn from_str(s: &str) -> Self {
-        match s.to_lowercase().as_str() {
-            "armor" => DefenseType::Armor,
-            "agility" => DefenseType::Agility,
-            "mystical" => DefenseType::Mystical,
-            _ => DefenseType::Armor,
-        }
-    }
-}
-
-/// Calculate damage multiplier based on attack vs defense type
-/// Returns a multiplier (e.g., 1.25 for +25%, 0.85 for -15%)
-pub fn get_damage_multiplier(attack: AttackType, defense: DefenseType) -> f32 {
-    match (attack, defense) {
-        // Blunt attacks
-        (AttackType::Blunt, DefenseType::Armor) => 1.25,    // +25%
-        (AttackType::Blunt, DefenseType::Agility) => 0.85,  // -15%
-        (AttackType::Blunt, DefenseType::Mystical) => 1.10, // +10%
-
-        // Pierce attacks
-        (AttackType::Pierce, DefenseType::Armor) => 0.80,   // -20%
-        (AttackType::Pierce, DefenseType::Agility) => 1.25, // +25%
-        (AttackType::Pierce, DefenseType::Mystical) => 0.90, // -10%
-
-        // Divine attacks
-        (AttackType::Divine, DefenseType::Armor) => 1.00,   // 0%
-        (AttackType::Divine, DefenseType::Agility) => 0.90, // -10%
-        (AttackType::Divine, DefenseType::Mystical) => 1.30, // +30%
-    }
-}
+use crate::config::{AuraEffect, BurnConfig, HazardConfig, TrailConfig};
+pub use crate::config::{get_damage_multiplier, AttackType, DefenseType};
+use crate::constants::VETERANCY_THRESHOLDS;
 
 // ==================== Core Components ====================
 
 #[derive(Component)]
 pub struct Enemy {
+    /// Unit name from the config, for the combat log
+    pub name: String,
     pub health: f32,
+    pub max_health: f32,
     pub speed: f32,
     pub current_waypoint: usize,
     pub gold_reward: i32,
     pub damage_to_base: i32,
     pub defense_type: DefenseType,
+    pub is_boss: bool,
+    /// Gold stolen when reaching the castle
+    pub gold_steal: i32,
+    /// Attack type this enemy takes no damage from
+    pub immune_to: Option<AttackType>,
+    /// Share of incoming damage taken after type multipliers; below 1.0 in
+    /// armored waves
+    pub damage_taken: f32,
+    /// Health healed per second, up to `max_health`
+    pub regen: f32,
+}
+
+impl Enemy {
+    /// Damage multiplier for `attack`, zero if the enemy is immune to it
+    pub fn damage_multiplier(&self, attack: AttackType) -> f32 {
+        if self.immune_to == Some(attack) {
+            0.0
+        } else {
+            get_damage_multiplier(attack, self.defense_type) * self.damage_taken
+        }
+    }
 }
 
 #[derive(Component)]
@@ -83,21 +49,146 @@ pub struct AnimationTimer {
 #[derive(Component)]
 pub struct Tower {
     pub tower_type_id: String,
-    pub range: f32,
-    pub damage: f32,
-    pub fire_rate: f32,
+    /// Permanent stats from the config, upgrades and veterancy. Only these
+    /// are ever modified directly.
+    pub base_range: f32,
+    pub base_damage: f32,
+    pub base_fire_rate: f32,
+    /// Base stats with the current `TowerBuffs` applied, recomputed by
+    /// `update_effective_tower_stats`. Combat reads these.
+    pub effective_range: f32,
+    pub effective_damage: f32,
+    pub effective_fire_rate: f32,
     pub cooldown: f32,
     pub projectile_sprite: String,
     pub projectile_speed: f32,
     pub attack_type: AttackType,
+    pub leads_target: bool,
+    pub ballistic: bool,
+    pub splash_radius: f32,
+    pub hazard: Option<HazardConfig>,
+    pub burn_on_hit: Option<BurnConfig>,
+    pub aura: Option<AuraEffect>,
+    pub trail: Option<TrailConfig>,
+    /// Enemies this tower has landed the killing blow on
+    pub kills: u32,
+    /// Player who placed it; None for towers placed offline
+    pub owner: Option<Identity>,
+}
+
+impl Tower {
+    /// Effective (damage, range, fire_rate) with `buffs` applied; with no
+    /// buffs they equal the base stats
+    pub fn buffed_stats(&self, buffs: &TowerBuffs) -> (f32, f32, f32) {
+        (
+            self.base_damage * (1.0 + buffs.damage_bonus),
+            self.base_range * (1.0 + buffs.range_bonus),
+            self.base_fire_rate * (1.0 - buffs.fire_rate_bonus).max(MIN_BUFFED_FIRE_RATE_FACTOR),
+        )
+    }
 }
 
+/// Tower placed by another player, mirrored from the server's `tower` row `id`
+#[derive(Component)]
+pub struct SyncedTower {
+    pub id: u64,
+}
+
+/// Our own tower placed online whose `tower` row the server hasn't sent
+/// yet. Removed if no row arrives in time: the server refused the placement.
+#[derive(Component, Default)]
+pub struct PendingTower {
+    /// Seconds since `place_tower` was sent
+    pub age: f32,
+}
+
+/// Our own tower, confirmed by the server as `tower` row `id`
+#[derive(Component)]
+pub struct ConfirmedTower {
+    pub id: u64,
+}
+
+/// Shortest time between shots buffs can bring a tower down to, as a
+/// fraction of its base fire rate
+const MIN_BUFFED_FIRE_RATE_FACTOR: f32 = 0.1;
+
+/// Temporary bonuses on a tower, as fractions (0.15 = +15%). Rebuilt every
+/// frame by the systems that grant them (support auras), so removing the
+/// source removes the buff.
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq)]
+pub struct TowerBuffs {
+    pub damage_bonus: f32,
+    pub range_bonus: f32,
+    /// Shortens the time between shots
+    pub fire_rate_bonus: f32,
+}
+
+/// Veterancy rank earned from kills, separate from purchased upgrades
+#[derive(Component, Default)]
+pub struct Veterancy {
+    pub rank: u32,
+}
+
+impl Veterancy {
+    /// Rank reached with `kills` kills
+    pub fn rank_for_kills(kills: u32) -> u32 {
+        VETERANCY_THRESHOLDS.iter().filter(|&&threshold| kills >= threshold).count() as u32
+    }
+}
+
+/// Star shown above a tower for each veterancy rank
+#[derive(Component)]
+pub struct VeterancyStar;
+
 #[derive(Component)]
 pub struct Projectile {
     pub damage: f32,
     pub speed: f32,
     pub target: Entity,
     pub attack_type: AttackType,
+    /// Homing projectiles follow the target; others fly straight along `direction`
+    pub homing: bool,
+    pub direction: Vec2,
+    /// Distance a non-homing projectile can still travel before it is removed
+    pub remaining_range: f32,
+    /// Tower that fired it, credited with the kill
+    pub source: Entity,
+    pub burn: Option<BurnConfig>,
+}
+
+/// Arc of a lobbed projectile. It lands on `impact` whatever the target
+/// does meanwhile and hits everything within `splash_radius` there.
+#[derive(Component)]
+pub struct Ballistic {
+    pub start: Vec2,
+    pub impact: Vec2,
+    /// Seconds since launch
+    pub elapsed: f32,
+    pub flight_time: f32,
+    /// Height of the arc's apex in pixels
+    pub apex_height: f32,
+    pub splash_radius: f32,
+}
+
+impl Ballistic {
+    pub fn landed(&self) -> bool {
+        self.elapsed >= self.flight_time
+    }
+}
+
+/// Leaves fading segments behind a projectile
+#[derive(Component)]
+pub struct Trail {
+    pub config: TrailConfig,
+    /// Where the last segment ended
+    pub last_point: Vec2,
+}
+
+/// One piece of a projectile trail, removed once faded
+#[derive(Component)]
+pub struct TrailSegment {
+    pub lifetime: Timer,
+    pub alpha: f32,
 }
 
 #[derive(Component)]
@@ -113,9 +204,66 @@ pub struct HealthBarFill {
 #[derive(Component)]
 pub struct Castle;
 
+/// Fill of the health bar above the castle, scaled to lives / max_lives
+#[derive(Component)]
+pub struct CastleHealthFill {
+    pub max_width: f32,
+}
+
+/// Full-screen red overlay that flashes when the castle loses lives
+#[derive(Component)]
+pub struct DamageVignette {
+    pub timer: Timer,
+}
+
+/// Full-screen red tint held during the last-stand slow motion
+#[derive(Component)]
+pub struct LastStandTint;
+
+/// Full-screen blue tint darkening the scene at night
+#[derive(Component)]
+pub struct NightTint;
+
+/// First-run tutorial panel at the top of the screen
+#[derive(Component)]
+pub struct TutorialPanel;
+
+/// Instructions of the current tutorial step
+#[derive(Component)]
+pub struct TutorialText;
+
+/// Button that ends the tutorial early
+#[derive(Component)]
+pub struct SkipTutorialButton;
+
+/// Root of the combat log panel, hidden while the log is toggled off
+#[derive(Component)]
+pub struct CombatLogPanel;
+
+/// Text listing the latest combat log lines
+#[derive(Component)]
+pub struct CombatLogText;
+
+/// Full-screen grey overlay for heavy fog
+#[derive(Component)]
+pub struct WeatherFog;
+
+/// Falling rain streak, positioned in percent of the screen
+#[derive(Component)]
+pub struct RainDrop {
+    pub x: f32,
+    pub y: f32,
+    /// Percent of the screen height per second
+    pub speed: f32,
+}
+
 #[derive(Component)]
 pub struct GameUI;
 
+/// Banner shown over the game while the connection is being restored
+#[derive(Component)]
+pub struct ReconnectingBanner;
+
 #[derive(Component)]
 pub struct TowerWheelMenu;
 
@@ -124,6 +272,45 @@ pub struct TowerWheelOption {
     pub tower_type_id: String,
 }
 
+/// Screen-space rectangle drawn while box selecting
+#[derive(Component)]
+pub struct SelectionBox;
+
+/// Highlight drawn under a selected tower
+#[derive(Component)]
+pub struct TowerSelectionOutline;
+
+/// Player-built block on a road tile; enemies path around it
+#[derive(Component)]
+pub struct Wall;
+
+/// Building that pays its owner gold on a timer, without workers
+#[derive(Component)]
+pub struct GoldGenerator {
+    pub amount: i32,
+    /// Repeats every payout interval
+    pub timer: Timer,
+}
+
+impl GoldGenerator {
+    pub fn new(interval: f32, amount: i32) -> Self {
+        Self {
+            amount,
+            timer: Timer::from_seconds(interval, TimerMode::Repeating),
+        }
+    }
+}
+
+/// Translucent square over the tile a new tower would be placed on
+#[derive(Component)]
+pub struct PlacementHighlight;
+
+/// Description shown for the wheel option under the pointer
+#[derive(Component)]
+pub struct TowerWheelTooltip {
+    pub tower_type_id: String,
+}
+
 #[derive(Component)]
 pub struct FogTile {
     pub tile_x: i32,
@@ -133,6 +320,10 @@ pub struct FogTile {
 #[derive(Component)]
 pub struct GameOverScreen;
 
+/// Button on the game over screen that returns to the main menu
+#[derive(Component)]
+pub struct GameOverMenuButton;
+
 // Resource types
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum ResourceType {
@@ -141,14 +332,30 @@ pub enum ResourceType {
     Meat,
 }
 
+impl ResourceType {
+    pub const ALL: [ResourceType; 3] = [ResourceType::Wood, ResourceType::Gold, ResourceType::Meat];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ResourceType::Wood => "Wood",
+            ResourceType::Gold => "Gold",
+            ResourceType::Meat => "Meat",
+        }
+    }
+}
+
 // Worker state machine
 #[derive(Component, Clone, Copy, PartialEq, Eq, Debug, Default)]
 pub enum WorkerState {
     #[default]
     Idle,
+    /// Walking to the rally point after being built
+    MovingToRally,
     MovingToResource,
     Harvesting,
     ReturningWithResource,
+    /// Running home from a nearby enemy
+    Fleeing,
 }
 
 // Worker component
@@ -157,8 +364,16 @@ pub struct Worker {
     pub speed: f32,
     pub home_building: Entity,
     pub current_resource: Option<ResourceType>,
+    /// Units gathered per trip before heading home
+    pub carry_capacity: i32,
+    /// Units gathered on the current trip
+    pub carrying: i32,
 }
 
+/// "xN" above a worker bringing home more than one unit
+#[derive(Component)]
+pub struct CarryLabel;
+
 // Target for workers
 #[derive(Component)]
 pub struct WorkerTarget {
@@ -185,6 +400,10 @@ pub struct WorkerBuilding {
     pub worker_capacity: i32,
     /// How many workers have been spawned so far
     pub current_workers: i32,
+    /// Carry capacity of its workers, raised in the house menu
+    pub carry_capacity: i32,
+    /// Tier from 1, raised in the house menu; see `worker::HOUSE_TIERS`
+    pub building_level: usize,
 }
 
 // Harvest progress timer
@@ -210,10 +429,50 @@ pub struct BuildWorkerOption {
     pub gold_cost: i32,
 }
 
+/// House menu button changing the worker cap of a resource by `delta`
+#[derive(Component)]
+pub struct WorkerCapButton {
+    pub resource: ResourceType,
+    pub delta: i32,
+}
+
+/// House menu text showing the worker cap of a resource
+#[derive(Component)]
+pub struct WorkerCapText(pub ResourceType);
+
+/// House menu button that starts placing the rally point
+#[derive(Component)]
+pub struct SetRallyButton;
+
+/// House menu button removing the rally point
+#[derive(Component)]
+pub struct ClearRallyButton;
+
+/// Flag drawn at the rally point
+#[derive(Component)]
+pub struct RallyFlag;
+
+/// House menu button raising the workers' carry capacity
+#[derive(Component)]
+pub struct UpgradeCarryOption {
+    pub gold_cost: i32,
+}
+
+/// House menu button raising the house to its next tier
+#[derive(Component)]
+pub struct UpgradeHouseOption {
+    pub gold_cost: i32,
+    pub wood_cost: i32,
+}
+
 // Tower upgrade menu components
 #[derive(Component)]
 pub struct TowerUpgradeMenu;
 
+/// Upgrade menu button that starts relocating the tower
+#[derive(Component)]
+pub struct MoveTowerButton;
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum UpgradeType {
     Damage,
@@ -224,9 +483,24 @@ pub enum UpgradeType {
 #[derive(Component)]
 pub struct TowerUpgradeOption {
     pub upgrade_type: UpgradeType,
+    /// Wood per tower upgraded
     pub wood_cost: i32,
 }
 
+/// Readiness bar above a tower, fills up as the cooldown runs out
+#[derive(Component)]
+pub struct TowerCooldownBar {
+    pub max_width: f32,
+}
+
+/// When the local player placed this tower and the gold it cost them, so
+/// the placement can be undone for a full refund for a short while
+#[derive(Component)]
+pub struct PlacedAt {
+    pub secs: f32,
+    pub paid: i32,
+}
+
 #[derive(Component, Default)]
 pub struct TowerLevel {
     pub damage_level: i32,
@@ -234,12 +508,112 @@ pub struct TowerLevel {
     pub fire_rate_level: i32,
 }
 
+/// Lingering damage zone left on the path by area-denial towers
+#[derive(Component)]
+pub struct GroundHazard {
+    pub radius: f32,
+    pub dps: f32,
+    pub attack_type: AttackType,
+    pub lifetime: Timer,
+    /// Tower that dropped it, credited with kills
+    pub source: Entity,
+}
+
+/// Stacking damage over time on an enemy. Every hit adds a stack (up to
+/// `max_stacks`) and refreshes `timer`; when the timer runs out one stack
+/// falls off.
+#[derive(Component)]
+pub struct Burn {
+    pub stacks: u32,
+    pub max_stacks: u32,
+    pub dps_per_stack: f32,
+    pub timer: Timer,
+    /// Time until the next damage tick
+    pub tick: Timer,
+    /// Tower that applied the latest stack, credited with kills
+    pub source: Entity,
+    /// Flame overlay child, scaled with the stack count
+    pub flame: Entity,
+}
+
+/// Icon above an enemy showing the attack type it is immune to
+#[derive(Component)]
+pub struct ImmunityIcon;
+
+/// Enemy of a bounty wave; `Enemy::gold_reward` already includes the bounty
+#[derive(Component)]
+pub struct Bounty;
+
+/// Enemy from the wave schedule, as (wave number, spawn index). Every client
+/// spawns the same schedule, so this names the same enemy on all of them.
+#[derive(Component, Clone, Copy)]
+pub struct SpawnId(pub i32, pub i32);
+
+/// Enemy of a linked spawn group; part of the damage any member takes is
+/// spread across the others still alive
+#[derive(Component)]
+pub struct Linked {
+    /// (wave number, spawn group index) the enemy was spawned from
+    pub group_id: (i32, usize),
+    /// Health when damage was last shared, to measure new damage against
+    pub last_health: f32,
+}
+
+/// Ring closing in on the start of the path just before an enemy appears
+/// there. It lasts until the enemy is due, plus a short fade.
+#[derive(Component)]
+pub struct SpawnTelegraph {
+    pub lifetime: Timer,
+}
+
+/// Flame sprite drawn over a burning enemy
+#[derive(Component)]
+pub struct BurnFlame;
+
 /// Temporary visual effect component for holy tower heal animation
 #[derive(Component)]
 pub struct HolyTowerEffect {
     pub lifetime: Timer,
 }
 
+/// Sparkle played over a tower when one of its upgrades goes through
+#[derive(Component)]
+pub struct UpgradeEffect {
+    pub lifetime: Timer,
+}
+
+/// Light beam from a holy tower to the enemy it struck, fading over its lifetime
+#[derive(Component)]
+pub struct HolyBeam {
+    pub lifetime: Timer,
+}
+
+/// Sprite color an enemy returns to once a hit flash wears off, white
+/// unless its unit type sets a tint
+#[derive(Component, Clone, Copy)]
+pub struct BaseTint(pub Color);
+
+/// Brief red tint on an enemy sprite after it takes damage
+#[derive(Component)]
+pub struct HitFlash {
+    pub timer: Timer,
+}
+
+impl Default for HitFlash {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(0.15, TimerMode::Once),
+        }
+    }
+}
+
+/// World-space text that drifts upwards and fades out, e.g. "-15g stolen!"
+#[derive(Component)]
+pub struct FloatingText {
+    pub lifetime: Timer,
+    pub velocity: Vec2,
+}
+
 /// Helper to get attack type icon path
 pub fn get_attack_type_icon(attack_type: AttackType) -> &'static str {
     match attack_type {
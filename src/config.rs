@@ -16,12 +16,48 @@ pub struct UnitType {
     pub frame_size: [u32; 2],
     #[serde(default = "default_defense_type")]
     pub defense_type: String,
+    #[serde(default)]
+    pub is_boss: bool,
+    /// Gold taken from the player when this unit reaches the castle
+    #[serde(default)]
+    pub gold_steal: i32,
+    /// Attack type ("blunt", "pierce" or "divine") that deals no damage at all
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub immune_to: Option<String>,
+    /// RGBA multiplied onto the sprite, so a variant such as an elite can
+    /// reuse another unit's art in a different color
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tint: Option<[f32; 4]>,
 }
 
 fn default_defense_type() -> String {
     "armor".to_string()
 }
 
+/// Blank unit with the same defaults as a file that leaves fields out;
+/// fill in the rest with struct update syntax
+impl Default for UnitType {
+    fn default() -> Self {
+        Self {
+            id: String::new(),
+            name: String::new(),
+            sprite_path: String::new(),
+            avatar_path: String::new(),
+            base_health: 0.0,
+            base_speed: 0.0,
+            damage_to_base: 0,
+            gold_reward: 0,
+            frame_count: 0,
+            frame_size: [0, 0],
+            defense_type: default_defense_type(),
+            is_boss: false,
+            gold_steal: 0,
+            immune_to: None,
+            tint: None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TowerType {
     pub id: String,
@@ -36,24 +72,312 @@ pub struct TowerType {
     pub description: String,
     #[serde(default = "default_attack_type")]
     pub attack_type: String,
+    /// Fire at the predicted intercept point instead of homing onto the target
+    #[serde(default)]
+    pub leads_target: bool,
+    /// Lob projectiles in an arc onto the predicted impact point instead of
+    /// flying them at the target; they only hit on landing
+    #[serde(default)]
+    pub ballistic: bool,
+    /// Radius around the impact point hit when a ballistic projectile lands
+    #[serde(default)]
+    pub splash_radius: f32,
+    /// Area-denial towers leave a damaging patch instead of firing projectiles
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hazard: Option<HazardConfig>,
+    /// Projectile hits set the target on fire
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub burn_on_hit: Option<BurnConfig>,
+    /// Support towers don't attack; they buff other towers within `range`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aura: Option<AuraEffect>,
+    /// Fading line drawn behind its projectiles
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trail: Option<TrailConfig>,
 }
 
 fn default_attack_type() -> String {
     "pierce".to_string()
 }
 
+/// Blank tower with the same defaults as a file that leaves fields out;
+/// fill in the rest with struct update syntax
+impl Default for TowerType {
+    fn default() -> Self {
+        Self {
+            id: String::new(),
+            name: String::new(),
+            sprite_path: String::new(),
+            cost: 0,
+            range: 0.0,
+            damage: 0.0,
+            fire_rate: 0.0,
+            projectile_sprite: String::new(),
+            projectile_speed: 0.0,
+            description: String::new(),
+            attack_type: default_attack_type(),
+            leads_target: false,
+            ballistic: false,
+            splash_radius: 0.0,
+            hazard: None,
+            burn_on_hit: None,
+            aura: None,
+            trail: None,
+        }
+    }
+}
+
+/// Ground patch left by area-denial towers; damage per second is the tower's `damage`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct HazardConfig {
+    pub radius: f32,
+    pub lifetime: f32,
+}
+
+/// Buff a support tower gives to towers in its range, as fractions
+/// (0.15 = +15% damage, 0.10 = 10% shorter time between shots)
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuraEffect {
+    pub damage_bonus: f32,
+    pub fire_rate_bonus: f32,
+}
+
+/// Stacking burn applied on hit; each stack adds `dps_per_stack`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct BurnConfig {
+    pub dps_per_stack: f32,
+    /// Seconds a stack lasts without being refreshed by another hit
+    pub duration: f32,
+    pub max_stacks: u32,
+}
+
+/// Trail behind a tower's projectiles, so slow or small shots are easier to follow
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TrailConfig {
+    /// RGBA, 0.0 - 1.0
+    pub color: [f32; 4],
+    pub width: f32,
+    /// Seconds each piece of the trail takes to fade out
+    pub lifetime: f32,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UnitSpawn {
     pub unit_id: String,
     pub count: i32,
     pub health_multiplier: f32,
+    /// The group's enemies share damage: part of every hit on one is spread
+    /// across the others still alive
+    #[serde(default)]
+    pub linked: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// One enemy of `unit_id` at its base health
+impl Default for UnitSpawn {
+    fn default() -> Self {
+        Self {
+            unit_id: String::new(),
+            count: 1,
+            health_multiplier: 1.0,
+            linked: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Wave {
     pub wave_number: i32,
     pub spawn_interval: f32,
     pub spawns: Vec<UnitSpawn>,
+    /// Catch-up wave: every enemy pays `BOUNTY_GOLD_MULTIPLIER` times its reward
+    #[serde(default)]
+    pub bounty: bool,
+    /// Affixes buffing every enemy of the wave
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub modifiers: Vec<WaveModifier>,
+    /// File under `WAVES_DIR` this wave was loaded from and is saved back to;
+    /// `None` for waves from the single `WAVES_FILE`
+    #[serde(skip)]
+    pub source: Option<String>,
+}
+
+/// Gold reward multiplier for enemies of a bounty wave
+pub const BOUNTY_GOLD_MULTIPLIER: i32 = 5;
+
+/// Speed multiplier of enemies in a `Fast` wave
+pub const FAST_SPEED_MULTIPLIER: f32 = 1.5;
+/// Share of incoming damage enemies of an `Armored` wave still take
+pub const ARMORED_DAMAGE_TAKEN: f32 = 0.7;
+/// Fraction of their max health enemies of a `Regen` wave heal per second
+pub const REGEN_PER_SECOND: f32 = 0.02;
+
+/// Global affix a wave can carry, buffing every enemy in it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WaveModifier {
+    Fast,
+    Armored,
+    Regen,
+}
+
+impl WaveModifier {
+    pub const ALL: [WaveModifier; 3] = [WaveModifier::Fast, WaveModifier::Armored, WaveModifier::Regen];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            WaveModifier::Fast => "Fast",
+            WaveModifier::Armored => "Armored",
+            WaveModifier::Regen => "Regenerating",
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            WaveModifier::Fast => format!("+{:.0}% speed", (FAST_SPEED_MULTIPLIER - 1.0) * 100.0),
+            WaveModifier::Armored => format!("-{:.0}% damage taken", (1.0 - ARMORED_DAMAGE_TAKEN) * 100.0),
+            WaveModifier::Regen => format!("heal {:.0}% HP/s", REGEN_PER_SECOND * 100.0),
+        }
+    }
+}
+
+/// What a wave's modifiers do to each of its enemies. Different modifiers
+/// combine; listing one twice counts once.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EnemyModifiers {
+    pub speed_multiplier: f32,
+    /// Share of incoming damage taken, after type multipliers
+    pub damage_taken: f32,
+    /// Fraction of max health healed per second
+    pub regen_per_second: f32,
+}
+
+impl Default for EnemyModifiers {
+    fn default() -> Self {
+        Self {
+            speed_multiplier: 1.0,
+            damage_taken: 1.0,
+            regen_per_second: 0.0,
+        }
+    }
+}
+
+impl EnemyModifiers {
+    pub fn from_wave(modifiers: &[WaveModifier]) -> Self {
+        let mut combined = Self::default();
+        for modifier in WaveModifier::ALL.into_iter().filter(|m| modifiers.contains(m)) {
+            match modifier {
+                WaveModifier::Fast => combined.speed_multiplier *= FAST_SPEED_MULTIPLIER,
+                WaveModifier::Armored => combined.damage_taken *= ARMORED_DAMAGE_TAKEN,
+                WaveModifier::Regen => combined.regen_per_second += REGEN_PER_SECOND,
+            }
+        }
+        combined
+    }
+}
+
+/// One enemy the spawner will produce, in spawn order
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpawnEvent {
+    /// Seconds after the wave starts
+    pub spawn_time: f32,
+    pub unit_id: String,
+    pub health: f32,
+    pub gold_reward: i32,
+    pub bounty: bool,
+    /// Index of the enemy's spawn group in the wave if that group is linked
+    pub link_group: Option<usize>,
+}
+
+/// Every enemy of `wave` in the order and at the times the spawner produces
+/// them: spawn groups in order, one enemy every `spawn_interval`, the first
+/// one interval after the wave starts. Groups with an unknown unit id are
+/// skipped.
+pub fn schedule_wave(wave: &Wave, units: &[UnitType]) -> Vec<SpawnEvent> {
+    let mut events = Vec::new();
+    for (group, spawn) in wave.spawns.iter().enumerate() {
+        let Some(unit) = units.iter().find(|u| u.id == spawn.unit_id) else {
+            continue;
+        };
+        for _ in 0..spawn.count.max(0) {
+            events.push(SpawnEvent {
+                spawn_time: (events.len() + 1) as f32 * wave.spawn_interval,
+                unit_id: unit.id.clone(),
+                health: unit.base_health * spawn.health_multiplier,
+                gold_reward: if wave.bounty {
+                    unit.gold_reward * BOUNTY_GOLD_MULTIPLIER
+                } else {
+                    unit.gold_reward
+                },
+                bounty: wave.bounty,
+                link_group: spawn.linked.then_some(group),
+            });
+        }
+    }
+    events
+}
+
+/// Content problems across `units.toml` and the waves
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ContentIssues {
+    /// Ids of units no wave spawns
+    pub unused_units: Vec<String>,
+    /// Wave numbers that would spawn nothing
+    pub empty_waves: Vec<i32>,
+    /// (wave number, unit id) for spawn groups naming a unit that doesn't exist
+    pub unknown_units: Vec<(i32, String)>,
+}
+
+impl ContentIssues {
+    pub fn is_empty(&self) -> bool {
+        self.unused_units.is_empty() && self.empty_waves.is_empty() && self.unknown_units.is_empty()
+    }
+}
+
+/// Find units no wave uses, waves that spawn nothing and spawn groups naming
+/// unknown units. Groups with a count of zero don't count as using a unit.
+pub fn find_content_issues(units: &[UnitType], waves: &[Wave]) -> ContentIssues {
+    let mut issues = ContentIssues::default();
+
+    for unit in units {
+        let used = waves
+            .iter()
+            .flat_map(|wave| &wave.spawns)
+            .any(|spawn| spawn.unit_id == unit.id && spawn.count > 0);
+        if !used {
+            issues.unused_units.push(unit.id.clone());
+        }
+    }
+
+    for wave in waves {
+        if schedule_wave(wave, units).is_empty() {
+            issues.empty_waves.push(wave.wave_number);
+        }
+        for spawn in &wave.spawns {
+            if !units.iter().any(|u| u.id == spawn.unit_id) {
+                issues.unknown_units.push((wave.wave_number, spawn.unit_id.clone()));
+            }
+        }
+    }
+
+    issues
+}
+
+/// Directory asset paths are relative to, as seen from the working directory
+pub const ASSETS_DIR: &str = "assets";
+
+/// Asset paths with no file under `ASSETS_DIR`, sorted and without
+/// duplicates. Empty paths mean "no sprite" and are skipped.
+pub fn find_missing_assets<'a>(paths: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut missing: Vec<String> = paths
+        .into_iter()
+        .filter(|path| !path.is_empty())
+        .filter(|path| !std::path::Path::new(ASSETS_DIR).join(path).is_file())
+        .map(str::to_string)
+        .collect();
+    missing.sort_unstable();
+    missing.dedup();
+    missing
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -62,10 +386,19 @@ pub struct UnitsConfig {
     pub units: Vec<UnitType>,
 }
 
+/// Directory of wave files, merged in filename order. Used instead of
+/// `WAVES_FILE` when it exists.
+pub const WAVES_DIR: &str = "waves";
+pub const WAVES_FILE: &str = "waves.toml";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[cfg_attr(feature = "bevy", derive(Resource))]
 pub struct WavesConfig {
     pub waves: Vec<Wave>,
+    /// Files under `WAVES_DIR` that were loaded, in merge order, so `save`
+    /// also rewrites files whose waves were all deleted
+    #[serde(skip)]
+    pub files: Vec<String>,
 }
 
 impl UnitsConfig {
@@ -76,14 +409,69 @@ impl UnitsConfig {
 }
 
 impl WavesConfig {
+    /// Waves from every `*.toml` in `WAVES_DIR` in filename order, or from
+    /// `WAVES_FILE` when the directory doesn't exist
     pub fn load() -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
-        let content = std::fs::read_to_string("waves.toml")?;
-        Ok(toml::from_str(&content)?)
+        let dir = std::path::Path::new(WAVES_DIR);
+        if !dir.is_dir() {
+            let content = std::fs::read_to_string(WAVES_FILE)?;
+            return Ok(toml::from_str(&content)?);
+        }
+
+        let mut paths: Vec<std::path::PathBuf> = std::fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+            .collect();
+        paths.sort();
+
+        let mut config = WavesConfig {
+            waves: Vec::new(),
+            files: Vec::new(),
+        };
+        for path in paths {
+            let file = path.display().to_string();
+            let content = std::fs::read_to_string(&path)?;
+            let part: WavesConfig =
+                toml::from_str(&content).map_err(|e| format!("{}: {}", file, e))?;
+            config.waves.extend(part.waves.into_iter().map(|wave| Wave {
+                source: Some(file.clone()),
+                ..wave
+            }));
+            config.files.push(file);
+        }
+        Ok(config)
+    }
+
+    /// Write each wave back to its `source` file, or to `WAVES_FILE` when it
+    /// has none
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut files: Vec<(String, Vec<Wave>)> =
+            self.files.iter().map(|file| (file.clone(), Vec::new())).collect();
+        for wave in &self.waves {
+            let file = wave.source.clone().unwrap_or_else(|| WAVES_FILE.to_string());
+            match files.iter_mut().find(|(name, _)| *name == file) {
+                Some((_, waves)) => waves.push(wave.clone()),
+                None => files.push((file, vec![wave.clone()])),
+            }
+        }
+
+        for (file, waves) in files {
+            let content = toml::to_string_pretty(&WavesConfig {
+                waves,
+                files: Vec::new(),
+            })?;
+            std::fs::write(file, content)?;
+        }
+        Ok(())
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TowersConfig {
+    /// Gold to move a placed tower to another tile
+    pub move_cost: i32,
+    /// Gold to wall off a road tile
+    pub wall_cost: i32,
     pub towers: Vec<TowerType>,
 }
 
@@ -93,3 +481,338 @@ impl TowersConfig {
         Ok(toml::from_str(&content)?)
     }
 }
+
+/// Purely visual animated sprites placed on map tiles, such as swaying bushes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AmbientConfig {
+    #[serde(default)]
+    pub decorations: Vec<AmbientDecoration>,
+}
+
+/// One sprite sheet (a row of frames) and the tiles it is drawn on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AmbientDecoration {
+    pub sprite_path: String,
+    pub frame_size: [u32; 2],
+    pub frame_count: usize,
+    /// Seconds per frame
+    pub frame_time: f32,
+    /// Map tiles as `[x, y]`, same coordinates as the path corners
+    pub tiles: Vec<(i32, i32)>,
+}
+
+impl AmbientConfig {
+    pub fn load() -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let content = std::fs::read_to_string("ambient.toml")?;
+        Ok(toml::from_str(&content)?)
+    }
+}
+
+/// Upper bounds on `starting_resources.toml`, to catch typos such as an
+/// extra zero (the server checks the same bounds)
+pub const MAX_STARTING_GOLD: i32 = 100_000;
+pub const MAX_STARTING_LIVES: i32 = 1_000;
+pub const MAX_STARTING_MATERIALS: i32 = 10_000;
+
+/// What a game starts with
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StartingResources {
+    pub gold: i32,
+    pub lives: i32,
+    #[serde(default)]
+    pub wood: i32,
+    #[serde(default)]
+    pub meat: i32,
+}
+
+impl Default for StartingResources {
+    fn default() -> Self {
+        Self {
+            gold: 100,
+            lives: 20,
+            wood: 0,
+            meat: 0,
+        }
+    }
+}
+
+impl StartingResources {
+    /// Reject negative amounts, a game without lives and anything above the
+    /// `MAX_STARTING_*` bounds
+    pub fn validate(&self) -> Result<(), String> {
+        let checks = [
+            ("gold", self.gold, 0, MAX_STARTING_GOLD),
+            ("lives", self.lives, 1, MAX_STARTING_LIVES),
+            ("wood", self.wood, 0, MAX_STARTING_MATERIALS),
+            ("meat", self.meat, 0, MAX_STARTING_MATERIALS),
+        ];
+        for (name, value, min, max) in checks {
+            if !(min..=max).contains(&value) {
+                return Err(format!("starting {} must be between {} and {}, not {}", name, min, max, value));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Starting resources per game mode, from `starting_resources.toml`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StartingResourcesConfig {
+    #[serde(default)]
+    pub standard: StartingResources,
+    #[serde(default)]
+    pub endless: StartingResources,
+}
+
+impl StartingResourcesConfig {
+    pub fn load() -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let content = std::fs::read_to_string("starting_resources.toml")?;
+        let config: Self = toml::from_str(&content)?;
+        config.standard.validate()?;
+        config.endless.validate()?;
+        Ok(config)
+    }
+
+    pub fn for_mode(&self, endless: bool) -> StartingResources {
+        if endless { self.endless } else { self.standard }
+    }
+}
+
+// ==================== Combat Type System ====================
+
+/// Attack types for towers/projectiles
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum AttackType {
+    #[default]
+    Blunt,   // Hammers, catapults, rocks
+    Pierce,  // Arrows, spears, bolts
+    Divine,  // Holy/magical damage
+}
+
+/// Defense types for enemies
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum DefenseType {
+    #[default]
+    Armor,    // Heavy armor - weak to blunt, strong vs pierce
+    Agility,  // Dodgy/fast - weak to pierce, strong vs blunt
+    Mystical, // Magical creatures - weak to divine
+}
+
+impl AttackType {
+    pub const ALL: [AttackType; 3] = [AttackType::Blunt, AttackType::Pierce, AttackType::Divine];
+
+    pub fn from_str(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "blunt" => AttackType::Blunt,
+            "pierce" => AttackType::Pierce,
+            "divine" => AttackType::Divine,
+            _ => AttackType::Blunt,
+        }
+    }
+}
+
+impl DefenseType {
+    pub const ALL: [DefenseType; 3] = [DefenseType::Armor, DefenseType::Agility, DefenseType::Mystical];
+
+    pub fn from_str(s: &str) -> Self {
+        match s.to_lowercase().as_str() {
+            "armor" => DefenseType::Armor,
+            "agility" => DefenseType::Agility,
+            "mystical" => DefenseType::Mystical,
+            _ => DefenseType::Armor,
+        }
+    }
+}
+
+/// Calculate damage multiplier based on attack vs defense type
+/// Returns a multiplier (e.g., 1.25 for +25%, 0.85 for -15%)
+pub fn get_damage_multiplier(attack: AttackType, defense: DefenseType) -> f32 {
+    match (attack, defense) {
+        // Blunt attacks
+        (AttackType::Blunt, DefenseType::Armor) => 1.25,    // +25%
+        (AttackType::Blunt, DefenseType::Agility) => 0.85,  // -15%
+        (AttackType::Blunt, DefenseType::Mystical) => 1.10, // +10%
+
+        // Pierce attacks
+        (AttackType::Pierce, DefenseType::Armor) => 0.80,   // -20%
+        (AttackType::Pierce, DefenseType::Agility) => 1.25, // +25%
+        (AttackType::Pierce, DefenseType::Mystical) => 0.90, // -10%
+
+        // Divine attacks
+        (AttackType::Divine, DefenseType::Armor) => 1.00,   // 0%
+        (AttackType::Divine, DefenseType::Agility) => 0.90, // -10%
+        (AttackType::Divine, DefenseType::Mystical) => 1.30, // +30%
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit(id: &str, base_health: f32) -> UnitType {
+        UnitType {
+            id: id.to_string(),
+            name: id.to_string(),
+            base_health,
+            base_speed: 50.0,
+            damage_to_base: 1,
+            gold_reward: 5,
+            ..Default::default()
+        }
+    }
+
+    fn wave(spawn_interval: f32, spawns: &[(&str, i32, f32)]) -> Wave {
+        Wave {
+            wave_number: 1,
+            spawn_interval,
+            spawns: spawns
+                .iter()
+                .map(|&(unit_id, count, health_multiplier)| UnitSpawn {
+                    unit_id: unit_id.to_string(),
+                    count,
+                    health_multiplier,
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn counts_match_spawn_groups() {
+        let units = [unit("warrior", 100.0), unit("archer", 60.0)];
+        let events = schedule_wave(&wave(1.0, &[("warrior", 3, 1.0), ("archer", 2, 1.0)]), &units);
+        assert_eq!(events.len(), 5);
+        assert_eq!(events.iter().filter(|e| e.unit_id == "warrior").count(), 3);
+        assert_eq!(events.iter().filter(|e| e.unit_id == "archer").count(), 2);
+    }
+
+    #[test]
+    fn groups_spawn_in_order() {
+        let units = [unit("warrior", 100.0), unit("archer", 60.0)];
+        let events = schedule_wave(&wave(1.0, &[("archer", 1, 1.0), ("warrior", 2, 1.0)]), &units);
+        let order: Vec<&str> = events.iter().map(|e| e.unit_id.as_str()).collect();
+        assert_eq!(order, ["archer", "warrior", "warrior"]);
+    }
+
+    #[test]
+    fn spawns_are_one_interval_apart() {
+        let units = [unit("warrior", 100.0)];
+        let events = schedule_wave(&wave(1.5, &[("warrior", 4, 1.0)]), &units);
+        let times: Vec<f32> = events.iter().map(|e| e.spawn_time).collect();
+        assert_eq!(times, [1.5, 3.0, 4.5, 6.0]);
+    }
+
+    #[test]
+    fn health_uses_the_group_multiplier() {
+        let units = [unit("warrior", 100.0)];
+        let events = schedule_wave(&wave(1.0, &[("warrior", 1, 1.0), ("warrior", 1, 1.5)]), &units);
+        assert_eq!(events[0].health, 100.0);
+        assert_eq!(events[1].health, 150.0);
+    }
+
+    #[test]
+    fn bounty_waves_multiply_the_reward() {
+        let units = [unit("warrior", 100.0)];
+        let mut bounty = wave(1.0, &[("warrior", 2, 1.0)]);
+        assert!(schedule_wave(&bounty, &units).iter().all(|e| !e.bounty && e.gold_reward == 5));
+
+        bounty.bounty = true;
+        let events = schedule_wave(&bounty, &units);
+        assert!(events.iter().all(|e| e.bounty && e.gold_reward == 5 * BOUNTY_GOLD_MULTIPLIER));
+    }
+
+    #[test]
+    fn repeated_wave_modifiers_count_once() {
+        assert_eq!(EnemyModifiers::from_wave(&[]), EnemyModifiers::default());
+        let modifiers = [WaveModifier::Fast, WaveModifier::Armored, WaveModifier::Fast];
+        let combined = EnemyModifiers::from_wave(&modifiers);
+        assert_eq!(combined.speed_multiplier, FAST_SPEED_MULTIPLIER);
+        assert_eq!(combined.damage_taken, ARMORED_DAMAGE_TAKEN);
+        assert_eq!(combined.regen_per_second, 0.0);
+    }
+
+    #[test]
+    fn only_linked_groups_get_a_link_group() {
+        let units = [unit("warrior", 100.0), unit("archer", 60.0)];
+        let mut linked = wave(1.0, &[("warrior", 2, 1.0), ("archer", 1, 1.0)]);
+        linked.spawns[0].linked = true;
+        let groups: Vec<Option<usize>> = schedule_wave(&linked, &units).iter().map(|e| e.link_group).collect();
+        assert_eq!(groups, [Some(0), Some(0), None]);
+    }
+
+    #[test]
+    fn unknown_units_and_empty_groups_are_skipped() {
+        let units = [unit("warrior", 100.0)];
+        let events = schedule_wave(
+            &wave(2.0, &[("dragon", 3, 1.0), ("warrior", 0, 1.0), ("warrior", 2, 1.0)]),
+            &units,
+        );
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].spawn_time, 2.0);
+        assert_eq!(events[1].spawn_time, 4.0);
+    }
+
+    #[test]
+    fn unused_units_are_reported() {
+        let units = [unit("warrior", 100.0), unit("archer", 60.0), unit("mage", 40.0)];
+        let waves = [wave(1.0, &[("warrior", 2, 1.0), ("mage", 0, 1.0)])];
+        let issues = find_content_issues(&units, &waves);
+        assert_eq!(issues.unused_units, ["archer", "mage"]);
+        assert!(issues.empty_waves.is_empty());
+    }
+
+    #[test]
+    fn empty_waves_and_unknown_units_are_reported() {
+        let units = [unit("warrior", 100.0)];
+        let mut empty = wave(1.0, &[]);
+        empty.wave_number = 2;
+        let mut unknown = wave(1.0, &[("dragon", 1, 1.0)]);
+        unknown.wave_number = 3;
+        let waves = [wave(1.0, &[("warrior", 1, 1.0)]), empty, unknown];
+
+        let issues = find_content_issues(&units, &waves);
+        assert_eq!(issues.empty_waves, [2, 3]);
+        assert_eq!(issues.unknown_units, [(3, "dragon".to_string())]);
+        assert!(issues.unused_units.is_empty());
+    }
+
+    #[test]
+    fn tidy_content_has_no_issues() {
+        let units = [unit("warrior", 100.0)];
+        let waves = [wave(1.0, &[("warrior", 3, 1.0)])];
+        assert!(find_content_issues(&units, &waves).is_empty());
+    }
+
+    #[test]
+    fn missing_assets_are_listed_once() {
+        let missing = find_missing_assets(["", "Nowhere/b.png", "Nowhere/a.png", "Nowhere/b.png"]);
+        assert_eq!(missing, ["Nowhere/a.png", "Nowhere/b.png"]);
+    }
+
+    #[test]
+    fn empty_wave_has_no_events() {
+        assert!(schedule_wave(&wave(1.0, &[]), &[unit("warrior", 100.0)]).is_empty());
+    }
+
+    #[test]
+    fn default_starting_resources_are_valid() {
+        assert_eq!(StartingResources::default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn out_of_bounds_starting_resources_are_rejected() {
+        let start = StartingResources::default();
+        assert!(StartingResources { gold: -1, ..start }.validate().is_err());
+        assert!(StartingResources { lives: 0, ..start }.validate().is_err());
+        assert!(StartingResources { wood: MAX_STARTING_MATERIALS + 1, ..start }.validate().is_err());
+        assert_eq!(StartingResources { gold: MAX_STARTING_GOLD, ..start }.validate(), Ok(()));
+    }
+
+    #[test]
+    fn starting_resources_default_per_mode() {
+        let config: StartingResourcesConfig = toml::from_str("[endless]\ngold = 250\nlives = 5").unwrap();
+        assert_eq!(config.for_mode(false), StartingResources::default());
+        assert_eq!(config.for_mode(true), StartingResources { gold: 250, lives: 5, wood: 0, meat: 0 });
+    }
+}
@@ -15,6 +15,46 @@ pub const CASTLE_SIZE: Vec2 = Vec2::new(320.0, 256.0);
 pub const MAP_WIDTH: i32 = 30;
 pub const MAP_HEIGHT: i32 = 20;
 
+// Leak streaks: leaks within this many seconds of each other chain into a streak
+pub const LEAK_STREAK_WINDOW: f32 = 10.0;
+
 // Fog of war
 pub const EXPLORE_COST: i32 = 50;
 pub const EXPLORE_RADIUS: i32 = 4;
+
+// Undoing a tower placement refunds it in full within this many seconds
+// (matches the server's `UNDO_GRACE_MICROS`)
+pub const TOWER_UNDO_GRACE_SECS: f32 = 10.0;
+
+// Markets: build cost, and the gold each one pays out every interval
+pub const MARKET_COST: i32 = 150;
+pub const MARKET_GOLD_INTERVAL: f32 = 10.0;
+pub const MARKET_GOLD_AMOUNT: i32 = 8;
+
+// Tower veterancy: kills needed for each rank, and the bonus each rank adds
+pub const VETERANCY_THRESHOLDS: [u32; 3] = [10, 25, 50];
+pub const VETERANCY_DAMAGE_BONUS: f32 = 0.05;
+pub const VETERANCY_FIRE_RATE_BONUS: f32 = 0.05;
+
+// Burn: seconds between damage ticks
+pub const BURN_TICK_INTERVAL: f32 = 0.5;
+
+// Day/night cycle: seconds per full day, and the tower range lost at night
+// when the game rules enable night penalties
+pub const DAY_LENGTH: f32 = 300.0;
+pub const NIGHT_TOWER_RANGE_PENALTY: f32 = 0.15;
+
+// Weather: tower range lost in heavy fog when the game rules enable weather
+// penalties, and seconds between offline weather changes
+pub const FOG_TOWER_RANGE_PENALTY: f32 = 0.2;
+pub const OFFLINE_WEATHER_INTERVAL: f32 = 90.0;
+
+// Reconnecting: delay before the first attempt, doubled after each one up to the max
+pub const RECONNECT_DELAY: f32 = 1.0;
+pub const RECONNECT_MAX_DELAY: f32 = 15.0;
+// Longest the client waits on quit for the connection to close cleanly
+pub const DISCONNECT_TIMEOUT: f32 = 0.5;
+
+// Seconds the loading screen waits for sprites and the server before offering
+// to continue without them
+pub const LOADING_TIMEOUT: f32 = 20.0;
@@ -4,8 +4,8 @@ use std::path::Path;
 use std::sync::mpsc::{channel, Receiver};
 use std::sync::{Arc, Mutex};
 
-use crate::config::{TowersConfig, UnitsConfig, WavesConfig};
-use crate::resources::{TowerConfigs, WaveConfigs};
+use crate::config::{TowersConfig, UnitsConfig, WavesConfig, WAVES_DIR, WAVES_FILE};
+use crate::resources::{EndlessMode, TowerConfigs, WaveConfigs};
 
 /// Resource for file watching (hot-reloading)
 #[derive(Resource, Clone)]
@@ -32,9 +32,11 @@ impl Plugin for DebugPlugin {
         watcher
             .watch(Path::new("units.toml"), RecursiveMode::NonRecursive)
             .expect("Failed to watch units.toml");
+        // Waves come from the directory when it exists, like WavesConfig::load
+        let waves_path = if Path::new(WAVES_DIR).is_dir() { WAVES_DIR } else { WAVES_FILE };
         watcher
-            .watch(Path::new("waves.toml"), RecursiveMode::NonRecursive)
-            .expect("Failed to watch waves.toml");
+            .watch(Path::new(waves_path), RecursiveMode::NonRecursive)
+            .expect("Failed to watch waves");
         watcher
             .watch(Path::new("towers.toml"), RecursiveMode::NonRecursive)
             .expect("Failed to watch towers.toml");
@@ -52,6 +54,7 @@ pub fn watch_config_files(
     file_watcher: Res<FileWatcher>,
     mut wave_configs: ResMut<WaveConfigs>,
     mut tower_configs: ResMut<TowerConfigs>,
+    endless: Res<EndlessMode>,
 ) {
     // Check for file change events (non-blocking)
     let receiver = file_watcher.receiver.lock().unwrap();
@@ -73,7 +76,14 @@ pub fn watch_config_files(
                             }
                             Err(e) => error!("Failed to reload units.toml: {}", e),
                         }
-                    } else if path.ends_with("waves.toml") {
+                    } else if path.ends_with("waves.toml")
+                        || (path.parent().is_some_and(|dir| dir.ends_with(WAVES_DIR))
+                            && path.extension().is_some_and(|ext| ext == "toml"))
+                    {
+                        // Endless runs generate their waves instead
+                        if endless.enabled {
+                            continue;
+                        }
                         match WavesConfig::load() {
                             Ok(config) => {
                                 wave_configs.waves = config.waves;
@@ -88,6 +98,8 @@ pub fn watch_config_files(
                         match TowersConfig::load() {
                             Ok(config) => {
                                 tower_configs.towers = config.towers;
+                                tower_configs.move_cost = config.move_cost;
+                                tower_configs.wall_cost = config.wall_cost;
                                 info!(
                                     "Hot-reloaded towers.toml - {} towers loaded",
                                     tower_configs.towers.len()
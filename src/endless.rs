@@ -0,0 +1,182 @@
+//! Procedural waves for endless mode. Each wave spends a health budget that
+//! grows with the wave number on the units from `units.toml`, so there is
+//! always a next wave once the authored ones would have run out.
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::config::{UnitSpawn, UnitType, Wave, WaveModifier};
+
+/// Total enemy health of wave 1, before the per-wave growth
+const BASE_BUDGET: f32 = 400.0;
+/// Budget added per wave, as a share of `BASE_BUDGET`
+const BUDGET_GROWTH: f32 = 0.3;
+/// Health multiplier added per wave, so later enemies get tougher as well
+/// as more numerous
+const HEALTH_GROWTH: f32 = 0.05;
+/// Most unit types besides the filler a wave draws from
+const ROSTER_SIZE: usize = 3;
+/// Every this many waves is a boss wave, if any unit is a boss
+const BOSS_EVERY: i32 = 10;
+/// First wave that can roll a modifier, and the chance it does
+const FIRST_MODIFIER_WAVE: i32 = 6;
+const MODIFIER_CHANCE: f64 = 0.35;
+const START_SPAWN_INTERVAL: f32 = 2.0;
+const MIN_SPAWN_INTERVAL: f32 = 0.4;
+const SPAWN_INTERVAL_STEP: f32 = 0.05;
+
+/// Health budget of `wave_number`
+pub fn wave_budget(wave_number: i32) -> f32 {
+    BASE_BUDGET * (1.0 + BUDGET_GROWTH * (wave_number - 1).max(0) as f32)
+}
+
+/// Generate wave `wave_number` from `units`. A few random non-boss types
+/// form the wave's roster; units are bought from it, cheaper ones more
+/// often, until the budget runs out, and the cheapest unit overall fills
+/// what is left. Every `BOSS_EVERY`th wave leads with a boss.
+pub fn generate_wave(wave_number: i32, units: &[UnitType], rng: &mut impl Rng) -> Wave {
+    let health_multiplier = 1.0 + HEALTH_GROWTH * (wave_number - 1).max(0) as f32;
+    let cost = |unit: &UnitType| unit.base_health * health_multiplier;
+    let mut budget = wave_budget(wave_number);
+    let mut spawns: Vec<UnitSpawn> = Vec::new();
+    let buy = |unit: &UnitType, spawns: &mut Vec<UnitSpawn>| {
+        match spawns.iter_mut().find(|spawn| spawn.unit_id == unit.id) {
+            Some(spawn) => spawn.count += 1,
+            None => spawns.push(UnitSpawn {
+                unit_id: unit.id.clone(),
+                count: 1,
+                health_multiplier,
+                linked: false,
+            }),
+        }
+    };
+
+    let bosses: Vec<&UnitType> = units.iter().filter(|unit| unit.is_boss).collect();
+    if wave_number % BOSS_EVERY == 0
+        && let Some(boss) = bosses.choose(rng)
+    {
+        budget -= cost(boss);
+        buy(boss, &mut spawns);
+    }
+
+    let regulars: Vec<&UnitType> = units
+        .iter()
+        .filter(|unit| !unit.is_boss && unit.base_health > 0.0)
+        .collect();
+    let roster: Vec<&UnitType> = regulars.choose_multiple(rng, ROSTER_SIZE).copied().collect();
+    loop {
+        let affordable: Vec<&UnitType> = roster.iter().copied().filter(|unit| cost(unit) <= budget).collect();
+        let Ok(unit) = affordable.choose_weighted(rng, |unit| 1.0 / cost(unit)) else {
+            break;
+        };
+        budget -= cost(unit);
+        buy(unit, &mut spawns);
+    }
+    if let Some(filler) = regulars.iter().min_by(|a, b| a.base_health.total_cmp(&b.base_health)) {
+        let filler_count = (budget / cost(filler)) as i32;
+        // Never an empty wave, even on a budget too small for anything
+        for _ in 0..filler_count.max(i32::from(spawns.is_empty())) {
+            buy(filler, &mut spawns);
+        }
+    }
+
+    let modifiers = if wave_number >= FIRST_MODIFIER_WAVE && rng.gen_bool(MODIFIER_CHANCE) {
+        WaveModifier::ALL.choose(rng).copied().into_iter().collect()
+    } else {
+        Vec::new()
+    };
+
+    Wave {
+        wave_number,
+        spawn_interval: (START_SPAWN_INTERVAL - SPAWN_INTERVAL_STEP * (wave_number - 1) as f32)
+            .max(MIN_SPAWN_INTERVAL),
+        spawns,
+        bounty: false,
+        modifiers,
+        source: None,
+    }
+}
+
+/// Generate wave `wave_number` of the run started with `seed`. Each wave
+/// gets its own generator, so the same seed always yields the same waves
+/// in whatever order they are asked for.
+pub fn generate_seeded_wave(seed: u64, wave_number: i32, units: &[UnitType]) -> Wave {
+    let mut rng = StdRng::seed_from_u64(seed ^ (wave_number as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    generate_wave(wave_number, units, &mut rng)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::schedule_wave;
+
+    fn unit(id: &str, base_health: f32, is_boss: bool) -> UnitType {
+        UnitType {
+            id: id.to_string(),
+            name: id.to_string(),
+            base_health,
+            base_speed: 50.0,
+            damage_to_base: 1,
+            gold_reward: 5,
+            is_boss,
+            ..Default::default()
+        }
+    }
+
+    fn units() -> Vec<UnitType> {
+        vec![
+            unit("snake", 20.0, false),
+            unit("warrior", 50.0, false),
+            unit("lancer", 80.0, false),
+            unit("bear", 140.0, false),
+            unit("ogre", 400.0, true),
+        ]
+    }
+
+    fn total_health(wave: &Wave, units: &[UnitType]) -> f32 {
+        schedule_wave(wave, units).iter().map(|event| event.health).sum()
+    }
+
+    #[test]
+    fn later_waves_have_more_total_health() {
+        let units = units();
+        for seed in 0..20 {
+            let mut previous = 0.0;
+            for wave_number in 1..=40 {
+                let total = total_health(&generate_seeded_wave(seed, wave_number, &units), &units);
+                assert!(total > previous, "seed {seed}: wave {wave_number} has {total} HP, the one before {previous}");
+                previous = total;
+            }
+        }
+    }
+
+    #[test]
+    fn waves_spend_most_of_their_budget() {
+        let units = units();
+        for wave_number in [1, 7, 10, 25] {
+            let total = total_health(&generate_seeded_wave(3, wave_number, &units), &units);
+            let budget = wave_budget(wave_number);
+            // Less than one snake left over, at this wave's health multiplier
+            assert!(total > budget - 20.0 * 3.0, "wave {wave_number}: {total} of {budget}");
+            assert!(total <= budget + 0.01, "wave {wave_number}: {total} of {budget}");
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_wave() {
+        let units = units();
+        let first = generate_seeded_wave(42, 12, &units);
+        let second = generate_seeded_wave(42, 12, &units);
+        assert_eq!(schedule_wave(&first, &units), schedule_wave(&second, &units));
+        assert_eq!(first.modifiers, second.modifiers);
+    }
+
+    #[test]
+    fn boss_waves_lead_with_a_boss() {
+        let units = units();
+        let wave = generate_seeded_wave(7, 20, &units);
+        assert_eq!(wave.spawns[0].unit_id, "ogre");
+        assert!(!generate_seeded_wave(7, 19, &units).spawns.iter().any(|spawn| spawn.unit_id == "ogre"));
+    }
+}
@@ -1,15 +1,26 @@
 use bevy::prelude::*;
+use spacetimedb_sdk::Identity;
+
+use crate::systems::audio::SoundEffect;
 
 /// Event fired when an enemy is killed
 #[derive(Message)]
 pub struct EnemyKilled {
     pub gold_reward: i32,
+    /// Unit name, e.g. "Warrior"
+    pub enemy: String,
+    /// Type of the tower credited with the kill, if it still exists
+    pub tower_type_id: Option<String>,
+    /// Player who placed that tower; None for towers placed offline
+    pub tower_owner: Option<Identity>,
 }
 
 /// Event fired when an enemy reaches the end of the path
 #[derive(Message)]
 pub struct EnemyReachedEnd {
     pub damage: i32,
+    pub enemy: String,
+    pub gold_stolen: i32,
 }
 
 /// Event fired when a wave is complete
@@ -32,6 +43,22 @@ pub struct ProjectileFired {
     pub target: Entity,
 }
 
+/// Request to play a sound cue. With a position it is panned and faded by
+/// its distance from the camera; without one it plays at full volume.
+#[derive(Message)]
+pub struct PlaySound {
+    pub effect: SoundEffect,
+    pub position: Option<Vec2>,
+}
+
+/// Request to write a diagnostics snapshot for a bug report
+#[derive(Message)]
+pub struct DumpDiagnostics;
+
+/// Request to leave the running game for the main menu, staying connected
+#[derive(Message)]
+pub struct LeaveGame;
+
 /// Plugin that registers all game events
 pub struct EventPlugin;
 
@@ -43,7 +70,10 @@ impl Plugin for EventPlugin {
                 .add_event::<EnemyReachedEnd>()
                 .add_event::<WaveComplete>()
                 .add_event::<TowerPlaced>()
-                .add_event::<ProjectileFired>();
+                .add_event::<ProjectileFired>()
+                .add_event::<PlaySound>()
+                .add_event::<DumpDiagnostics>()
+                .add_event::<LeaveGame>();
         }
     }
 }
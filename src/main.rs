@@ -3,9 +3,12 @@ mod auth;
 #[cfg(feature = "bevy-demo")]
 mod debug;
 #[cfg(feature = "bevy-demo")]
+mod endless;
+#[cfg(feature = "bevy-demo")]
 mod module_bindings;
 
 mod bevy;
+mod combat;
 mod components;
 mod config;
 mod constants;
@@ -13,24 +16,30 @@ mod events;
 mod map;
 mod resources;
 mod systems;
+mod user_settings;
 use config::{TowersConfig, UnitsConfig, WavesConfig};
 
 use ::bevy::prelude::*;
 use bevy_spacetimedb::*;
+use module_bindings::game_status_table::GameStatusTableAccess;
+use module_bindings::player_state_table::PlayerStateTableAccess;
 use module_bindings::user_table::UserTableAccess;
+use module_bindings::wave_start_table::WaveStartTableAccess;
+use module_bindings::wave_vote_table::WaveVoteTableAccess;
 use module_bindings::{DbConnection, RemoteModule, RemoteTables};
 
 use auth::{
-    AuthConfig, AuthState, CallbackServerState, check_auth_and_connect, load_token_from_file,
+    AuthConfig, AuthState, CallbackServerState, check_auth_and_connect, is_well_formed_token, load_token_from_file,
     start_login,
 };
 use bevy::BevyPlugin;
 #[cfg(feature = "bevy-demo")]
 use debug::DebugPlugin;
 use events::EventPlugin;
-use map::{create_path_waypoints, create_blocked_tiles};
+use map::create_blocked_tiles;
 use resources::*;
 use systems::*;
+use user_settings::{Settings, SettingsPlugin};
 
 fn main() {
     let units = UnitsConfig::load()
@@ -39,9 +48,7 @@ fn main() {
     let waves = WavesConfig::load()
         .expect("Failed to load waves.toml")
         .waves;
-    let towers = TowersConfig::load()
-        .expect("Failed to load towers.toml")
-        .towers;
+    let towers = TowersConfig::load().expect("Failed to load towers.toml");
 
     let spawner = if let Some(first_wave) = waves.first() {
         EnemySpawner::from_wave_config(first_wave)
@@ -49,22 +56,55 @@ fn main() {
         panic!("No waves defined in waves.toml!");
     };
 
-    let stdb_uri =
-        std::env::var("SPACETIMEDB_URI").unwrap_or_else(|_| "http://127.0.0.1:3000".to_string());
-    let stdb_module = std::env::var("SPACETIMEDB_MODULE").unwrap_or_else(|_| "td-mmo".to_string());
+    // Server from the settings file; the environment still overrides it
+    let network = Settings::load().map(|settings| settings.network).unwrap_or_default();
+    let stdb_uri = std::env::var("SPACETIMEDB_URI").unwrap_or(network.host);
+    let stdb_module = std::env::var("SPACETIMEDB_MODULE").unwrap_or(network.module);
     let stdb_token = std::env::var("SPACETIMEDB_TOKEN")
         .ok()
+        .filter(|token| {
+            let valid = is_well_formed_token(token);
+            if !valid {
+                warn!("Ignoring SPACETIMEDB_TOKEN: it is not a well-formed token");
+            }
+            valid
+        })
         .or_else(load_token_from_file);
 
+    let blocked_tiles = {
+        let (blocked, castle) = create_blocked_tiles();
+        BlockedTiles { tiles: blocked, castle_tiles: castle, walls: Default::default() }
+    };
+    let path_points =
+        compute_enemy_path(&blocked_tiles, None).expect("No road path from spawn to castle");
+
     let initial_state = AppState::MainMenu;
 
     let mut app = App::new();
 
-    app.add_plugins(BevyPlugin)
+    app.add_plugins(SettingsPlugin)
+        .add_plugins(BevyPlugin)
         .add_plugins(EventPlugin)
         .add_plugins(MenuPlugin)
         .add_plugins(ColorSelectPlugin)
+        .add_plugins(LoadingPlugin)
+        .add_plugins(MissingTexturePlugin)
         .add_plugins(CursorPlugin)
+        .add_plugins(InputActionPlugin)
+        .add_plugins(SettingsMenuPlugin)
+        .add_plugins(PauseMenuPlugin)
+        .add_plugins(LeaveGamePlugin)
+        .add_plugins(PresetsPlugin)
+        .add_plugins(ConsolePlugin)
+        .add_plugins(ChatPlugin)
+        .add_plugins(WeatherPlugin)
+        .add_plugins(AchievementsPlugin)
+        .add_plugins(StatsPlugin)
+        .add_plugins(SoundPlugin)
+        .add_plugins(QualityPlugin)
+        .add_plugins(EffectsPlugin)
+        .add_plugins(FocusFirePlugin)
+        .add_plugins(DiagnosticsDumpPlugin)
         .add_plugins(WaveManagerPlugin);
 
     // Store connection config for deferred connection
@@ -72,6 +112,7 @@ fn main() {
         uri: stdb_uri.clone(),
         module: stdb_module.clone(),
         token: stdb_token.clone(),
+        fresh_identity: std::env::var("FRESH_IDENTITY").is_ok_and(|v| v == "1"),
     });
 
     let stdb_plugin = StdbPlugin::<DbConnection, RemoteModule>::default()
@@ -79,40 +120,61 @@ fn main() {
         .with_module_name(&stdb_module)
         .with_run_fn(DbConnection::run_threaded)
         .with_delayed_connect(true)
-        .add_table(|tables: &RemoteTables| tables.user());
+        .add_table(|tables: &RemoteTables| tables.user())
+        .add_table(|tables: &RemoteTables| tables.wave_vote())
+        .add_table(|tables: &RemoteTables| tables.wave_start())
+        .add_table(|tables: &RemoteTables| tables.player_state())
+        .add_table(|tables: &RemoteTables| tables.game_status());
 
     app.add_plugins(stdb_plugin);
 
     app.insert_state(initial_state)
         .init_resource::<GameState>()
+        .init_resource::<GameRules>()
+        .init_resource::<InfiniteResources>()
+        .init_resource::<EndlessMode>()
+        .init_resource::<EconomySetup>()
+        .init_resource::<LastStand>()
+        .init_resource::<ConnectionStatus>()
+        .init_resource::<TimeOfDay>()
+        .init_resource::<CombatLog>()
+        .init_resource::<Tutorial>()
         .init_resource::<AuthConfig>()
         .init_resource::<AuthState>()
         .insert_resource(WavesConfig::load().unwrap())
         .insert_resource(UnitsConfig::load().unwrap())
         .insert_resource(spawner)
         .insert_resource(PathWaypoints {
-            points: create_path_waypoints(),
+            points: path_points,
         })
         .insert_resource(WaveConfigs { units, waves })
-        .insert_resource(TowerConfigs { towers })
+        .insert_resource(TowerConfigs {
+            towers: towers.towers,
+            move_cost: towers.move_cost,
+            wall_cost: towers.wall_cost,
+        })
         .insert_resource(TowerWheelState {
             active: false,
             position: Vec2::ZERO,
+            tapped_open: false,
         })
         .insert_resource(FogOfWar::new())
         .init_resource::<RecruitMenuState>()
         .init_resource::<HouseMenuState>()
+        .init_resource::<WorkerAllocation>()
+        .init_resource::<RallyPoint>()
         .init_resource::<TowerUpgradeMenuState>()
-        .insert_resource({
-            let (blocked, castle) = create_blocked_tiles();
-            BlockedTiles { tiles: blocked, castle_tiles: castle }
-        })
+        .init_resource::<SelectedTowers>()
+        .init_resource::<TowerMoveState>()
+        .insert_resource(blocked_tiles)
         .add_systems(Startup, setup_camera)
         .add_systems(Update, (handle_login_request, check_auth_and_connect))
         .add_systems(OnEnter(AppState::ColorSelect), connect_to_spacetimedb)
+        // Last, so an exit requested anywhere in the frame is seen
+        .add_systems(Last, disconnect_on_exit)
         .add_systems(
             OnEnter(AppState::InGame),
-            (setup_game, setup_fog_of_war, setup_online_users_ui, setup_top_bar, setup_effectiveness_hint, setup_resource_gathering).chain(),
+            (apply_starting_resources, setup_game, setup_ambient_decorations, setup_fog_of_war, setup_online_users_ui, setup_top_bar, setup_effectiveness_hint, setup_resource_gathering, setup_reconnecting_banner, setup_combat_log, setup_tutorial, start_server_game).chain(),
         )
         .add_systems(
             Update,
@@ -124,6 +186,8 @@ fn main() {
                 on_user_updated,
                 on_user_deleted,
                 update_online_users_ui,
+                reconnect_to_spacetimedb,
+                update_reconnecting_banner,
             ),
         )
         .add_systems(
@@ -136,17 +200,17 @@ fn main() {
                 hide_tower_wheel_menu,
                 handle_tower_selection,
                 tower_shooting,
-                update_holy_tower_effects,
-                move_projectiles,
+                (update_holy_tower_effects, update_holy_beams),
+                ((move_projectiles, move_ballistic_projectiles), spawn_projectile_trails).chain(),
                 handle_projectile_hits,
                 update_health_bars,
-                update_top_bar,
+                (update_top_bar, anchor_below_top_bar),
                 show_recruit_menu,
                 hide_recruit_menu,
                 handle_recruit_selection,
                 show_tower_upgrade_menu,
                 hide_tower_upgrade_menu,
-                handle_tower_upgrade,
+                (handle_tower_upgrade, celebrate_tower_upgrades, update_upgrade_effects).chain(),
             )
                 .run_if(in_state(AppState::InGame)),
         )
@@ -156,6 +220,22 @@ fn main() {
                 cleanup_dead_enemies,
                 check_game_over,
                 update_fog_visibility,
+                update_hit_flash,
+                update_castle_health,
+                update_damage_vignette,
+                update_tower_cooldown_bars,
+                toggle_overlays,
+                update_ground_hazards,
+                update_trail_segments,
+                update_tower_wheel_tooltip,
+                update_placement_highlight,
+                box_select_towers,
+                update_selection_outlines.after(box_select_towers),
+                handle_move_tower_button,
+                place_moving_tower.before(handle_move_tower_button),
+                update_leak_streak,
+                update_leak_streak_warning,
+                undo_last_tower_placement,
             )
                 .run_if(in_state(AppState::InGame)),
         )
@@ -164,23 +244,61 @@ fn main() {
             (
                 spawn_workers,
                 worker_find_resource,
+                worker_danger.before(worker_movement),
                 worker_movement,
                 worker_arrive_check,
                 worker_harvest,
                 worker_sprite_update,
+                update_carry_labels.after(worker_sprite_update),
                 animate_worker_sprites,
                 show_house_menu,
                 hide_house_menu,
                 handle_build_worker,
+                handle_upgrade_carry,
+                handle_upgrade_house,
+                generate_gold,
+                (handle_worker_cap_buttons, enforce_worker_caps, update_worker_cap_texts).chain(),
+                (place_rally_point, handle_rally_buttons, sync_rally_flag).chain(),
             )
                 .run_if(in_state(AppState::InGame)),
         )
         .add_systems(
             Update,
-            (camera_zoom, camera_pan).run_if(in_state(AppState::InGame)),
+            (camera_zoom, camera_pan, apply_screen_shake.after(camera_pan))
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            Update,
+            (
+                update_last_stand,
+                pause_on_focus_loss,
+                retarget_enemies_on_path_change,
+                sync_gold_changes,
+                reconcile_gold.after(sync_gold_changes),
+                (sync_remote_towers, confirm_pending_towers, follow_tower_rows, sync_walls),
+                sync_lives.before(check_game_over),
+                update_tower_veterancy,
+                update_floating_text,
+                update_burns,
+                regenerate_enemies,
+                spread_linked_damage,
+                draw_enemy_links,
+                (apply_tower_auras, update_effective_tower_stats)
+                    .chain()
+                    .before(tower_shooting),
+                draw_tower_auras,
+                draw_range_overlap_warning,
+                update_time_of_day.before(apply_tower_auras),
+                (record_combat_events, toggle_combat_log, update_combat_log_panel).chain(),
+                (update_tutorial, handle_skip_tutorial_button),
+                (update_spawn_telegraphs, draw_spawn_telegraphs),
+            )
+                .run_if(in_state(AppState::InGame)),
         )
+        .add_systems(OnExit(AppState::InGame), reset_last_stand)
         // Game over screen
         .add_systems(OnEnter(AppState::GameOver), setup_game_over_screen)
+        .add_systems(Update, handle_game_over_buttons.run_if(in_state(AppState::GameOver)))
         .add_systems(OnExit(AppState::GameOver), cleanup_game_over_screen);
 
     // Add debug plugin for hot-reloading (only in bevy-demo feature)
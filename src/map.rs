@@ -19,47 +19,40 @@ pub fn world_to_tile(world_pos: Vec2) -> (i32, i32) {
     (tile_x, tile_y)
 }
 
-pub fn create_path_waypoints() -> Vec<Vec2> {
-    // Based on your tilemap, manually define the path waypoints
-    // Starting from left, following the road tiles
-    // Converting tile coordinates to world coordinates
-    let waypoints = vec![
-        (0, 10),  // Start left side, row 10
-        (5, 10),  // Move right
-        (5, 4),   // Turn up
-        (11, 4),  // Move right
-        (11, 16), // Move down
-        (17, 16), // Move right
-        (17, 4),  // Move up
-        (23, 4),  // Move right
-        (23, 11), // Move down
-        (26, 11), // End at castle (right side)
-    ];
+/// Corners of the road from the enemy spawn (first) to the castle (last)
+pub const PATH_CORNERS: [(i32, i32); 10] = [
+    (0, 10),  // Start left side, row 10
+    (5, 10),  // Move right
+    (5, 4),   // Turn up
+    (11, 4),  // Move right
+    (11, 16), // Move down
+    (17, 16), // Move right
+    (17, 4),  // Move up
+    (23, 4),  // Move right
+    (23, 11), // Move down
+    (26, 11), // End at castle (right side)
+];
 
-    // Convert tile coordinates to world positions
-    waypoints
-        .iter()
-        .map(|(x, y)| tile_to_world(*x, *y))
-        .collect()
+/// Tiles on the straight segments between `PATH_CORNERS`
+pub fn center_lane_tiles() -> HashSet<(i32, i32)> {
+    let mut tiles = HashSet::new();
+    for pair in PATH_CORNERS.windows(2) {
+        let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+        for x in x1.min(x2)..=x1.max(x2) {
+            for y in y1.min(y2)..=y1.max(y2) {
+                tiles.insert((x, y));
+            }
+        }
+    }
+    tiles
 }
 
 /// Create the set of blocked tiles (road path + castle)
 pub fn create_blocked_tiles() -> (HashSet<(i32, i32)>, HashSet<(i32, i32)>) {
     let mut road_tiles = HashSet::new();
 
-    // Road path segments (same waypoints as path, but we fill in all tiles between them)
-    let waypoints: Vec<(i32, i32)> = vec![
-        (0, 10),
-        (5, 10),
-        (5, 4),
-        (11, 4),
-        (11, 16),
-        (17, 16),
-        (17, 4),
-        (23, 4),
-        (23, 11),
-        (26, 11),
-    ];
+    // Road path segments (fill in all tiles between the path corners)
+    let waypoints = PATH_CORNERS;
 
     // Fill in road tiles between waypoints
     for i in 0..waypoints.len() - 1 {
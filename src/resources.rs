@@ -1,7 +1,9 @@
 use bevy::prelude::*;
 
-use crate::config::{TowerType, UnitType, Wave};
-use crate::constants::{MAP_HEIGHT, MAP_WIDTH};
+use crate::components::ResourceType;
+use crate::config::{StartingResources, TowerType, UnitType, Wave};
+use crate::constants::{LEAK_STREAK_WINDOW, MAP_HEIGHT, MAP_WIDTH};
+use crate::user_settings::ColorblindMode;
 
 /// SpacetimeDB connection configuration (for deferred connection)
 #[derive(Resource, Clone)]
@@ -9,31 +11,342 @@ pub struct StdbConfig {
     pub uri: String,
     pub module: String,
     pub token: Option<String>,
+    /// Connect without any saved token, so the server hands out a new
+    /// identity. Lets two clients on one machine play as different players.
+    pub fresh_identity: bool,
+}
+
+/// State of the live SpacetimeDB connection. Kept apart from `AuthState`:
+/// the session survives a dropped connection, only the link is retried.
+#[derive(Resource, Default, Clone, Copy, PartialEq, Debug)]
+pub enum ConnectionStatus {
+    #[default]
+    Offline,
+    Connected,
+    /// Connection dropped; the next attempt is made when `retry_in` runs out
+    Reconnecting { attempt: u32, retry_in: f32 },
 }
 
 #[derive(Resource)]
 pub struct GameState {
     pub lives: i32,
+    pub max_lives: i32,
+    /// Gold shown and spent locally. Online this is a prediction:
+    /// `confirmed_gold` plus the changes the server hasn't confirmed yet
     pub gold: i32,
     pub wood: i32,
     pub meat: i32,
     pub wave: i32,
     pub score: i32,
+    /// Gold income this game, after leak streak reductions
+    pub gold_earned: i32,
+    /// Enemies that reached the castle since the current wave started
+    pub leaks_this_wave: i32,
+    /// Consecutive leaks, each within `LEAK_STREAK_WINDOW` of the previous one
+    pub leak_streak: i32,
+    /// Time left before the current leak streak runs out
+    pub leak_streak_timer: f32,
+    /// Gold earned (+) or spent (-) locally and not yet sent to the server.
+    /// Tower placements are sent with `place_tower` instead.
+    pub unsynced_gold: Vec<i32>,
+    /// Gold changes sent to the server and waiting for confirmation, oldest first
+    pub in_flight_gold: Vec<InFlightGold>,
+    /// Last balance from the server: our `player_state`, or the shared pool
+    /// in `game_status` in a shared economy. None while offline
+    pub confirmed_gold: Option<i32>,
+    /// Online, the economy of the running game from `game_status`
+    pub economy_mode: EconomyMode,
+    /// Online the lives are shared and come from the server's `game_status`
+    pub lives_from_server: bool,
+    /// Enemies that reached the castle, not yet reported with `report_leak`
+    pub unsynced_leaks: Vec<Leak>,
+    /// Thieves that reached the castle, not yet reported with `report_theft`
+    pub unsynced_thefts: Vec<Theft>,
+}
+
+/// How gold is kept in an online game. Offline there is only one player,
+/// so both work the same.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EconomyMode {
+    /// Every player earns and spends their own gold
+    #[default]
+    Split,
+    /// Every player earns into and spends from one pool
+    Shared,
+}
+
+/// Economy picked in the main menu. Sent with `start_game`, which only
+/// uses it if this player starts a fresh game; players joining a running
+/// game follow its mode.
+#[derive(Resource, Default, Debug, Clone, Copy)]
+pub struct EconomySetup {
+    pub mode: EconomyMode,
+}
+
+/// An enemy that reached the castle. Every client simulates every enemy and
+/// reports the same leak, so the server takes lives once per `spawn`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Leak {
+    /// (wave number, spawn index) of the enemy, as in `SpawnId`
+    pub spawn: (i32, i32),
+    pub damage: i32,
+}
+
+/// A thief that reached the castle. The server takes the gold from its own
+/// balance, once per `spawn` from a shared pool.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theft {
+    /// (wave number, spawn index) of the enemy, as in `SpawnId`
+    pub spawn: (i32, i32),
+    pub amount: i32,
+}
+
+/// A gold change the server hasn't confirmed yet
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InFlightGold {
+    pub delta: i32,
+    /// Seconds since it was sent
+    pub age: f32,
 }
 
 impl Default for GameState {
     fn default() -> Self {
+        let start = StartingResources::default();
         Self {
-            lives: 20,
-            gold: 100,
-            wood: 0,
-            meat: 0,
+            lives: start.lives,
+            max_lives: start.lives,
+            gold: start.gold,
+            wood: start.wood,
+            meat: start.meat,
             wave: 1,
             score: 0,
+            gold_earned: 0,
+            leaks_this_wave: 0,
+            leak_streak: 0,
+            leak_streak_timer: 0.0,
+            unsynced_gold: Vec::new(),
+            in_flight_gold: Vec::new(),
+            confirmed_gold: None,
+            economy_mode: EconomyMode::default(),
+            lives_from_server: false,
+            unsynced_leaks: Vec::new(),
+            unsynced_thefts: Vec::new(),
+        }
+    }
+}
+
+impl GameState {
+    /// Give a new game its starting gold, lives and materials
+    pub fn start_with(&mut self, start: StartingResources) {
+        self.gold = start.gold;
+        self.lives = start.lives;
+        self.max_lives = start.lives;
+        self.wood = start.wood;
+        self.meat = start.meat;
+    }
+
+    /// Count an enemy reaching the castle; with `streaks` enabled it also
+    /// extends the leak streak that reduces gold income
+    pub fn register_leak(&mut self, streaks: bool) {
+        self.leaks_this_wave += 1;
+        if streaks {
+            self.leak_streak += 1;
+            self.leak_streak_timer = LEAK_STREAK_WINDOW;
+        }
+    }
+
+    /// Lose lives to an enemy reaching the castle. Shared lives are only
+    /// taken by the server, so online the leak is queued for `sync_lives`;
+    /// enemies not from the wave schedule (`spawn` None) can't be matched
+    /// across clients and cost nothing there.
+    pub fn lose_lives(&mut self, damage: i32, spawn: Option<(i32, i32)>) {
+        if !self.lives_from_server {
+            self.lives -= damage;
+        } else if let Some(spawn) = spawn {
+            self.unsynced_leaks.push(Leak { spawn, damage });
+        }
+    }
+
+    /// Lose up to `amount` gold to a thief reaching the castle, returning
+    /// how much it took, never more than we have. Online the server takes it
+    /// from its balance, so it's queued for `sync_gold_changes` rather than
+    /// predicted; thieves not from the wave schedule steal nothing there.
+    pub fn lose_gold_to_thief(&mut self, amount: i32, spawn: Option<(i32, i32)>) -> i32 {
+        let stolen = amount.min(self.gold).max(0);
+        if self.confirmed_gold.is_none() {
+            self.gold -= stolen;
+        } else if let Some(spawn) = spawn
+            && stolen > 0
+        {
+            self.unsynced_thefts.push(Theft { spawn, amount });
+        }
+        stolen
+    }
+
+    /// Gold income multiplier from the active leak streak
+    pub fn gold_income_multiplier(&self) -> f32 {
+        match self.leak_streak {
+            0 | 1 => 1.0,
+            2 => 0.75,
+            3 => 0.5,
+            _ => 0.25,
+        }
+    }
+
+    /// Add gold income, reduced while a leak streak is active
+    pub fn earn_gold(&mut self, amount: i32) {
+        let earned = (amount as f32 * self.gold_income_multiplier()).round() as i32;
+        self.gold += earned;
+        self.gold_earned += earned;
+        if earned > 0 {
+            self.unsynced_gold.push(earned);
+        }
+    }
+
+    /// Spend gold on something other than a tower placement
+    pub fn spend_gold(&mut self, cost: i32) {
+        self.gold -= cost;
+        self.unsynced_gold.push(-cost);
+    }
+
+    /// Record a gold change that was just sent to the server
+    pub fn expect_server_gold(&mut self, delta: i32) {
+        self.in_flight_gold.push(InFlightGold { delta, age: 0.0 });
+    }
+
+    /// Rebuild the predicted `gold` from the confirmed balance and every
+    /// change the server hasn't confirmed yet
+    pub fn reconcile_gold(&mut self) {
+        let Some(confirmed) = self.confirmed_gold else { return };
+        let in_flight: i32 = self.in_flight_gold.iter().map(|change| change.delta).sum();
+        let unsynced: i32 = self.unsynced_gold.iter().sum();
+        self.gold = confirmed + in_flight + unsynced;
+    }
+}
+
+/// Optional gameplay rules for the current match
+#[derive(Resource)]
+pub struct GameRules {
+    /// Consecutive leaks temporarily reduce gold income
+    pub leak_streak_penalties: bool,
+    /// Towers lose range at night; otherwise the cycle is only cosmetic
+    pub night_penalties: bool,
+    /// Heavy fog cuts tower range; otherwise weather is only cosmetic
+    pub weather_penalties: bool,
+    /// Enemies near workers kill them, and threatened workers flee home
+    pub worker_danger: bool,
+    /// Stockpile limits for gathered resources; deposits past them are lost
+    pub storage_caps: Option<StorageCaps>,
+}
+
+impl GameRules {
+    pub fn storage_cap(&self, resource: ResourceType) -> Option<i32> {
+        self.storage_caps.map(|caps| caps.cap(resource))
+    }
+}
+
+/// Most of each resource the stockpile holds when storage caps are on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StorageCaps {
+    pub wood: i32,
+    pub gold: i32,
+    pub meat: i32,
+}
+
+impl StorageCaps {
+    pub fn cap(&self, resource: ResourceType) -> i32 {
+        match resource {
+            ResourceType::Wood => self.wood,
+            ResourceType::Gold => self.gold,
+            ResourceType::Meat => self.meat,
+        }
+    }
+}
+
+impl Default for StorageCaps {
+    fn default() -> Self {
+        Self {
+            wood: 150,
+            gold: 1500,
+            meat: 150,
+        }
+    }
+}
+
+impl Default for GameRules {
+    fn default() -> Self {
+        Self {
+            leak_streak_penalties: true,
+            night_penalties: false,
+            weather_penalties: false,
+            worker_danger: false,
+            storage_caps: None,
         }
     }
 }
 
+/// Sandbox mode: building, upgrading and recruiting are free. Stockpiles are
+/// left untouched while it is on, so switching it off resumes from the same
+/// balances. Offline only; online the server charges for towers regardless.
+#[derive(Resource, Default, Debug, Clone, Copy, PartialEq)]
+pub struct InfiniteResources(pub bool);
+
+impl InfiniteResources {
+    /// Whether `stock` covers `cost`, which it always does in sandbox mode
+    pub fn affords(&self, stock: i32, cost: i32) -> bool {
+        self.0 || stock >= cost
+    }
+
+    /// What a purchase takes from the stockpile: nothing in sandbox mode
+    pub fn charge(&self, cost: i32) -> i32 {
+        if self.0 { 0 } else { cost }
+    }
+}
+
+/// Endless mode: waves come from `endless::generate_wave` instead of the
+/// authored ones, without end. `seed` is drawn when the run starts and
+/// fixes every wave of it.
+#[derive(Resource, Default, Debug, Clone, Copy)]
+pub struct EndlessMode {
+    pub enabled: bool,
+    pub seed: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WeatherKind {
+    #[default]
+    Clear,
+    Rain,
+    Fog,
+}
+
+/// Current weather. Online it follows the server's `weather` row.
+#[derive(Resource, Default)]
+pub struct Weather {
+    pub kind: WeatherKind,
+    /// Strength of the fog overlay, eased towards 1.0 while foggy
+    pub fog_density: f32,
+}
+
+/// Position in the day/night cycle. Online it follows the server's
+/// `game_clock` so co-op players share it.
+#[derive(Resource, Default)]
+pub struct TimeOfDay {
+    /// 0.0 is dawn, 0.5 dusk and 0.75 midnight; wraps at 1.0
+    pub phase: f32,
+}
+
+impl TimeOfDay {
+    /// 0.0 all day, rising to 1.0 at midnight
+    pub fn darkness(&self) -> f32 {
+        (-(self.phase * std::f32::consts::TAU).sin()).max(0.0)
+    }
+
+    pub fn is_night(&self) -> bool {
+        self.darkness() > 0.5
+    }
+}
+
 #[derive(States, Debug, Clone, Eq, PartialEq, Hash, Default)]
 pub enum AppState {
     /// Main Menu
@@ -41,6 +354,8 @@ pub enum AppState {
     MainMenu,
     /// Color and username selection screen
     ColorSelect,
+    /// Waiting for sprites and the server connection
+    Loading,
     /// Main game
     InGame,
     /// Game over screen
@@ -52,6 +367,8 @@ pub struct EnemySpawner {
     pub timer: Timer,
     pub enemies_this_wave: i32,
     pub enemies_spawned: i32,
+    /// The next enemy's spawn telegraph is already showing
+    pub telegraphed: bool,
 }
 
 impl EnemySpawner {
@@ -61,6 +378,7 @@ impl EnemySpawner {
             timer: Timer::from_seconds(wave.spawn_interval, TimerMode::Repeating),
             enemies_this_wave: total_enemies,
             enemies_spawned: 0,
+            telegraphed: false,
         }
     }
 }
@@ -79,12 +397,201 @@ pub struct WaveConfigs {
 #[derive(Resource)]
 pub struct TowerConfigs {
     pub towers: Vec<TowerType>,
+    /// Gold to move a placed tower, from `towers.toml`
+    pub move_cost: i32,
+    /// Gold to wall off a road tile, from `towers.toml`
+    pub wall_cost: i32,
+}
+
+/// Recent combat events, newest last, shown in the combat log panel
+#[derive(Resource, Default)]
+pub struct CombatLog {
+    pub lines: std::collections::VecDeque<String>,
+    pub visible: bool,
+}
+
+impl CombatLog {
+    pub fn push(&mut self, line: String) {
+        self.lines.push_back(line);
+        while self.lines.len() > COMBAT_LOG_CAPACITY {
+            self.lines.pop_front();
+        }
+    }
+}
+
+/// Lines kept in the combat log; older ones are dropped
+const COMBAT_LOG_CAPACITY: usize = 100;
+
+/// Steps of the first-run tutorial, in order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TutorialStep {
+    #[default]
+    OpenWheel,
+    Explore,
+    BuildWorker,
+    UpgradeTower,
+    Done,
+}
+
+impl TutorialStep {
+    pub fn next(self) -> Self {
+        match self {
+            TutorialStep::OpenWheel => TutorialStep::Explore,
+            TutorialStep::Explore => TutorialStep::BuildWorker,
+            TutorialStep::BuildWorker => TutorialStep::UpgradeTower,
+            TutorialStep::UpgradeTower | TutorialStep::Done => TutorialStep::Done,
+        }
+    }
+
+    pub fn instructions(self) -> &'static str {
+        match self {
+            TutorialStep::OpenWheel => {
+                "Left-click an explored tile to open the tower wheel. Right-click closes it again."
+            }
+            TutorialStep::Explore => {
+                "Fog hides the map. Open the wheel near the fog and pick Explore to reveal the area around it."
+            }
+            TutorialStep::BuildWorker => {
+                "Click your house and build a worker. Workers gather wood and meat for you."
+            }
+            TutorialStep::UpgradeTower => {
+                "Build a tower from the wheel, then click it and pick an upgrade."
+            }
+            TutorialStep::Done => "",
+        }
+    }
+}
+
+/// First-run tutorial. `progress` is the step's progress measure when it
+/// started; the step is done once the measure grows past it.
+#[derive(Resource, Default)]
+pub struct Tutorial {
+    pub step: TutorialStep,
+    pub progress: u32,
+}
+
+/// Set while the frame rate is too low for non-essential visuals; systems
+/// drawing those skip them while `reduced` is on
+#[derive(Resource, Default, PartialEq)]
+pub struct VisualQuality {
+    pub reduced: bool,
+}
+
+/// Colors that carry meaning (good/bad, full/empty), picked for the
+/// colorblind mode in the settings. Purely decorative colors stay put.
+#[derive(Resource, Clone, Copy, PartialEq, Debug)]
+pub struct Palette {
+    pub health_high: Color,
+    pub health_mid: Color,
+    pub health_low: Color,
+    /// Effectiveness bonus in the type hint
+    pub bonus: Color,
+    /// Effectiveness penalty in the type hint
+    pub penalty: Color,
+    pub placement_valid: Color,
+    pub placement_invalid: Color,
+}
+
+impl Palette {
+    pub fn new(mode: ColorblindMode) -> Self {
+        match mode {
+            ColorblindMode::Off => Self {
+                health_high: Color::srgb(0.0, 1.0, 0.0),
+                health_mid: Color::srgb(1.0, 1.0, 0.0),
+                health_low: Color::srgb(1.0, 0.0, 0.0),
+                bonus: Color::srgb(0.3, 1.0, 0.3),
+                penalty: Color::srgb(1.0, 0.4, 0.4),
+                placement_valid: Color::srgba(0.3, 1.0, 0.3, 0.35),
+                placement_invalid: Color::srgba(1.0, 0.2, 0.2, 0.35),
+            },
+            // Red and green look alike: blue against orange instead
+            ColorblindMode::Deuteranopia => Self {
+                health_high: Color::srgb(0.35, 0.7, 1.0),
+                health_mid: Color::srgb(0.95, 0.9, 0.25),
+                health_low: Color::srgb(0.85, 0.37, 0.0),
+                bonus: Color::srgb(0.45, 0.75, 1.0),
+                penalty: Color::srgb(0.95, 0.55, 0.2),
+                placement_valid: Color::srgba(0.35, 0.7, 1.0, 0.35),
+                placement_invalid: Color::srgba(0.85, 0.37, 0.0, 0.45),
+            },
+            // As above, but red also looks dark, so the bad end is a bright orange
+            ColorblindMode::Protanopia => Self {
+                health_high: Color::srgb(0.35, 0.7, 1.0),
+                health_mid: Color::srgb(0.95, 0.95, 0.6),
+                health_low: Color::srgb(1.0, 0.65, 0.0),
+                bonus: Color::srgb(0.45, 0.75, 1.0),
+                penalty: Color::srgb(1.0, 0.7, 0.2),
+                placement_valid: Color::srgba(0.35, 0.7, 1.0, 0.35),
+                placement_invalid: Color::srgba(1.0, 0.65, 0.0, 0.45),
+            },
+            // Blue and yellow look alike: cyan against red instead
+            ColorblindMode::Tritanopia => Self {
+                health_high: Color::srgb(0.0, 0.85, 0.85),
+                health_mid: Color::srgb(1.0, 0.6, 0.8),
+                health_low: Color::srgb(0.9, 0.1, 0.1),
+                bonus: Color::srgb(0.3, 0.9, 0.9),
+                penalty: Color::srgb(1.0, 0.35, 0.35),
+                placement_valid: Color::srgba(0.0, 0.85, 0.85, 0.35),
+                placement_invalid: Color::srgba(0.9, 0.1, 0.1, 0.4),
+            },
+        }
+    }
+
+    /// Health bar color for a fraction of health left
+    pub fn health(&self, fraction: f32) -> Color {
+        if fraction > 0.6 {
+            self.health_high
+        } else if fraction > 0.3 {
+            self.health_mid
+        } else {
+            self.health_low
+        }
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::new(ColorblindMode::Off)
+    }
+}
+
+/// Toggles for optional in-world overlays
+#[derive(Resource)]
+pub struct OverlaySettings {
+    pub show_cooldowns: bool,
+    /// Warn while placing a tower whose range mostly covers ground other
+    /// towers already cover
+    pub range_overlap_warning: bool,
+}
+
+impl Default for OverlaySettings {
+    fn default() -> Self {
+        Self {
+            show_cooldowns: true,
+            range_overlap_warning: true,
+        }
+    }
+}
+
+/// Tower being relocated after pressing "Move" in the upgrade menu
+#[derive(Resource, Default)]
+pub struct TowerMoveState {
+    pub moving: Option<Entity>,
+}
+
+/// Towers picked with box selection; group commands apply to all of them
+#[derive(Resource, Default)]
+pub struct SelectedTowers {
+    pub towers: std::collections::HashSet<Entity>,
 }
 
 #[derive(Resource)]
 pub struct TowerWheelState {
     pub active: bool,
     pub position: Vec2,
+    /// Opened by a tap without hover (touch / gamepad), so it stays open
+    /// until the next tap picks an option
+    pub tapped_open: bool,
 }
 
 #[derive(Resource)]
@@ -138,6 +645,50 @@ pub struct RecruitMenuState {
 #[derive(Resource, Default)]
 pub struct HouseMenuState {
     pub active: bool,
+    /// The next click places the rally point. The menu counts as active
+    /// meanwhile, which keeps the other menus from opening on that click.
+    pub placing_rally: bool,
+}
+
+/// Where newly built workers walk before looking for a resource
+#[derive(Resource, Default)]
+pub struct RallyPoint {
+    pub position: Option<Vec2>,
+}
+
+/// How many workers may gather each resource, set in the house menu.
+/// None means no limit.
+#[derive(Resource, Default)]
+pub struct WorkerAllocation {
+    pub wood: Option<i32>,
+    pub gold: Option<i32>,
+    pub meat: Option<i32>,
+}
+
+impl WorkerAllocation {
+    pub fn cap(&self, resource: ResourceType) -> Option<i32> {
+        match resource {
+            ResourceType::Wood => self.wood,
+            ResourceType::Gold => self.gold,
+            ResourceType::Meat => self.meat,
+        }
+    }
+
+    /// Change the cap of `resource` by `delta`. Lowering "no limit" starts
+    /// from `workers`; raising a cap past `workers` removes it.
+    pub fn adjust(&mut self, resource: ResourceType, delta: i32, workers: i32) {
+        let cap = match resource {
+            ResourceType::Wood => &mut self.wood,
+            ResourceType::Gold => &mut self.gold,
+            ResourceType::Meat => &mut self.meat,
+        };
+        let next = cap.unwrap_or(workers) + delta;
+        *cap = if (cap.is_none() && delta > 0) || next > workers {
+            None
+        } else {
+            Some(next.max(0))
+        };
+    }
 }
 
 #[derive(Resource, Default)]
@@ -153,6 +704,8 @@ pub struct BlockedTiles {
     pub tiles: std::collections::HashSet<(i32, i32)>,
     /// Castle tile coordinates
     pub castle_tiles: std::collections::HashSet<(i32, i32)>,
+    /// Road tiles walled off by players; enemies path around them
+    pub walls: std::collections::HashSet<(i32, i32)>,
 }
 
 impl BlockedTiles {
@@ -160,6 +713,7 @@ impl BlockedTiles {
         Self {
             tiles: std::collections::HashSet::new(),
             castle_tiles: std::collections::HashSet::new(),
+            walls: std::collections::HashSet::new(),
         }
     }
 
@@ -174,6 +728,10 @@ impl BlockedTiles {
     pub fn is_road(&self, tile_x: i32, tile_y: i32) -> bool {
         self.tiles.contains(&(tile_x, tile_y)) && !self.castle_tiles.contains(&(tile_x, tile_y))
     }
+
+    pub fn is_wall(&self, tile_x: i32, tile_y: i32) -> bool {
+        self.walls.contains(&(tile_x, tile_y))
+    }
 }
 
 impl Default for BlockedTiles {
@@ -181,3 +739,43 @@ impl Default for BlockedTiles {
         Self::new()
     }
 }
+
+/// Slow-motion moment when the castle is about to fall
+#[derive(Resource, Default)]
+pub struct LastStand {
+    /// Real seconds left in the current slow motion
+    pub remaining: f32,
+    /// Real seconds before it can trigger again
+    pub cooldown: f32,
+}
+
+/// Camera shake driven by a decaying trauma value (0.0 - 1.0)
+#[derive(Resource)]
+pub struct ScreenShake {
+    pub trauma: f32,
+    /// Multiplier on the shake offset (0.0 - 1.0)
+    pub intensity: f32,
+    /// Accessibility toggle; when false trauma is ignored
+    pub enabled: bool,
+    /// Offset applied to the camera last frame, removed before the next one
+    pub offset: Vec2,
+}
+
+impl ScreenShake {
+    pub fn add_trauma(&mut self, amount: f32) {
+        if self.enabled {
+            self.trauma = (self.trauma + amount).min(1.0);
+        }
+    }
+}
+
+impl Default for ScreenShake {
+    fn default() -> Self {
+        Self {
+            trauma: 0.0,
+            intensity: 1.0,
+            enabled: true,
+            offset: Vec2::ZERO,
+        }
+    }
+}
@@ -0,0 +1,370 @@
+//! Rough headless playthrough of a wave against a fixed tower layout, for
+//! checking balance from the config editors without starting the game.
+//!
+//! Deliberately simpler than the game: hits land instantly, towers always
+//! target the enemy furthest along the road, and support auras, burns,
+//! walls, upgrades and veterancy are ignored.
+
+use crate::combat::compute_damage;
+use crate::config::{schedule_wave, AttackType, DefenseType, EnemyModifiers, TowerType, UnitType, Wave};
+
+/// World pixels per map tile, as in the game
+const TILE: f32 = 32.0;
+const MAP_WIDTH: i32 = 30;
+const MAP_HEIGHT: i32 = 20;
+/// Road corners from spawn to castle; mirrors `map::PATH_CORNERS`, which
+/// can't be used here because the map module needs Bevy
+const PATH_CORNERS: [(i32, i32); 10] = [
+    (0, 10),
+    (5, 10),
+    (5, 4),
+    (11, 4),
+    (11, 16),
+    (17, 16),
+    (17, 4),
+    (23, 4),
+    (23, 11),
+    (26, 11),
+];
+const STARTING_LIVES: i32 = 20;
+const TIME_STEP: f32 = 0.05;
+/// Give up on enemies that somehow never finish
+const MAX_DURATION: f32 = 900.0;
+
+/// Outcome of `simulate_wave`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimResult {
+    pub towers: usize,
+    pub enemies: usize,
+    pub killed: usize,
+    pub leaked: usize,
+    pub lives_left: i32,
+    pub max_lives: i32,
+    pub gold_earned: i32,
+    /// Seconds until the last enemy died or reached the castle
+    pub duration: f32,
+}
+
+impl SimResult {
+    pub fn survived(&self) -> bool {
+        self.lives_left > 0
+    }
+}
+
+struct SimTower {
+    position: (f32, f32),
+    range: f32,
+    damage: f32,
+    fire_rate: f32,
+    attack: AttackType,
+    /// Hazard towers damage everything in range every second instead of shooting
+    area: bool,
+    cooldown: f32,
+}
+
+struct SimEnemy {
+    spawn_time: f32,
+    health: f32,
+    max_health: f32,
+    /// Health healed per second
+    regen: f32,
+    speed: f32,
+    /// Pixels travelled along the road
+    progress: f32,
+    defense: DefenseType,
+    immune_to: Option<AttackType>,
+    /// Share of incoming damage taken, from the wave's modifiers
+    damage_taken: f32,
+    damage_to_base: i32,
+    gold_reward: i32,
+    done: bool,
+}
+
+impl SimEnemy {
+    fn damage_from(&self, damage: f32, attack: AttackType) -> f32 {
+        compute_damage(damage, attack, self.defense, self.immune_to) * self.damage_taken
+    }
+}
+
+fn tile_center((x, y): (i32, i32)) -> (f32, f32) {
+    ((x as f32 + 0.5) * TILE, (y as f32 + 0.5) * TILE)
+}
+
+fn road_tiles() -> Vec<(i32, i32)> {
+    let mut tiles = Vec::new();
+    for pair in PATH_CORNERS.windows(2) {
+        let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+        for x in x1.min(x2)..=x1.max(x2) {
+            for y in y1.min(y2)..=y1.max(y2) {
+                if !tiles.contains(&(x, y)) {
+                    tiles.push((x, y));
+                }
+            }
+        }
+    }
+    tiles
+}
+
+/// Point `distance` pixels along the road, or `None` past the castle
+fn point_on_road(distance: f32) -> Option<(f32, f32)> {
+    let mut remaining = distance;
+    for pair in PATH_CORNERS.windows(2) {
+        let (ax, ay) = tile_center(pair[0]);
+        let (bx, by) = tile_center(pair[1]);
+        let length = ((bx - ax).powi(2) + (by - ay).powi(2)).sqrt();
+        if remaining <= length {
+            let t = remaining / length;
+            return Some((ax + (bx - ax) * t, ay + (by - ay) * t));
+        }
+        remaining -= length;
+    }
+    None
+}
+
+/// One tower of every attacking type, each on the free tile next to the road
+/// that covers the most road, at least two tiles from the others
+pub fn default_layout(tower_types: &[TowerType]) -> Vec<(TowerType, (f32, f32))> {
+    let road = road_tiles();
+    let mut candidates: Vec<((i32, i32), usize)> = Vec::new();
+    for x in 0..MAP_WIDTH {
+        for y in 0..MAP_HEIGHT {
+            if road.contains(&(x, y)) {
+                continue;
+            }
+            let next_to_road = road.iter().any(|(rx, ry)| (rx - x).abs() <= 1 && (ry - y).abs() <= 1);
+            if !next_to_road {
+                continue;
+            }
+            let coverage = road
+                .iter()
+                .filter(|(rx, ry)| (rx - x).pow(2) + (ry - y).pow(2) <= 9)
+                .count();
+            candidates.push(((x, y), coverage));
+        }
+    }
+    // Stable sort keeps ties in tile order, so the layout is deterministic
+    candidates.sort_by_key(|(_, coverage)| std::cmp::Reverse(*coverage));
+
+    let mut taken: Vec<(i32, i32)> = Vec::new();
+    let mut layout = Vec::new();
+    for tower_type in tower_types.iter().filter(|t| t.aura.is_none()) {
+        let Some((tile, _)) = candidates
+            .iter()
+            .find(|(tile, _)| taken.iter().all(|(tx, ty)| (tx - tile.0).abs().max((ty - tile.1).abs()) >= 2))
+        else {
+            break;
+        };
+        taken.push(*tile);
+        layout.push((tower_type.clone(), tile_center(*tile)));
+    }
+    layout
+}
+
+/// Play `wave` against towers at `layout`, with enemy health scaled by
+/// `difficulty`
+pub fn simulate_wave(
+    wave: &Wave,
+    units: &[UnitType],
+    layout: &[(TowerType, (f32, f32))],
+    difficulty: f32,
+) -> SimResult {
+    let mut towers: Vec<SimTower> = layout
+        .iter()
+        .map(|(tower_type, position)| SimTower {
+            position: *position,
+            range: tower_type.range,
+            damage: tower_type.damage,
+            fire_rate: tower_type.fire_rate,
+            attack: AttackType::from_str(&tower_type.attack_type),
+            area: tower_type.hazard.is_some(),
+            cooldown: 0.0,
+        })
+        .collect();
+
+    let modifiers = EnemyModifiers::from_wave(&wave.modifiers);
+    let mut enemies: Vec<SimEnemy> = schedule_wave(wave, units)
+        .into_iter()
+        .filter_map(|event| {
+            let unit = units.iter().find(|u| u.id == event.unit_id)?;
+            let health = event.health * difficulty;
+            Some(SimEnemy {
+                spawn_time: event.spawn_time,
+                health,
+                max_health: health,
+                regen: health * modifiers.regen_per_second,
+                speed: unit.base_speed * modifiers.speed_multiplier,
+                progress: 0.0,
+                defense: DefenseType::from_str(&unit.defense_type),
+                immune_to: unit.immune_to.as_deref().map(AttackType::from_str),
+                damage_taken: modifiers.damage_taken,
+                damage_to_base: unit.damage_to_base,
+                gold_reward: event.gold_reward,
+                done: false,
+            })
+        })
+        .collect();
+
+    let mut result = SimResult {
+        towers: towers.len(),
+        enemies: enemies.len(),
+        killed: 0,
+        leaked: 0,
+        lives_left: STARTING_LIVES,
+        max_lives: STARTING_LIVES,
+        gold_earned: 0,
+        duration: 0.0,
+    };
+
+    let mut time = 0.0;
+    while enemies.iter().any(|e| !e.done) && time < MAX_DURATION {
+        time += TIME_STEP;
+
+        // Move, and let whatever reaches the castle through
+        let mut positions: Vec<Option<(f32, f32)>> = Vec::with_capacity(enemies.len());
+        for enemy in enemies.iter_mut() {
+            if enemy.done || enemy.spawn_time > time {
+                positions.push(None);
+                continue;
+            }
+            enemy.progress += enemy.speed * TIME_STEP;
+            enemy.health = (enemy.health + enemy.regen * TIME_STEP).min(enemy.max_health);
+            match point_on_road(enemy.progress) {
+                Some(position) => positions.push(Some(position)),
+                None => {
+                    enemy.done = true;
+                    result.leaked += 1;
+                    result.lives_left -= enemy.damage_to_base;
+                    positions.push(None);
+                }
+            }
+        }
+
+        for tower in towers.iter_mut() {
+            let in_range = |position: &Option<(f32, f32)>| {
+                position.is_some_and(|(x, y)| {
+                    (x - tower.position.0).powi(2) + (y - tower.position.1).powi(2)
+                        <= tower.range * tower.range
+                })
+            };
+
+            if tower.area {
+                for (enemy, position) in enemies.iter_mut().zip(&positions) {
+                    if !enemy.done && in_range(position) {
+                        enemy.health -= enemy.damage_from(tower.damage, tower.attack) * TIME_STEP;
+                    }
+                }
+                continue;
+            }
+
+            tower.cooldown -= TIME_STEP;
+            if tower.cooldown > 0.0 {
+                continue;
+            }
+            let target = enemies
+                .iter_mut()
+                .zip(&positions)
+                .filter(|(enemy, position)| !enemy.done && enemy.health > 0.0 && in_range(position))
+                .max_by(|(a, _), (b, _)| a.progress.total_cmp(&b.progress));
+            if let Some((enemy, _)) = target {
+                enemy.health -= enemy.damage_from(tower.damage, tower.attack);
+                tower.cooldown = tower.fire_rate;
+            }
+        }
+
+        for enemy in enemies.iter_mut() {
+            if !enemy.done && enemy.spawn_time <= time && enemy.health <= 0.0 {
+                enemy.done = true;
+                result.killed += 1;
+                result.gold_earned += enemy.gold_reward;
+            }
+        }
+        result.duration = time;
+    }
+
+    result.lives_left = result.lives_left.max(0);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{UnitSpawn, WaveModifier};
+
+    fn unit(base_health: f32) -> UnitType {
+        UnitType {
+            id: "warrior".to_string(),
+            name: "Warrior".to_string(),
+            base_health,
+            base_speed: 100.0,
+            damage_to_base: 2,
+            gold_reward: 5,
+            ..Default::default()
+        }
+    }
+
+    fn tower(damage: f32) -> TowerType {
+        TowerType {
+            id: "catapult".to_string(),
+            name: "Catapult".to_string(),
+            cost: 100,
+            range: 400.0,
+            damage,
+            fire_rate: 0.5,
+            projectile_speed: 200.0,
+            attack_type: "blunt".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn wave(count: i32) -> Wave {
+        Wave {
+            wave_number: 1,
+            spawn_interval: 1.0,
+            spawns: vec![UnitSpawn {
+                unit_id: "warrior".to_string(),
+                count,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn without_towers_every_enemy_leaks() {
+        let result = simulate_wave(&wave(3), &[unit(100.0)], &[], 1.0);
+        assert_eq!(result.leaked, 3);
+        assert_eq!(result.killed, 0);
+        assert_eq!(result.lives_left, STARTING_LIVES - 6);
+        assert!(result.survived());
+    }
+
+    #[test]
+    fn strong_towers_kill_the_whole_wave() {
+        let layout = default_layout(&[tower(1000.0)]);
+        let result = simulate_wave(&wave(3), &[unit(100.0)], &layout, 1.0);
+        assert_eq!(result.killed, 3);
+        assert_eq!(result.leaked, 0);
+        assert_eq!(result.gold_earned, 15);
+        assert_eq!(result.lives_left, STARTING_LIVES);
+    }
+
+    #[test]
+    fn difficulty_scales_enemy_health() {
+        // 25 blunt damage vs armor is 31.25 a hit; 10 hits kill 300 HP but not 3000
+        let layout = default_layout(&[tower(25.0)]);
+        let easy = simulate_wave(&wave(1), &[unit(300.0)], &layout, 1.0);
+        let hard = simulate_wave(&wave(1), &[unit(300.0)], &layout, 10.0);
+        assert_eq!(easy.killed, 1);
+        assert_eq!(hard.leaked, 1);
+    }
+
+    #[test]
+    fn armored_waves_take_less_damage() {
+        // Enough hits to kill 500 HP, but not once each deals only 70%
+        let layout = default_layout(&[tower(25.0)]);
+        let mut armored = wave(1);
+        armored.modifiers.push(WaveModifier::Armored);
+        assert_eq!(simulate_wave(&wave(1), &[unit(500.0)], &layout, 1.0).killed, 1);
+        assert_eq!(simulate_wave(&armored, &[unit(500.0)], &layout, 1.0).leaked, 1);
+    }
+}
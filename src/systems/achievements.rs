@@ -0,0 +1,337 @@
+use bevy::prelude::*;
+
+use crate::components::{Enemy, GameUI};
+use crate::events::EnemyKilled;
+use crate::resources::{AppState, GameState};
+use crate::systems::input::{ActionState, InputAction};
+use crate::systems::top_bar::BelowTopBar;
+use crate::user_settings::Settings;
+
+/// Seconds an unlock toast stays on screen
+const TOAST_SECS: f32 = 4.0;
+
+/// What has to happen to unlock an achievement
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AchievementGoal {
+    /// Enemies killed over all games
+    Kills(u64),
+    /// Waves cleared in one game without losing
+    WavesSurvived(i32),
+    /// Gold held at once
+    Gold(i32),
+}
+
+pub struct Achievement {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    pub goal: AchievementGoal,
+}
+
+pub const ACHIEVEMENTS: &[Achievement] = &[
+    Achievement {
+        id: "first_blood",
+        name: "First Blood",
+        description: "Kill an enemy",
+        goal: AchievementGoal::Kills(1),
+    },
+    Achievement {
+        id: "slayer",
+        name: "Slayer",
+        description: "Kill 1000 enemies",
+        goal: AchievementGoal::Kills(1000),
+    },
+    Achievement {
+        id: "survivor",
+        name: "Survivor",
+        description: "Survive 10 waves in one game",
+        goal: AchievementGoal::WavesSurvived(10),
+    },
+    Achievement {
+        id: "veteran",
+        name: "Veteran",
+        description: "Survive 25 waves in one game",
+        goal: AchievementGoal::WavesSurvived(25),
+    },
+    Achievement {
+        id: "hoarder",
+        name: "Hoarder",
+        description: "Hold 5000 gold at once",
+        goal: AchievementGoal::Gold(5000),
+    },
+];
+
+/// Counters the achievements are checked against. Loaded from and written
+/// back to `Settings`; kills are only written on unlocks and when a game
+/// ends, so the settings file isn't rewritten on every kill.
+#[derive(Resource, Default)]
+pub struct AchievementProgress {
+    pub unlocked: Vec<String>,
+    pub total_kills: u64,
+    pub waves_survived: i32,
+    pub gold: i32,
+    pub panel_open: bool,
+}
+
+impl AchievementProgress {
+    pub fn is_unlocked(&self, achievement: &Achievement) -> bool {
+        self.unlocked.iter().any(|id| id == achievement.id)
+    }
+
+    /// (current, target) towards `goal`
+    pub fn progress(&self, goal: AchievementGoal) -> (u64, u64) {
+        match goal {
+            AchievementGoal::Kills(target) => (self.total_kills, target),
+            AchievementGoal::WavesSurvived(target) => {
+                (self.waves_survived.max(0) as u64, target.max(0) as u64)
+            }
+            AchievementGoal::Gold(target) => (self.gold.max(0) as u64, target.max(0) as u64),
+        }
+    }
+}
+
+/// "Achievement unlocked" notice that fades out
+#[derive(Component)]
+pub struct AchievementToast {
+    pub timer: Timer,
+}
+
+/// Root of the achievements list panel
+#[derive(Component)]
+pub struct AchievementsPanel;
+
+pub struct AchievementsPlugin;
+
+impl Plugin for AchievementsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AchievementProgress>()
+            .add_systems(Startup, load_achievement_progress)
+            .add_systems(
+                Update,
+                (
+                    check_achievements,
+                    update_achievement_toasts,
+                    toggle_achievements_panel,
+                    sync_achievements_panel,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(OnExit(AppState::InGame), save_achievement_progress);
+    }
+}
+
+fn load_achievement_progress(settings: Res<Settings>, mut progress: ResMut<AchievementProgress>) {
+    progress.unlocked = settings.achievements.unlocked.clone();
+    progress.total_kills = settings.achievements.total_kills;
+}
+
+/// Count kills, follow the game state and unlock whatever was reached
+fn check_achievements(
+    mut commands: Commands,
+    mut killed: MessageReader<EnemyKilled>,
+    game_state: Res<GameState>,
+    enemies: Query<(), With<Enemy>>,
+    mut progress: ResMut<AchievementProgress>,
+    mut settings: ResMut<Settings>,
+) {
+    let kills = killed.read().count() as u64;
+    if kills > 0 {
+        progress.total_kills += kills;
+    }
+    // `wave` moves on once a wave has finished spawning; it counts as
+    // survived when its last enemy is gone and the castle still stands
+    if game_state.lives > 0 && enemies.is_empty() {
+        let survived = game_state.wave - 1;
+        if survived > progress.waves_survived {
+            progress.waves_survived = survived;
+        }
+    }
+    if game_state.gold != progress.gold {
+        progress.gold = game_state.gold;
+    }
+
+    let mut unlocked_any = false;
+    for achievement in ACHIEVEMENTS {
+        if progress.is_unlocked(achievement) {
+            continue;
+        }
+        let (current, target) = progress.progress(achievement.goal);
+        if current < target {
+            continue;
+        }
+        info!("Achievement unlocked: {}", achievement.name);
+        progress.unlocked.push(achievement.id.to_string());
+        spawn_toast(&mut commands, achievement);
+        unlocked_any = true;
+    }
+
+    if unlocked_any {
+        settings.achievements.unlocked = progress.unlocked.clone();
+        settings.achievements.total_kills = progress.total_kills;
+    }
+}
+
+fn spawn_toast(commands: &mut Commands, achievement: &Achievement) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(140.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            Pickable::IGNORE,
+            BelowTopBar { offset: 98.0 },
+            AchievementToast {
+                timer: Timer::from_seconds(TOAST_SECS, TimerMode::Once),
+            },
+            GameUI,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(format!(
+                    "Achievement unlocked: {}\n{}",
+                    achievement.name, achievement.description
+                )),
+                TextFont {
+                    font_size: 20.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(1.0, 0.85, 0.3)),
+                TextLayout::new_with_justify(Justify::Center),
+                TextShadow::default(),
+                Node {
+                    padding: UiRect::all(Val::Px(10.0)),
+                    ..default()
+                },
+                BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.75)),
+                BorderRadius::all(Val::Px(6.0)),
+            ));
+        });
+}
+
+/// Fade toasts out over their last second, then remove them
+fn update_achievement_toasts(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut toasts: Query<(Entity, &mut AchievementToast, &Children)>,
+    mut texts: Query<(&mut TextColor, &mut BackgroundColor)>,
+) {
+    for (entity, mut toast, children) in toasts.iter_mut() {
+        toast.timer.tick(time.delta());
+        if toast.timer.is_finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let alpha = toast.timer.remaining_secs().min(1.0);
+        for child in children.iter() {
+            if let Ok((mut color, mut background)) = texts.get_mut(child) {
+                color.0.set_alpha(alpha);
+                background.0.set_alpha(0.75 * alpha);
+            }
+        }
+    }
+}
+
+fn toggle_achievements_panel(actions: Res<ActionState>, mut progress: ResMut<AchievementProgress>) {
+    if actions.just_pressed(InputAction::ToggleAchievements) {
+        progress.panel_open = !progress.panel_open;
+    }
+}
+
+/// Rebuild the list when it is opened or progress changes while it is open
+fn sync_achievements_panel(
+    mut commands: Commands,
+    progress: Res<AchievementProgress>,
+    panels: Query<Entity, With<AchievementsPanel>>,
+) {
+    if !progress.is_changed() {
+        return;
+    }
+    for entity in panels.iter() {
+        commands.entity(entity).despawn();
+    }
+    if !progress.panel_open {
+        return;
+    }
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(80.0),
+                left: Val::Px(10.0),
+                width: Val::Px(320.0),
+                max_width: Val::Percent(40.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.0),
+                padding: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.05, 0.05, 0.15, 0.9)),
+            BorderRadius::all(Val::Px(6.0)),
+            BelowTopBar { offset: 38.0 },
+            AchievementsPanel,
+            GameUI,
+        ))
+        .with_children(|panel| {
+            let unlocked = ACHIEVEMENTS.iter().filter(|a| progress.is_unlocked(a)).count();
+            panel.spawn((
+                Text::new(format!("Achievements ({}/{})", unlocked, ACHIEVEMENTS.len())),
+                TextFont {
+                    font_size: 20.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+
+            for achievement in ACHIEVEMENTS {
+                let (line, color) = if progress.is_unlocked(achievement) {
+                    (
+                        format!("[x] {} - {}", achievement.name, achievement.description),
+                        Color::srgb(1.0, 0.85, 0.3),
+                    )
+                } else {
+                    let (current, target) = progress.progress(achievement.goal);
+                    (
+                        format!(
+                            "[ ] {} - {} ({}/{})",
+                            achievement.name,
+                            achievement.description,
+                            current.min(target),
+                            target
+                        ),
+                        Color::srgb(0.7, 0.7, 0.7),
+                    )
+                };
+                panel.spawn((
+                    Text::new(line),
+                    TextFont {
+                        font_size: 14.0,
+                        ..default()
+                    },
+                    TextColor(color),
+                ));
+            }
+        });
+}
+
+/// Keep the lifetime kill count and close the panel when a game ends
+#[allow(clippy::type_complexity)]
+fn save_achievement_progress(
+    mut commands: Commands,
+    mut progress: ResMut<AchievementProgress>,
+    mut settings: ResMut<Settings>,
+    ui: Query<Entity, Or<(With<AchievementsPanel>, With<AchievementToast>)>>,
+) {
+    for entity in ui.iter() {
+        commands.entity(entity).despawn();
+    }
+    progress.panel_open = false;
+    progress.waves_survived = 0;
+    progress.gold = 0;
+    settings.achievements.unlocked = progress.unlocked.clone();
+    settings.achievements.total_kills = progress.total_kills;
+}
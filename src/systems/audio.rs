@@ -0,0 +1,254 @@
+use bevy::audio::{DefaultSpatialScale, SpatialScale, Volume};
+use bevy::prelude::*;
+use bevy::window::WindowFocused;
+use rand::Rng;
+
+use crate::events::{EnemyReachedEnd, PlaySound};
+use crate::resources::AppState;
+use crate::user_settings::Settings;
+
+/// Pixels from the camera within which positioned sounds play at full volume;
+/// further away they fade with the square of the distance
+const SPATIAL_FULL_VOLUME_DISTANCE: f32 = 200.0;
+/// Distance between the listener's ears in pixels. Wide, so sounds on one
+/// side of the screen are clearly panned to that side.
+const LISTENER_EAR_GAP: f32 = 400.0;
+/// Default seconds over which plays of the same sound are counted
+const SOUND_LIMIT_WINDOW: f32 = 0.1;
+/// Default plays of one sound allowed per window; the rest are dropped
+const SOUND_LIMIT_PER_WINDOW: usize = 2;
+
+/// Sound cues played by the game
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SoundEffect {
+    /// An enemy reached the castle and cost lives
+    BaseHit,
+    /// A tower fired a projectile
+    TowerFire,
+    /// A projectile hit an enemy
+    ProjectileHit,
+    /// A tower's upgrade went through
+    Upgrade,
+}
+
+impl SoundEffect {
+    pub const ALL: [SoundEffect; 4] = [
+        SoundEffect::BaseHit,
+        SoundEffect::TowerFire,
+        SoundEffect::ProjectileHit,
+        SoundEffect::Upgrade,
+    ];
+
+    pub fn path(&self) -> &'static str {
+        match self {
+            SoundEffect::BaseHit => "Audio/base_hit.ogg",
+            SoundEffect::TowerFire => "Audio/tower_fire.ogg",
+            SoundEffect::ProjectileHit => "Audio/projectile_hit.ogg",
+            SoundEffect::Upgrade => "Audio/upgrade.ogg",
+        }
+    }
+
+    /// (pitch, volume) variation: each play's speed differs by up to the
+    /// first either way and its volume drops by up to the second, so sounds
+    /// played over and over don't ring identically. Cues stay consistent.
+    pub fn variation(&self) -> (f32, f32) {
+        match self {
+            SoundEffect::BaseHit | SoundEffect::Upgrade => (0.0, 0.0),
+            SoundEffect::TowerFire => (0.08, 0.15),
+            SoundEffect::ProjectileHit => (0.12, 0.25),
+        }
+    }
+}
+
+/// Handles loaded once at startup so playing a cue never waits on the disk
+#[derive(Resource, Default)]
+pub struct SoundEffects {
+    handles: Vec<(SoundEffect, Handle<AudioSource>)>,
+    /// Window is in the background with `mute_on_focus_loss` on
+    muted_unfocused: bool,
+}
+
+impl SoundEffects {
+    fn get(&self, effect: SoundEffect) -> Option<Handle<AudioSource>> {
+        self.handles
+            .iter()
+            .find(|(loaded, _)| *loaded == effect)
+            .map(|(_, handle)| handle.clone())
+    }
+}
+
+/// Collapses bursts of the same sound, e.g. a dozen towers firing in one
+/// frame, into a few plays
+#[derive(Resource)]
+pub struct SoundLimiter {
+    /// Seconds over which plays of the same sound are counted
+    pub window: f32,
+    pub max_per_window: usize,
+    /// (sound, time played) within the last window
+    recent: Vec<(SoundEffect, f32)>,
+}
+
+impl Default for SoundLimiter {
+    fn default() -> Self {
+        Self {
+            window: SOUND_LIMIT_WINDOW,
+            max_per_window: SOUND_LIMIT_PER_WINDOW,
+            recent: Vec::new(),
+        }
+    }
+}
+
+impl SoundLimiter {
+    /// Whether `effect` may play at time `now`, recording it if so
+    pub fn allow(&mut self, effect: SoundEffect, now: f32) -> bool {
+        let window = self.window;
+        self.recent.retain(|(_, played)| now - played < window);
+        let playing = self.recent.iter().filter(|(recent, _)| *recent == effect).count();
+        if playing >= self.max_per_window {
+            return false;
+        }
+        self.recent.push((effect, now));
+        true
+    }
+}
+
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SoundEffects>()
+            .init_resource::<SoundLimiter>()
+            .insert_resource(DefaultSpatialScale(SpatialScale::new_2d(1.0 / SPATIAL_FULL_VOLUME_DISTANCE)))
+            .add_systems(Startup, load_sound_effects)
+            .add_systems(Update, (add_spatial_listener, mute_on_focus_loss))
+            .add_systems(
+                Update,
+                (play_base_hit_sound, play_sounds)
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+fn load_sound_effects(asset_server: Res<AssetServer>, mut sounds: ResMut<SoundEffects>) {
+    sounds.handles = SoundEffect::ALL
+        .iter()
+        .map(|effect| (*effect, asset_server.load(effect.path())))
+        .collect();
+}
+
+/// Positioned sounds are heard from the camera, so they follow it when it
+/// pans and zooms
+fn add_spatial_listener(mut commands: Commands, cameras: Query<Entity, (With<Camera2d>, Without<SpatialListener>)>) {
+    for camera in cameras.iter() {
+        commands.entity(camera).insert(SpatialListener::new(LISTENER_EAR_GAP));
+    }
+}
+
+/// Spawn a one-shot player for `effect` at the settings' effect volume.
+/// With a `position` and spatial audio enabled it is heard from there.
+pub fn play_sound(
+    commands: &mut Commands,
+    sounds: &SoundEffects,
+    settings: &Settings,
+    effect: SoundEffect,
+    position: Option<Vec2>,
+) {
+    let audio = &settings.audio;
+    if audio.muted || sounds.muted_unfocused {
+        return;
+    }
+    let Some(handle) = sounds.get(effect) else { return };
+    let (pitch_variation, volume_variation) = effect.variation();
+    let mut rng = rand::thread_rng();
+    let speed = 1.0 + rng.gen_range(-pitch_variation..=pitch_variation);
+    let volume = audio.master_volume * audio.sfx_volume * (1.0 - rng.gen_range(0.0..=volume_variation));
+    let playback = PlaybackSettings::DESPAWN
+        .with_volume(Volume::Linear(volume))
+        .with_speed(speed);
+    match position.filter(|_| audio.spatial) {
+        Some(position) => commands.spawn((
+            AudioPlayer::new(handle),
+            playback.with_spatial(true),
+            Transform::from_translation(position.extend(0.0)),
+        )),
+        None => commands.spawn((AudioPlayer::new(handle), playback)),
+    };
+}
+
+/// Silence what is playing when the window loses focus, and keep new sounds
+/// from starting until it is back
+fn mute_on_focus_loss(
+    mut focus: MessageReader<WindowFocused>,
+    settings: Res<Settings>,
+    mut sounds: ResMut<SoundEffects>,
+    mut sinks: Query<&mut AudioSink>,
+    mut spatial_sinks: Query<&mut SpatialAudioSink>,
+) {
+    let Some(focused) = focus.read().last().map(|event| event.focused) else { return };
+    let muted = !focused && settings.audio.mute_on_focus_loss;
+    if sounds.muted_unfocused == muted {
+        return;
+    }
+    sounds.muted_unfocused = muted;
+    for mut sink in sinks.iter_mut() {
+        if muted {
+            sink.mute();
+        } else {
+            sink.unmute();
+        }
+    }
+    for mut sink in spatial_sinks.iter_mut() {
+        if muted {
+            sink.mute();
+        } else {
+            sink.unmute();
+        }
+    }
+}
+
+fn play_sounds(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut requests: MessageReader<PlaySound>,
+    mut limiter: ResMut<SoundLimiter>,
+    sounds: Res<SoundEffects>,
+    settings: Res<Settings>,
+) {
+    let now = time.elapsed_secs();
+    for request in requests.read() {
+        if limiter.allow(request.effect, now) {
+            play_sound(&mut commands, &sounds, &settings, request.effect, request.position);
+        }
+    }
+}
+
+/// One hit per frame however many enemies leaked, so a crowd reaching the
+/// castle together doesn't stack into noise. It stays non-spatial: the
+/// castle may be off screen, and this is the cue for exactly that.
+fn play_base_hit_sound(mut reached_end: MessageReader<EnemyReachedEnd>, mut sounds: MessageWriter<PlaySound>) {
+    if reached_end.read().filter(|leak| leak.damage > 0).count() > 0 {
+        sounds.write(PlaySound {
+            effect: SoundEffect::BaseHit,
+            position: None,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bursts_of_one_sound_are_capped_per_window() {
+        let mut limiter = SoundLimiter::default();
+        let allowed = (0..10)
+            .filter(|_| limiter.allow(SoundEffect::TowerFire, 0.0))
+            .count();
+        assert_eq!(allowed, SOUND_LIMIT_PER_WINDOW);
+        // Other sounds have their own budget
+        assert!(limiter.allow(SoundEffect::ProjectileHit, 0.0));
+        // And the window frees up again
+        assert!(limiter.allow(SoundEffect::TowerFire, SOUND_LIMIT_WINDOW));
+    }
+}
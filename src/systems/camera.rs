@@ -1,57 +1,66 @@
-use bevy::input::mouse::MouseWheel;
 use bevy::prelude::*;
+use rand::Rng;
 
-pub fn camera_zoom(
-    mut scroll_events: MessageReader<MouseWheel>,
-    mut query: Query<&mut Transform, With<Camera2d>>,
-) {
-    for event in scroll_events.read() {
-        for mut transform in query.iter_mut() {
-            // Zoom in/out based on scroll direction
-            let zoom_delta = event.y * 0.1;
-
-            // Update camera scale (larger = zoomed in, smaller = zoomed out)
-            let new_scale = (transform.scale.x + zoom_delta).clamp(0.3, 3.0);
-            transform.scale = Vec3::splat(new_scale);
-        }
+use crate::resources::ScreenShake;
+use crate::systems::input::ActionState;
+
+/// Maximum camera offset in pixels at full trauma
+const MAX_SHAKE_OFFSET: f32 = 12.0;
+/// Trauma lost per second
+const SHAKE_DECAY: f32 = 1.5;
+
+pub fn camera_zoom(actions: Res<ActionState>, mut query: Query<&mut Transform, With<Camera2d>>) {
+    if actions.zoom == 0.0 {
+        return;
+    }
+    for mut transform in query.iter_mut() {
+        // Update camera scale (larger = zoomed out, smaller = zoomed in)
+        let new_scale = (transform.scale.x + actions.zoom).clamp(0.3, 3.0);
+        transform.scale = Vec3::splat(new_scale);
+    }
+}
+
+pub fn camera_pan(actions: Res<ActionState>, mut query: Query<&mut Transform, With<Camera2d>>) {
+    if actions.pan == Vec2::ZERO {
+        return;
+    }
+    for mut transform in query.iter_mut() {
+        // Move camera in opposite direction of the drag (inverted controls feel more natural)
+        // Scale movement by camera scale so panning speed feels consistent
+        transform.translation.x -= actions.pan.x * transform.scale.x;
+        transform.translation.y += actions.pan.y * transform.scale.y; // Y is inverted in screen space
     }
 }
 
-pub fn camera_pan(
-    mouse_button: Res<ButtonInput<MouseButton>>,
-    mut motion_events: MessageReader<CursorMoved>,
+/// Apply a random camera offset scaled by trauma, removing last frame's offset first
+/// so panning and zooming keep working on the unshaken position
+pub fn apply_screen_shake(
+    time: Res<Time>,
+    mut shake: ResMut<ScreenShake>,
     mut query: Query<&mut Transform, With<Camera2d>>,
-    mut last_pos: Local<Option<Vec2>>,
 ) {
-    // Check if right mouse button or middle mouse button is pressed
-    let is_dragging =
-        mouse_button.pressed(MouseButton::Right) || mouse_button.pressed(MouseButton::Middle);
-
-    if is_dragging {
-        for event in motion_events.read() {
-            if let Some(last) = *last_pos {
-                for mut transform in query.iter_mut() {
-                    // Calculate delta movement
-                    let delta = event.position - last;
-
-                    // Move camera in opposite direction (inverted controls feel more natural)
-                    // Scale movement by camera scale so panning speed feels consistent
-                    transform.translation.x -= delta.x * transform.scale.x;
-                    transform.translation.y += delta.y * transform.scale.y; // Y is inverted in screen space
-                }
-            }
-            *last_pos = Some(event.position);
-        }
-    } else {
-        // Reset last position when not dragging
-        if mouse_button.just_released(MouseButton::Right)
-            || mouse_button.just_released(MouseButton::Middle)
-        {
-            *last_pos = None;
-        }
-        // Update last position even when not dragging to prevent jumps
-        for event in motion_events.read() {
-            *last_pos = Some(event.position);
-        }
+    let Ok(mut transform) = query.single_mut() else {
+        return;
+    };
+
+    transform.translation.x -= shake.offset.x;
+    transform.translation.y -= shake.offset.y;
+    shake.offset = Vec2::ZERO;
+
+    if !shake.enabled || shake.trauma <= 0.0 {
+        shake.trauma = 0.0;
+        return;
     }
+
+    // Squared trauma gives a sharper falloff for small hits
+    let amount = shake.trauma * shake.trauma * shake.intensity * MAX_SHAKE_OFFSET;
+    let mut rng = rand::thread_rng();
+    let offset = Vec2::new(rng.gen_range(-1.0..=1.0), rng.gen_range(-1.0..=1.0)) * amount;
+
+    // Scale by zoom so the shake looks the same on screen at any zoom level
+    transform.translation.x += offset.x * transform.scale.x;
+    transform.translation.y += offset.y * transform.scale.y;
+    shake.offset = offset * transform.scale.truncate();
+
+    shake.trauma = (shake.trauma - SHAKE_DECAY * time.delta_secs()).max(0.0);
 }
@@ -0,0 +1,320 @@
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+use spacetimedb_sdk::{Identity, Table, Timestamp};
+
+use crate::components::GameUI;
+use crate::module_bindings::send_message_reducer::send_message;
+use crate::module_bindings::send_private_message_reducer::send_private_message;
+use crate::module_bindings::set_typing_reducer::set_typing;
+use crate::module_bindings::user_table::UserTableAccess;
+use crate::module_bindings::visible_message_table::VisibleMessageTableAccess;
+use crate::module_bindings::{Message, User};
+use crate::resources::AppState;
+use crate::systems::console::ConsoleState;
+use crate::systems::input::ActionState;
+use crate::systems::networking::SpacetimeDB;
+
+/// Messages shown in the chat log
+const MAX_CHAT_LINES: usize = 8;
+/// Local notices (errors, "not connected") kept below the messages
+const MAX_NOTICES: usize = 3;
+const MAX_INPUT_LEN: usize = 120;
+/// Seconds between `set_typing` refreshes while typing. The server shows the
+/// indicator for 5 seconds per call, so this keeps it up without a call per key.
+const TYPING_REFRESH_INTERVAL: f32 = 2.0;
+
+/// What a submitted chat line asks for
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChatInput {
+    Say(String),
+    Whisper { name: String, text: String },
+}
+
+impl ChatInput {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix("/w ") {
+            let Some((name, text)) = rest.trim().split_once(' ') else {
+                return Err("Usage: /w <name> <text>".to_string());
+            };
+            return Ok(Self::Whisper {
+                name: name.to_string(),
+                text: text.trim().to_string(),
+            });
+        }
+        if line.starts_with('/') {
+            return Err(format!("Unknown chat command '{}'", line));
+        }
+        Ok(Self::Say(line.to_string()))
+    }
+}
+
+/// Chat input opened with Enter
+#[derive(Resource, Default)]
+pub struct ChatState {
+    pub open: bool,
+    pub input: String,
+    /// Lines submitted with Enter, sent by `send_chat_messages`
+    pub submitted: Vec<String>,
+    /// Local feedback such as an unknown whisper recipient
+    pub notices: Vec<String>,
+}
+
+impl ChatState {
+    pub fn notice(&mut self, line: impl Into<String>) {
+        self.notices.push(line.into());
+        if self.notices.len() > MAX_NOTICES {
+            let excess = self.notices.len() - MAX_NOTICES;
+            self.notices.drain(..excess);
+        }
+    }
+}
+
+/// Text showing the chat log and input line
+#[derive(Component)]
+pub struct ChatText;
+
+pub struct ChatPlugin;
+
+impl Plugin for ChatPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ChatState>()
+            .add_systems(OnEnter(AppState::InGame), setup_chat)
+            .add_systems(
+                Update,
+                (type_chat_input, sync_typing, send_chat_messages, update_chat_text)
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(OnExit(AppState::InGame), close_chat);
+    }
+}
+
+fn setup_chat(mut commands: Commands) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(10.0),
+                bottom: Val::Px(200.0),
+                max_width: Val::Px(420.0),
+                ..default()
+            },
+            GameUI,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(""),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+                TextShadow::default(),
+                ChatText,
+            ));
+        });
+}
+
+/// Enter opens the input line and sends it; Escape closes it
+fn type_chat_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut keyboard_input: MessageReader<KeyboardInput>,
+    console: Res<ConsoleState>,
+    mut chat: ResMut<ChatState>,
+    mut actions: ResMut<ActionState>,
+) {
+    if !chat.open {
+        keyboard_input.clear();
+        if keyboard.just_pressed(KeyCode::Enter) && !console.open {
+            chat.open = true;
+            actions.text_entry = true;
+        }
+        return;
+    }
+    actions.text_entry = true;
+
+    for input in keyboard_input.read() {
+        if !input.state.is_pressed() {
+            continue;
+        }
+        match &input.logical_key {
+            Key::Escape => {
+                chat.open = false;
+                chat.input.clear();
+            }
+            Key::Enter => {
+                let line = std::mem::take(&mut chat.input);
+                if !line.trim().is_empty() {
+                    chat.submitted.push(line);
+                }
+                chat.open = false;
+            }
+            Key::Backspace => {
+                chat.input.pop();
+            }
+            Key::Space if chat.input.len() < MAX_INPUT_LEN => chat.input.push(' '),
+            Key::Character(text) => {
+                for c in text.chars() {
+                    if !c.is_control() && chat.input.len() < MAX_INPUT_LEN {
+                        chat.input.push(c);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Tell the server while a message is being composed, debounced to one call
+/// every `TYPING_REFRESH_INTERVAL`
+fn sync_typing(
+    chat: Res<ChatState>,
+    time: Res<Time>,
+    stdb: Option<SpacetimeDB>,
+    mut last_sent: Local<Option<f32>>,
+) {
+    let Some(stdb) = stdb else { return };
+    let typing = chat.open && !chat.input.trim().is_empty();
+    let now = time.elapsed_secs();
+
+    if typing {
+        let due = last_sent.is_none_or(|sent| now - sent >= TYPING_REFRESH_INTERVAL);
+        if due {
+            if let Err(e) = stdb.reducers().set_typing(true) {
+                warn!("Failed to send typing state: {}", e);
+            }
+            *last_sent = Some(now);
+        }
+    } else if last_sent.take().is_some() && chat.submitted.is_empty() {
+        // Sending a message clears the indicator on the server already
+        if let Err(e) = stdb.reducers().set_typing(false) {
+            warn!("Failed to send typing state: {}", e);
+        }
+    }
+}
+
+fn send_chat_messages(mut chat: ResMut<ChatState>, stdb: Option<SpacetimeDB>) {
+    if chat.submitted.is_empty() {
+        return;
+    }
+
+    for line in std::mem::take(&mut chat.submitted) {
+        let input = match ChatInput::parse(&line) {
+            Ok(input) => input,
+            Err(e) => {
+                chat.notice(e);
+                continue;
+            }
+        };
+        let Some(stdb) = stdb.as_ref() else {
+            chat.notice("Chat needs a server connection");
+            continue;
+        };
+
+        let sent = match input {
+            ChatInput::Say(text) => stdb.reducers().send_message(text),
+            ChatInput::Whisper { name, text } => {
+                let recipient = stdb.db().user().iter().find(|user| {
+                    user.name
+                        .as_deref()
+                        .is_some_and(|user_name| user_name.eq_ignore_ascii_case(&name))
+                });
+                let Some(recipient) = recipient else {
+                    chat.notice(format!("No player named '{}'", name));
+                    continue;
+                };
+                stdb.reducers().send_private_message(recipient.identity, text)
+            }
+        };
+        if let Err(e) = sent {
+            chat.notice(format!("Failed to send: {}", e));
+        }
+    }
+}
+
+/// Rebuild the log from `visible_message` when it or the chat state changes.
+/// Reading the table each time also drops messages the server pruned.
+fn update_chat_text(
+    chat: Res<ChatState>,
+    stdb: Option<SpacetimeDB>,
+    mut last_seen: Local<(u64, Option<Timestamp>, Vec<String>)>,
+    mut texts: Query<&mut Text, With<ChatText>>,
+) {
+    let mut messages: Vec<Message> = stdb
+        .as_ref()
+        .map(|stdb| stdb.db().visible_message().iter().collect())
+        .unwrap_or_default();
+    let users: Vec<User> = stdb
+        .as_ref()
+        .map(|stdb| stdb.db().user().iter().collect())
+        .unwrap_or_default();
+    let me = stdb.as_ref().and_then(|stdb| stdb.try_identity());
+
+    let now = Timestamp::now();
+    let typing: Vec<String> = users
+        .iter()
+        .filter(|user| user.online && Some(user.identity) != me)
+        .filter(|user| user.typing_until.is_some_and(|until| until > now))
+        .map(|user| user.name.clone().unwrap_or_else(|| "Anonymous".to_string()))
+        .collect();
+
+    let seen = (
+        messages.len() as u64,
+        messages.iter().map(|message| message.sent).max(),
+        typing,
+    );
+    if !chat.is_changed() && *last_seen == seen {
+        return;
+    }
+    *last_seen = seen;
+
+    messages.sort_by_key(|message| message.sent);
+
+    let skip = messages.len().saturating_sub(MAX_CHAT_LINES);
+    let mut lines: Vec<String> = messages
+        .iter()
+        .skip(skip)
+        .map(|message| chat_line(message, &users, me))
+        .collect();
+    lines.extend(chat.notices.iter().cloned());
+    match last_seen.2.as_slice() {
+        [] => {}
+        [name] => lines.push(format!("{} is typing...", name)),
+        names => lines.push(format!("{} are typing...", names.join(", "))),
+    }
+    if chat.open {
+        lines.push(format!("> {}|", chat.input));
+    }
+
+    let text = lines.join("\n");
+    for mut chat_text in texts.iter_mut() {
+        if chat_text.0 != text {
+            chat_text.0 = text.clone();
+        }
+    }
+}
+
+fn chat_line(message: &Message, users: &[User], me: Option<Identity>) -> String {
+    let name_of = |identity: Identity| {
+        users
+            .iter()
+            .find(|user| user.identity == identity)
+            .and_then(|user| user.name.clone())
+            .unwrap_or_else(|| "Anonymous".to_string())
+    };
+
+    match message.to {
+        None => format!("{}: {}", name_of(message.sender), message.text),
+        Some(recipient) if Some(message.sender) == me => {
+            format!("[to {}] {}", name_of(recipient), message.text)
+        }
+        Some(_) => format!("[from {}] {}", name_of(message.sender), message.text),
+    }
+}
+
+fn close_chat(mut chat: ResMut<ChatState>) {
+    chat.open = false;
+    chat.input.clear();
+    chat.submitted.clear();
+}
@@ -353,7 +353,7 @@ fn handle_continue_button(
                     }
                 }
             }
-            next_state.set(AppState::InGame);
+            next_state.set(AppState::Loading);
         }
     }
 }
@@ -0,0 +1,367 @@
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+
+use crate::components::Enemy;
+use crate::config::EnemyModifiers;
+use crate::events::DumpDiagnostics;
+use crate::resources::{AppState, EnemySpawner, GameState, InfiniteResources, PathWaypoints, WaveConfigs};
+use crate::systems::enemy::spawn_enemy;
+use crate::systems::input::ActionState;
+use crate::systems::networking::SpacetimeDB;
+use crate::systems::wave_manager_ui::WaveManager;
+
+/// Environment variable that enables cheat commands in release builds
+const ADMIN_ENV_VAR: &str = "TD_ADMIN";
+const MAX_HISTORY_LINES: usize = 12;
+const MAX_INPUT_LEN: usize = 64;
+/// Upper bound for `spawn`, so a typo can't freeze the game
+const MAX_SPAWN_COUNT: u32 = 200;
+
+/// Resource a `give` command adds to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GiveKind {
+    Gold,
+    Wood,
+    Meat,
+    Lives,
+}
+
+/// A parsed console command
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConsoleCommand {
+    Help,
+    Clear,
+    Give { kind: GiveKind, amount: i32 },
+    Spawn { unit_id: String, count: u32 },
+    SetWave(i32),
+    KillAll,
+    /// Toggle sandbox mode, where everything is free
+    Infinite,
+    Diagnostics,
+}
+
+const HELP_TEXT: &str = "Commands: help, clear, give <gold|wood|meat|lives> <amount>, \
+                         spawn <unit> [count], set wave <n>, kill all, infinite, diagnostics \
+                         (all but help, clear and diagnostics work offline only)";
+
+impl ConsoleCommand {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let words: Vec<String> = line.split_whitespace().map(str::to_lowercase).collect();
+        let words: Vec<&str> = words.iter().map(String::as_str).collect();
+
+        match words.as_slice() {
+            ["help"] => Ok(Self::Help),
+            ["clear"] => Ok(Self::Clear),
+            ["give", kind, amount] => {
+                let kind = match *kind {
+                    "gold" => GiveKind::Gold,
+                    "wood" => GiveKind::Wood,
+                    "meat" => GiveKind::Meat,
+                    "lives" => GiveKind::Lives,
+                    other => return Err(format!("Unknown resource '{}'", other)),
+                };
+                let amount = amount
+                    .parse()
+                    .map_err(|_| format!("'{}' is not a number", amount))?;
+                Ok(Self::Give { kind, amount })
+            }
+            ["spawn", unit_id] => Ok(Self::Spawn {
+                unit_id: unit_id.to_string(),
+                count: 1,
+            }),
+            ["spawn", unit_id, count] => {
+                let count: u32 = count
+                    .parse()
+                    .map_err(|_| format!("'{}' is not a number", count))?;
+                Ok(Self::Spawn {
+                    unit_id: unit_id.to_string(),
+                    count: count.min(MAX_SPAWN_COUNT),
+                })
+            }
+            ["set", "wave", wave] => {
+                let wave = wave
+                    .parse()
+                    .map_err(|_| format!("'{}' is not a number", wave))?;
+                Ok(Self::SetWave(wave))
+            }
+            ["kill", "all"] => Ok(Self::KillAll),
+            ["infinite"] => Ok(Self::Infinite),
+            ["diagnostics"] => Ok(Self::Diagnostics),
+            [] => Err("Empty command".to_string()),
+            _ => Err(format!("Unknown command '{}', try 'help'", line.trim())),
+        }
+    }
+
+    /// Commands that change the game need the admin flag
+    pub fn requires_admin(&self) -> bool {
+        !matches!(self, Self::Help | Self::Clear | Self::Diagnostics)
+    }
+}
+
+/// Debug console toggled with the backtick key
+#[derive(Resource, Default)]
+pub struct ConsoleState {
+    pub open: bool,
+    pub input: String,
+    pub history: Vec<String>,
+    /// Lines submitted with Enter, run by `run_console_commands`
+    pub submitted: Vec<String>,
+    /// Allows cheat commands; on in debug builds or with `TD_ADMIN=1`
+    pub admin: bool,
+}
+
+impl ConsoleState {
+    pub fn print(&mut self, line: impl Into<String>) {
+        self.history.push(line.into());
+        if self.history.len() > MAX_HISTORY_LINES {
+            let excess = self.history.len() - MAX_HISTORY_LINES;
+            self.history.drain(..excess);
+        }
+    }
+}
+
+/// Marker for the console panel root
+#[derive(Component)]
+pub struct ConsolePanel;
+
+/// Text showing the console history and input line
+#[derive(Component)]
+pub struct ConsoleText;
+
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        let admin = cfg!(debug_assertions) || std::env::var(ADMIN_ENV_VAR).is_ok_and(|v| v == "1");
+
+        app.insert_resource(ConsoleState {
+            admin,
+            ..default()
+        })
+        .add_systems(
+            Update,
+            (
+                type_console_input,
+                run_console_commands,
+                sync_console_panel,
+                update_console_text,
+            )
+                .chain()
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(OnExit(AppState::InGame), close_console);
+    }
+}
+
+/// Toggle with backtick; while open, typed characters go to the input line
+fn type_console_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut keyboard_input: MessageReader<KeyboardInput>,
+    mut console: ResMut<ConsoleState>,
+    mut actions: ResMut<ActionState>,
+) {
+    if keyboard.just_pressed(KeyCode::Backquote) {
+        console.open = !console.open;
+    }
+    if !console.open {
+        keyboard_input.clear();
+        return;
+    }
+    actions.text_entry = true;
+
+    for input in keyboard_input.read() {
+        if !input.state.is_pressed() || input.key_code == KeyCode::Backquote {
+            continue;
+        }
+        match &input.logical_key {
+            Key::Escape => console.open = false,
+            Key::Enter => {
+                let line = std::mem::take(&mut console.input);
+                if !line.trim().is_empty() {
+                    console.submitted.push(line);
+                }
+            }
+            Key::Backspace => {
+                console.input.pop();
+            }
+            Key::Space if console.input.len() < MAX_INPUT_LEN => console.input.push(' '),
+            Key::Character(text) => {
+                for c in text.chars() {
+                    if !c.is_control() && console.input.len() < MAX_INPUT_LEN {
+                        console.input.push(c);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_console_commands(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
+    mut console: ResMut<ConsoleState>,
+    mut game_state: ResMut<GameState>,
+    mut spawner: ResMut<EnemySpawner>,
+    mut wave_manager: ResMut<WaveManager>,
+    wave_configs: Res<WaveConfigs>,
+    waypoints: Res<PathWaypoints>,
+    mut enemies: Query<&mut Enemy>,
+    mut dump: MessageWriter<DumpDiagnostics>,
+    mut infinite: ResMut<InfiniteResources>,
+    stdb: Option<SpacetimeDB>,
+) {
+    if console.submitted.is_empty() {
+        return;
+    }
+
+    for line in std::mem::take(&mut console.submitted) {
+        console.print(format!("> {}", line));
+
+        let command = match ConsoleCommand::parse(&line) {
+            Ok(command) => command,
+            Err(e) => {
+                console.print(e);
+                continue;
+            }
+        };
+        if command.requires_admin() && !console.admin {
+            console.print(format!("Admin only (set {}=1)", ADMIN_ENV_VAR));
+            continue;
+        }
+        // Every client runs the same game, so changing only ours would put it
+        // out of step with the other players'
+        if command.requires_admin() && stdb.is_some() {
+            console.print("Cheats only work offline");
+            continue;
+        }
+
+        match command {
+            ConsoleCommand::Help => console.print(HELP_TEXT),
+            ConsoleCommand::Clear => console.history.clear(),
+            ConsoleCommand::Give { kind, amount } => {
+                let value = match kind {
+                    GiveKind::Gold => &mut game_state.gold,
+                    GiveKind::Wood => &mut game_state.wood,
+                    GiveKind::Meat => &mut game_state.meat,
+                    GiveKind::Lives => &mut game_state.lives,
+                };
+                *value += amount;
+                console.print(format!("Gave {} {:?}", amount, kind));
+            }
+            ConsoleCommand::Spawn { unit_id, count } => {
+                let Some(unit_type) = wave_configs.units.iter().find(|u| u.id == unit_id) else {
+                    console.print(format!("Unknown unit '{}'", unit_id));
+                    continue;
+                };
+                let start_pos = waypoints.points.first().copied().unwrap_or(Vec2::ZERO);
+                for _ in 0..count {
+                    spawn_enemy(&mut commands, &asset_server, &mut texture_atlases, unit_type, unit_type.base_health, unit_type.gold_reward, EnemyModifiers::default(), start_pos);
+                }
+                console.print(format!("Spawned {} {}", count, unit_type.name));
+            }
+            ConsoleCommand::SetWave(wave) => {
+                let Some(config) = usize::try_from(wave - 1).ok().and_then(|i| wave_configs.waves.get(i)) else {
+                    console.print(format!("Wave must be 1-{}", wave_configs.waves.len()));
+                    continue;
+                };
+                game_state.wave = wave;
+                *spawner = EnemySpawner::from_wave_config(config);
+                wave_manager.wave_active = false;
+                wave_manager.current_prep_time = wave_manager.preparation_time;
+                console.print(format!("Wave set to {}", wave));
+            }
+            ConsoleCommand::KillAll => {
+                // No gold: kill rewards are only paid by towers and hazards
+                let mut killed = 0;
+                for mut enemy in enemies.iter_mut() {
+                    if enemy.health > 0.0 {
+                        enemy.health = 0.0;
+                        killed += 1;
+                    }
+                }
+                console.print(format!("Killed {} enemies", killed));
+            }
+            ConsoleCommand::Infinite => {
+                infinite.0 = !infinite.0;
+                console.print(if infinite.0 { "Infinite resources on" } else { "Infinite resources off" });
+            }
+            ConsoleCommand::Diagnostics => {
+                dump.write(DumpDiagnostics);
+            }
+        }
+    }
+}
+
+fn sync_console_panel(
+    mut commands: Commands,
+    console: Res<ConsoleState>,
+    panels: Query<Entity, With<ConsolePanel>>,
+) {
+    if panels.is_empty() != console.open {
+        return;
+    }
+
+    for entity in panels.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    if console.open {
+        commands
+            .spawn((
+                Node {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(0.0),
+                    bottom: Val::Px(0.0),
+                    width: Val::Percent(100.0),
+                    padding: UiRect::all(Val::Px(8.0)),
+                    ..default()
+                },
+                BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.8)),
+                Interaction::default(),
+                FocusPolicy::Block,
+                GlobalZIndex(200),
+                ConsolePanel,
+            ))
+            .with_children(|panel| {
+                panel.spawn((
+                    Text::new(console_text(&console)),
+                    TextFont {
+                        font_size: 14.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgb(0.8, 1.0, 0.8)),
+                    ConsoleText,
+                ));
+            });
+    }
+}
+
+fn update_console_text(console: Res<ConsoleState>, mut texts: Query<&mut Text, With<ConsoleText>>) {
+    if !console.is_changed() {
+        return;
+    }
+    for mut text in texts.iter_mut() {
+        text.0 = console_text(&console);
+    }
+}
+
+fn console_text(console: &ConsoleState) -> String {
+    let mut text = console.history.join("\n");
+    if !text.is_empty() {
+        text.push('\n');
+    }
+    text.push_str(&format!("> {}|", console.input));
+    text
+}
+
+fn close_console(mut commands: Commands, mut console: ResMut<ConsoleState>, panels: Query<Entity, With<ConsolePanel>>) {
+    console.open = false;
+    console.input.clear();
+    for entity in panels.iter() {
+        commands.entity(entity).despawn();
+    }
+}
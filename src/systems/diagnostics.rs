@@ -0,0 +1,132 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin};
+use bevy::prelude::*;
+use serde_json::json;
+
+use crate::components::{Enemy, Projectile, Tower};
+use crate::events::DumpDiagnostics;
+use crate::resources::{AppState, CombatLog, ConnectionStatus, GameState, StdbConfig, TowerConfigs, WaveConfigs};
+use crate::systems::console::ConsoleState;
+use crate::systems::input::{ActionState, InputAction};
+use crate::user_settings::config_file_path;
+
+/// Combat log lines included in a snapshot, newest last
+const SNAPSHOT_LOG_LINES: usize = 30;
+
+pub struct DiagnosticsDumpPlugin;
+
+impl Plugin for DiagnosticsDumpPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (request_diagnostics_dump, write_diagnostics_dump)
+                .chain()
+                .run_if(in_state(AppState::InGame)),
+        );
+    }
+}
+
+fn request_diagnostics_dump(actions: Res<ActionState>, mut dump: MessageWriter<DumpDiagnostics>) {
+    if actions.just_pressed(InputAction::DumpDiagnostics) {
+        dump.write(DumpDiagnostics);
+    }
+}
+
+/// Write a JSON snapshot of the game next to the settings file, for
+/// attaching to bug reports. The auth token is never included.
+#[allow(clippy::too_many_arguments)]
+fn write_diagnostics_dump(
+    mut requests: MessageReader<DumpDiagnostics>,
+    mut console: ResMut<ConsoleState>,
+    game_state: Res<GameState>,
+    connection: Res<ConnectionStatus>,
+    stdb_config: Res<StdbConfig>,
+    combat_log: Res<CombatLog>,
+    diagnostics: Res<DiagnosticsStore>,
+    tower_configs: Res<TowerConfigs>,
+    wave_configs: Res<WaveConfigs>,
+    entities: Query<(Has<Enemy>, Has<Tower>, Has<Projectile>)>,
+) {
+    if requests.read().count() == 0 {
+        return;
+    }
+
+    let written_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+    let fps = diagnostics.get(&FrameTimeDiagnosticsPlugin::FPS);
+    let frame_time = diagnostics.get(&FrameTimeDiagnosticsPlugin::FRAME_TIME);
+    let (mut total, mut enemies, mut towers, mut projectiles) = (0, 0, 0, 0);
+    for (enemy, tower, projectile) in entities.iter() {
+        total += 1;
+        enemies += enemy as usize;
+        towers += tower as usize;
+        projectiles += projectile as usize;
+    }
+    let log_start = combat_log.lines.len().saturating_sub(SNAPSHOT_LOG_LINES);
+
+    let snapshot = json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "written_at_unix": written_at,
+        "game_state": {
+            "lives": game_state.lives,
+            "max_lives": game_state.max_lives,
+            "gold": game_state.gold,
+            "wood": game_state.wood,
+            "meat": game_state.meat,
+            "wave": game_state.wave,
+            "score": game_state.score,
+            "gold_earned": game_state.gold_earned,
+            "leaks_this_wave": game_state.leaks_this_wave,
+            "leak_streak": game_state.leak_streak,
+            "confirmed_gold": game_state.confirmed_gold,
+            "unsynced_gold_changes": game_state.unsynced_gold.len(),
+            "in_flight_gold_changes": game_state.in_flight_gold.len(),
+            "unsynced_leaks": game_state.unsynced_leaks.len(),
+            "lives_from_server": game_state.lives_from_server,
+        },
+        "entities": {
+            "total": total,
+            "enemies": enemies,
+            "towers": towers,
+            "projectiles": projectiles,
+        },
+        "connection": {
+            "status": format!("{:?}", *connection),
+            "uri": stdb_config.uri,
+            "module": stdb_config.module,
+            "token": stdb_config.token.as_ref().map(|_| "<redacted>"),
+            "fresh_identity": stdb_config.fresh_identity,
+        },
+        "performance": {
+            "fps": fps.and_then(|fps| fps.value()),
+            "fps_smoothed": fps.and_then(|fps| fps.smoothed()),
+            "fps_average": fps.and_then(|fps| fps.average()),
+            "frame_time_ms": frame_time.and_then(|frame_time| frame_time.smoothed()),
+        },
+        "config": {
+            "towers": tower_configs.towers.iter().map(|tower| &tower.id).collect::<Vec<_>>(),
+            "units": wave_configs.units.iter().map(|unit| &unit.id).collect::<Vec<_>>(),
+            "waves": wave_configs.waves.len(),
+        },
+        "combat_log": combat_log.lines.iter().skip(log_start).collect::<Vec<_>>(),
+    });
+
+    let path = config_file_path(&format!("diagnostics-{}.json", written_at));
+    let result = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::write(&path, serde_json::to_string_pretty(&snapshot).unwrap_or_default()));
+    match result {
+        Ok(()) => {
+            info!("Wrote diagnostics to {}", path.display());
+            console.print(format!("Diagnostics written to {}", path.display()));
+        }
+        Err(e) => {
+            warn!("Failed to write diagnostics to {}: {}", path.display(), e);
+            console.print(format!("Could not write diagnostics: {}", e));
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use bevy::prelude::*;
+
+use crate::components::{FloatingText, HolyBeam, HolyTowerEffect, SpawnTelegraph, TrailSegment, UpgradeEffect};
+use crate::resources::AppState;
+
+/// World pixels beyond the screen edge within which effects stay visible,
+/// so they don't pop at the border while the camera pans
+const VIEW_MARGIN: f32 = 64.0;
+
+/// Short-lived visual that can be capped and culled. Effects past the cap
+/// are despawned oldest first, judged by how far their lifetime has run.
+pub trait Effect: Component {
+    /// Most of this effect alive at once
+    const MAX_ACTIVE: usize;
+
+    fn lifetime(&self) -> &Timer;
+}
+
+impl Effect for HolyTowerEffect {
+    const MAX_ACTIVE: usize = 30;
+
+    fn lifetime(&self) -> &Timer {
+        &self.lifetime
+    }
+}
+
+impl Effect for HolyBeam {
+    const MAX_ACTIVE: usize = 30;
+
+    fn lifetime(&self) -> &Timer {
+        &self.lifetime
+    }
+}
+
+impl Effect for UpgradeEffect {
+    const MAX_ACTIVE: usize = 20;
+
+    fn lifetime(&self) -> &Timer {
+        &self.lifetime
+    }
+}
+
+impl Effect for SpawnTelegraph {
+    const MAX_ACTIVE: usize = 10;
+
+    fn lifetime(&self) -> &Timer {
+        &self.lifetime
+    }
+}
+
+impl Effect for TrailSegment {
+    const MAX_ACTIVE: usize = 400;
+
+    fn lifetime(&self) -> &Timer {
+        &self.lifetime
+    }
+}
+
+impl Effect for FloatingText {
+    const MAX_ACTIVE: usize = 40;
+
+    fn lifetime(&self) -> &Timer {
+        &self.lifetime
+    }
+}
+
+/// World-space area the camera currently shows, padded by `VIEW_MARGIN`
+#[derive(Resource, Default)]
+pub struct ViewBounds {
+    pub rect: Option<Rect>,
+}
+
+impl ViewBounds {
+    /// Whether `position` is on screen; everything counts as visible until
+    /// the bounds are known
+    pub fn contains(&self, position: Vec2) -> bool {
+        self.rect.is_none_or(|rect| rect.contains(position))
+    }
+}
+
+pub struct EffectsPlugin;
+
+impl Plugin for EffectsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ViewBounds>()
+            .add_systems(Update, update_view_bounds.run_if(in_state(AppState::InGame)));
+        add_effect::<HolyTowerEffect>(app);
+        add_effect::<HolyBeam>(app);
+        add_effect::<UpgradeEffect>(app);
+        add_effect::<SpawnTelegraph>(app);
+        add_effect::<TrailSegment>(app);
+        add_effect::<FloatingText>(app);
+    }
+}
+
+fn add_effect<T: Effect>(app: &mut App) {
+    app.add_systems(
+        Update,
+        (cap_effects::<T>, cull_offscreen_effects::<T>.after(update_view_bounds))
+            .run_if(in_state(AppState::InGame)),
+    );
+}
+
+/// Map the corners of the window to the world through the camera
+fn update_view_bounds(cameras: Query<(&Camera, &GlobalTransform), With<Camera2d>>, mut bounds: ResMut<ViewBounds>) {
+    let Ok((camera, camera_transform)) = cameras.single() else { return };
+    let Some(size) = camera.logical_viewport_size() else { return };
+    let (Ok(top_left), Ok(bottom_right)) = (
+        camera.viewport_to_world_2d(camera_transform, Vec2::ZERO),
+        camera.viewport_to_world_2d(camera_transform, size),
+    ) else {
+        return;
+    };
+    bounds.rect = Some(Rect::from_corners(top_left, bottom_right).inflate(VIEW_MARGIN));
+}
+
+/// Despawn the oldest effects of a kind once there are more than it allows
+fn cap_effects<T: Effect>(mut commands: Commands, effects: Query<(Entity, &T)>) {
+    let excess = effects.iter().count().saturating_sub(T::MAX_ACTIVE);
+    if excess == 0 {
+        return;
+    }
+
+    let mut by_age: Vec<_> = effects
+        .iter()
+        .map(|(entity, effect)| (entity, effect.lifetime().elapsed()))
+        .collect();
+    by_age.sort_by_key(|(_, age)| std::cmp::Reverse(*age));
+    for (entity, _) in by_age.into_iter().take(excess) {
+        commands.queue_silenced(move |world: &mut World| {
+            if let Ok(entity_mut) = world.get_entity_mut(entity) {
+                entity_mut.despawn();
+            }
+        });
+    }
+}
+
+/// Hide effects outside the view. Their lifetimes keep running so they
+/// still expire on time; only drawing and cosmetic updates are skipped.
+fn cull_offscreen_effects<T: Effect>(bounds: Res<ViewBounds>, mut effects: Query<(&GlobalTransform, &mut Visibility), With<T>>) {
+    for (transform, mut visibility) in effects.iter_mut() {
+        visibility.set_if_neq(if bounds.contains(transform.translation().truncate()) {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+    }
+}
@@ -1,8 +1,15 @@
 use bevy::prelude::*;
 
-use crate::components::{AnimationTimer, DefenseType, Enemy, HealthBar, HealthBarFill};
+use bevy::platform::collections::HashMap;
+use spacetimedb_sdk::Identity;
+
+use crate::combat::share_linked_damage;
+use crate::components::{get_attack_type_icon, AnimationTimer, AttackType, BaseTint, Bounty, DefenseType, Enemy, HealthBar, HealthBarFill, ImmunityIcon, Linked, SpawnId, SpawnTelegraph, Tower};
+use crate::config::{schedule_wave, EnemyModifiers, UnitType};
+use crate::events::{EnemyKilled, EnemyReachedEnd};
 use crate::constants::{SCALED_TILE_SIZE, WARRIOR_FRAME_SIZE};
-use crate::resources::{EnemySpawner, GameState, PathWaypoints, WaveConfigs};
+use crate::resources::{EconomyMode, EnemySpawner, GameRules, GameState, PathWaypoints, ScreenShake, WaveConfigs};
+use crate::systems::ui::spawn_floating_text;
 use crate::systems::WaveManager;
 
 #[derive(Component)]
@@ -10,6 +17,23 @@ pub struct AnimationInfo {
     pub frame_count: usize,
 }
 
+/// Seconds before each enemy appears that its spawn telegraph shows
+const SPAWN_TELEGRAPH_LEAD: f32 = 0.6;
+/// With fast spawns the lead shrinks to this share of the spawn interval,
+/// so one enemy's telegraph ends before the next one's starts
+const SPAWN_TELEGRAPH_INTERVAL_SHARE: f32 = 0.75;
+/// Seconds the ring lingers, fading, after the enemy has appeared
+const SPAWN_TELEGRAPH_FADE: f32 = 0.2;
+const SPAWN_TELEGRAPH_START_RADIUS: f32 = SCALED_TILE_SIZE * 1.2;
+const SPAWN_TELEGRAPH_END_RADIUS: f32 = SCALED_TILE_SIZE * 0.4;
+const SPAWN_TELEGRAPH_COLOR: Color = Color::srgb(0.75, 0.35, 1.0);
+
+/// How long before an enemy due every `spawn_interval` seconds its
+/// telegraph shows
+fn spawn_telegraph_lead(spawn_interval: f32) -> f32 {
+    SPAWN_TELEGRAPH_LEAD.min(spawn_interval * SPAWN_TELEGRAPH_INTERVAL_SHARE)
+}
+
 
 // ============================================================================
 // Modified spawn_enemies integration
@@ -34,6 +58,24 @@ pub fn spawn_enemies(
 
     spawner.timer.tick(time.delta());
 
+    // Telegraph the next enemy at the start of the path shortly before it comes
+    let lead = spawn_telegraph_lead(spawner.timer.duration().as_secs_f32());
+    if !spawner.telegraphed
+        && spawner.enemies_spawned < spawner.enemies_this_wave
+        && spawner.timer.remaining_secs() <= lead
+        && ((game_state.wave - 1) as usize) < wave_configs.waves.len()
+    {
+        spawner.telegraphed = true;
+        let start_pos = waypoints.points.first().copied().unwrap_or(Vec2::ZERO);
+        commands.spawn((
+            Transform::from_translation(start_pos.extend(2.0)),
+            Visibility::default(),
+            SpawnTelegraph {
+                lifetime: Timer::from_seconds(spawner.timer.remaining_secs() + SPAWN_TELEGRAPH_FADE, TimerMode::Once),
+            },
+        ));
+    }
+
     if spawner.timer.just_finished() && spawner.enemies_spawned < spawner.enemies_this_wave {
         let current_wave_idx = (game_state.wave - 1) as usize;
         if current_wave_idx >= wave_configs.waves.len() {
@@ -41,85 +83,270 @@ pub fn spawn_enemies(
         }
 
         let wave = &wave_configs.waves[current_wave_idx];
+        // The timer fires every spawn_interval, matching the schedule's spawn times
+        let schedule = schedule_wave(wave, &wave_configs.units);
+        let modifiers = EnemyModifiers::from_wave(&wave.modifiers);
 
-        let mut cumulative_count = 0;
-        let mut selected_spawn = None;
+        if let Some(event) = schedule.get(spawner.enemies_spawned as usize) {
+            if let Some(unit_type) = wave_configs.units.iter().find(|u| u.id == event.unit_id) {
+                let start_pos = waypoints.points.first().copied().unwrap_or(Vec2::ZERO);
+                let enemy_entity = spawn_enemy(
+                    &mut commands,
+                    &asset_server,
+                    &mut texture_atlases,
+                    unit_type,
+                    event.health,
+                    event.gold_reward,
+                    modifiers,
+                    start_pos,
+                );
+                commands
+                    .entity(enemy_entity)
+                    .insert(SpawnId(game_state.wave, spawner.enemies_spawned));
+                if event.bounty {
+                    mark_bounty(&mut commands, &asset_server, enemy_entity, unit_type);
+                }
+                if let Some(group) = event.link_group {
+                    commands.entity(enemy_entity).insert(Linked {
+                        group_id: (game_state.wave, group),
+                        last_health: event.health,
+                    });
+                }
+            }
+        }
+
+        spawner.enemies_spawned += 1;
+        spawner.telegraphed = false;
+
+        if spawner.enemies_spawned >= schedule.len() as i32 {
+            spawner.enemies_spawned = 0;
+            game_state.wave += 1;
 
-        for spawn in &wave.spawns {
-            let spawn_end = cumulative_count + spawn.count;
-            if spawner.enemies_spawned >= cumulative_count && spawner.enemies_spawned < spawn_end {
-                selected_spawn = Some(spawn);
-                break;
+            if (game_state.wave - 1) < wave_configs.waves.len() as i32 {
+                let next_wave = &wave_configs.waves[(game_state.wave - 1) as usize];
+                spawner.timer =
+                    Timer::from_seconds(next_wave.spawn_interval, TimerMode::Repeating);
+                spawner.enemies_this_wave = next_wave.spawns.iter().map(|s| s.count).sum();
             }
-            cumulative_count = spawn_end;
         }
+    }
+}
+
+/// Spawn one enemy of `unit_type` with its health bar at the start of the
+/// path, buffed by its wave's `modifiers`
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_enemy(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    texture_atlases: &mut ResMut<Assets<TextureAtlasLayout>>,
+    unit_type: &UnitType,
+    max_health: f32,
+    gold_reward: i32,
+    modifiers: EnemyModifiers,
+    start_pos: Vec2,
+) -> Entity {
+    let [frame_width, frame_height] = unit_type.frame_size;
+    let layout = TextureAtlasLayout::from_grid(
+        UVec2::new(frame_width, frame_height),
+        unit_type.frame_count as u32,
+        1,
+        None,
+        None,
+    );
+    let texture_atlas_layout = texture_atlases.add(layout);
 
-        let total_count = wave.spawns.iter().map(|s| s.count).sum::<i32>();
+    let enemy_scale = SCALED_TILE_SIZE / frame_width as f32;
+    let tint = unit_type
+        .tint
+        .map_or(Color::WHITE, |[r, g, b, a]| Color::srgba(r, g, b, a));
 
-        if let Some(spawn) = selected_spawn {
-            if let Some(unit_type) = wave_configs.units.iter().find(|u| u.id == spawn.unit_id) {
-                let start_pos = waypoints.points.first().copied().unwrap_or(Vec2::ZERO);
-                let max_health = unit_type.base_health * spawn.health_multiplier;
-
-                let [frame_width, frame_height] = unit_type.frame_size;
-                let layout = TextureAtlasLayout::from_grid(
-                    UVec2::new(frame_width, frame_height),
-                    unit_type.frame_count as u32,
-                    1,
-                    None,
-                    None,
-                );
-                let texture_atlas_layout = texture_atlases.add(layout);
-
-                let enemy_scale = SCALED_TILE_SIZE / frame_width as f32;
-
-                let enemy_entity = commands
-                    .spawn((
-                        Sprite::from_atlas_image(
-                            asset_server.load(&unit_type.sprite_path),
-                            TextureAtlas {
-                                layout: texture_atlas_layout,
-                                index: 0,
-                            },
-                        ),
-                        Transform::from_xyz(start_pos.x, start_pos.y, 1.0)
-                            .with_scale(Vec3::splat(enemy_scale)),
-                        Enemy {
-                            health: max_health,
-                            speed: unit_type.base_speed,
-                            current_waypoint: 0,
-                            gold_reward: unit_type.gold_reward,
-                            damage_to_base: unit_type.damage_to_base,
-                            defense_type: DefenseType::from_str(&unit_type.defense_type),
-                        },
-                        AnimationTimer {
-                            timer: Timer::from_seconds(0.1, TimerMode::Repeating),
-                        },
-                        AnimationInfo {
-                            frame_count: unit_type.frame_count,
-                        },
-                    ))
-                    .id();
-
-                spawn_health_bar(&mut commands, &asset_server, enemy_entity, max_health, SCALED_TILE_SIZE);
-
-                spawner.enemies_spawned += 1;
-
-                if spawner.enemies_spawned >= total_count {
-                    spawner.enemies_spawned = 0;
-                    game_state.wave += 1;
-
-                    if (game_state.wave - 1) < wave_configs.waves.len() as i32 {
-                        let next_wave = &wave_configs.waves[(game_state.wave - 1) as usize];
-                        spawner.timer =
-                            Timer::from_seconds(next_wave.spawn_interval, TimerMode::Repeating);
-                        spawner.enemies_this_wave = next_wave.spawns.iter().map(|s| s.count).sum();
-                    }
+    let enemy_entity = commands
+        .spawn((
+            Sprite {
+                color: tint,
+                ..Sprite::from_atlas_image(
+                    asset_server.load(&unit_type.sprite_path),
+                    TextureAtlas {
+                        layout: texture_atlas_layout,
+                        index: 0,
+                    },
+                )
+            },
+            BaseTint(tint),
+            Transform::from_xyz(start_pos.x, start_pos.y, 1.0)
+                .with_scale(Vec3::splat(enemy_scale)),
+            Enemy {
+                name: unit_type.name.clone(),
+                health: max_health,
+                max_health,
+                speed: unit_type.base_speed * modifiers.speed_multiplier,
+                current_waypoint: 0,
+                gold_reward,
+                damage_to_base: unit_type.damage_to_base,
+                defense_type: DefenseType::from_str(&unit_type.defense_type),
+                is_boss: unit_type.is_boss,
+                gold_steal: unit_type.gold_steal,
+                immune_to: unit_type.immune_to.as_deref().map(AttackType::from_str),
+                damage_taken: modifiers.damage_taken,
+                regen: max_health * modifiers.regen_per_second,
+            },
+            AnimationTimer {
+                timer: Timer::from_seconds(0.1, TimerMode::Repeating),
+            },
+            AnimationInfo {
+                frame_count: unit_type.frame_count,
+            },
+        ))
+        .id();
+
+    spawn_health_bar(commands, asset_server, enemy_entity, max_health, SCALED_TILE_SIZE);
+    if let Some(immune_to) = unit_type.immune_to.as_deref().map(AttackType::from_str) {
+        spawn_immunity_icon(commands, asset_server, enemy_entity, unit_type, immune_to);
+    }
+    enemy_entity
+}
+
+/// Greyed-out icon of the attack type the enemy ignores, left of its head
+fn spawn_immunity_icon(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    enemy_entity: Entity,
+    unit_type: &UnitType,
+    immune_to: AttackType,
+) {
+    // Children are sized in sprite pixels, like the health bar
+    let [frame_width, frame_height] = unit_type.frame_size;
+    let icon = commands
+        .spawn((
+            Sprite {
+                image: asset_server.load(get_attack_type_icon(immune_to)),
+                color: Color::srgba(0.6, 0.6, 0.6, 0.9),
+                custom_size: Some(Vec2::splat(frame_width as f32 * 0.25)),
+                ..default()
+            },
+            Transform::from_xyz(-(frame_width as f32) * 0.35, frame_height as f32 * 0.3, 4.0),
+            ImmunityIcon,
+        ))
+        .id();
+    commands.entity(enemy_entity).add_child(icon);
+}
+
+/// Tag a bounty enemy and show a gold coin above its head
+fn mark_bounty(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    enemy_entity: Entity,
+    unit_type: &UnitType,
+) {
+    // Children are sized in sprite pixels, like the health bar
+    let coin_size = unit_type.frame_size[0] as f32 * 0.3;
+    let coin = commands
+        .spawn((
+            Sprite {
+                image: asset_server.load("UI Elements/UI Elements/Icons/Gold_Icon.png"),
+                custom_size: Some(Vec2::splat(coin_size)),
+                ..default()
+            },
+            Transform::from_xyz(0.0, unit_type.frame_size[1] as f32 * 0.45, 4.0),
+        ))
+        .id();
+
+    commands.entity(enemy_entity).insert(Bounty).add_child(coin);
+}
+
+/// Heal enemies of regenerating waves, up to their max health
+pub fn regenerate_enemies(time: Res<Time>, mut enemies: Query<&mut Enemy>) {
+    for mut enemy in enemies.iter_mut() {
+        if enemy.regen > 0.0 && enemy.health > 0.0 && enemy.health < enemy.max_health {
+            enemy.health = (enemy.health + enemy.regen * time.delta_secs()).min(enemy.max_health);
+        }
+    }
+}
+
+/// Fraction of each hit on a linked enemy passed on to the rest of its group
+const LINK_DAMAGE_SHARE: f32 = 0.5;
+/// Damage passed on from a linked enemy never leaves the others below this
+const LINK_MIN_HEALTH: f32 = 1.0;
+const LINK_LINE_COLOR: Color = Color::srgba(0.6, 0.8, 1.0, 0.3);
+
+/// Spread the damage linked enemies took since last frame across their
+/// groups. It runs after the hit, so kills already credited stand.
+pub fn spread_linked_damage(mut enemies: Query<(Entity, &mut Enemy, &mut Linked)>) {
+    let mut groups: HashMap<(i32, usize), Vec<Entity>> = HashMap::default();
+    for (entity, _, linked) in enemies.iter() {
+        groups.entry(linked.group_id).or_default().push(entity);
+    }
+
+    for members in groups.into_values() {
+        let mut healths: Vec<(f32, f32)> = members
+            .iter()
+            .filter_map(|entity| enemies.get(*entity).ok())
+            .map(|(_, enemy, linked)| (linked.last_health, enemy.health))
+            .collect();
+        share_linked_damage(&mut healths, LINK_DAMAGE_SHARE, LINK_MIN_HEALTH);
+        for (entity, (_, health)) in members.into_iter().zip(healths) {
+            if let Ok((_, mut enemy, mut linked)) = enemies.get_mut(entity) {
+                if enemy.health != health {
+                    enemy.health = health;
                 }
+                linked.last_health = health;
             }
         }
     }
 }
+
+/// Faint lines joining the living members of each linked group
+pub fn draw_enemy_links(mut gizmos: Gizmos, enemies: Query<(&Transform, &Enemy, &Linked)>) {
+    let mut groups: HashMap<(i32, usize), Vec<Vec2>> = HashMap::default();
+    for (transform, enemy, linked) in enemies.iter() {
+        if enemy.health > 0.0 {
+            groups.entry(linked.group_id).or_default().push(transform.translation.truncate());
+        }
+    }
+    for positions in groups.values() {
+        for pair in positions.windows(2) {
+            gizmos.line_2d(pair[0], pair[1], LINK_LINE_COLOR);
+        }
+    }
+}
+
+/// Expire spawn telegraphs once their enemy is out and the ring has faded
+pub fn update_spawn_telegraphs(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut telegraphs: Query<(Entity, &mut SpawnTelegraph)>,
+) {
+    for (entity, mut telegraph) in telegraphs.iter_mut() {
+        telegraph.lifetime.tick(time.delta());
+        if telegraph.lifetime.is_finished() {
+            commands.entity(entity).try_despawn();
+        }
+    }
+}
+
+/// A glowing ring that closes in on the spawn point and brightens as the
+/// enemy comes due, then fades once it is out
+pub fn draw_spawn_telegraphs(mut gizmos: Gizmos, telegraphs: Query<(&Transform, &Visibility, &SpawnTelegraph)>) {
+    for (transform, visibility, telegraph) in telegraphs.iter() {
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+        let fade_start = (telegraph.lifetime.duration().as_secs_f32() - SPAWN_TELEGRAPH_FADE).max(f32::EPSILON);
+        let elapsed = telegraph.lifetime.elapsed_secs();
+        let (progress, alpha) = if elapsed < fade_start {
+            let progress = elapsed / fade_start;
+            (progress, 0.3 + 0.7 * progress)
+        } else {
+            (1.0, 1.0 - (elapsed - fade_start) / SPAWN_TELEGRAPH_FADE)
+        };
+        let radius = SPAWN_TELEGRAPH_START_RADIUS.lerp(SPAWN_TELEGRAPH_END_RADIUS, progress);
+        let center = transform.translation.truncate();
+        gizmos.circle_2d(center, radius, SPAWN_TELEGRAPH_COLOR.with_alpha(alpha));
+        // Softer outer ring for the glow
+        gizmos.circle_2d(center, radius + 3.0, SPAWN_TELEGRAPH_COLOR.with_alpha(alpha * 0.35));
+    }
+}
+
 fn spawn_health_bar(
     commands: &mut Commands,
     asset_server: &Res<AssetServer>,
@@ -146,6 +373,8 @@ fn spawn_health_bar(
                 ..default()
             },
             Transform::from_xyz(0.0, scaled_tile_size * 2.5, 5.0), // Position above enemy, higher z
+            // Shown by update_health_bars on the first hit
+            Visibility::Hidden,
             HealthBar { max_health },
         ))
         .id();
@@ -177,12 +406,14 @@ fn spawn_health_bar(
 
 pub fn move_enemies(
     mut commands: Commands,
-    mut enemies: Query<(Entity, &mut Transform, &mut Enemy, Option<&Children>)>,
+    mut enemies: Query<(Entity, &mut Transform, &mut Enemy, Option<&SpawnId>, Option<&Children>)>,
     time: Res<Time>,
     mut game_state: ResMut<GameState>,
+    rules: Res<GameRules>,
     waypoints: Res<PathWaypoints>,
+    mut reached_end: MessageWriter<EnemyReachedEnd>,
 ) {
-    for (entity, mut transform, mut enemy, children) in enemies.iter_mut() {
+    for (entity, mut transform, mut enemy, spawn_id, children) in enemies.iter_mut() {
         // Get current and next waypoint
         if enemy.current_waypoint >= waypoints.points.len() {
             // Reached the end (castle) - despawn enemy and deal damage
@@ -201,7 +432,25 @@ pub fn move_enemies(
                     entity_mut.despawn();
                 }
             });
-            game_state.lives -= enemy.damage_to_base;
+            let spawn = spawn_id.map(|id| (id.0, id.1));
+            game_state.lose_lives(enemy.damage_to_base, spawn);
+            game_state.register_leak(rules.leak_streak_penalties);
+
+            // Thieves take gold as well, but never more than the player has
+            let stolen = game_state.lose_gold_to_thief(enemy.gold_steal, spawn);
+            if stolen > 0 {
+                spawn_floating_text(
+                    &mut commands,
+                    transform.translation.truncate(),
+                    format!("-{}g stolen!", stolen),
+                    Color::srgb(1.0, 0.8, 0.1),
+                );
+            }
+            reached_end.write(EnemyReachedEnd {
+                damage: enemy.damage_to_base,
+                enemy: enemy.name.clone(),
+                gold_stolen: stolen,
+            });
             continue;
         }
 
@@ -223,12 +472,47 @@ pub fn move_enemies(
     }
 }
 
+/// Pay out and record an enemy's death, crediting `killer` (the tower whose
+/// hit, hazard or burn finished it) if it still exists. Every way an enemy
+/// can die goes through here; cleanup_dead_enemies only despawns.
+///
+/// Every client simulates every enemy, so in a shared economy each kill
+/// would be paid into the pool once per player. There only the owner of the
+/// killing tower, `local_player`, claims the reward.
+pub fn award_kill(
+    enemy: &Enemy,
+    killer: Option<&mut Tower>,
+    local_player: Option<Identity>,
+    game_state: &mut GameState,
+    killed: &mut MessageWriter<EnemyKilled>,
+) {
+    let ours = local_player.is_some() && killer.as_ref().is_some_and(|tower| tower.owner == local_player);
+    if game_state.economy_mode == EconomyMode::Split || ours {
+        game_state.earn_gold(enemy.gold_reward);
+    }
+    game_state.score += enemy.gold_reward;
+    let killer = killer.map(|tower| {
+        tower.kills += 1;
+        (tower.tower_type_id.clone(), tower.owner)
+    });
+    killed.write(EnemyKilled {
+        gold_reward: enemy.gold_reward,
+        enemy: enemy.name.clone(),
+        tower_type_id: killer.as_ref().map(|(id, _)| id.clone()),
+        tower_owner: killer.and_then(|(_, owner)| owner),
+    });
+}
+
 pub fn cleanup_dead_enemies(
     mut commands: Commands,
     enemies: Query<(Entity, &Enemy, Option<&Children>)>,
+    mut shake: ResMut<ScreenShake>,
 ) {
     for (entity, enemy, children) in enemies.iter() {
         if enemy.health <= 0.0 {
+            if enemy.is_boss {
+                shake.add_trauma(0.6);
+            }
             // Despawn children (health bar) first
             if let Some(children) = children {
                 for child in children.iter() {
@@ -247,3 +531,15 @@ pub fn cleanup_dead_enemies(
         }
     }
 }
+
+/// Let the leak streak run out once no enemy has leaked for a while
+pub fn update_leak_streak(time: Res<Time>, mut game_state: ResMut<GameState>) {
+    if game_state.leak_streak == 0 {
+        return;
+    }
+    game_state.leak_streak_timer -= time.delta_secs();
+    if game_state.leak_streak_timer <= 0.0 {
+        game_state.leak_streak = 0;
+        game_state.leak_streak_timer = 0.0;
+    }
+}
@@ -0,0 +1,350 @@
+use bevy::prelude::*;
+use bevy_spacetimedb::{ReadInsertMessage, ReadUpdateMessage};
+use spacetimedb_sdk::Table;
+use std::collections::HashSet;
+
+use crate::components::{ConfirmedTower, PendingTower, SyncedTower, TowerUpgradeMenu, Wall};
+use crate::map::world_to_tile;
+use crate::module_bindings::claim_gold_reducer::claim_gold;
+use crate::module_bindings::game_status_table::GameStatusTableAccess;
+use crate::module_bindings::player_state_table::PlayerStateTableAccess;
+use crate::module_bindings::report_leak_reducer::report_leak;
+use crate::module_bindings::report_theft_reducer::report_theft;
+use crate::module_bindings::spend_gold_reducer::spend_gold;
+use crate::module_bindings::tower_table::TowerTableAccess;
+use crate::module_bindings::wall_table::WallTableAccess;
+use crate::module_bindings::{EconomyMode as ServerEconomyMode, GameStatus, PlayerState};
+use crate::resources::{BlockedTiles, EconomyMode, GameState, PathWaypoints, SelectedTowers, TowerConfigs, TowerUpgradeMenuState};
+use crate::systems::networking::SpacetimeDB;
+use crate::systems::stats::GameStats;
+use crate::systems::pathfinding::compute_enemy_path;
+use crate::systems::tower::{spawn_tower, spawn_wall};
+
+/// In-flight changes the server hasn't confirmed after this long were rejected
+const IN_FLIGHT_TIMEOUT: f32 = 5.0;
+/// Row id of the server's single `game_status` row
+const GAME_STATUS_ID: u32 = 0;
+
+/// Send local gold income, spending and thefts to the server. Offline the
+/// changes are simply dropped since local gold is all there is.
+pub fn sync_gold_changes(mut game_state: ResMut<GameState>, stdb: Option<SpacetimeDB>) {
+    if game_state.unsynced_gold.is_empty() && game_state.unsynced_thefts.is_empty() {
+        return;
+    }
+    let changes = std::mem::take(&mut game_state.unsynced_gold);
+    let thefts = std::mem::take(&mut game_state.unsynced_thefts);
+    let Some(stdb) = stdb else { return };
+
+    // Not predicted locally, so nothing to expect: the balance just moves
+    for theft in thefts {
+        let (wave, spawn_index) = theft.spawn;
+        if let Err(e) = stdb.reducers().report_theft(wave, spawn_index, theft.amount) {
+            error!("Failed to report theft: {}", e);
+        }
+    }
+
+    for delta in changes {
+        let sent = if delta > 0 {
+            stdb.reducers().claim_gold(delta)
+        } else {
+            stdb.reducers().spend_gold(-delta)
+        };
+        match sent {
+            Ok(()) => game_state.expect_server_gold(delta),
+            Err(e) => error!("Failed to send gold change {}: {}", delta, e),
+        }
+    }
+    game_state.reconcile_gold();
+}
+
+/// Match server gold updates against the in-flight gold changes. The
+/// balance is our `player_state`, or the pool in `game_status` in a shared
+/// economy, whose updates also carry the other players' changes. Those are
+/// told apart by `gold_changed_by` and only move the balance, since a
+/// teammate's kill reward has the same amount as ours. The server runs
+/// reducers in order, so in-flight changes older than the one an update
+/// confirms were rejected and are dropped, as are changes left unconfirmed
+/// for too long. The shown gold is then rebuilt from the confirmed balance.
+#[allow(clippy::too_many_arguments)]
+pub fn reconcile_gold(
+    player_inserted: Option<ReadInsertMessage<PlayerState>>,
+    player_updated: Option<ReadUpdateMessage<PlayerState>>,
+    status_inserted: Option<ReadInsertMessage<GameStatus>>,
+    status_updated: Option<ReadUpdateMessage<GameStatus>>,
+    stdb: Option<SpacetimeDB>,
+    time: Res<Time>,
+    mut game_state: ResMut<GameState>,
+) {
+    let Some(stdb) = stdb else { return };
+    let Some(identity) = stdb.try_identity() else { return };
+    let mut changed = false;
+
+    // Follow the running game's economy. Changes sent against the other
+    // balance can no longer be matched, so they are dropped.
+    if let Some(status) = stdb.db().game_status().id().find(&GAME_STATUS_ID) {
+        let mode = EconomyMode::from(status.economy_mode);
+        if game_state.economy_mode != mode {
+            game_state.economy_mode = mode;
+            game_state.confirmed_gold = match mode {
+                EconomyMode::Shared => Some(status.shared_gold),
+                EconomyMode::Split => stdb.db().player_state().identity().find(&identity).map(|state| state.gold),
+            };
+            game_state.in_flight_gold.clear();
+            changed = true;
+        }
+    }
+    let shared = game_state.economy_mode == EconomyMode::Shared;
+
+    // (old, new) balances of the pool we use, in order, and whether the
+    // change was ours; no old balance for a new row
+    let mut balances: Vec<(Option<i32>, i32, bool)> = Vec::new();
+    if let Some(mut messages) = player_inserted {
+        balances.extend(
+            messages
+                .read()
+                .filter(|msg| !shared && msg.row.identity == identity)
+                .map(|msg| (None, msg.row.gold, true)),
+        );
+    }
+    if let Some(mut messages) = player_updated {
+        balances.extend(
+            messages
+                .read()
+                .filter(|msg| !shared && msg.new.identity == identity)
+                .map(|msg| (Some(msg.old.gold), msg.new.gold, true)),
+        );
+    }
+    if let Some(mut messages) = status_inserted {
+        balances.extend(messages.read().filter(|_| shared).map(|msg| (None, msg.row.shared_gold, false)));
+    }
+    if let Some(mut messages) = status_updated {
+        balances.extend(
+            messages
+                .read()
+                .filter(|_| shared)
+                .map(|msg| {
+                    let ours = msg.new.gold_changed_by == Some(identity);
+                    (Some(msg.old.shared_gold), msg.new.shared_gold, ours)
+                }),
+        );
+    }
+
+    for (old, new, ours) in balances {
+        match old {
+            None => game_state.in_flight_gold.clear(),
+            // Lives changes update the game_status row too
+            Some(old) if old == new => continue,
+            Some(_) if !ours => {}
+            Some(old) => {
+                let delta = new - old;
+                if let Some(index) = game_state.in_flight_gold.iter().position(|change| change.delta == delta) {
+                    if index > 0 {
+                        warn!("Server rejected {} gold changes", index);
+                    }
+                    game_state.in_flight_gold.drain(..=index);
+                }
+            }
+        }
+        game_state.confirmed_gold = Some(new);
+        changed = true;
+    }
+
+    let dt = time.delta_secs();
+    let before = game_state.in_flight_gold.len();
+    for change in game_state.in_flight_gold.iter_mut() {
+        change.age += dt;
+    }
+    game_state.in_flight_gold.retain(|change| change.age < IN_FLIGHT_TIMEOUT);
+    if game_state.in_flight_gold.len() != before {
+        warn!("Gold changes timed out without server confirmation");
+        changed = true;
+    }
+
+    if changed {
+        game_state.reconcile_gold();
+    }
+}
+
+impl From<ServerEconomyMode> for EconomyMode {
+    fn from(mode: ServerEconomyMode) -> Self {
+        match mode {
+            ServerEconomyMode::Split => EconomyMode::Split,
+            ServerEconomyMode::Shared => EconomyMode::Shared,
+        }
+    }
+}
+
+impl From<EconomyMode> for ServerEconomyMode {
+    fn from(mode: EconomyMode) -> Self {
+        match mode {
+            EconomyMode::Split => ServerEconomyMode::Split,
+            EconomyMode::Shared => ServerEconomyMode::Shared,
+        }
+    }
+}
+
+/// Report castle damage to the server and follow the shared lives in
+/// `game_status`. Without the row (offline, or before the subscription
+/// arrives) lives stay local and queued damage is applied directly.
+pub fn sync_lives(mut game_state: ResMut<GameState>, stdb: Option<SpacetimeDB>) {
+    let status = stdb.as_ref().and_then(|stdb| stdb.db().game_status().id().find(&GAME_STATUS_ID));
+    game_state.lives_from_server = status.is_some();
+
+    let leaks = std::mem::take(&mut game_state.unsynced_leaks);
+    match (stdb.as_ref(), status.is_some()) {
+        (Some(stdb), true) => {
+            for leak in leaks {
+                let (wave, spawn_index) = leak.spawn;
+                if let Err(e) = stdb.reducers().report_leak(wave, spawn_index, leak.damage) {
+                    error!("Failed to report leak: {}", e);
+                }
+            }
+        }
+        _ => game_state.lives -= leaks.iter().map(|leak| leak.damage).sum::<i32>(),
+    }
+
+    if let Some(status) = status {
+        // A finished game reads as no lives left, which ends it here too
+        let lives = if status.game_over { 0 } else { status.lives };
+        if game_state.lives != lives {
+            game_state.lives = lives;
+        }
+        if game_state.max_lives != status.max_lives {
+            game_state.max_lives = status.max_lives;
+        }
+    }
+}
+
+/// Mirror the towers other players placed from the server's `tower` table:
+/// spawn rows that are new, in their owner's color, and despawn towers whose
+/// row is gone (sold, or the owner started a new game). Our own rows are
+/// skipped since those towers were spawned when placed.
+pub fn sync_remote_towers(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    stdb: Option<SpacetimeDB>,
+    tower_configs: Res<TowerConfigs>,
+    synced: Query<(Entity, &SyncedTower)>,
+) {
+    let Some(stdb) = stdb else { return };
+    let Some(me) = stdb.try_identity() else { return };
+    let rows: Vec<_> = stdb.db().tower().iter().filter(|row| row.owner != me).collect();
+
+    for (entity, tower) in synced.iter() {
+        if !rows.iter().any(|row| row.id == tower.id) {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    for row in rows {
+        if synced.iter().any(|(_, tower)| tower.id == row.id) {
+            continue;
+        }
+        let Some(tower_type) = tower_configs.towers.iter().find(|t| t.id == row.tower_type_id) else {
+            warn!("Synced tower {} has unknown type '{}'", row.id, row.tower_type_id);
+            continue;
+        };
+        let position = Vec3::new(row.x, row.y, 1.0);
+        let entity = spawn_tower(&mut commands, &asset_server, position, tower_type, Some(Res::clone(&stdb)), Some(row.owner));
+        commands.entity(entity).insert(SyncedTower { id: row.id });
+    }
+}
+
+/// Build the walls in the server's `wall` table, so every player's enemies
+/// take the same way around them. Online this is the only way walls go up;
+/// walls whose row is gone (a new game started) come down again.
+pub fn sync_walls(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    stdb: Option<SpacetimeDB>,
+    mut blocked_tiles: ResMut<BlockedTiles>,
+    mut waypoints: ResMut<PathWaypoints>,
+    walls: Query<(Entity, &Transform), With<Wall>>,
+) {
+    let Some(stdb) = stdb else { return };
+    let rows: HashSet<(i32, i32)> = stdb.db().wall().iter().map(|row| (row.tile_x, row.tile_y)).collect();
+    if rows == blocked_tiles.walls {
+        return;
+    }
+
+    for (entity, transform) in walls.iter() {
+        if !rows.contains(&world_to_tile(transform.translation.truncate())) {
+            commands.entity(entity).despawn();
+        }
+    }
+    for &tile in rows.difference(&blocked_tiles.walls) {
+        spawn_wall(&mut commands, &asset_server, tile);
+    }
+    blocked_tiles.walls = rows;
+    // The server only accepts walls that leave a way through
+    match compute_enemy_path(&blocked_tiles, None) {
+        Some(path) => waypoints.points = path,
+        None => warn!("Walls from the server block the enemy path"),
+    }
+}
+
+/// Keep towers where their `tower` row puts them, so a move shows up for
+/// every player: ours once the server accepted `move_tower`, the others'
+/// whenever their owners move them
+pub fn follow_tower_rows(
+    stdb: Option<SpacetimeDB>,
+    mut towers: Query<(&mut Transform, AnyOf<(&SyncedTower, &ConfirmedTower)>)>,
+) {
+    let Some(stdb) = stdb else { return };
+    for (mut transform, (synced, confirmed)) in towers.iter_mut() {
+        let Some(id) = synced.map(|tower| tower.id).or(confirmed.map(|tower| tower.id)) else { continue };
+        let Some(row) = stdb.db().tower().id().find(&id) else { continue };
+        if transform.translation.x != row.x || transform.translation.y != row.y {
+            transform.translation.x = row.x;
+            transform.translation.y = row.y;
+        }
+    }
+}
+
+/// Link our own towers placed online to the `tower` rows the server created
+/// for them. A tower whose row hasn't arrived after `IN_FLIGHT_TIMEOUT` was
+/// refused (not enough server gold, or the tile was taken) and is removed;
+/// its cost comes back through `reconcile_gold`.
+#[allow(clippy::too_many_arguments)]
+pub fn confirm_pending_towers(
+    mut commands: Commands,
+    stdb: Option<SpacetimeDB>,
+    time: Res<Time>,
+    mut stats: ResMut<GameStats>,
+    mut selected: ResMut<SelectedTowers>,
+    mut upgrade_menu: ResMut<TowerUpgradeMenuState>,
+    upgrade_menu_entities: Query<Entity, With<TowerUpgradeMenu>>,
+    mut pending: Query<(Entity, &Transform, &mut PendingTower)>,
+    confirmed: Query<&ConfirmedTower>,
+) {
+    let Some(stdb) = stdb else { return };
+    let Some(me) = stdb.try_identity() else { return };
+    let mut linked: Vec<u64> = confirmed.iter().map(|tower| tower.id).collect();
+
+    for (entity, transform, mut tower) in pending.iter_mut() {
+        let position = transform.translation.truncate();
+        let row = stdb.db().tower().iter().find(|row| {
+            row.owner == me && !linked.contains(&row.id) && Vec2::new(row.x, row.y).distance(position) < 1.0
+        });
+        if let Some(row) = row {
+            linked.push(row.id);
+            commands.entity(entity).remove::<PendingTower>().insert(ConfirmedTower { id: row.id });
+            continue;
+        }
+
+        tower.age += time.delta_secs();
+        if tower.age >= IN_FLIGHT_TIMEOUT {
+            warn!("Server refused the tower at ({}, {})", position.x, position.y);
+            stats.towers_built = stats.towers_built.saturating_sub(1);
+            selected.towers.remove(&entity);
+            if upgrade_menu.selected_tower == Some(entity) {
+                for menu in upgrade_menu_entities.iter() {
+                    commands.entity(menu).despawn();
+                }
+                upgrade_menu.active = false;
+                upgrade_menu.selected_tower = None;
+            }
+            commands.entity(entity).despawn();
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use bevy::prelude::*;
+
+use crate::combat::nearest_in_range;
+use crate::components::Enemy;
+use crate::constants::SCALED_TILE_SIZE;
+use crate::resources::AppState;
+use crate::systems::input::{ActionState, InputAction};
+
+const FOCUS_MARKER_SPRITE: &str = "UI Elements/UI Elements/Swords/Swords.png";
+/// Marker size and height above the enemy's centre, in world pixels
+const FOCUS_MARKER_SIZE: Vec2 = Vec2::new(14.0, 20.0);
+const FOCUS_MARKER_HEIGHT: f32 = SCALED_TILE_SIZE * 0.75;
+
+/// Enemy the player told towers to focus. Every tower that has it in range
+/// and can damage it shoots it before its usual nearest target.
+#[derive(Resource, Default)]
+pub struct FocusTarget(pub Option<Entity>);
+
+/// Crossed swords over the focused enemy, a child of it
+#[derive(Component)]
+pub struct FocusMarker;
+
+pub struct FocusFirePlugin;
+
+impl Plugin for FocusFirePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FocusTarget>()
+            .add_systems(
+                Update,
+                (set_focus_target, clear_dead_focus_target, sync_focus_marker)
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(OnExit(AppState::InGame), clear_focus_target);
+    }
+}
+
+/// The Focus Fire key focuses the enemy under the pointer. Pressing it on
+/// the focused enemy or on empty ground clears the focus.
+fn set_focus_target(
+    actions: Res<ActionState>,
+    mut focus: ResMut<FocusTarget>,
+    enemies: Query<(Entity, &Transform, &Enemy)>,
+) {
+    if !actions.just_pressed(InputAction::FocusFire) {
+        return;
+    }
+    let Some(pointer) = actions.pointer_world else { return };
+
+    let hovered = nearest_in_range(
+        pointer.into(),
+        SCALED_TILE_SIZE / 2.0,
+        enemies
+            .iter()
+            .filter(|(_, _, enemy)| enemy.health > 0.0)
+            .map(|(entity, transform, _)| (entity, transform.translation.truncate().into())),
+    );
+    focus.0 = hovered.filter(|entity| focus.0 != Some(*entity));
+}
+
+/// Drop the focus once its enemy dies or leaves the map
+fn clear_dead_focus_target(mut focus: ResMut<FocusTarget>, enemies: Query<&Enemy>) {
+    let Some(target) = focus.0 else { return };
+    if !enemies.get(target).is_ok_and(|enemy| enemy.health > 0.0) {
+        focus.0 = None;
+    }
+}
+
+/// Move the marker to the focused enemy when the focus changes
+fn sync_focus_marker(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    focus: Res<FocusTarget>,
+    markers: Query<Entity, With<FocusMarker>>,
+    enemies: Query<&Transform, With<Enemy>>,
+) {
+    if !focus.is_changed() {
+        return;
+    }
+    for marker in markers.iter() {
+        // Gone already if the enemy it was over just died
+        commands.entity(marker).try_despawn();
+    }
+    let Some(target) = focus.0 else { return };
+    let Ok(transform) = enemies.get(target) else { return };
+
+    // Children are sized in sprite pixels, so undo the enemy's scale
+    let scale = transform.scale.truncate().max(Vec2::splat(f32::EPSILON));
+    let marker = commands
+        .spawn((
+            Sprite {
+                image: asset_server.load(FOCUS_MARKER_SPRITE),
+                custom_size: Some(FOCUS_MARKER_SIZE / scale),
+                ..default()
+            },
+            Transform::from_xyz(0.0, FOCUS_MARKER_HEIGHT / scale.y, 5.0),
+            FocusMarker,
+        ))
+        .id();
+    commands.entity(target).add_child(marker);
+}
+
+fn clear_focus_target(mut focus: ResMut<FocusTarget>) {
+    focus.0 = None;
+}
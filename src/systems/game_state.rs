@@ -1,7 +1,30 @@
+use bevy::ecs::prelude::ChildSpawnerCommands;
 use bevy::prelude::*;
+use bevy::window::WindowFocused;
 
-use crate::components::GameOverScreen;
-use crate::resources::{AppState, GameState};
+use crate::components::{GameOverMenuButton, GameOverScreen, LastStandTint, NightTint};
+use crate::constants::DAY_LENGTH;
+use crate::events::LeaveGame;
+use crate::module_bindings::game_clock_table::GameClockTableAccess;
+use crate::resources::{AppState, GameState, LastStand, TimeOfDay};
+use crate::systems::menu::{spawn_nine_slice_button, ButtonStyle};
+use crate::systems::networking::SpacetimeDB;
+use crate::systems::stats::{stats_breakdown, GameStats};
+use crate::user_settings::Settings;
+
+/// Lives below which losing another life triggers the last stand
+const LAST_STAND_LIVES: i32 = 3;
+/// Game speed at the height of the last stand
+const LAST_STAND_SPEED: f32 = 0.35;
+/// Real seconds the slow motion lasts, including the ramp back to normal
+const LAST_STAND_DURATION: f32 = 2.0;
+/// Real seconds before the last stand can trigger again
+const LAST_STAND_COOLDOWN: f32 = 20.0;
+const LAST_STAND_TINT_ALPHA: f32 = 0.25;
+/// Night tint opacity at midnight
+const NIGHT_TINT_ALPHA: f32 = 0.4;
+/// Row id of the server's single `game_clock` row
+const GAME_CLOCK_ID: u32 = 0;
 
 pub fn check_game_over(game_state: Res<GameState>, mut next_state: ResMut<NextState<AppState>>) {
     if game_state.lives <= 0 {
@@ -9,7 +32,111 @@ pub fn check_game_over(game_state: Res<GameState>, mut next_state: ResMut<NextSt
     }
 }
 
-pub fn setup_game_over_screen(mut commands: Commands) {
+/// Briefly slow time and tint the screen red when a life is lost while lives
+/// are critical. Timers run on real time so the slow motion doesn't stretch itself.
+pub fn update_last_stand(
+    real_time: Res<Time<Real>>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    game_state: Res<GameState>,
+    settings: Res<Settings>,
+    mut last_stand: ResMut<LastStand>,
+    mut last_lives: Local<Option<i32>>,
+    mut tints: Query<&mut BackgroundColor, With<LastStandTint>>,
+) {
+    let dt = real_time.delta_secs();
+    last_stand.cooldown = (last_stand.cooldown - dt).max(0.0);
+
+    let lost_life = last_lives.is_some_and(|previous| game_state.lives < previous);
+    *last_lives = Some(game_state.lives);
+
+    let enabled = settings.graphics.last_stand_slow_motion;
+    if enabled
+        && lost_life
+        && game_state.lives > 0
+        && game_state.lives < LAST_STAND_LIVES
+        && last_stand.cooldown <= 0.0
+    {
+        last_stand.remaining = LAST_STAND_DURATION;
+        last_stand.cooldown = LAST_STAND_COOLDOWN;
+    }
+
+    if last_stand.remaining <= 0.0 {
+        return;
+    }
+    last_stand.remaining = if enabled { (last_stand.remaining - dt).max(0.0) } else { 0.0 };
+
+    // Full effect for the first half, then ease back to normal speed
+    let strength = (last_stand.remaining / (LAST_STAND_DURATION * 0.5)).min(1.0);
+    virtual_time.set_relative_speed(1.0 - (1.0 - LAST_STAND_SPEED) * strength);
+    for mut background in tints.iter_mut() {
+        background.0 = Color::srgba(0.6, 0.0, 0.0, LAST_STAND_TINT_ALPHA * strength);
+    }
+}
+
+/// Advance the day/night cycle and darken the screen at night. Online the
+/// phase comes from the server clock; offline it runs on game time.
+pub fn update_time_of_day(
+    time: Res<Time>,
+    stdb: Option<SpacetimeDB>,
+    mut time_of_day: ResMut<TimeOfDay>,
+    mut tints: Query<&mut BackgroundColor, With<NightTint>>,
+) {
+    let clock = stdb.and_then(|stdb| stdb.db().game_clock().id().find(&GAME_CLOCK_ID));
+    time_of_day.phase = match clock {
+        Some(clock) => {
+            let elapsed = spacetimedb_sdk::Timestamp::now()
+                .duration_since(clock.day_started)
+                .map_or(0.0, |elapsed| elapsed.as_secs_f32());
+            (elapsed / DAY_LENGTH).fract()
+        }
+        None => (time_of_day.phase + time.delta_secs() / DAY_LENGTH).fract(),
+    };
+
+    let tint = Color::srgba(0.05, 0.08, 0.25, NIGHT_TINT_ALPHA * time_of_day.darkness());
+    for mut background in tints.iter_mut() {
+        background.set_if_neq(BackgroundColor(tint));
+    }
+}
+
+/// Make sure leaving the game never leaves time slowed down or paused
+pub fn reset_last_stand(mut virtual_time: ResMut<Time<Virtual>>, mut last_stand: ResMut<LastStand>) {
+    *last_stand = LastStand::default();
+    virtual_time.set_relative_speed(1.0);
+    virtual_time.unpause();
+}
+
+/// Pause an offline game while the window is in the background. Online the
+/// game is shared, so it keeps running and only the sound is muted.
+pub fn pause_on_focus_loss(
+    mut focus: MessageReader<WindowFocused>,
+    settings: Res<Settings>,
+    stdb: Option<SpacetimeDB>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    mut paused_by_focus: Local<bool>,
+) {
+    let Some(focused) = focus.read().last().map(|event| event.focused) else { return };
+    if focused {
+        if std::mem::take(&mut *paused_by_focus) {
+            virtual_time.unpause();
+        }
+    } else if settings.graphics.pause_on_focus_loss && stdb.is_none() && !virtual_time.is_paused() {
+        virtual_time.pause();
+        *paused_by_focus = true;
+    }
+}
+
+/// Width of each column in the game-over stats table
+const STATS_COLUMN_WIDTHS: [f32; 6] = [200.0, 80.0, 80.0, 80.0, 80.0, 80.0];
+
+pub fn setup_game_over_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    stdb: Option<SpacetimeDB>,
+    game_state: Res<GameState>,
+    stats: Res<GameStats>,
+) {
+    let rows = stats_breakdown(stdb.as_ref(), &game_state, &stats);
+
     commands
         .spawn((
             Node {
@@ -19,6 +146,7 @@ pub fn setup_game_over_screen(mut commands: Commands) {
                 justify_content: JustifyContent::Center,
                 align_items: AlignItems::Center,
                 flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.0),
                 ..default()
             },
             BackgroundColor(Color::linear_rgba(0.0, 0.0, 0.0, 0.7)),
@@ -33,7 +161,77 @@ pub fn setup_game_over_screen(mut commands: Commands) {
                     ..default()
                 },
                 TextColor(Color::WHITE),
+                Node {
+                    margin: UiRect::bottom(Val::Px(20.0)),
+                    ..default()
+                },
+            ));
+
+            parent.spawn((
+                Text::new(format!("Reached wave {}  -  Score {}", game_state.wave, game_state.score)),
+                TextFont {
+                    font_size: 22.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                Node {
+                    margin: UiRect::bottom(Val::Px(12.0)),
+                    ..default()
+                },
             ));
+
+            let header = ["Player", "Towers", "Kills", "Gold", "Wood", "Meat"].map(String::from);
+            spawn_stats_row(parent, &header, Color::srgb(1.0, 0.85, 0.3));
+            for row in rows {
+                let cells = [
+                    row.name,
+                    row.towers_built.to_string(),
+                    row.kills.to_string(),
+                    row.gold_earned.to_string(),
+                    row.wood_gathered.to_string(),
+                    row.meat_gathered.to_string(),
+                ];
+                spawn_stats_row(parent, &cells, Color::WHITE);
+            }
+
+            parent.spawn(Node {
+                height: Val::Px(24.0),
+                ..default()
+            });
+            spawn_nine_slice_button(parent, &asset_server, ButtonStyle::BigBlue, "Main Menu", GameOverMenuButton);
+        });
+}
+
+pub fn handle_game_over_buttons(
+    menu: Query<&Interaction, (Changed<Interaction>, With<GameOverMenuButton>)>,
+    mut leave_game: MessageWriter<LeaveGame>,
+) {
+    if menu.iter().any(|interaction| *interaction == Interaction::Pressed) {
+        leave_game.write(LeaveGame);
+    }
+}
+
+fn spawn_stats_row(parent: &mut ChildSpawnerCommands, cells: &[String; 6], color: Color) {
+    parent
+        .spawn(Node {
+            flex_direction: FlexDirection::Row,
+            ..default()
+        })
+        .with_children(|row| {
+            for (cell, width) in cells.iter().zip(STATS_COLUMN_WIDTHS) {
+                row.spawn((
+                    Text::new(cell.clone()),
+                    TextFont {
+                        font_size: 18.0,
+                        ..default()
+                    },
+                    TextColor(color),
+                    Node {
+                        width: Val::Px(width),
+                        ..default()
+                    },
+                ));
+            }
         });
 }
 
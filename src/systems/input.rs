@@ -0,0 +1,512 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::input::gamepad::{Gamepad, GamepadButton};
+use bevy::input::mouse::{AccumulatedMouseMotion, MouseWheel};
+use bevy::input::touch::{Touch, Touches};
+use bevy::input::InputSystems;
+use bevy::prelude::*;
+use bevy::ui::UiSystems;
+
+/// Stick values below this are treated as zero
+const STICK_DEADZONE: f32 = 0.15;
+/// Gamepad virtual cursor speed in screen pixels per second
+const GAMEPAD_CURSOR_SPEED: f32 = 600.0;
+/// Gamepad camera pan speed in screen pixels per second
+const GAMEPAD_PAN_SPEED: f32 = 600.0;
+/// Gamepad zoom speed in camera scale units per second
+const GAMEPAD_ZOOM_SPEED: f32 = 1.5;
+/// Zoom change per mouse wheel step
+const MOUSE_ZOOM_STEP: f32 = 0.1;
+/// Zoom change per pixel of pinch distance
+const PINCH_ZOOM_FACTOR: f32 = 0.005;
+
+/// Device-independent gameplay actions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputAction {
+    /// Open the tower wheel / pick a wheel option / click a map object
+    /// (left mouse, gamepad A, one-finger touch)
+    Select,
+    /// Close the open menu (right mouse, Escape, gamepad B, two-finger tap)
+    Cancel,
+    /// Show/hide optional overlays such as tower cooldown bars (C)
+    ToggleOverlays,
+    /// Modifier that turns a Select drag into a box selection (Shift)
+    MultiSelect,
+    /// Open/close the tower layout presets panel (P)
+    TogglePresets,
+    /// Show/hide the combat log (L)
+    ToggleCombatLog,
+    /// Show/hide the achievements panel (H)
+    ToggleAchievements,
+    /// Write a diagnostics snapshot for bug reports (F12)
+    DumpDiagnostics,
+    /// Make towers focus the enemy under the pointer (F, gamepad Y)
+    FocusFire,
+    /// Take back the tower just placed (Ctrl + Z; the key is bindable, Ctrl
+    /// is always required)
+    UndoPlacement,
+    /// Select dragged while the MultiSelect modifier was held; replaces Select
+    /// for the whole drag
+    BoxSelect,
+    /// Open/close the pause menu (the Cancel key, gamepad Start). Unlike
+    /// Cancel it ignores the right mouse button.
+    Menu,
+}
+
+impl InputAction {
+    /// Actions that can be bound to a keyboard key
+    pub const BINDABLE: [InputAction; 10] = [
+        InputAction::Select,
+        InputAction::Cancel,
+        InputAction::ToggleOverlays,
+        InputAction::MultiSelect,
+        InputAction::TogglePresets,
+        InputAction::ToggleCombatLog,
+        InputAction::ToggleAchievements,
+        InputAction::DumpDiagnostics,
+        InputAction::FocusFire,
+        InputAction::UndoPlacement,
+    ];
+
+    /// Name used in the key bindings file
+    pub fn id(&self) -> &'static str {
+        match self {
+            InputAction::Select => "select",
+            InputAction::Cancel => "cancel",
+            InputAction::ToggleOverlays => "toggle_overlays",
+            InputAction::MultiSelect => "multi_select",
+            InputAction::TogglePresets => "toggle_presets",
+            InputAction::ToggleCombatLog => "toggle_combat_log",
+            InputAction::ToggleAchievements => "toggle_achievements",
+            InputAction::DumpDiagnostics => "dump_diagnostics",
+            InputAction::FocusFire => "focus_fire",
+            InputAction::UndoPlacement => "undo_placement",
+            InputAction::BoxSelect => "box_select",
+            InputAction::Menu => "menu",
+        }
+    }
+
+    /// Name shown in the settings menu
+    pub fn label(&self) -> &'static str {
+        match self {
+            InputAction::Select => "Select",
+            InputAction::Cancel => "Cancel / Close",
+            InputAction::ToggleOverlays => "Toggle Overlays",
+            InputAction::MultiSelect => "Box Select (hold)",
+            InputAction::TogglePresets => "Tower Presets",
+            InputAction::ToggleCombatLog => "Combat Log",
+            InputAction::ToggleAchievements => "Achievements",
+            InputAction::DumpDiagnostics => "Diagnostics Dump",
+            InputAction::FocusFire => "Focus Fire",
+            InputAction::UndoPlacement => "Undo Placement (Ctrl+)",
+            InputAction::BoxSelect => "Box Select",
+            InputAction::Menu => "Pause Menu",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::BINDABLE.into_iter().find(|action| action.id() == id)
+    }
+}
+
+/// Keys that can be bound, used to parse key names from the bindings file
+const BINDABLE_KEYS: &[KeyCode] = &[
+    KeyCode::KeyA, KeyCode::KeyB, KeyCode::KeyC, KeyCode::KeyD, KeyCode::KeyE, KeyCode::KeyF,
+    KeyCode::KeyG, KeyCode::KeyH, KeyCode::KeyI, KeyCode::KeyJ, KeyCode::KeyK, KeyCode::KeyL,
+    KeyCode::KeyM, KeyCode::KeyN, KeyCode::KeyO, KeyCode::KeyP, KeyCode::KeyQ, KeyCode::KeyR,
+    KeyCode::KeyS, KeyCode::KeyT, KeyCode::KeyU, KeyCode::KeyV, KeyCode::KeyW, KeyCode::KeyX,
+    KeyCode::KeyY, KeyCode::KeyZ,
+    KeyCode::Digit0, KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3, KeyCode::Digit4,
+    KeyCode::Digit5, KeyCode::Digit6, KeyCode::Digit7, KeyCode::Digit8, KeyCode::Digit9,
+    KeyCode::F1, KeyCode::F2, KeyCode::F3, KeyCode::F4, KeyCode::F5, KeyCode::F6,
+    KeyCode::F7, KeyCode::F8, KeyCode::F9, KeyCode::F10, KeyCode::F11, KeyCode::F12,
+    KeyCode::Escape, KeyCode::Space, KeyCode::Tab, KeyCode::Enter, KeyCode::Backspace,
+    KeyCode::ArrowUp, KeyCode::ArrowDown, KeyCode::ArrowLeft, KeyCode::ArrowRight,
+    KeyCode::ShiftLeft, KeyCode::ShiftRight, KeyCode::ControlLeft, KeyCode::ControlRight,
+    KeyCode::AltLeft, KeyCode::AltRight, KeyCode::Backquote, KeyCode::Minus, KeyCode::Equal,
+];
+
+/// Display/storage name of a key, e.g. "KeyC" or "Escape"
+pub fn key_name(key: KeyCode) -> String {
+    format!("{:?}", key)
+}
+
+/// Whether `key` can be stored in the bindings file
+pub fn is_bindable_key(key: KeyCode) -> bool {
+    BINDABLE_KEYS.contains(&key)
+}
+
+fn key_from_name(name: &str) -> Option<KeyCode> {
+    BINDABLE_KEYS.iter().copied().find(|key| key_name(*key) == name)
+}
+
+/// Keyboard key for each bindable action, persisted through `Settings`
+#[derive(Resource, Clone)]
+pub struct KeyBindings {
+    bindings: HashMap<InputAction, KeyCode>,
+}
+
+impl KeyBindings {
+    pub fn defaults() -> Self {
+        Self {
+            bindings: HashMap::from([
+                (InputAction::Cancel, KeyCode::Escape),
+                (InputAction::ToggleOverlays, KeyCode::KeyC),
+                (InputAction::MultiSelect, KeyCode::ShiftLeft),
+                (InputAction::TogglePresets, KeyCode::KeyP),
+                (InputAction::ToggleCombatLog, KeyCode::KeyL),
+                (InputAction::ToggleAchievements, KeyCode::KeyH),
+                (InputAction::DumpDiagnostics, KeyCode::F12),
+                (InputAction::FocusFire, KeyCode::KeyF),
+                (InputAction::UndoPlacement, KeyCode::KeyZ),
+            ]),
+        }
+    }
+
+    pub fn key(&self, action: InputAction) -> Option<KeyCode> {
+        self.bindings.get(&action).copied()
+    }
+
+    /// Bind `key` to `action`, unbinding it from any other action
+    pub fn set(&mut self, action: InputAction, key: KeyCode) {
+        self.bindings.retain(|_, bound| *bound != key);
+        self.bindings.insert(action, key);
+    }
+
+    pub fn pressed(&self, keyboard: &ButtonInput<KeyCode>, action: InputAction) -> bool {
+        self.key(action).is_some_and(|key| keyboard.pressed(key))
+    }
+
+    /// Build bindings from persisted `action id -> key name` pairs, starting
+    /// from the defaults so actions missing from the map keep a key
+    pub fn from_names(names: &HashMap<String, String>) -> Self {
+        let mut bindings = Self::defaults();
+        for (action_id, key) in names {
+            match (InputAction::from_id(action_id), key_from_name(key)) {
+                (Some(action), Some(key)) => bindings.set(action, key),
+                _ => warn!("Ignoring unknown key binding '{}' = '{}'", action_id, key),
+            }
+        }
+        bindings
+    }
+
+    /// Bindings as `action id -> key name` pairs for the settings file
+    pub fn to_names(&self) -> HashMap<String, String> {
+        self.bindings
+            .iter()
+            .map(|(action, key)| (action.id().to_string(), key_name(*key)))
+            .collect()
+    }
+}
+
+/// Which device currently drives the pointer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PointerSource {
+    #[default]
+    Mouse,
+    Gamepad,
+    Touch,
+}
+
+impl PointerSource {
+    /// Touch and gamepad have no hover state, so menus open on tap and
+    /// tooltips show on hold instead
+    pub fn has_hover(&self) -> bool {
+        *self == PointerSource::Mouse
+    }
+}
+
+/// Per-frame action state built from mouse, keyboard and gamepad input.
+/// Gameplay systems read this instead of the raw input resources.
+#[derive(Resource, Default)]
+pub struct ActionState {
+    pressed: HashSet<InputAction>,
+    just_pressed: HashSet<InputAction>,
+    just_released: HashSet<InputAction>,
+    pub source: PointerSource,
+    /// Pointer position in window coordinates
+    pub pointer_screen: Option<Vec2>,
+    /// Pointer position in world coordinates
+    pub pointer_world: Option<Vec2>,
+    /// Camera drag this frame in screen pixels (camera moves opposite to it)
+    pub pan: Vec2,
+    /// Camera scale change this frame (positive = zoom out)
+    pub zoom: f32,
+    /// How long Select has been held, for tap-and-hold tooltips
+    pub select_hold_secs: f32,
+    /// Set every frame by UI while a text field has focus; key bindings are
+    /// ignored on the next frame and the flag is cleared
+    pub text_entry: bool,
+}
+
+impl ActionState {
+    pub fn pressed(&self, action: InputAction) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    pub fn just_pressed(&self, action: InputAction) -> bool {
+        self.just_pressed.contains(&action)
+    }
+
+    pub fn just_released(&self, action: InputAction) -> bool {
+        self.just_released.contains(&action)
+    }
+
+    /// Record whether any device currently holds `action`
+    fn set_held(&mut self, action: InputAction, held: bool) {
+        if held {
+            if self.pressed.insert(action) {
+                self.just_pressed.insert(action);
+            }
+        } else if self.pressed.remove(&action) {
+            self.just_released.insert(action);
+        }
+    }
+
+    fn begin_frame(&mut self) {
+        self.just_pressed.clear();
+        self.just_released.clear();
+        self.pan = Vec2::ZERO;
+        self.zoom = 0.0;
+    }
+}
+
+fn stick(value: Vec2) -> Vec2 {
+    if value.length() < STICK_DEADZONE {
+        Vec2::ZERO
+    } else {
+        value
+    }
+}
+
+/// Build the `ActionState` for this frame from every input device
+pub fn collect_input_actions(
+    mut actions: ResMut<ActionState>,
+    mouse_button: Res<ButtonInput<MouseButton>>,
+    mouse_motion: Res<AccumulatedMouseMotion>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut scroll_events: MessageReader<MouseWheel>,
+    mut cursor_events: MessageReader<CursorMoved>,
+    gamepads: Query<&Gamepad>,
+    touches: Res<Touches>,
+    mut windows: Query<&mut Window>,
+    mut last_cursor: Local<Option<Vec2>>,
+    time: Res<Time>,
+) {
+    actions.begin_frame();
+    let dt = time.delta_secs();
+
+    // ---- Mouse ----
+    if mouse_motion.delta != Vec2::ZERO || mouse_button.get_just_pressed().next().is_some() {
+        actions.source = PointerSource::Mouse;
+    }
+
+    for event in scroll_events.read() {
+        actions.zoom += event.y * MOUSE_ZOOM_STEP;
+    }
+
+    // Right or middle drag pans the camera
+    let is_dragging =
+        mouse_button.pressed(MouseButton::Right) || mouse_button.pressed(MouseButton::Middle);
+    for event in cursor_events.read() {
+        if is_dragging
+            && actions.source == PointerSource::Mouse
+            && let Some(last) = *last_cursor
+        {
+            actions.pan += event.position - last;
+        }
+        *last_cursor = Some(event.position);
+    }
+
+    // Keyboard bindings are ignored while a text field has focus
+    let text_entry = std::mem::take(&mut actions.text_entry);
+    let key_held = |action| !text_entry && bindings.pressed(&keyboard, action);
+
+    let mut select_held =
+        mouse_button.pressed(MouseButton::Left) || key_held(InputAction::Select);
+    let mut cancel_held =
+        mouse_button.pressed(MouseButton::Right) || key_held(InputAction::Cancel);
+    let mut menu_held = key_held(InputAction::Cancel);
+    let mut focus_fire_held = key_held(InputAction::FocusFire);
+
+    // ---- Gamepad ----
+    let mut gamepad_cursor_delta = Vec2::ZERO;
+    for gamepad in gamepads.iter() {
+        let left = stick(gamepad.left_stick());
+        let right = stick(gamepad.right_stick());
+        let buttons_used = gamepad.get_just_pressed().next().is_some();
+
+        if left != Vec2::ZERO || right != Vec2::ZERO || buttons_used {
+            actions.source = PointerSource::Gamepad;
+        }
+
+        select_held |= gamepad.pressed(GamepadButton::South);
+        cancel_held |= gamepad.pressed(GamepadButton::East);
+        menu_held |= gamepad.pressed(GamepadButton::Start);
+        focus_fire_held |= gamepad.pressed(GamepadButton::North);
+
+        // Screen space has y pointing down
+        gamepad_cursor_delta += Vec2::new(left.x, -left.y) * GAMEPAD_CURSOR_SPEED * dt;
+        actions.pan += Vec2::new(-right.x, right.y) * GAMEPAD_PAN_SPEED * dt;
+
+        if gamepad.pressed(GamepadButton::LeftTrigger) {
+            actions.zoom += GAMEPAD_ZOOM_SPEED * dt;
+        }
+        if gamepad.pressed(GamepadButton::RightTrigger) {
+            actions.zoom -= GAMEPAD_ZOOM_SPEED * dt;
+        }
+    }
+
+    // ---- Touch ----
+    // One finger acts as Select at the finger, two fingers pinch-zoom and pan
+    let active_touches: Vec<&Touch> = touches.iter().collect();
+    let mut touch_position = None;
+    if touches.any_just_pressed() || !active_touches.is_empty() {
+        actions.source = PointerSource::Touch;
+    }
+    match active_touches.as_slice() {
+        [touch] => {
+            select_held = true;
+            touch_position = Some(touch.position());
+        }
+        [first, second, ..] => {
+            let distance = first.position().distance(second.position());
+            let previous_distance = first.previous_position().distance(second.previous_position());
+            actions.zoom -= (distance - previous_distance) * PINCH_ZOOM_FACTOR;
+
+            let midpoint = (first.position() + second.position()) / 2.0;
+            let previous_midpoint = (first.previous_position() + second.previous_position()) / 2.0;
+            actions.pan += midpoint - previous_midpoint;
+
+            // A second finger landing cancels the open menu
+            if touches.iter_just_pressed().next().is_some() {
+                cancel_held = true;
+            }
+        }
+        [] => {
+            // Report the release where the finger was lifted
+            touch_position = touches.iter_just_released().next().map(|touch| touch.position());
+        }
+    }
+
+    // A press that starts with the modifier held becomes a box selection
+    // until it is released, so the other Select consumers never see it
+    let multi_select_held = key_held(InputAction::MultiSelect);
+    let box_selecting = select_held
+        && (actions.pressed(InputAction::BoxSelect)
+            || (multi_select_held && !actions.pressed(InputAction::Select)));
+    actions.set_held(InputAction::MultiSelect, multi_select_held);
+    actions.set_held(InputAction::BoxSelect, box_selecting);
+    actions.set_held(InputAction::Select, select_held && !box_selecting);
+    actions.set_held(InputAction::Cancel, cancel_held);
+    actions.set_held(InputAction::Menu, menu_held);
+    if actions.pressed.contains(&InputAction::Select) {
+        actions.select_hold_secs += dt;
+    } else {
+        actions.select_hold_secs = 0.0;
+    }
+    actions.set_held(
+        InputAction::ToggleOverlays,
+        key_held(InputAction::ToggleOverlays),
+    );
+    actions.set_held(
+        InputAction::TogglePresets,
+        key_held(InputAction::TogglePresets),
+    );
+    actions.set_held(
+        InputAction::ToggleCombatLog,
+        key_held(InputAction::ToggleCombatLog),
+    );
+    actions.set_held(
+        InputAction::ToggleAchievements,
+        key_held(InputAction::ToggleAchievements),
+    );
+    actions.set_held(
+        InputAction::DumpDiagnostics,
+        key_held(InputAction::DumpDiagnostics),
+    );
+    actions.set_held(InputAction::FocusFire, focus_fire_held);
+    let ctrl_held = keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    actions.set_held(
+        InputAction::UndoPlacement,
+        ctrl_held && key_held(InputAction::UndoPlacement),
+    );
+
+    // ---- Pointer ----
+    let Ok(mut window) = windows.single_mut() else {
+        actions.pointer_screen = None;
+        return;
+    };
+
+    actions.pointer_screen = match actions.source {
+        PointerSource::Mouse => window.cursor_position(),
+        PointerSource::Touch => touch_position.or(actions.pointer_screen),
+        PointerSource::Gamepad => {
+            let size = window.size();
+            let current = actions
+                .pointer_screen
+                .or_else(|| window.cursor_position())
+                .unwrap_or(size / 2.0);
+            let moved = (current + gamepad_cursor_delta).clamp(Vec2::ZERO, size);
+
+            // Warp the OS cursor so UI hover and the custom cursor follow the stick
+            if moved != current || window.cursor_position().is_none() {
+                window.set_cursor_position(Some(moved));
+            }
+            Some(moved)
+        }
+    };
+}
+
+/// Convert the pointer to world space once the camera has been positioned
+pub fn update_pointer_world(
+    mut actions: ResMut<ActionState>,
+    camera: Query<(&Camera, &GlobalTransform)>,
+) {
+    actions.pointer_world = actions.pointer_screen.and_then(|screen_pos| {
+        let (camera, camera_transform) = camera.single().ok()?;
+        camera.viewport_to_world_2d(camera_transform, screen_pos).ok()
+    });
+}
+
+/// Let gamepad A press whichever UI button the virtual cursor hovers
+pub fn press_hovered_buttons(
+    actions: Res<ActionState>,
+    mut buttons: Query<&mut Interaction, With<Button>>,
+) {
+    if actions.source != PointerSource::Gamepad || !actions.just_pressed(InputAction::Select) {
+        return;
+    }
+
+    for mut interaction in buttons.iter_mut() {
+        if *interaction == Interaction::Hovered {
+            *interaction = Interaction::Pressed;
+        }
+    }
+}
+
+/// Clicks that land on UI (buttons, blocking panels) don't reach the world
+pub fn swallow_ui_clicks(mut actions: ResMut<ActionState>, interactions: Query<&Interaction>) {
+    if actions.just_pressed(InputAction::Select)
+        && interactions.iter().any(|interaction| *interaction == Interaction::Pressed)
+    {
+        actions.just_pressed.remove(&InputAction::Select);
+    }
+}
+
+pub struct InputActionPlugin;
+
+impl Plugin for InputActionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActionState>().add_systems(
+            PreUpdate,
+            (
+                collect_input_actions.after(InputSystems).before(UiSystems::Focus),
+                update_pointer_world.after(collect_input_actions),
+                press_hovered_buttons.after(UiSystems::Focus),
+                swallow_ui_clicks.after(press_hovered_buttons),
+            ),
+        );
+    }
+}
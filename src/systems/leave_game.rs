@@ -0,0 +1,129 @@
+use bevy::ecs::entity::EntityHashSet;
+use bevy::prelude::*;
+
+use crate::config::WavesConfig;
+use crate::events::LeaveGame;
+use crate::module_bindings::leave_game_reducer::leave_game as leave_server_game;
+use crate::resources::{
+    AppState, BlockedTiles, CombatLog, EndlessMode, EnemySpawner, FogOfWar, GameState, HouseMenuState, PathWaypoints,
+    RallyPoint, RecruitMenuState, SelectedTowers, TowerMoveState, TowerUpgradeMenuState, TowerWheelState,
+    WaveConfigs, WorkerAllocation,
+};
+use crate::systems::networking::SpacetimeDB;
+use crate::systems::pathfinding::compute_enemy_path;
+use crate::systems::stats::GameStats;
+
+/// Entities that existed when the game started. Everything else was spawned
+/// by the game and goes when the player leaves it.
+#[derive(Resource)]
+struct PreGameEntities(EntityHashSet);
+
+pub struct LeaveGamePlugin;
+
+impl Plugin for LeaveGamePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnTransition {
+                exited: AppState::Loading,
+                entered: AppState::InGame,
+            },
+            record_pre_game_entities,
+        )
+        .add_systems(
+            Update,
+            leave_game.run_if(in_state(AppState::InGame).or(in_state(AppState::GameOver))),
+        )
+        .add_systems(
+            OnTransition {
+                exited: AppState::InGame,
+                entered: AppState::MainMenu,
+            },
+            (despawn_game_entities, reset_game_resources).chain(),
+        )
+        .add_systems(
+            OnTransition {
+                exited: AppState::GameOver,
+                entered: AppState::MainMenu,
+            },
+            (despawn_game_entities, reset_game_resources).chain(),
+        );
+    }
+}
+
+fn record_pre_game_entities(world: &mut World) {
+    let entities = world.query::<Entity>().iter(world).collect();
+    world.insert_resource(PreGameEntities(entities));
+}
+
+/// Tell the server we left and go back to the main menu. The connection
+/// stays open, so starting another game rejoins right away.
+fn leave_game(
+    mut requests: MessageReader<LeaveGame>,
+    stdb: Option<SpacetimeDB>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if requests.read().count() == 0 {
+        return;
+    }
+    if let Some(stdb) = stdb
+        && let Err(e) = stdb.reducers().leave_game()
+    {
+        error!("Failed to leave the game on the server: {}", e);
+    }
+    info!("Leaving the game");
+    next_state.set(AppState::MainMenu);
+}
+
+/// Despawn the map, units, effects and game UI: every entity that wasn't
+/// there before the game started
+fn despawn_game_entities(world: &mut World) {
+    let Some(PreGameEntities(kept)) = world.remove_resource::<PreGameEntities>() else { return };
+    let spawned: Vec<Entity> = world.query::<Entity>().iter(world).filter(|entity| !kept.contains(entity)).collect();
+    for entity in spawned {
+        // Children go with their parent, so some are gone already
+        if let Ok(entity_mut) = world.get_entity_mut(entity) {
+            entity_mut.despawn();
+        }
+    }
+}
+
+/// Put the game's resources back the way a new game expects them. Rules,
+/// settings and sandbox mode are the player's choices and stay.
+fn reset_game_resources(
+    mut commands: Commands,
+    endless: Res<EndlessMode>,
+    waves_config: Res<WavesConfig>,
+    mut wave_configs: ResMut<WaveConfigs>,
+    mut blocked_tiles: ResMut<BlockedTiles>,
+    mut waypoints: ResMut<PathWaypoints>,
+) {
+    commands.insert_resource(GameState::default());
+    commands.insert_resource(GameStats::default());
+    commands.insert_resource(CombatLog::default());
+    commands.insert_resource(FogOfWar::new());
+    commands.insert_resource(SelectedTowers::default());
+    commands.insert_resource(TowerMoveState::default());
+    commands.insert_resource(TowerUpgradeMenuState::default());
+    commands.insert_resource(TowerWheelState {
+        active: false,
+        position: Vec2::ZERO,
+        tapped_open: false,
+    });
+    commands.insert_resource(HouseMenuState::default());
+    commands.insert_resource(RecruitMenuState::default());
+    commands.insert_resource(WorkerAllocation::default());
+    commands.insert_resource(RallyPoint::default());
+
+    // An endless run swapped out the authored waves
+    if endless.enabled {
+        wave_configs.waves = waves_config.waves.clone();
+    }
+    if let Some(first_wave) = wave_configs.waves.first() {
+        commands.insert_resource(EnemySpawner::from_wave_config(first_wave));
+    }
+
+    blocked_tiles.walls.clear();
+    if let Some(points) = compute_enemy_path(&blocked_tiles, None) {
+        waypoints.points = points;
+    }
+}
@@ -0,0 +1,348 @@
+use bevy::prelude::*;
+use bevy_spacetimedb::StdbConnection;
+
+use crate::config::{find_missing_assets, AmbientConfig};
+use crate::constants::LOADING_TIMEOUT;
+use crate::module_bindings::DbConnection;
+use crate::resources::{AppState, ConnectionStatus, TowerConfigs, WaveConfigs};
+use crate::systems::menu::{ButtonStyle, spawn_nine_slice_button};
+use crate::systems::status_effects::FLAME_SPRITE;
+use crate::systems::worker::{house_sprite_path, HOUSE_TIERS, RALLY_FLAG_SPRITE};
+
+const LOADING_BACKGROUND: Color = Color::srgb(0.15, 0.28, 0.32);
+const PROGRESS_BAR_WIDTH: f32 = 360.0;
+const PROGRESS_FILL_COLOR: Color = Color::srgb(0.3, 0.7, 0.4);
+const LOADING_ERROR_COLOR: Color = Color::srgb(0.95, 0.4, 0.35);
+/// Missing assets listed by name on the loading screen; the rest are counted
+const MISSING_ASSETS_SHOWN: usize = 12;
+const PLAYER_COLOR_DIRS: [&str; 4] = ["Blue", "Yellow", "Purple", "Black"];
+/// Every worker animation and the avatar, under each color's Pawn folder
+const PAWN_SPRITES: [&str; 12] = [
+    "Pawn_Avatar.png",
+    "Pawn_Idle.png",
+    "Pawn_Run.png",
+    "Pawn_Run Axe.png",
+    "Pawn_Run Pickaxe.png",
+    "Pawn_Run Knife.png",
+    "Pawn_Interact Axe.png",
+    "Pawn_Interact Pickaxe.png",
+    "Pawn_Interact Knife.png",
+    "Pawn_Run Wood.png",
+    "Pawn_Run Gold.png",
+    "Pawn_Run Meat.png",
+];
+
+/// Sprites requested before the game starts. The handles are kept for the
+/// whole session so nothing is unloaded before the scene spawns it.
+#[derive(Resource, Default)]
+pub struct PreloadedAssets {
+    pub images: Vec<Handle<Image>>,
+}
+
+/// Referenced asset files that don't exist, found once at startup. The game
+/// still runs, drawing the `MissingTexture` placeholder where they would be.
+#[derive(Resource, Default)]
+pub struct MissingAssets {
+    pub paths: Vec<String>,
+}
+
+/// Progress of the current loading screen
+#[derive(Resource, Default)]
+pub struct LoadingProgress {
+    elapsed: f32,
+    /// Stopped on missing assets or a timeout; waiting for the player to
+    /// choose how to go on
+    halted: bool,
+}
+
+/// Root of the loading screen
+#[derive(Component)]
+pub struct LoadingScreen;
+
+#[derive(Component)]
+pub struct LoadingStatusText;
+
+#[derive(Component)]
+pub struct LoadingProgressFill;
+
+/// Holds the buttons shown after a timeout
+#[derive(Component)]
+pub struct LoadingErrorActions;
+
+#[derive(Component)]
+pub struct ContinueAnywayButton;
+
+#[derive(Component)]
+pub struct BackToMenuButton;
+
+/// Shows a progress screen between the menus and the game until the sprites
+/// are loaded and the server connection, if one was started, is up
+pub struct LoadingPlugin;
+
+impl Plugin for LoadingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PreloadedAssets>()
+            .init_resource::<MissingAssets>()
+            .init_resource::<LoadingProgress>()
+            .add_systems(Startup, validate_assets)
+            .add_systems(OnEnter(AppState::Loading), (preload_assets, setup_loading_screen))
+            .add_systems(
+                Update,
+                (update_loading, handle_loading_error_buttons).run_if(in_state(AppState::Loading)),
+            )
+            .add_systems(OnExit(AppState::Loading), cleanup_loading_screen);
+    }
+}
+
+/// Tower, projectile and unit sprites from the config files
+fn configured_sprites<'a>(towers: &'a TowerConfigs, waves: &'a WaveConfigs) -> impl Iterator<Item = &'a str> {
+    towers
+        .towers
+        .iter()
+        .flat_map(|tower| [tower.sprite_path.as_str(), tower.projectile_sprite.as_str()])
+        .chain(waves.units.iter().map(|unit| unit.sprite_path.as_str()))
+}
+
+/// Assets the code loads by name rather than from a config file
+fn fixed_asset_paths() -> Vec<String> {
+    let mut paths: Vec<String> = [
+        "map.tmx",
+        FLAME_SPRITE,
+        RALLY_FLAG_SPRITE,
+        "UI Elements/UI Elements/Icons/Gold_Icon.png",
+        "UI Elements/UI Elements/Bars/SmallBar_Base.png",
+        "Terrain/Resources/Meat/Sheep/Sheep_Idle.png",
+        "Terrain/Resources/Wood/Trees/Stump 1.png",
+    ]
+    .map(String::from)
+    .into();
+    paths.extend((1..=4).map(|i| format!("Terrain/Resources/Wood/Trees/Tree{}.png", i)));
+    paths.extend((4..=6).map(|i| format!("Terrain/Resources/Gold/Gold Stones/Gold Stone {}_Highlight.png", i)));
+    for color_dir in PLAYER_COLOR_DIRS {
+        paths.push(format!("Decorations/Buildings/{} Buildings/Castle.png", color_dir));
+        paths.extend((1..=HOUSE_TIERS.len()).map(|level| house_sprite_path(color_dir, level)));
+        paths.extend(PAWN_SPRITES.iter().map(|sprite| format!("Units/{} Units/Pawn/{}", color_dir, sprite)));
+    }
+    paths
+}
+
+/// Check that every asset the configs and code refer to exists, so content
+/// mistakes show up at launch rather than as an invisible enemy in wave 7
+fn validate_assets(towers: Res<TowerConfigs>, waves: Res<WaveConfigs>, mut missing: ResMut<MissingAssets>) {
+    let ambient = AmbientConfig::load().unwrap_or_default();
+    let fixed = fixed_asset_paths();
+    missing.paths = find_missing_assets(
+        configured_sprites(&towers, &waves)
+            .chain(waves.units.iter().map(|unit| unit.avatar_path.as_str()))
+            .chain(ambient.decorations.iter().map(|decoration| decoration.sprite_path.as_str()))
+            .chain(fixed.iter().map(String::as_str)),
+    );
+    for path in &missing.paths {
+        error!("Missing asset: {}", path);
+    }
+}
+
+/// Request every tower, projectile and unit sprite so the first wave doesn't
+/// stall on the disk
+fn preload_assets(
+    asset_server: Res<AssetServer>,
+    towers: Res<TowerConfigs>,
+    waves: Res<WaveConfigs>,
+    mut preloaded: ResMut<PreloadedAssets>,
+    mut progress: ResMut<LoadingProgress>,
+) {
+    *progress = LoadingProgress::default();
+    let mut paths: Vec<&str> = configured_sprites(&towers, &waves)
+        .filter(|path| !path.is_empty())
+        .collect();
+    paths.sort_unstable();
+    paths.dedup();
+    preloaded.images = paths.into_iter().map(|path| asset_server.load(path.to_string())).collect();
+}
+
+fn setup_loading_screen(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(20.0),
+                ..default()
+            },
+            BackgroundColor(LOADING_BACKGROUND),
+            LoadingScreen,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Loading"),
+                TextFont { font_size: 32.0, ..default() },
+                TextColor(Color::WHITE),
+            ));
+
+            parent
+                .spawn((
+                    Node {
+                        width: Val::Px(PROGRESS_BAR_WIDTH),
+                        height: Val::Px(16.0),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(0.1, 0.18, 0.22)),
+                    BorderRadius::all(Val::Px(8.0)),
+                ))
+                .with_children(|bar| {
+                    bar.spawn((
+                        Node {
+                            width: Val::Percent(0.0),
+                            height: Val::Percent(100.0),
+                            ..default()
+                        },
+                        BackgroundColor(PROGRESS_FILL_COLOR),
+                        BorderRadius::all(Val::Px(8.0)),
+                        LoadingProgressFill,
+                    ));
+                });
+
+            parent.spawn((
+                Text::new(""),
+                TextFont { font_size: 16.0, ..default() },
+                TextColor(Color::srgb(0.7, 0.78, 0.78)),
+                LoadingStatusText,
+            ));
+
+            parent
+                .spawn((
+                    Node {
+                        display: Display::None,
+                        column_gap: Val::Px(20.0),
+                        ..default()
+                    },
+                    LoadingErrorActions,
+                ))
+                .with_children(|actions| {
+                    spawn_nine_slice_button(actions, &asset_server, ButtonStyle::BigBlue, "Continue", ContinueAnywayButton);
+                    spawn_nine_slice_button(actions, &asset_server, ButtonStyle::BigRed, "Back", BackToMenuButton);
+                });
+        });
+}
+
+/// Track sprites and the connection, starting the game once both are ready.
+/// Missing assets or a timeout stop here instead, offering a way on or out.
+#[allow(clippy::too_many_arguments)]
+fn update_loading(
+    time: Res<Time>,
+    asset_server: Res<AssetServer>,
+    preloaded: Res<PreloadedAssets>,
+    missing: Res<MissingAssets>,
+    stdb: Option<Res<StdbConnection<DbConnection>>>,
+    status: Res<ConnectionStatus>,
+    mut progress: ResMut<LoadingProgress>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut status_text: Query<(&mut Text, &mut TextColor), With<LoadingStatusText>>,
+    mut fill: Query<&mut Node, (With<LoadingProgressFill>, Without<LoadingErrorActions>)>,
+    mut actions: Query<&mut Node, (With<LoadingErrorActions>, Without<LoadingProgressFill>)>,
+) {
+    if progress.halted {
+        return;
+    }
+    progress.elapsed += time.delta_secs();
+
+    // A sprite that failed to load is done as far as waiting goes; the game
+    // draws a placeholder instead
+    let total = preloaded.images.len();
+    let failed = preloaded
+        .images
+        .iter()
+        .filter(|handle| asset_server.load_state(*handle).is_failed())
+        .count();
+    let loaded = preloaded
+        .images
+        .iter()
+        .filter(|handle| asset_server.is_loaded_with_dependencies(*handle))
+        .count();
+    let assets_ready = loaded + failed >= total;
+    // Without a connection resource the player chose to play offline
+    let connection_ready = stdb.is_none() || *status == ConnectionStatus::Connected;
+
+    let steps = total + 1;
+    let done = loaded + failed + usize::from(connection_ready);
+    if let Ok(mut fill) = fill.single_mut() {
+        fill.width = Val::Percent(100.0 * done as f32 / steps as f32);
+    }
+
+    let ready = assets_ready && connection_ready;
+    if ready && failed > 0 {
+        warn!("{} of {} sprites failed to load", failed, total);
+    }
+    if ready && missing.paths.is_empty() {
+        next_state.set(AppState::InGame);
+        return;
+    }
+
+    let Ok((mut text, mut color)) = status_text.single_mut() else {
+        return;
+    };
+    if !ready && progress.elapsed < LOADING_TIMEOUT {
+        text.0 = if assets_ready {
+            "Connecting to the server...".to_string()
+        } else {
+            format!("Loading sprites {}/{}", loaded + failed, total)
+        };
+        return;
+    }
+
+    progress.halted = true;
+    text.0 = if ready {
+        missing_assets_message(&missing.paths)
+    } else {
+        warn!(
+            "Loading timed out after {}s: {}/{} sprites, connected: {}",
+            LOADING_TIMEOUT,
+            loaded + failed,
+            total,
+            connection_ready
+        );
+        if connection_ready {
+            "Loading is taking too long.".to_string()
+        } else {
+            "Could not reach the server. Continue offline?".to_string()
+        }
+    };
+    color.0 = LOADING_ERROR_COLOR;
+    if let Ok(mut actions) = actions.single_mut() {
+        actions.display = Display::Flex;
+    }
+}
+
+/// The first few missing paths, one per line, and how many more there are
+fn missing_assets_message(paths: &[String]) -> String {
+    let mut message = format!("{} asset files are missing and will show as a magenta placeholder:", paths.len());
+    for path in paths.iter().take(MISSING_ASSETS_SHOWN) {
+        message.push('\n');
+        message.push_str(path);
+    }
+    if paths.len() > MISSING_ASSETS_SHOWN {
+        message.push_str(&format!("\n...and {} more (see the log)", paths.len() - MISSING_ASSETS_SHOWN));
+    }
+    message
+}
+
+fn handle_loading_error_buttons(
+    continue_button: Query<&Interaction, (Changed<Interaction>, With<ContinueAnywayButton>)>,
+    back_button: Query<&Interaction, (Changed<Interaction>, With<BackToMenuButton>)>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if continue_button.iter().any(|interaction| *interaction == Interaction::Pressed) {
+        next_state.set(AppState::InGame);
+    } else if back_button.iter().any(|interaction| *interaction == Interaction::Pressed) {
+        next_state.set(AppState::MainMenu);
+    }
+}
+
+fn cleanup_loading_screen(mut commands: Commands, screens: Query<Entity, With<LoadingScreen>>) {
+    for screen in screens.iter() {
+        commands.entity(screen).despawn();
+    }
+}
@@ -1,4 +1,5 @@
-use crate::resources::AppState;
+use crate::resources::{AppState, EconomyMode, EconomySetup, EndlessMode, StdbConfig};
+use crate::systems::settings::SettingsMenuState;
 use bevy::prelude::*;
 
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
@@ -108,6 +109,10 @@ struct MenuUI;
 #[derive(Component)]
 struct PlayButton;
 
+/// Starts a game in `EndlessMode`
+#[derive(Component)]
+struct EndlessButton;
+
 #[derive(Component)]
 struct SettingsButton;
 
@@ -117,6 +122,22 @@ struct QuitButton;
 #[derive(Component)]
 pub struct LoginButton;
 
+/// Checkbox that toggles `StdbConfig::fresh_identity`
+#[derive(Component)]
+struct NewIdentityCheckbox;
+
+/// Tick mark inside the new identity checkbox
+#[derive(Component)]
+struct NewIdentityCheckText;
+
+/// Checkbox that picks the shared economy in `EconomySetup`
+#[derive(Component)]
+struct SharedGoldCheckbox;
+
+/// Tick mark inside the shared gold checkbox
+#[derive(Component)]
+struct SharedGoldCheckText;
+
 pub struct MenuPlugin;
 
 impl Plugin for MenuPlugin {
@@ -127,10 +148,15 @@ impl Plugin for MenuPlugin {
                 Update,
                 (
                     button_interaction::<PlayButton>,
+                    button_interaction::<EndlessButton>,
                     button_interaction::<SettingsButton>,
                     button_interaction::<QuitButton>,
                     button_interaction::<LoginButton>,
                     update_nine_slice_textures,
+                    toggle_new_identity,
+                    update_new_identity_checkbox,
+                    toggle_shared_gold,
+                    update_shared_gold_checkbox,
                 )
                     .run_if(in_state(AppState::MainMenu)),
             )
@@ -138,7 +164,12 @@ impl Plugin for MenuPlugin {
     }
 }
 
-fn setup_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn setup_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    stdb_config: Res<StdbConfig>,
+    economy: Res<EconomySetup>,
+) {
     commands.spawn((
         Node {
             position_type: PositionType::Absolute,
@@ -178,6 +209,14 @@ fn setup_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
                 Some("UI Elements/UI Elements/Icons/Play_Icon.png"),
                 PlayButton,
             );
+            spawn_nine_slice_button_with_icon(
+                parent,
+                &asset_server,
+                ButtonStyle::SmallBlueRound,
+                "ENDLESS",
+                Some("UI Elements/UI Elements/Icons/Play_Icon.png"),
+                EndlessButton,
+            );
             spawn_nine_slice_button_with_icon(
                 parent,
                 &asset_server,
@@ -203,12 +242,31 @@ fn setup_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
                 position_type: PositionType::Absolute,
                 bottom: Val::Px(50.0),
                 right: Val::Px(50.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::End,
+                row_gap: Val::Px(12.0),
                 ..default()
             },
             GlobalZIndex(10),
             MenuUI,
         ))
         .with_children(|parent| {
+            spawn_checkbox_row(
+                parent,
+                "Shared gold",
+                economy.mode == EconomyMode::Shared,
+                SharedGoldCheckbox,
+                SharedGoldCheckText,
+            );
+            // For testing co-op with several clients on one machine
+            spawn_checkbox_row(
+                parent,
+                "New identity",
+                stdb_config.fresh_identity,
+                NewIdentityCheckbox,
+                NewIdentityCheckText,
+            );
+
             spawn_nine_slice_button(
                 parent,
                 &asset_server,
@@ -219,6 +277,59 @@ fn setup_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
         });
 }
 
+/// A checkbox with a label to its right. The tick is an "X" in the text
+/// marked with `check_marker`.
+fn spawn_checkbox_row(
+    parent: &mut ChildSpawnerCommands,
+    label: &str,
+    checked: bool,
+    box_marker: impl Component,
+    check_marker: impl Component,
+) {
+    parent
+        .spawn(Node {
+            flex_direction: FlexDirection::Row,
+            align_items: AlignItems::Center,
+            column_gap: Val::Px(8.0),
+            ..default()
+        })
+        .with_children(|row| {
+            row.spawn((
+                Button,
+                Node {
+                    width: Val::Px(24.0),
+                    height: Val::Px(24.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    border: UiRect::all(Val::Px(2.0)),
+                    ..default()
+                },
+                BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+                BorderColor::all(Color::WHITE),
+                box_marker,
+            ))
+            .with_children(|checkbox| {
+                checkbox.spawn((
+                    Text::new(if checked { "X" } else { "" }),
+                    TextFont {
+                        font_size: 16.0,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                    check_marker,
+                ));
+            });
+            row.spawn((
+                Text::new(label),
+                TextFont {
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+        });
+}
+
 pub fn spawn_nine_slice_button<M: Component>(
     parent: &mut ChildSpawnerCommands,
     asset_server: &AssetServer,
@@ -343,15 +454,24 @@ fn button_interaction<M: Component>(
     mut next_state: ResMut<NextState<AppState>>,
     mut exit: EventWriter<AppExit>,
     mut login_event: EventWriter<LoginRequestEvent>,
+    mut settings_menu: ResMut<SettingsMenuState>,
+    mut endless: ResMut<EndlessMode>,
 ) {
     for interaction in &query {
         if *interaction == Interaction::Pressed {
             if std::any::type_name::<M>().contains("PlayButton") {
+                endless.enabled = false;
+                next_state.set(AppState::ColorSelect);
+            } else if std::any::type_name::<M>().contains("EndlessButton") {
+                *endless = EndlessMode {
+                    enabled: true,
+                    seed: rand::random(),
+                };
                 next_state.set(AppState::ColorSelect);
             } else if std::any::type_name::<M>().contains("QuitButton") {
                 exit.write(AppExit::Success);
             } else if std::any::type_name::<M>().contains("SettingsButton") {
-                info!("Settings pressed");
+                settings_menu.open = true;
             } else if std::any::type_name::<M>().contains("LoginButton") {
                 login_event.write(LoginRequestEvent);
             }
@@ -359,6 +479,55 @@ fn button_interaction<M: Component>(
     }
 }
 
+fn toggle_new_identity(
+    query: Query<&Interaction, (Changed<Interaction>, With<NewIdentityCheckbox>)>,
+    mut stdb_config: ResMut<StdbConfig>,
+) {
+    for interaction in &query {
+        if *interaction == Interaction::Pressed {
+            stdb_config.fresh_identity = !stdb_config.fresh_identity;
+        }
+    }
+}
+
+fn update_new_identity_checkbox(
+    stdb_config: Res<StdbConfig>,
+    mut texts: Query<&mut Text, With<NewIdentityCheckText>>,
+) {
+    if !stdb_config.is_changed() {
+        return;
+    }
+    for mut text in &mut texts {
+        text.0 = if stdb_config.fresh_identity { "X" } else { "" }.to_string();
+    }
+}
+
+fn toggle_shared_gold(
+    query: Query<&Interaction, (Changed<Interaction>, With<SharedGoldCheckbox>)>,
+    mut economy: ResMut<EconomySetup>,
+) {
+    for interaction in &query {
+        if *interaction == Interaction::Pressed {
+            economy.mode = match economy.mode {
+                EconomyMode::Split => EconomyMode::Shared,
+                EconomyMode::Shared => EconomyMode::Split,
+            };
+        }
+    }
+}
+
+fn update_shared_gold_checkbox(
+    economy: Res<EconomySetup>,
+    mut texts: Query<&mut Text, With<SharedGoldCheckText>>,
+) {
+    if !economy.is_changed() {
+        return;
+    }
+    for mut text in &mut texts {
+        text.0 = if economy.mode == EconomyMode::Shared { "X" } else { "" }.to_string();
+    }
+}
+
 /// Event to trigger login from menu
 #[derive(Message)]
 pub struct LoginRequestEvent;
@@ -0,0 +1,94 @@
+use bevy::asset::{AssetLoadFailedEvent, RenderAssetUsages};
+use bevy::image::ImageSampler;
+use bevy::platform::collections::HashSet;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+/// Squares per side of the placeholder's checkerboard
+const CHECKER_SQUARES: u32 = 4;
+const CHECKER_COLORS: [[u8; 4]; 2] = [[255, 0, 255, 255], [0, 0, 0, 255]];
+/// Size given to a placeholder when the sprite had no size of its own to keep
+const PLACEHOLDER_SIZE: Vec2 = Vec2::splat(64.0);
+
+/// Magenta and black checkerboard drawn in place of textures that failed to
+/// load. It is built in memory, so it can't go missing itself.
+#[derive(Resource)]
+pub struct MissingTexture(pub Handle<Image>);
+
+/// Textures that failed to load this session
+#[derive(Resource, Default)]
+pub struct FailedTextures {
+    ids: HashSet<AssetId<Image>>,
+}
+
+/// Swaps sprites whose texture failed to load for the `MissingTexture`
+/// placeholder, so missing content is obvious rather than invisible
+pub struct MissingTexturePlugin;
+
+impl Plugin for MissingTexturePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FailedTextures>()
+            .add_systems(Startup, create_missing_texture)
+            .add_systems(Update, (record_failed_textures, replace_missing_textures).chain());
+    }
+}
+
+fn create_missing_texture(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let data = (0..CHECKER_SQUARES * CHECKER_SQUARES)
+        .flat_map(|i| CHECKER_COLORS[((i / CHECKER_SQUARES + i % CHECKER_SQUARES) % 2) as usize])
+        .collect();
+    let mut image = Image::new(
+        Extent3d {
+            width: CHECKER_SQUARES,
+            height: CHECKER_SQUARES,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    // Crisp squares however far the sprite stretches it
+    image.sampler = ImageSampler::nearest();
+    commands.insert_resource(MissingTexture(images.add(image)));
+}
+
+fn record_failed_textures(mut failures: MessageReader<AssetLoadFailedEvent<Image>>, mut failed: ResMut<FailedTextures>) {
+    for failure in failures.read() {
+        warn!("Drawing a placeholder for {}: {}", failure.path, failure.error);
+        failed.ids.insert(failure.id);
+    }
+}
+
+/// Point sprites at the placeholder, both ones already on screen when their
+/// texture fails and ones spawned or re-textured afterwards. Sprite sheets
+/// lose their atlas and keep the size of one frame.
+fn replace_missing_textures(
+    failed: Res<FailedTextures>,
+    placeholder: Option<Res<MissingTexture>>,
+    layouts: Res<Assets<TextureAtlasLayout>>,
+    mut sprites: Query<&mut Sprite>,
+) {
+    let Some(placeholder) = placeholder else { return };
+    if failed.ids.is_empty() {
+        return;
+    }
+    // New failures can hit sprites that haven't changed since they spawned
+    let check_all = failed.is_changed();
+    for mut sprite in sprites.iter_mut() {
+        if !(check_all || sprite.is_changed()) || !failed.ids.contains(&sprite.image.id()) {
+            continue;
+        }
+        if sprite.custom_size.is_none() {
+            let frame_size = sprite
+                .texture_atlas
+                .as_ref()
+                .and_then(|atlas| layouts.get(&atlas.layout)?.textures.get(atlas.index).copied())
+                .map(|frame| frame.size().as_vec2());
+            sprite.custom_size = Some(frame_size.unwrap_or(PLACEHOLDER_SIZE));
+        }
+        sprite.image = placeholder.0.clone();
+        sprite.texture_atlas = None;
+        sprite.rect = None;
+    }
+}
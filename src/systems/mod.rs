@@ -1,35 +1,77 @@
+pub mod achievements;
+pub mod audio;
 pub mod camera;
+pub mod chat;
 pub mod color_select;
+pub mod console;
 pub mod cursor;
+pub mod diagnostics;
 pub mod enemy;
+pub mod effects;
+pub mod entity_sync;
+pub mod focus_fire;
 pub mod fog;
 pub mod game_state;
+pub mod input;
+pub mod leave_game;
+pub mod loading;
 pub mod menu;
+pub mod missing_texture;
 pub mod networking;
+pub mod pause_menu;
+pub mod pathfinding;
 pub mod player_list;
+pub mod presets;
 pub mod projectile;
+pub mod quality;
 pub mod recruit;
+pub mod selection;
+pub mod settings;
 pub mod setup;
+pub mod stats;
+pub mod status_effects;
 pub mod tower;
 pub mod ui;
 pub mod wave_manager_ui;
+pub mod weather;
 pub mod top_bar;
 pub mod worker;
 
+pub use achievements::*;
+pub use audio::*;
 pub use camera::*;
+pub use chat::*;
 pub use color_select::*;
+pub use console::*;
 pub use cursor::*;
+pub use diagnostics::*;
 pub use enemy::*;
+pub use effects::*;
+pub use entity_sync::*;
+pub use focus_fire::*;
 pub use fog::*;
 pub use game_state::*;
+pub use input::*;
+pub use leave_game::*;
+pub use loading::*;
 pub use menu::*;
+pub use missing_texture::*;
 pub use networking::*;
+pub use pause_menu::*;
+pub use pathfinding::*;
 pub use player_list::*;
+pub use presets::*;
 pub use projectile::*;
+pub use quality::*;
 pub use recruit::*;
+pub use selection::*;
+pub use settings::*;
 pub use setup::*;
+pub use stats::*;
+pub use status_effects::*;
 pub use tower::*;
 pub use ui::*;
 pub use wave_manager_ui::*;
+pub use weather::*;
 pub use top_bar::*;
 pub use worker::*;
@@ -1,18 +1,36 @@
 use bevy::prelude::*;
 use bevy_spacetimedb::*;
+use spacetimedb_sdk::DbContext;
 
-use crate::module_bindings::{DbConnection, User};
+use crate::auth::discard_saved_token;
+use crate::module_bindings::start_game_reducer::start_game;
+use crate::module_bindings::{DbConnection, RemoteModule, User};
+use crate::components::{GameUI, ReconnectingBanner};
+use crate::constants::{DISCONNECT_TIMEOUT, RECONNECT_DELAY, RECONNECT_MAX_DELAY};
+use crate::resources::{ConnectionStatus, EconomySetup, StdbConfig};
+use crate::systems::top_bar::BelowTopBar;
 
 /// Type alias for cleaner SpacetimeDB resource access
 pub type SpacetimeDB<'a> = Res<'a, StdbConnection<DbConnection>>;
 
 /// System to handle SpacetimeDB connection events
-pub fn on_connected(messages: Option<ReadStdbConnectedMessage>, stdb: Option<SpacetimeDB>) {
+///
+/// Also runs after a reconnect, restoring the subscriptions on the new connection.
+pub fn on_connected(
+    messages: Option<ReadStdbConnectedMessage>,
+    stdb: Option<SpacetimeDB>,
+    mut status: ResMut<ConnectionStatus>,
+) {
     let (Some(mut messages), Some(stdb)) = (messages, stdb) else {
         return;
     };
     for _ in messages.read() {
-        info!("Connected to SpacetimeDB!");
+        if matches!(*status, ConnectionStatus::Reconnecting { .. }) {
+            info!("Reconnected to SpacetimeDB!");
+        } else {
+            info!("Connected to SpacetimeDB!");
+        }
+        status.set_if_neq(ConnectionStatus::Connected);
 
         // Subscribe to the user table to get all online users
         stdb.subscription_builder()
@@ -24,27 +42,137 @@ pub fn on_connected(messages: Option<ReadStdbConnectedMessage>, stdb: Option<Spa
             .on_applied(|_| info!("My User subscription applied"))
             .on_error(|_, err| error!("My User subscription failed: {}", err))
             .subscribe("SELECT * FROM my_user");
+
+        stdb.subscription_builder()
+            .on_applied(|_| info!("Wave vote subscription applied"))
+            .on_error(|_, err| error!("Wave vote subscription failed: {}", err))
+            .subscribe(["SELECT * FROM wave_vote", "SELECT * FROM wave_start"]);
+
+        stdb.subscription_builder()
+            .on_applied(|_| info!("Clock, weather and game status subscription applied"))
+            .on_error(|_, err| error!("Clock, weather and game status subscription failed: {}", err))
+            .subscribe(["SELECT * FROM game_clock", "SELECT * FROM weather", "SELECT * FROM game_status"]);
+
+        stdb.subscription_builder()
+            .on_applied(|_| info!("Chat subscription applied"))
+            .on_error(|_, err| error!("Chat subscription failed: {}", err))
+            .subscribe("SELECT * FROM visible_message");
+
+        stdb.subscription_builder()
+            .on_applied(|_| info!("Player state subscription applied"))
+            .on_error(|_, err| error!("Player state subscription failed: {}", err))
+            .subscribe(["SELECT * FROM player_state", "SELECT * FROM player_stats", "SELECT * FROM tower"]);
     }
 }
 
 /// System to handle SpacetimeDB disconnection events
-pub fn on_disconnected(messages: Option<ReadStdbDisconnectedMessage>) {
+///
+/// The game keeps running; `reconnect_to_spacetimedb` takes over from here.
+pub fn on_disconnected(
+    messages: Option<ReadStdbDisconnectedMessage>,
+    mut status: ResMut<ConnectionStatus>,
+) {
     let Some(mut messages) = messages else {
         return;
     };
     for _ in messages.read() {
-        warn!("Disconnected from SpacetimeDB");
+        warn!("Disconnected from SpacetimeDB, reconnecting...");
+        *status = ConnectionStatus::Reconnecting {
+            attempt: 0,
+            retry_in: RECONNECT_DELAY,
+        };
+    }
+}
+
+/// Close the connection when the game quits, by window or menu, so the
+/// server runs `identity_disconnected` and the other players see us leave
+/// right away instead of after a timeout
+pub fn disconnect_on_exit(mut exits: MessageReader<AppExit>, stdb: Option<SpacetimeDB>) {
+    if exits.read().count() == 0 {
+        return;
     }
+    let Some(stdb) = stdb else { return };
+    info!("Disconnecting from SpacetimeDB...");
+    if let Err(e) = stdb.conn().disconnect() {
+        warn!("Failed to disconnect cleanly: {}", e);
+        return;
+    }
+    // The close is sent from the connection's thread; give it a moment
+    // before the process ends
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs_f32(DISCONNECT_TIMEOUT);
+    while stdb.conn().is_active() && std::time::Instant::now() < deadline {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+}
+
+/// Retry a dropped connection with exponential backoff, reusing the
+/// session's token so the player keeps their identity
+pub fn reconnect_to_spacetimedb(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut status: ResMut<ConnectionStatus>,
+    config: Option<Res<StdbConfig>>,
+) {
+    // Counting down skips change detection so the banner isn't touched every frame
+    let ConnectionStatus::Reconnecting { attempt, retry_in } = status.bypass_change_detection()
+    else {
+        return;
+    };
+    *retry_in -= time.delta_secs();
+    if *retry_in > 0.0 {
+        return;
+    }
+
+    let attempt = *attempt + 1;
+    *status = ConnectionStatus::Reconnecting {
+        attempt,
+        retry_in: (RECONNECT_DELAY * 2f32.powi(attempt as i32)).min(RECONNECT_MAX_DELAY),
+    };
+
+    let token = config
+        .filter(|config| !config.fresh_identity)
+        .and_then(|config| config.token.clone());
+    info!("Reconnect attempt {}...", attempt);
+    commands.queue(move |world: &mut World| {
+        connect_with_token::<DbConnection, RemoteModule>(world, token);
+    });
 }
 
 /// System to handle SpacetimeDB connection errors
-pub fn on_connection_error(messages: Option<ReadStdbConnectionErrorMessage>) {
+///
+/// A saved token that the server rejects is discarded and the connection is
+/// retried once with a fresh identity.
+pub fn on_connection_error(
+    mut commands: Commands,
+    messages: Option<ReadStdbConnectionErrorMessage>,
+    config: Option<ResMut<StdbConfig>>,
+    status: Res<ConnectionStatus>,
+) {
     let Some(mut messages) = messages else {
         return;
     };
+    let mut failed = false;
     for msg in messages.read() {
         error!("SpacetimeDB connection error: {:?}", msg.err);
+        failed = true;
     }
+
+    let Some(mut config) = config else {
+        return;
+    };
+    // While reconnecting the token already worked, so the network is to blame
+    let reconnecting = matches!(*status, ConnectionStatus::Reconnecting { .. });
+    if !failed || reconnecting || config.fresh_identity || config.token.is_none() {
+        return;
+    }
+
+    // Taking the token makes this a one-time retry
+    config.token = None;
+    discard_saved_token();
+    warn!("Connection with the saved token failed, retrying with a fresh identity");
+    commands.queue(|world: &mut World| {
+        connect_with_token::<DbConnection, RemoteModule>(world, None);
+    });
 }
 
 /// System to handle new users being inserted
@@ -85,4 +213,91 @@ pub fn on_user_deleted(messages: Option<ReadDeleteMessage<User>>) {
         let name = msg.row.name.as_deref().unwrap_or("Anonymous");
         info!("User removed: {}", name);
     }
-}
\ No newline at end of file
+}
+
+/// Reset the server-side gold and towers when a game starts. A fresh game
+/// gets the economy picked in the menu.
+pub fn start_server_game(stdb: Option<SpacetimeDB>, economy: Res<EconomySetup>) {
+    let Some(stdb) = stdb else { return };
+    if let Err(e) = stdb.reducers().start_game(economy.mode.into()) {
+        error!("Failed to start game on server: {}", e);
+    }
+}
+
+/// Check that `host` answers and serves `module`, without opening a game
+/// connection. Blocking; run it off the main thread.
+pub fn test_connection(host: &str, module: &str) -> Result<String, String> {
+    let host = host.trim().trim_end_matches('/');
+    if !host.starts_with("http://") && !host.starts_with("https://") {
+        return Err("Host must start with http:// or https://".to_string());
+    }
+    if module.trim().is_empty() {
+        return Err("Module name is empty".to_string());
+    }
+
+    let url = format!("{}/v1/database/{}", host, module.trim());
+    match ureq::get(&url).timeout(std::time::Duration::from_secs(5)).call() {
+        Ok(_) => Ok(format!("Connected: module '{}' found", module.trim())),
+        Err(ureq::Error::Status(404, _)) => {
+            Err(format!("Server reachable, but it has no module '{}'", module.trim()))
+        }
+        Err(ureq::Error::Status(code, _)) => Err(format!("Server answered with HTTP {}", code)),
+        Err(e) => Err(format!("Cannot reach {}: {}", host, e)),
+    }
+}
+
+/// Spawn the hidden "Reconnecting" banner shown over the game while the connection is down
+pub fn setup_reconnecting_banner(mut commands: Commands) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(70.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            Visibility::Hidden,
+            BelowTopBar { offset: 28.0 },
+            ReconnectingBanner,
+            GameUI,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        padding: UiRect::axes(Val::Px(16.0), Val::Px(8.0)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+                ))
+                .with_children(|banner| {
+                    banner.spawn((
+                        Text::new("Connection lost - reconnecting..."),
+                        TextFont {
+                            font_size: 20.0,
+                            ..default()
+                        },
+                        TextColor(Color::srgb(1.0, 0.8, 0.3)),
+                    ));
+                });
+        });
+}
+
+/// Show the banner while reconnecting
+pub fn update_reconnecting_banner(
+    status: Res<ConnectionStatus>,
+    mut banners: Query<&mut Visibility, With<ReconnectingBanner>>,
+) {
+    if !status.is_changed() {
+        return;
+    }
+    let reconnecting = matches!(*status, ConnectionStatus::Reconnecting { .. });
+    for mut visibility in &mut banners {
+        visibility.set_if_neq(if reconnecting {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+    }
+}
@@ -0,0 +1,243 @@
+use bevy::prelude::*;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use crate::components::Enemy;
+use crate::constants::SCALED_TILE_SIZE;
+use crate::map::{center_lane_tiles, tile_to_world, PATH_CORNERS};
+use crate::resources::{BlockedTiles, PathWaypoints};
+
+pub type Tile = (i32, i32);
+
+/// Step costs on the road: enemies keep to the centre lane and only use the
+/// side lanes to get around walls
+const CENTER_LANE_COST: u32 = 1;
+const SIDE_LANE_COST: u32 = 3;
+
+/// Shortest 4-connected path from `start` to `goal`, both included.
+/// `cost` gives the cost of stepping onto a tile (at least 1), or `None` if
+/// the tile can't be entered. Returns `None` when the goal is unreachable.
+pub fn find_path(start: Tile, goal: Tile, cost: impl Fn(Tile) -> Option<u32>) -> Option<Vec<Tile>> {
+    let heuristic = |(x, y): Tile| (x - goal.0).unsigned_abs() + (y - goal.1).unsigned_abs();
+
+    let mut open = BinaryHeap::new();
+    let mut came_from: HashMap<Tile, Tile> = HashMap::new();
+    let mut best_cost: HashMap<Tile, u32> = HashMap::from([(start, 0)]);
+    let mut closed: HashSet<Tile> = HashSet::new();
+    open.push(Reverse((heuristic(start), 0, start)));
+
+    while let Some(Reverse((_, cost_so_far, tile))) = open.pop() {
+        if tile == goal {
+            let mut path = vec![tile];
+            let mut current = tile;
+            while let Some(&previous) = came_from.get(&current) {
+                path.push(previous);
+                current = previous;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        if !closed.insert(tile) {
+            continue;
+        }
+
+        let (x, y) = tile;
+        for neighbor in [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)] {
+            if closed.contains(&neighbor) {
+                continue;
+            }
+            let Some(step) = cost(neighbor) else { continue };
+            let next_cost = cost_so_far + step;
+            if best_cost.get(&neighbor).is_none_or(|&known| next_cost < known) {
+                best_cost.insert(neighbor, next_cost);
+                came_from.insert(neighbor, tile);
+                open.push(Reverse((next_cost + heuristic(neighbor), next_cost, neighbor)));
+            }
+        }
+    }
+
+    None
+}
+
+/// Route from the spawn to the castle along the road, around walls.
+/// `extra_wall` tests a wall that hasn't been built yet.
+pub fn compute_enemy_path(blocked_tiles: &BlockedTiles, extra_wall: Option<Tile>) -> Option<Vec<Vec2>> {
+    let center_lane = center_lane_tiles();
+    let start = PATH_CORNERS[0];
+    let goal = PATH_CORNERS[PATH_CORNERS.len() - 1];
+
+    let cost = |tile: Tile| {
+        let (x, y) = tile;
+        // Enemies only walk on the road and into the castle
+        if !blocked_tiles.is_blocked(x, y) || blocked_tiles.is_wall(x, y) || extra_wall == Some(tile) {
+            None
+        } else if center_lane.contains(&tile) {
+            Some(CENTER_LANE_COST)
+        } else {
+            Some(SIDE_LANE_COST)
+        }
+    };
+
+    find_path(start, goal, cost)
+        .map(|tiles| tiles.into_iter().map(|(x, y)| tile_to_world(x, y)).collect())
+}
+
+/// Whether a tower on grid point `snapped` would stand on the enemy path.
+/// Towers sit on tile corners, so they cover the four tiles around `snapped`.
+pub fn overlaps_enemy_path(snapped: Vec2, waypoints: &PathWaypoints) -> bool {
+    waypoints.points.iter().any(|point| {
+        let offset = (*point - snapped).abs();
+        offset.x < SCALED_TILE_SIZE && offset.y < SCALED_TILE_SIZE
+    })
+}
+
+/// After the path changes, point every enemy at the closest waypoint ahead of it
+pub fn retarget_enemies_on_path_change(
+    waypoints: Res<PathWaypoints>,
+    mut enemies: Query<(&Transform, &mut Enemy)>,
+) {
+    if !waypoints.is_changed() || waypoints.is_added() || waypoints.points.is_empty() {
+        return;
+    }
+
+    let last = waypoints.points.len() - 1;
+    for (transform, mut enemy) in enemies.iter_mut() {
+        let position = transform.translation.truncate();
+        let closest = waypoints
+            .points
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.distance(position).total_cmp(&b.distance(position)))
+            .map(|(index, _)| index)
+            .unwrap_or(0);
+        enemy.current_waypoint = (closest + 1).min(last);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Grid from rows of text: '.' walkable, '#' blocked, '~' walkable at cost 5
+    fn grid(rows: &[&str]) -> impl Fn(Tile) -> Option<u32> {
+        let rows: Vec<Vec<char>> = rows.iter().map(|row| row.chars().collect()).collect();
+        move |(x, y)| {
+            let cell = rows.get(usize::try_from(y).ok()?)?.get(usize::try_from(x).ok()?)?;
+            match cell {
+                '.' => Some(1),
+                '~' => Some(5),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn straight_line_on_open_grid() {
+        let path = find_path((0, 0), (3, 0), grid(&["....", "...."])).unwrap();
+        assert_eq!(path, vec![(0, 0), (1, 0), (2, 0), (3, 0)]);
+    }
+
+    #[test]
+    fn start_equals_goal() {
+        let path = find_path((1, 1), (1, 1), grid(&["...", "...", "..."])).unwrap();
+        assert_eq!(path, vec![(1, 1)]);
+    }
+
+    #[test]
+    fn routes_around_walls() {
+        let path = find_path(
+            (0, 0),
+            (2, 0),
+            grid(&[
+                ".#.", //
+                ".#.", //
+                "...",
+            ]),
+        )
+        .unwrap();
+        assert_eq!(path.len(), 7);
+        assert_eq!(path.first(), Some(&(0, 0)));
+        assert_eq!(path.last(), Some(&(2, 0)));
+        assert!(path.contains(&(1, 2)));
+    }
+
+    #[test]
+    fn fully_blocked_has_no_path() {
+        let path = find_path(
+            (0, 0),
+            (2, 0),
+            grid(&[
+                ".#.", //
+                ".#.", //
+                ".#.",
+            ]),
+        );
+        assert!(path.is_none());
+    }
+
+    #[test]
+    fn prefers_cheaper_tiles_over_shorter_route() {
+        let path = find_path(
+            (0, 0),
+            (2, 0),
+            grid(&[
+                ".~.", //
+                "...",
+            ]),
+        )
+        .unwrap();
+        assert_eq!(path, vec![(0, 0), (0, 1), (1, 1), (2, 1), (2, 0)]);
+    }
+
+    #[test]
+    fn path_steps_are_adjacent() {
+        let path = find_path(
+            (0, 0),
+            (4, 4),
+            grid(&[
+                ".....", //
+                "####.", //
+                ".....", //
+                ".####", //
+                ".....",
+            ]),
+        )
+        .unwrap();
+        for pair in path.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            assert_eq!((a.0 - b.0).abs() + (a.1 - b.1).abs(), 1);
+        }
+        assert_eq!(path.len(), 17);
+    }
+
+    #[test]
+    fn default_map_follows_the_center_lane() {
+        let (tiles, castle_tiles) = crate::map::create_blocked_tiles();
+        let blocked = BlockedTiles {
+            tiles,
+            castle_tiles,
+            walls: HashSet::new(),
+        };
+        let path = compute_enemy_path(&blocked, None).unwrap();
+        let lane = center_lane_tiles();
+        for point in path {
+            assert!(lane.contains(&crate::map::world_to_tile(point)));
+        }
+    }
+
+    #[test]
+    fn wall_across_the_road_blocks_the_path() {
+        let (tiles, castle_tiles) = crate::map::create_blocked_tiles();
+        // Row 10 at x = 2 is a horizontal stretch of road, three lanes wide (y 9..=11)
+        let mut blocked = BlockedTiles {
+            tiles,
+            castle_tiles,
+            walls: HashSet::from([(2, 9), (2, 10)]),
+        };
+        assert!(compute_enemy_path(&blocked, None).is_some());
+        assert!(compute_enemy_path(&blocked, Some((2, 11))).is_none());
+
+        blocked.walls.insert((2, 11));
+        assert!(compute_enemy_path(&blocked, None).is_none());
+    }
+}
@@ -0,0 +1,226 @@
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+
+use crate::components::GameUI;
+use crate::events::LeaveGame;
+use crate::resources::{
+    AppState, HouseMenuState, RecruitMenuState, SelectedTowers, TowerMoveState, TowerUpgradeMenuState,
+    TowerWheelState,
+};
+use crate::systems::input::{collect_input_actions, ActionState, InputAction};
+use crate::systems::menu::{spawn_nine_slice_button, ButtonStyle};
+use crate::systems::networking::SpacetimeDB;
+use crate::systems::presets::PresetMenuState;
+use crate::systems::settings::SettingsMenuState;
+
+/// Whether the in-game pause menu is open
+#[derive(Resource, Default)]
+pub struct PauseMenuState {
+    pub open: bool,
+    /// The menu paused the game, so closing it resumes. Only offline: the
+    /// online game is shared and keeps running.
+    paused_game: bool,
+}
+
+/// Root of the pause menu overlay
+#[derive(Component)]
+pub struct PauseMenu;
+
+#[derive(Component)]
+pub struct PauseResumeButton;
+
+#[derive(Component)]
+pub struct PauseSettingsButton;
+
+#[derive(Component)]
+pub struct PauseLeaveButton;
+
+#[derive(Component)]
+pub struct PauseQuitButton;
+
+pub struct PauseMenuPlugin;
+
+impl Plugin for PauseMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PauseMenuState>()
+            // Before Update, so the menus Escape is about to close still
+            // count as open and keep the pause menu shut
+            .add_systems(
+                PreUpdate,
+                toggle_pause_menu
+                    .after(collect_input_actions)
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(
+                Update,
+                (sync_pause_menu, handle_pause_menu_buttons).run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(OnExit(AppState::InGame), close_pause_menu);
+    }
+}
+
+/// Escape (or gamepad Start) closes whatever menu is open first; only with
+/// nothing else open does it toggle the pause menu
+#[allow(clippy::too_many_arguments)]
+fn toggle_pause_menu(
+    actions: Res<ActionState>,
+    mut state: ResMut<PauseMenuState>,
+    settings_menu: Res<SettingsMenuState>,
+    wheel: Res<TowerWheelState>,
+    upgrade_menu: Res<TowerUpgradeMenuState>,
+    house_menu: Res<HouseMenuState>,
+    recruit_menu: Res<RecruitMenuState>,
+    presets: Res<PresetMenuState>,
+    move_state: Res<TowerMoveState>,
+    selected: Res<SelectedTowers>,
+) {
+    if !actions.just_pressed(InputAction::Menu) || settings_menu.open {
+        return;
+    }
+    if state.open {
+        state.open = false;
+        return;
+    }
+    let other_menu_open = wheel.active
+        || upgrade_menu.active
+        || house_menu.active
+        || recruit_menu.active
+        || presets.open
+        || move_state.moving.is_some()
+        || !selected.towers.is_empty();
+    if !other_menu_open {
+        state.open = true;
+    }
+}
+
+/// Spawn or remove the overlay when the menu opens or closes, pausing an
+/// offline game while it is up
+fn sync_pause_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut state: ResMut<PauseMenuState>,
+    stdb: Option<SpacetimeDB>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    menus: Query<Entity, With<PauseMenu>>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+
+    if !state.open {
+        for entity in menus.iter() {
+            commands.entity(entity).despawn();
+        }
+        if std::mem::take(&mut state.paused_game) {
+            virtual_time.unpause();
+        }
+        return;
+    }
+    if !menus.is_empty() {
+        return;
+    }
+
+    let online = stdb.is_some();
+    if !online && !virtual_time.is_paused() {
+        virtual_time.pause();
+        state.paused_game = true;
+    }
+    spawn_pause_menu(&mut commands, &asset_server, online);
+}
+
+fn spawn_pause_menu(commands: &mut Commands, asset_server: &AssetServer, online: bool) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+            // Clicks on the dimmed backdrop are swallowed instead of reaching the map
+            Interaction::default(),
+            FocusPolicy::Block,
+            GlobalZIndex(90),
+            PauseMenu,
+            GameUI,
+        ))
+        .with_children(|overlay| {
+            overlay
+                .spawn((
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(12.0),
+                        padding: UiRect::all(Val::Px(24.0)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(0.1, 0.1, 0.15, 0.95)),
+                    BorderRadius::all(Val::Px(8.0)),
+                ))
+                .with_children(|panel| {
+                    panel.spawn((
+                        Text::new(if online { "MENU" } else { "PAUSED" }),
+                        TextFont {
+                            font_size: 36.0,
+                            ..default()
+                        },
+                        TextColor(Color::WHITE),
+                    ));
+                    if online {
+                        panel.spawn((
+                            Text::new("The game keeps running for the other players"),
+                            TextFont {
+                                font_size: 14.0,
+                                ..default()
+                            },
+                            TextColor(Color::srgb(0.7, 0.7, 0.7)),
+                        ));
+                    }
+
+                    spawn_nine_slice_button(panel, asset_server, ButtonStyle::BigBlue, "Resume", PauseResumeButton);
+                    spawn_nine_slice_button(panel, asset_server, ButtonStyle::BigBlue, "Settings", PauseSettingsButton);
+                    spawn_nine_slice_button(panel, asset_server, ButtonStyle::BigBlue, "Leave Game", PauseLeaveButton);
+                    spawn_nine_slice_button(panel, asset_server, ButtonStyle::BigRed, "Quit Game", PauseQuitButton);
+                });
+        });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn handle_pause_menu_buttons(
+    resume: Query<&Interaction, (Changed<Interaction>, With<PauseResumeButton>)>,
+    settings: Query<&Interaction, (Changed<Interaction>, With<PauseSettingsButton>)>,
+    leave: Query<&Interaction, (Changed<Interaction>, With<PauseLeaveButton>)>,
+    quit: Query<&Interaction, (Changed<Interaction>, With<PauseQuitButton>)>,
+    mut state: ResMut<PauseMenuState>,
+    mut settings_menu: ResMut<SettingsMenuState>,
+    mut leave_game: MessageWriter<LeaveGame>,
+    mut exit: MessageWriter<AppExit>,
+) {
+    if resume.iter().any(|interaction| *interaction == Interaction::Pressed) {
+        state.open = false;
+    }
+    if settings.iter().any(|interaction| *interaction == Interaction::Pressed) {
+        settings_menu.open = true;
+    }
+    if leave.iter().any(|interaction| *interaction == Interaction::Pressed) {
+        leave_game.write(LeaveGame);
+    }
+    if quit.iter().any(|interaction| *interaction == Interaction::Pressed) {
+        exit.write(AppExit::Success);
+    }
+}
+
+fn close_pause_menu(
+    mut commands: Commands,
+    mut state: ResMut<PauseMenuState>,
+    menus: Query<Entity, With<PauseMenu>>,
+) {
+    // reset_last_stand unpauses time on the way out
+    *state = PauseMenuState::default();
+    for entity in menus.iter() {
+        commands.entity(entity).despawn();
+    }
+}
@@ -28,6 +28,7 @@ pub fn setup_online_users_ui(mut commands: Commands) {
             flex_direction: FlexDirection::Column,
             row_gap: Val::Px(15.0),
             width: Val::Px(320.0),
+            max_width: Val::Percent(40.0),
             ..default()
         },
         OnlineUsersPanel,
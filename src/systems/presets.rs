@@ -0,0 +1,505 @@
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+use serde::{Deserialize, Serialize};
+
+use crate::components::{PendingTower, SyncedTower, Tower};
+use crate::constants::SCALED_TILE_SIZE;
+use crate::module_bindings::place_tower_reducer::place_tower;
+use crate::resources::{AppState, BlockedTiles, FogOfWar, GameState, InfiniteResources, PathWaypoints, TowerConfigs};
+use crate::systems::input::{ActionState, InputAction};
+use crate::systems::menu::{spawn_nine_slice_button, ButtonStyle};
+use crate::systems::top_bar::BelowTopBar;
+use crate::systems::tower::{is_buildable, spawn_tower, SpacetimeDB};
+use crate::user_settings::config_file_path;
+
+const PRESETS_FILE: &str = "presets.toml";
+const MAX_PRESET_NAME_LEN: usize = 24;
+
+/// One tower of a saved layout, on the tower grid (multiples of a tile)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PresetTower {
+    pub tower_type_id: String,
+    pub grid_x: i32,
+    pub grid_y: i32,
+}
+
+impl PresetTower {
+    pub fn world_position(&self) -> Vec2 {
+        Vec2::new(self.grid_x as f32, self.grid_y as f32) * SCALED_TILE_SIZE
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayoutPreset {
+    pub name: String,
+    pub towers: Vec<PresetTower>,
+}
+
+/// Saved tower layouts, persisted to `presets.toml` next to `settings.toml`
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PresetLibrary {
+    pub presets: Vec<LayoutPreset>,
+}
+
+impl PresetLibrary {
+    pub fn load() -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let content = std::fs::read_to_string(config_file_path(PRESETS_FILE))?;
+        Ok(toml::from_str(&content)?)
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let path = config_file_path(PRESETS_FILE);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn load_or_default() -> Self {
+        Self::load().unwrap_or_default()
+    }
+
+    /// Store `preset`, replacing any preset with the same name
+    pub fn insert(&mut self, preset: LayoutPreset) {
+        match self.presets.iter_mut().find(|p| p.name == preset.name) {
+            Some(existing) => *existing = preset,
+            None => self.presets.push(preset),
+        }
+    }
+}
+
+/// Whether the presets panel is open and the name typed for the next save
+#[derive(Resource, Default)]
+pub struct PresetMenuState {
+    pub open: bool,
+    pub name: String,
+}
+
+/// Marker for the presets panel root
+#[derive(Component)]
+pub struct PresetPanel;
+
+/// Text showing the preset name being typed
+#[derive(Component)]
+pub struct PresetNameText;
+
+/// Button that saves the current towers under the typed name
+#[derive(Component)]
+pub struct SavePresetButton;
+
+/// Button that places the towers of a preset (index into `PresetLibrary`)
+#[derive(Component)]
+pub struct LoadPresetButton(pub usize);
+
+/// Button that removes a preset (index into `PresetLibrary`)
+#[derive(Component)]
+pub struct DeletePresetButton(pub usize);
+
+/// Button that closes the presets panel
+#[derive(Component)]
+pub struct ClosePresetsButton;
+
+pub struct PresetsPlugin;
+
+impl Plugin for PresetsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(PresetLibrary::load_or_default())
+            .init_resource::<PresetMenuState>()
+            .add_systems(
+                Update,
+                (
+                    (toggle_presets_panel, type_preset_name).chain(),
+                    sync_presets_panel,
+                    update_preset_name_text,
+                    handle_save_preset_button,
+                    handle_load_preset_buttons,
+                    handle_delete_preset_buttons,
+                    handle_close_presets_button,
+                    save_preset_library.run_if(resource_changed::<PresetLibrary>),
+                )
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(OnExit(AppState::InGame), close_presets_panel);
+    }
+}
+
+fn toggle_presets_panel(actions: Res<ActionState>, mut state: ResMut<PresetMenuState>) {
+    if actions.just_pressed(InputAction::TogglePresets) {
+        state.open = !state.open;
+    }
+}
+
+/// While the panel is open, typed characters go to the preset name
+/// instead of the key bindings
+fn type_preset_name(
+    mut keyboard_input: MessageReader<KeyboardInput>,
+    mut state: ResMut<PresetMenuState>,
+    mut actions: ResMut<ActionState>,
+    mut was_open: Local<bool>,
+) {
+    if state.open {
+        actions.text_entry = true;
+    }
+    // Skip the key press that opened the panel
+    let just_opened = state.open && !*was_open;
+    *was_open = state.open;
+    if !state.open || just_opened {
+        keyboard_input.clear();
+        return;
+    }
+
+    for input in keyboard_input.read() {
+        if !input.state.is_pressed() {
+            continue;
+        }
+        match &input.logical_key {
+            Key::Escape => state.open = false,
+            Key::Backspace => {
+                state.name.pop();
+            }
+            Key::Space if state.name.len() < MAX_PRESET_NAME_LEN => state.name.push(' '),
+            Key::Character(text) => {
+                for c in text.chars() {
+                    if (c.is_alphanumeric() || c == '-' || c == '_')
+                        && state.name.len() < MAX_PRESET_NAME_LEN
+                    {
+                        state.name.push(c);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Spawn, refresh or remove the panel whenever it opens, closes or the
+/// library changes. The name text is updated in place so typing doesn't
+/// rebuild the panel.
+fn sync_presets_panel(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    state: Res<PresetMenuState>,
+    library: Res<PresetLibrary>,
+    panels: Query<Entity, With<PresetPanel>>,
+) {
+    let open_changed = panels.is_empty() == state.open;
+    if !open_changed && !library.is_changed() {
+        return;
+    }
+
+    for entity in panels.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    if state.open {
+        spawn_presets_panel(&mut commands, &asset_server, &state, &library);
+    }
+}
+
+fn spawn_presets_panel(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    state: &PresetMenuState,
+    library: &PresetLibrary,
+) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                right: Val::Px(16.0),
+                top: Val::Px(80.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(10.0),
+                padding: UiRect::all(Val::Px(16.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.1, 0.1, 0.15, 0.95)),
+            BorderRadius::all(Val::Px(8.0)),
+            // Clicks on the panel don't reach the map
+            Interaction::default(),
+            FocusPolicy::Block,
+            GlobalZIndex(60),
+            BelowTopBar { offset: 38.0 },
+            PresetPanel,
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                Text::new("LAYOUT PRESETS"),
+                TextFont {
+                    font_size: 24.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+
+            panel.spawn((
+                Text::new(format!("Name: {}|", state.name)),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                PresetNameText,
+            ));
+
+            spawn_nine_slice_button(
+                panel,
+                asset_server,
+                ButtonStyle::SmallBlueSquare,
+                "SAVE",
+                SavePresetButton,
+            );
+
+            if library.presets.is_empty() {
+                panel.spawn((
+                    Text::new("No presets saved yet"),
+                    TextFont {
+                        font_size: 14.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgb(0.6, 0.6, 0.6)),
+                ));
+            }
+
+            for (index, preset) in library.presets.iter().enumerate() {
+                panel
+                    .spawn(Node {
+                        width: Val::Px(320.0),
+                        flex_direction: FlexDirection::Row,
+                        justify_content: JustifyContent::SpaceBetween,
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(8.0),
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        row.spawn((
+                            Text::new(format!("{} ({})", preset.name, preset.towers.len())),
+                            TextFont {
+                                font_size: 16.0,
+                                ..default()
+                            },
+                            TextColor(Color::WHITE),
+                        ));
+                        spawn_nine_slice_button(
+                            row,
+                            asset_server,
+                            ButtonStyle::SmallBlueSquare,
+                            "LOAD",
+                            LoadPresetButton(index),
+                        );
+                        spawn_nine_slice_button(
+                            row,
+                            asset_server,
+                            ButtonStyle::SmallRedSquare,
+                            "X",
+                            DeletePresetButton(index),
+                        );
+                    });
+            }
+
+            spawn_nine_slice_button(
+                panel,
+                asset_server,
+                ButtonStyle::SmallBlueSquare,
+                "CLOSE",
+                ClosePresetsButton,
+            );
+        });
+}
+
+fn update_preset_name_text(
+    state: Res<PresetMenuState>,
+    mut texts: Query<&mut Text, With<PresetNameText>>,
+) {
+    if !state.is_changed() {
+        return;
+    }
+    for mut text in texts.iter_mut() {
+        text.0 = format!("Name: {}|", state.name);
+    }
+}
+
+/// Save every tower we placed under the typed name (or "Preset N" if empty)
+fn handle_save_preset_button(
+    query: Query<&Interaction, (Changed<Interaction>, With<SavePresetButton>)>,
+    mut state: ResMut<PresetMenuState>,
+    mut library: ResMut<PresetLibrary>,
+    towers: Query<(&Transform, &Tower), Without<SyncedTower>>,
+) {
+    for interaction in query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        let name = match state.name.trim() {
+            "" => format!("Preset {}", library.presets.len() + 1),
+            name => name.to_string(),
+        };
+        let towers = towers
+            .iter()
+            .map(|(transform, tower)| {
+                let grid = (transform.translation.truncate() / SCALED_TILE_SIZE).round();
+                PresetTower {
+                    tower_type_id: tower.tower_type_id.clone(),
+                    grid_x: grid.x as i32,
+                    grid_y: grid.y as i32,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        info!("Saved preset '{}' with {} towers", name, towers.len());
+        library.insert(LayoutPreset { name, towers });
+        state.name.clear();
+    }
+}
+
+/// Place the towers of a preset in order while gold lasts. Towers on tiles
+/// that are occupied, unexplored or on the enemy path are skipped.
+#[allow(clippy::too_many_arguments)]
+fn handle_load_preset_buttons(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    query: Query<(&Interaction, &LoadPresetButton), Changed<Interaction>>,
+    library: Res<PresetLibrary>,
+    mut game_state: ResMut<GameState>,
+    tower_configs: Res<TowerConfigs>,
+    fog: Res<FogOfWar>,
+    blocked_tiles: Res<BlockedTiles>,
+    waypoints: Res<PathWaypoints>,
+    existing_towers: Query<&Transform, With<Tower>>,
+    stdb: Option<SpacetimeDB>,
+    infinite: Res<InfiniteResources>,
+) {
+    for (interaction, button) in query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let Some(preset) = library.presets.get(button.0) else { continue };
+
+        // Towers spawned below aren't visible to the query until next frame
+        let mut occupied: Vec<Vec2> = existing_towers.iter().map(|t| t.translation.truncate()).collect();
+        let mut placed = 0;
+        let mut skipped = 0;
+
+        for preset_tower in &preset.towers {
+            let position = preset_tower.world_position();
+            let Some(tower_type) = tower_configs.towers.iter().find(|t| t.id == preset_tower.tower_type_id) else {
+                skipped += 1;
+                continue;
+            };
+
+            let buildable = is_buildable(position, &fog, &blocked_tiles, &waypoints, occupied.iter().copied());
+            if !buildable || !infinite.affords(game_state.gold, tower_type.cost) {
+                skipped += 1;
+                continue;
+            }
+
+            // Online the tower stays pending until the server confirms it, and
+            // the server charges the full cost since sandbox mode is offline only
+            let paid = if let Some(stdb) = stdb.as_ref() {
+                if let Err(e) = stdb.reducers().place_tower(tower_type.id.clone(), position.x, position.y) {
+                    error!("Failed to send place_tower: {}", e);
+                    skipped += 1;
+                    continue;
+                }
+                game_state.expect_server_gold(-tower_type.cost);
+                tower_type.cost
+            } else {
+                infinite.charge(tower_type.cost)
+            };
+            let owner = stdb.as_ref().and_then(|stdb| stdb.try_identity());
+            let tower = spawn_tower(&mut commands, &asset_server, position.extend(1.0), tower_type, stdb.as_ref().map(Res::clone), owner);
+            if stdb.is_some() {
+                commands.entity(tower).insert(PendingTower::default());
+            }
+            game_state.gold -= paid;
+            occupied.push(position);
+            placed += 1;
+        }
+
+        info!(
+            "Loaded preset '{}': placed {} towers, skipped {} (occupied, invalid or unaffordable)",
+            preset.name, placed, skipped
+        );
+    }
+}
+
+fn handle_delete_preset_buttons(
+    query: Query<(&Interaction, &DeletePresetButton), Changed<Interaction>>,
+    mut library: ResMut<PresetLibrary>,
+) {
+    for (interaction, button) in query.iter() {
+        if *interaction == Interaction::Pressed && button.0 < library.presets.len() {
+            library.presets.remove(button.0);
+        }
+    }
+}
+
+fn handle_close_presets_button(
+    query: Query<&Interaction, (Changed<Interaction>, With<ClosePresetsButton>)>,
+    mut state: ResMut<PresetMenuState>,
+) {
+    for interaction in query.iter() {
+        if *interaction == Interaction::Pressed {
+            state.open = false;
+        }
+    }
+}
+
+fn save_preset_library(library: Res<PresetLibrary>) {
+    // Nothing to write right after loading
+    if library.is_added() {
+        return;
+    }
+    if let Err(e) = library.save() {
+        error!("Failed to save presets: {}", e);
+    }
+}
+
+fn close_presets_panel(
+    mut commands: Commands,
+    mut state: ResMut<PresetMenuState>,
+    panels: Query<Entity, With<PresetPanel>>,
+) {
+    state.open = false;
+    for entity in panels.iter() {
+        commands.entity(entity).despawn();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::input::mouse::{AccumulatedMouseMotion, MouseWheel};
+    use bevy::input::touch::Touches;
+
+    use super::*;
+    use crate::systems::input::{collect_input_actions, KeyBindings};
+
+    #[test]
+    fn pressing_the_presets_key_opens_the_panel() {
+        let mut world = World::new();
+        world.init_resource::<ActionState>();
+        world.init_resource::<PresetMenuState>();
+        world.init_resource::<ButtonInput<MouseButton>>();
+        world.init_resource::<ButtonInput<KeyCode>>();
+        world.init_resource::<AccumulatedMouseMotion>();
+        world.init_resource::<Touches>();
+        world.init_resource::<Time>();
+        world.init_resource::<Messages<MouseWheel>>();
+        world.init_resource::<Messages<CursorMoved>>();
+        let bindings = KeyBindings::defaults();
+        let key = bindings.key(InputAction::TogglePresets).unwrap();
+        world.insert_resource(bindings);
+
+        world.resource_mut::<ButtonInput<KeyCode>>().press(key);
+        world.run_system_once(collect_input_actions).unwrap();
+        world.run_system_once(toggle_presets_panel).unwrap();
+
+        assert!(world.resource::<PresetMenuState>().open);
+    }
+}
@@ -1,18 +1,42 @@
 use bevy::prelude::*;
 
-use crate::components::{get_damage_multiplier, Enemy, Projectile};
-use crate::resources::GameState;
+use crate::components::{Ballistic, Burn, Enemy, HitFlash, Projectile, Tower, Trail, TrailSegment};
+use crate::events::{EnemyKilled, PlaySound};
+use crate::resources::{GameState, VisualQuality};
+use crate::systems::audio::SoundEffect;
+use crate::systems::enemy::award_kill;
+use crate::systems::networking::SpacetimeDB;
+use crate::systems::status_effects::apply_burn;
+use crate::systems::ui::spawn_immune_text;
 
 /// Projectile hit radius - larger value prevents overshooting issues
 const HIT_RADIUS: f32 = 16.0;
+/// Pixels a projectile travels between trail segments
+const TRAIL_SEGMENT_LENGTH: f32 = 6.0;
 
 pub fn move_projectiles(
     mut commands: Commands,
-    mut projectiles: Query<(Entity, &mut Transform, &Projectile)>,
+    mut projectiles: Query<(Entity, &mut Transform, &mut Projectile), Without<Ballistic>>,
     enemies: Query<&Transform, (With<Enemy>, Without<Projectile>)>,
     time: Res<Time>,
 ) {
-    for (projectile_entity, mut projectile_transform, projectile) in projectiles.iter_mut() {
+    for (projectile_entity, mut projectile_transform, mut projectile) in projectiles.iter_mut() {
+        // Non-homing projectiles fly straight at the aim point until they run out of range
+        if !projectile.homing {
+            let move_distance = projectile.speed * time.delta_secs();
+            projectile_transform.translation += projectile.direction.extend(0.0) * move_distance;
+            projectile.remaining_range -= move_distance;
+
+            if projectile.remaining_range <= 0.0 {
+                commands.queue_silenced(move |world: &mut World| {
+                    if let Ok(entity_mut) = world.get_entity_mut(projectile_entity) {
+                        entity_mut.despawn();
+                    }
+                });
+            }
+            continue;
+        }
+
         // Get target position
         if let Ok(enemy_transform) = enemies.get(projectile.target) {
             let to_target = enemy_transform.translation - projectile_transform.translation;
@@ -44,53 +68,192 @@ pub fn move_projectiles(
     }
 }
 
+/// Carry lobbed projectiles along their arc: the ground position moves
+/// steadily from launch to impact while a parabolic height is added on top
+pub fn move_ballistic_projectiles(time: Res<Time>, mut projectiles: Query<(&mut Transform, &mut Ballistic)>) {
+    for (mut transform, mut ballistic) in projectiles.iter_mut() {
+        ballistic.elapsed = (ballistic.elapsed + time.delta_secs()).min(ballistic.flight_time);
+        let t = ballistic.elapsed / ballistic.flight_time;
+        let ground = ballistic.start.lerp(ballistic.impact, t);
+        let height = 4.0 * ballistic.apex_height * t * (1.0 - t);
+        transform.translation = (ground + Vec2::Y * height).extend(transform.translation.z);
+
+        // Face along the arc: rising after launch, falling onto the impact
+        let ground_velocity = (ballistic.impact - ballistic.start) / ballistic.flight_time;
+        let climb = 4.0 * ballistic.apex_height * (1.0 - 2.0 * t) / ballistic.flight_time;
+        let velocity = ground_velocity + Vec2::Y * climb;
+        transform.rotation = Quat::from_rotation_z(velocity.y.atan2(velocity.x));
+    }
+}
+
+/// Lay a segment from the last trail point to the projectile each time it
+/// has moved far enough; the segments stay behind and fade on their own.
+/// No trails are laid while visual quality is reduced.
+pub fn spawn_projectile_trails(
+    mut commands: Commands,
+    quality: Res<VisualQuality>,
+    mut projectiles: Query<(&Transform, &mut Trail), With<Projectile>>,
+) {
+    for (transform, mut trail) in projectiles.iter_mut() {
+        let position = transform.translation.truncate();
+        if quality.reduced {
+            // Keep up so the trail resumes from here rather than with one long segment
+            trail.last_point = position;
+            continue;
+        }
+        let offset = position - trail.last_point;
+        let length = offset.length();
+        if length < TRAIL_SEGMENT_LENGTH {
+            continue;
+        }
+
+        let [r, g, b, a] = trail.config.color;
+        commands.spawn((
+            Sprite {
+                color: Color::srgba(r, g, b, a),
+                custom_size: Some(Vec2::new(length, trail.config.width)),
+                ..default()
+            },
+            Transform::from_translation(((trail.last_point + position) / 2.0).extend(transform.translation.z - 0.1))
+                .with_rotation(Quat::from_rotation_z(offset.y.atan2(offset.x))),
+            TrailSegment {
+                lifetime: Timer::from_seconds(trail.config.lifetime, TimerMode::Once),
+                alpha: a,
+            },
+        ));
+        trail.last_point = position;
+    }
+}
+
+/// Fade trail segments out over their lifetime, then remove them
+pub fn update_trail_segments(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut segments: Query<(Entity, &mut TrailSegment, &mut Sprite, &Visibility)>,
+) {
+    for (entity, mut segment, mut sprite, visibility) in segments.iter_mut() {
+        segment.lifetime.tick(time.delta());
+        if segment.lifetime.is_finished() {
+            commands.queue_silenced(move |world: &mut World| {
+                if let Ok(entity_mut) = world.get_entity_mut(entity) {
+                    entity_mut.despawn();
+                }
+            });
+            continue;
+        }
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+        sprite.color.set_alpha(segment.alpha * (1.0 - segment.lifetime.fraction()));
+    }
+}
+
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn handle_projectile_hits(
     mut commands: Commands,
-    projectiles: Query<(Entity, &Transform, &Projectile)>,
-    mut enemies: Query<(Entity, &Transform, &mut Enemy, Option<&Children>)>,
+    asset_server: Res<AssetServer>,
+    mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
+    projectiles: Query<(Entity, &Transform, &Projectile, Option<&Ballistic>)>,
+    mut enemies: Query<(Entity, &Transform, &mut Enemy, Option<&mut Burn>)>,
+    mut towers: Query<&mut Tower>,
     mut game_state: ResMut<GameState>,
+    mut killed: MessageWriter<EnemyKilled>,
+    mut sounds: MessageWriter<PlaySound>,
+    stdb: Option<SpacetimeDB>,
 ) {
-    for (projectile_entity, projectile_transform, projectile) in projectiles.iter() {
-        if let Ok((enemy_entity, enemy_transform, mut enemy, children)) =
-            enemies.get_mut(projectile.target)
-        {
-            let distance = projectile_transform
-                .translation
-                .distance(enemy_transform.translation);
-
-            if distance < HIT_RADIUS {
-                // Calculate damage with type effectiveness multiplier
-                let multiplier = get_damage_multiplier(projectile.attack_type, enemy.defense_type);
-                let final_damage = projectile.damage * multiplier;
-                enemy.health -= final_damage;
+    let local_player = stdb.and_then(|stdb| stdb.try_identity());
+    for (projectile_entity, projectile_transform, projectile, ballistic) in projectiles.iter() {
+        let hits: Vec<Entity> = match ballistic {
+            // Lobbed projectiles only come down at the end of their arc, onto
+            // everything around the impact point
+            Some(ballistic) => {
+                if !ballistic.landed() {
+                    continue;
+                }
+                let radius = ballistic.splash_radius.max(HIT_RADIUS);
+                enemies
+                    .iter()
+                    .filter(|(_, enemy_transform, enemy, ..)| {
+                        enemy.health > 0.0 && enemy_transform.translation.truncate().distance(ballistic.impact) < radius
+                    })
+                    .map(|(entity, ..)| entity)
+                    .collect()
+            }
+            None if projectile.homing => enemies
+                .get(projectile.target)
+                .ok()
+                .filter(|(_, enemy_transform, ..)| {
+                    projectile_transform.translation.distance(enemy_transform.translation) < HIT_RADIUS
+                })
+                .map(|(entity, ..)| entity)
+                .into_iter()
+                .collect(),
+            // Straight-flying projectiles hit whichever enemy they pass through first
+            None => enemies
+                .iter()
+                .find(|(_, enemy_transform, enemy, ..)| {
+                    enemy.health > 0.0
+                        && projectile_transform.translation.distance(enemy_transform.translation) < HIT_RADIUS
+                })
+                .map(|(entity, ..)| entity)
+                .into_iter()
+                .collect(),
+        };
+        // A lob that lands on empty ground is spent all the same
+        if hits.is_empty() && ballistic.is_none() {
+            continue;
+        }
 
-                // Despawn projectile
-                commands.queue_silenced(move |world: &mut World| {
-                    if let Ok(entity_mut) = world.get_entity_mut(projectile_entity) {
-                        entity_mut.despawn();
-                    }
-                });
+        // Despawn projectile
+        commands.queue_silenced(move |world: &mut World| {
+            if let Ok(entity_mut) = world.get_entity_mut(projectile_entity) {
+                entity_mut.despawn();
+            }
+        });
 
-                // Enemy died
-                if enemy.health <= 0.0 {
-                    // Despawn children (health bar) first
-                    if let Some(children) = children {
-                        for child in children.iter() {
-                            commands.queue_silenced(move |world: &mut World| {
-                                if let Ok(entity_mut) = world.get_entity_mut(child) {
-                                    entity_mut.despawn();
-                                }
-                            });
-                        }
-                    }
-                    let entity_to_despawn = enemy_entity;
-                    commands.queue_silenced(move |world: &mut World| {
-                        if let Ok(entity_mut) = world.get_entity_mut(entity_to_despawn) {
-                            entity_mut.despawn();
-                        }
+        for hit_entity in hits {
+            if let Ok((enemy_entity, enemy_transform, mut enemy, burn)) =
+                enemies.get_mut(hit_entity)
+            {
+                // Homing shots can land on an enemy another hit already killed
+                let was_alive = enemy.health > 0.0;
+                // Calculate damage with type effectiveness multiplier
+                let multiplier = enemy.damage_multiplier(projectile.attack_type);
+                if multiplier > 0.0 {
+                    enemy.health -= projectile.damage * multiplier;
+                    commands.entity(enemy_entity).try_insert(HitFlash::default());
+                    sounds.write(PlaySound {
+                        effect: SoundEffect::ProjectileHit,
+                        position: Some(enemy_transform.translation.truncate()),
                     });
-                    game_state.gold += enemy.gold_reward;
-                    game_state.score += enemy.gold_reward;
+                } else {
+                    spawn_immune_text(&mut commands, enemy_transform.translation.truncate());
+                }
+
+                if let Some(config) = projectile.burn
+                    && multiplier > 0.0
+                    && enemy.health > 0.0
+                {
+                    apply_burn(
+                        &mut commands,
+                        &asset_server,
+                        &mut texture_atlases,
+                        enemy_entity,
+                        burn,
+                        config,
+                        projectile.source,
+                    );
+                }
+
+                // Enemy died; cleanup_dead_enemies despawns it and shakes for bosses
+                if was_alive && enemy.health <= 0.0 {
+                    award_kill(
+                        &enemy,
+                        towers.get_mut(projectile.source).ok().as_deref_mut(),
+                        local_player,
+                        &mut game_state,
+                        &mut killed,
+                    );
                 }
             }
         }
@@ -0,0 +1,51 @@
+use bevy::diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin};
+use bevy::prelude::*;
+
+use crate::resources::VisualQuality;
+use crate::user_settings::Settings;
+
+pub struct QualityPlugin;
+
+impl Plugin for QualityPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+            app.add_plugins(FrameTimeDiagnosticsPlugin::default());
+        }
+        app.init_resource::<VisualQuality>()
+            .add_systems(Update, update_visual_quality);
+    }
+}
+
+/// Reduce visuals when the smoothed frame rate drops below the settings'
+/// threshold and restore them once it climbs past the higher one
+fn update_visual_quality(
+    diagnostics: Res<DiagnosticsStore>,
+    settings: Res<Settings>,
+    mut quality: ResMut<VisualQuality>,
+) {
+    let graphics = &settings.graphics;
+    if !graphics.adaptive_quality {
+        quality.set_if_neq(VisualQuality { reduced: false });
+        return;
+    }
+    let Some(fps) = diagnostics
+        .get(&FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed())
+    else {
+        return;
+    };
+
+    let threshold = if quality.reduced {
+        graphics.restore_quality_above_fps
+    } else {
+        graphics.reduce_quality_below_fps
+    };
+    let reduced = fps < threshold as f64;
+    if quality.set_if_neq(VisualQuality { reduced }) {
+        if reduced {
+            info!("Frame rate dropped to {:.0} FPS, reducing visual effects", fps);
+        } else {
+            info!("Frame rate recovered to {:.0} FPS, restoring visual effects", fps);
+        }
+    }
+}
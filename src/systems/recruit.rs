@@ -5,7 +5,8 @@ use spacetimedb_sdk::Table;
 use crate::components::{RecruitMenu, RecruitOption};
 use crate::map::world_to_tile;
 use crate::module_bindings::{DbConnection, Color as PlayerColor, MyUserTableAccess};
-use crate::resources::{BlockedTiles, GameState, RecruitMenuState, TowerWheelState};
+use crate::resources::{BlockedTiles, GameState, InfiniteResources, RecruitMenuState, TowerMoveState, TowerWheelState};
+use crate::systems::input::{ActionState, InputAction};
 
 pub type SpacetimeDB<'a> = Res<'a, StdbConnection<DbConnection>>;
 
@@ -71,24 +72,24 @@ pub fn show_recruit_menu(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
-    mouse_button: Res<ButtonInput<MouseButton>>,
-    windows: Query<&Window>,
-    camera: Query<(&Camera, &GlobalTransform)>,
+    actions: Res<ActionState>,
     mut menu_state: ResMut<RecruitMenuState>,
     tower_wheel_state: Res<TowerWheelState>,
+    move_state: Res<TowerMoveState>,
     blocked_tiles: Res<BlockedTiles>,
     existing_menus: Query<Entity, With<RecruitMenu>>,
     stdb: Option<SpacetimeDB>,
 ) {
     // Don't show if tower wheel is active or recruit menu already open
-    if !mouse_button.just_pressed(MouseButton::Left) || menu_state.active || tower_wheel_state.active {
+    if !actions.just_pressed(InputAction::Select)
+        || menu_state.active
+        || tower_wheel_state.active
+        || move_state.moving.is_some()
+    {
         return;
     }
 
-    let Ok(window) = windows.single() else { return };
-    let Ok((camera, camera_transform)) = camera.single() else { return };
-    let Some(cursor_pos) = window.cursor_position() else { return };
-    let Ok(world_pos) = camera.viewport_to_world_2d(camera_transform, cursor_pos) else { return };
+    let Some(world_pos) = actions.pointer_world else { return };
 
     // Check if clicked on castle tile
     let (tile_x, tile_y) = world_to_tile(world_pos);
@@ -294,8 +295,7 @@ fn spawn_unit_card(
 /// Hide recruit menu on right-click or escape
 pub fn hide_recruit_menu(
     mut commands: Commands,
-    mouse_button: Res<ButtonInput<MouseButton>>,
-    keyboard: Res<ButtonInput<KeyCode>>,
+    actions: Res<ActionState>,
     mut menu_state: ResMut<RecruitMenuState>,
     menu_entities: Query<Entity, With<RecruitMenu>>,
 ) {
@@ -303,7 +303,7 @@ pub fn hide_recruit_menu(
         return;
     }
 
-    if mouse_button.just_pressed(MouseButton::Right) || keyboard.just_pressed(KeyCode::Escape) {
+    if actions.just_pressed(InputAction::Cancel) {
         for entity in menu_entities.iter() {
             commands.entity(entity).despawn();
         }
@@ -321,11 +321,12 @@ pub fn handle_recruit_selection(
     mut game_state: ResMut<GameState>,
     mut menu_state: ResMut<RecruitMenuState>,
     menu_entities: Query<Entity, With<RecruitMenu>>,
+    infinite: Res<InfiniteResources>,
 ) {
     for (interaction, option) in interaction_query.iter_mut() {
         if *interaction == Interaction::Pressed {
-            if game_state.meat >= option.meat_cost {
-                game_state.meat -= option.meat_cost;
+            if infinite.affords(game_state.meat, option.meat_cost) {
+                game_state.meat -= infinite.charge(option.meat_cost);
                 info!(
                     "Recruited unit: {} for {} meat",
                     option.unit_id, option.meat_cost
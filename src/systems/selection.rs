@@ -0,0 +1,141 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+use crate::components::{SelectionBox, SyncedTower, Tower, TowerSelectionOutline};
+use crate::constants::SCALED_TILE_SIZE;
+use crate::resources::{SelectedTowers, TowerUpgradeMenuState};
+use crate::systems::input::{ActionState, InputAction};
+
+/// Box drags shorter than this (screen pixels) count as a click on one tower
+const CLICK_DRAG_THRESHOLD: f32 = 4.0;
+const SELECTION_COLOR: Color = Color::srgba(1.0, 0.85, 0.2, 0.9);
+
+/// Rubber-band selection: hold the MultiSelect modifier and drag to select
+/// every tower inside the rectangle. A modifier-click toggles a single tower.
+pub fn box_select_towers(
+    mut commands: Commands,
+    actions: Res<ActionState>,
+    upgrade_menu_state: Res<TowerUpgradeMenuState>,
+    mut selected: ResMut<SelectedTowers>,
+    // Screen and world position where the drag started
+    mut drag_start: Local<Option<(Vec2, Vec2)>>,
+    // Only our own towers; mirrored ones belong to other players
+    towers: Query<(Entity, &Transform), (With<Tower>, Without<SyncedTower>)>,
+    mut boxes: Query<(Entity, &mut Node), With<SelectionBox>>,
+) {
+    if actions.just_pressed(InputAction::BoxSelect) {
+        if let (Some(screen), Some(world)) = (actions.pointer_screen, actions.pointer_world) {
+            *drag_start = Some((screen, world));
+            commands.spawn((
+                Node {
+                    position_type: PositionType::Absolute,
+                    left: Val::Px(screen.x),
+                    top: Val::Px(screen.y),
+                    border: UiRect::all(Val::Px(1.0)),
+                    ..default()
+                },
+                BorderColor::all(SELECTION_COLOR),
+                BackgroundColor(SELECTION_COLOR.with_alpha(0.15)),
+                GlobalZIndex(40),
+                Pickable::IGNORE,
+                SelectionBox,
+            ));
+        }
+        return;
+    }
+
+    let Some((start_screen, start_world)) = *drag_start else {
+        // Cancel drops the selection unless it is closing the upgrade menu
+        if actions.just_pressed(InputAction::Cancel) && !upgrade_menu_state.active {
+            selected.towers.clear();
+        }
+        return;
+    };
+
+    if actions.pressed(InputAction::BoxSelect) {
+        if let Some(current) = actions.pointer_screen {
+            let min = start_screen.min(current);
+            let size = (current - start_screen).abs();
+            for (_, mut node) in boxes.iter_mut() {
+                node.left = Val::Px(min.x);
+                node.top = Val::Px(min.y);
+                node.width = Val::Px(size.x);
+                node.height = Val::Px(size.y);
+            }
+        }
+        return;
+    }
+
+    // Drag released
+    *drag_start = None;
+    for (entity, _) in boxes.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    let Some(end_world) = actions.pointer_world else { return };
+    let end_screen = actions.pointer_screen.unwrap_or(start_screen);
+
+    if start_screen.distance(end_screen) < CLICK_DRAG_THRESHOLD {
+        let clicked = towers.iter().find(|(_, transform)| {
+            transform.translation.truncate().distance(end_world) < SCALED_TILE_SIZE / 2.0
+        });
+        if let Some((entity, _)) = clicked
+            && !selected.towers.remove(&entity)
+        {
+            selected.towers.insert(entity);
+        }
+        return;
+    }
+
+    let rect = Rect::from_corners(start_world, end_world);
+    selected.towers = towers
+        .iter()
+        .filter(|(_, transform)| rect.contains(transform.translation.truncate()))
+        .map(|(entity, _)| entity)
+        .collect();
+    info!("Selected {} towers", selected.towers.len());
+}
+
+/// Keep an outline under every selected tower
+pub fn update_selection_outlines(
+    mut commands: Commands,
+    mut selected: ResMut<SelectedTowers>,
+    towers: Query<&Transform, (With<Tower>, Without<SyncedTower>)>,
+    outlines: Query<(Entity, &ChildOf), With<TowerSelectionOutline>>,
+) {
+    // Forget towers that have been removed
+    if selected.towers.iter().any(|entity| !towers.contains(*entity)) {
+        selected.towers.retain(|entity| towers.contains(*entity));
+    }
+
+    if !selected.is_changed() {
+        return;
+    }
+
+    let mut outlined = HashSet::new();
+    for (entity, child_of) in outlines.iter() {
+        if selected.towers.contains(&child_of.parent()) {
+            outlined.insert(child_of.parent());
+        } else {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    for &tower_entity in selected.towers.difference(&outlined) {
+        let Ok(transform) = towers.get(tower_entity) else { continue };
+        // Counter-scaled so the outline size is in world pixels
+        let scale = transform.scale.x;
+        commands.entity(tower_entity).with_children(|parent| {
+            parent.spawn((
+                Sprite {
+                    color: SELECTION_COLOR.with_alpha(0.35),
+                    custom_size: Some(Vec2::splat(SCALED_TILE_SIZE + 8.0)),
+                    ..default()
+                },
+                Transform::from_xyz(0.0, 0.0, -0.1).with_scale(Vec3::splat(1.0 / scale)),
+                TowerSelectionOutline,
+            ));
+        });
+    }
+}
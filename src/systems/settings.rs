@@ -0,0 +1,826 @@
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+use bevy::tasks::{futures::check_ready, AsyncComputeTaskPool, Task};
+use bevy::ui::FocusPolicy;
+
+use crate::resources::{AppState, OverlaySettings};
+use crate::systems::input::{is_bindable_key, key_name, ActionState, InputAction, KeyBindings};
+use crate::systems::menu::{spawn_nine_slice_button, ButtonStyle};
+use crate::systems::networking::test_connection;
+use crate::user_settings::{Settings, UI_SCALE_STEPS};
+
+const MAX_SERVER_FIELD_LEN: usize = 96;
+
+/// Whether the settings panel is open and which action is waiting for a key
+#[derive(Resource, Default)]
+pub struct SettingsMenuState {
+    pub open: bool,
+    pub capturing: Option<InputAction>,
+}
+
+/// Marker for the settings panel root
+#[derive(Component)]
+pub struct SettingsPanel;
+
+/// Button that starts capturing a new key for an action
+#[derive(Component)]
+pub struct RebindButton(pub InputAction);
+
+/// Button that turns the range overlap warning on or off
+#[derive(Component)]
+pub struct ToggleRangeOverlapButton;
+
+/// Button that turns muting in the background on or off
+#[derive(Component)]
+pub struct ToggleMuteOnFocusLossButton;
+
+/// Button that turns pausing offline games in the background on or off
+#[derive(Component)]
+pub struct TogglePauseOnFocusLossButton;
+
+/// Button that turns adaptive visual quality on or off
+#[derive(Component)]
+pub struct ToggleAdaptiveQualityButton;
+
+/// Button that restores the default key bindings
+/// Steps through `UI_SCALE_STEPS`
+#[derive(Component)]
+pub struct CycleUiScaleButton;
+
+#[derive(Component)]
+pub struct ToggleScaleWorldButton;
+
+/// Steps through the colorblind palettes
+#[derive(Component)]
+pub struct CycleColorblindModeButton;
+
+#[derive(Component)]
+pub struct ResetBindingsButton;
+
+/// Button that closes the settings panel
+#[derive(Component)]
+pub struct CloseSettingsButton;
+
+/// Editable server setting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServerField {
+    Host,
+    Module,
+}
+
+impl ServerField {
+    fn label(self) -> &'static str {
+        match self {
+            ServerField::Host => "Host",
+            ServerField::Module => "Module",
+        }
+    }
+
+    fn value(self, settings: &Settings) -> &str {
+        match self {
+            ServerField::Host => &settings.network.host,
+            ServerField::Module => &settings.network.module,
+        }
+    }
+}
+
+/// Server field being typed into; saved to `Settings` on Enter
+#[derive(Resource, Default)]
+pub struct ServerFieldEdit {
+    pub field: Option<ServerField>,
+    pub buffer: String,
+}
+
+/// Running or finished "Test Connection" check
+#[derive(Resource, Default)]
+pub struct ConnectionTest {
+    pub task: Option<Task<Result<String, String>>>,
+    pub result: Option<Result<String, String>>,
+}
+
+/// Text showing the value of a server field
+#[derive(Component)]
+pub struct ServerFieldText(pub ServerField);
+
+/// Button that starts editing a server field
+#[derive(Component)]
+pub struct EditServerFieldButton(pub ServerField);
+
+/// Button that checks the configured server
+#[derive(Component)]
+pub struct TestConnectionButton;
+
+/// Result line of the connection test
+#[derive(Component)]
+pub struct ConnectionStatusText;
+
+pub struct SettingsMenuPlugin;
+
+impl Plugin for SettingsMenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SettingsMenuState>()
+            .init_resource::<ServerFieldEdit>()
+            .init_resource::<ConnectionTest>()
+            .add_systems(
+                Update,
+                (
+                    sync_settings_panel,
+                    handle_rebind_buttons,
+                    capture_rebind_key,
+                    handle_toggle_range_overlap_button,
+                    handle_focus_loss_toggle_buttons,
+                    handle_toggle_adaptive_quality_button,
+                    handle_ui_scale_buttons,
+                    handle_colorblind_mode_button,
+                    handle_reset_bindings_button,
+                    handle_close_settings_button,
+                    handle_edit_server_field_buttons,
+                    type_server_field,
+                    handle_test_connection_button,
+                    poll_connection_test,
+                    update_server_texts,
+                    claim_keyboard,
+                )
+                    // Opened from the main menu or the pause menu
+                    .run_if(in_state(AppState::MainMenu).or(in_state(AppState::InGame))),
+            )
+            .add_systems(OnExit(AppState::MainMenu), close_settings_panel)
+            .add_systems(OnExit(AppState::InGame), close_settings_panel);
+    }
+}
+
+/// Spawn, refresh or remove the panel whenever the menu state changes
+fn sync_settings_panel(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    state: Res<SettingsMenuState>,
+    bindings: Res<KeyBindings>,
+    overlays: Res<OverlaySettings>,
+    settings: Res<Settings>,
+    panels: Query<Entity, With<SettingsPanel>>,
+) {
+    if !state.is_changed() && !bindings.is_changed() && !overlays.is_changed() && !settings.is_changed() {
+        return;
+    }
+
+    for entity in panels.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    if state.open {
+        spawn_settings_panel(&mut commands, &asset_server, &state, &bindings, &overlays, &settings);
+    }
+}
+
+/// Shown value of a server field: the edit buffer while typing into it
+fn server_field_text(field: ServerField, settings: &Settings, edit: &ServerFieldEdit) -> String {
+    if edit.field == Some(field) {
+        format!("{}|", edit.buffer)
+    } else {
+        field.value(settings).to_string()
+    }
+}
+
+fn connection_status_text(test: &ConnectionTest) -> (String, Color) {
+    match (&test.task, &test.result) {
+        (Some(_), _) => ("Testing...".to_string(), Color::srgb(0.8, 0.8, 0.8)),
+        (None, Some(Ok(message))) => (message.clone(), Color::srgb(0.4, 0.9, 0.4)),
+        (None, Some(Err(message))) => (message.clone(), Color::srgb(1.0, 0.4, 0.4)),
+        (None, None) => ("Changes apply on the next launch".to_string(), Color::srgb(0.6, 0.6, 0.6)),
+    }
+}
+
+fn spawn_settings_panel(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    state: &SettingsMenuState,
+    bindings: &KeyBindings,
+    overlays: &OverlaySettings,
+    settings: &Settings,
+) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+            // Keep the main menu buttons underneath from reacting
+            FocusPolicy::Block,
+            GlobalZIndex(100),
+            SettingsPanel,
+        ))
+        .with_children(|overlay| {
+            overlay
+                .spawn((
+                    Node {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(12.0),
+                        padding: UiRect::all(Val::Px(24.0)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(0.1, 0.1, 0.15, 0.95)),
+                    BorderRadius::all(Val::Px(8.0)),
+                ))
+                .with_children(|panel| {
+                    panel.spawn((
+                        Text::new("SETTINGS"),
+                        TextFont {
+                            font_size: 36.0,
+                            ..default()
+                        },
+                        TextColor(Color::WHITE),
+                    ));
+
+                    panel.spawn((
+                        Text::new("Controls"),
+                        TextFont {
+                            font_size: 22.0,
+                            ..default()
+                        },
+                        TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                    ));
+
+                    for action in InputAction::BINDABLE {
+                        let key_label = if state.capturing == Some(action) {
+                            "Press a key...".to_string()
+                        } else {
+                            bindings.key(action).map(key_name).unwrap_or_else(|| "-".to_string())
+                        };
+
+                        panel
+                            .spawn(Node {
+                                width: Val::Px(420.0),
+                                flex_direction: FlexDirection::Row,
+                                justify_content: JustifyContent::SpaceBetween,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            })
+                            .with_children(|row| {
+                                row.spawn((
+                                    Text::new(action.label()),
+                                    TextFont {
+                                        font_size: 18.0,
+                                        ..default()
+                                    },
+                                    TextColor(Color::WHITE),
+                                ));
+                                spawn_nine_slice_button(
+                                    row,
+                                    asset_server,
+                                    ButtonStyle::SmallBlueSquare,
+                                    &key_label,
+                                    RebindButton(action),
+                                );
+                            });
+                    }
+
+                    panel.spawn((
+                        Text::new("Display"),
+                        TextFont {
+                            font_size: 22.0,
+                            ..default()
+                        },
+                        TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                    ));
+
+                    panel
+                        .spawn(Node {
+                            width: Val::Px(420.0),
+                            flex_direction: FlexDirection::Row,
+                            justify_content: JustifyContent::SpaceBetween,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        })
+                        .with_children(|row| {
+                            row.spawn((
+                                Text::new("Range Overlap Warning"),
+                                TextFont {
+                                    font_size: 18.0,
+                                    ..default()
+                                },
+                                TextColor(Color::WHITE),
+                            ));
+                            spawn_nine_slice_button(
+                                row,
+                                asset_server,
+                                ButtonStyle::SmallBlueSquare,
+                                if overlays.range_overlap_warning { "ON" } else { "OFF" },
+                                ToggleRangeOverlapButton,
+                            );
+                        });
+
+                    panel
+                        .spawn(Node {
+                            width: Val::Px(420.0),
+                            flex_direction: FlexDirection::Row,
+                            justify_content: JustifyContent::SpaceBetween,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        })
+                        .with_children(|row| {
+                            row.spawn((
+                                Text::new("Mute In Background"),
+                                TextFont {
+                                    font_size: 18.0,
+                                    ..default()
+                                },
+                                TextColor(Color::WHITE),
+                            ));
+                            spawn_nine_slice_button(
+                                row,
+                                asset_server,
+                                ButtonStyle::SmallBlueSquare,
+                                if settings.audio.mute_on_focus_loss { "ON" } else { "OFF" },
+                                ToggleMuteOnFocusLossButton,
+                            );
+                        });
+
+                    panel
+                        .spawn(Node {
+                            width: Val::Px(420.0),
+                            flex_direction: FlexDirection::Row,
+                            justify_content: JustifyContent::SpaceBetween,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        })
+                        .with_children(|row| {
+                            row.spawn((
+                                Text::new("Pause In Background (offline)"),
+                                TextFont {
+                                    font_size: 18.0,
+                                    ..default()
+                                },
+                                TextColor(Color::WHITE),
+                            ));
+                            spawn_nine_slice_button(
+                                row,
+                                asset_server,
+                                ButtonStyle::SmallBlueSquare,
+                                if settings.graphics.pause_on_focus_loss { "ON" } else { "OFF" },
+                                TogglePauseOnFocusLossButton,
+                            );
+                        });
+
+                    panel
+                        .spawn(Node {
+                            width: Val::Px(420.0),
+                            flex_direction: FlexDirection::Row,
+                            justify_content: JustifyContent::SpaceBetween,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        })
+                        .with_children(|row| {
+                            row.spawn((
+                                Text::new("Reduce Effects When Slow"),
+                                TextFont {
+                                    font_size: 18.0,
+                                    ..default()
+                                },
+                                TextColor(Color::WHITE),
+                            ));
+                            spawn_nine_slice_button(
+                                row,
+                                asset_server,
+                                ButtonStyle::SmallBlueSquare,
+                                if settings.graphics.adaptive_quality { "ON" } else { "OFF" },
+                                ToggleAdaptiveQualityButton,
+                            );
+                        });
+
+                    panel
+                        .spawn(Node {
+                            width: Val::Px(420.0),
+                            flex_direction: FlexDirection::Row,
+                            justify_content: JustifyContent::SpaceBetween,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        })
+                        .with_children(|row| {
+                            row.spawn((
+                                Text::new("Interface Scale"),
+                                TextFont {
+                                    font_size: 18.0,
+                                    ..default()
+                                },
+                                TextColor(Color::WHITE),
+                            ));
+                            spawn_nine_slice_button(
+                                row,
+                                asset_server,
+                                ButtonStyle::SmallBlueSquare,
+                                &format!("{:.0}%", settings.ui_scale() * 100.0),
+                                CycleUiScaleButton,
+                            );
+                        });
+
+                    panel
+                        .spawn(Node {
+                            width: Val::Px(420.0),
+                            flex_direction: FlexDirection::Row,
+                            justify_content: JustifyContent::SpaceBetween,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        })
+                        .with_children(|row| {
+                            row.spawn((
+                                Text::new("Scale Map Too"),
+                                TextFont {
+                                    font_size: 18.0,
+                                    ..default()
+                                },
+                                TextColor(Color::WHITE),
+                            ));
+                            spawn_nine_slice_button(
+                                row,
+                                asset_server,
+                                ButtonStyle::SmallBlueSquare,
+                                if settings.graphics.scale_world { "ON" } else { "OFF" },
+                                ToggleScaleWorldButton,
+                            );
+                        });
+
+                    panel
+                        .spawn(Node {
+                            width: Val::Px(420.0),
+                            flex_direction: FlexDirection::Row,
+                            justify_content: JustifyContent::SpaceBetween,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        })
+                        .with_children(|row| {
+                            row.spawn((
+                                Text::new("Colorblind Mode"),
+                                TextFont {
+                                    font_size: 18.0,
+                                    ..default()
+                                },
+                                TextColor(Color::WHITE),
+                            ));
+                            spawn_nine_slice_button(
+                                row,
+                                asset_server,
+                                ButtonStyle::SmallBlueSquare,
+                                settings.graphics.colorblind_mode.label(),
+                                CycleColorblindModeButton,
+                            );
+                        });
+
+                    panel.spawn((
+                        Text::new("Server"),
+                        TextFont {
+                            font_size: 22.0,
+                            ..default()
+                        },
+                        TextColor(Color::srgb(0.8, 0.8, 0.8)),
+                    ));
+
+                    for field in [ServerField::Host, ServerField::Module] {
+                        panel
+                            .spawn(Node {
+                                width: Val::Px(420.0),
+                                flex_direction: FlexDirection::Row,
+                                justify_content: JustifyContent::SpaceBetween,
+                                align_items: AlignItems::Center,
+                                column_gap: Val::Px(8.0),
+                                ..default()
+                            })
+                            .with_children(|row| {
+                                row.spawn((
+                                    Text::new(field.label()),
+                                    TextFont {
+                                        font_size: 18.0,
+                                        ..default()
+                                    },
+                                    TextColor(Color::WHITE),
+                                ));
+                                // Filled in by update_server_texts
+                                row.spawn((
+                                    Text::new(""),
+                                    TextFont {
+                                        font_size: 16.0,
+                                        ..default()
+                                    },
+                                    TextColor(Color::srgb(0.8, 0.9, 1.0)),
+                                    ServerFieldText(field),
+                                ));
+                                spawn_nine_slice_button(
+                                    row,
+                                    asset_server,
+                                    ButtonStyle::SmallBlueSquare,
+                                    "EDIT",
+                                    EditServerFieldButton(field),
+                                );
+                            });
+                    }
+
+                    spawn_nine_slice_button(
+                        panel,
+                        asset_server,
+                        ButtonStyle::SmallBlueRound,
+                        "TEST",
+                        TestConnectionButton,
+                    );
+                    panel.spawn((
+                        Text::new(""),
+                        TextFont {
+                            font_size: 14.0,
+                            ..default()
+                        },
+                        TextColor(Color::srgb(0.6, 0.6, 0.6)),
+                        ConnectionStatusText,
+                    ));
+
+                    panel
+                        .spawn(Node {
+                            flex_direction: FlexDirection::Row,
+                            column_gap: Val::Px(16.0),
+                            margin: UiRect::top(Val::Px(12.0)),
+                            ..default()
+                        })
+                        .with_children(|buttons| {
+                            spawn_nine_slice_button(
+                                buttons,
+                                asset_server,
+                                ButtonStyle::SmallRedSquare,
+                                "DEFAULTS",
+                                ResetBindingsButton,
+                            );
+                            spawn_nine_slice_button(
+                                buttons,
+                                asset_server,
+                                ButtonStyle::SmallBlueSquare,
+                                "BACK",
+                                CloseSettingsButton,
+                            );
+                        });
+                });
+        });
+}
+
+fn handle_rebind_buttons(
+    query: Query<(&Interaction, &RebindButton), Changed<Interaction>>,
+    mut state: ResMut<SettingsMenuState>,
+) {
+    for (interaction, button) in query.iter() {
+        if *interaction == Interaction::Pressed {
+            state.capturing = Some(button.0);
+        }
+    }
+}
+
+/// Assign the next key press to the action being rebound
+fn capture_rebind_key(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut state: ResMut<SettingsMenuState>,
+    mut bindings: ResMut<KeyBindings>,
+) {
+    let Some(action) = state.capturing else { return };
+    let Some(key) = keyboard
+        .get_just_pressed()
+        .copied()
+        .find(|key| is_bindable_key(*key))
+    else {
+        return;
+    };
+
+    bindings.set(action, key);
+    state.capturing = None;
+}
+
+fn handle_toggle_range_overlap_button(
+    query: Query<&Interaction, (Changed<Interaction>, With<ToggleRangeOverlapButton>)>,
+    mut overlays: ResMut<OverlaySettings>,
+) {
+    for interaction in query.iter() {
+        if *interaction == Interaction::Pressed {
+            overlays.range_overlap_warning = !overlays.range_overlap_warning;
+        }
+    }
+}
+
+fn handle_focus_loss_toggle_buttons(
+    mute: Query<&Interaction, (Changed<Interaction>, With<ToggleMuteOnFocusLossButton>)>,
+    pause: Query<&Interaction, (Changed<Interaction>, With<TogglePauseOnFocusLossButton>)>,
+    mut settings: ResMut<Settings>,
+) {
+    for interaction in mute.iter() {
+        if *interaction == Interaction::Pressed {
+            settings.audio.mute_on_focus_loss = !settings.audio.mute_on_focus_loss;
+        }
+    }
+    for interaction in pause.iter() {
+        if *interaction == Interaction::Pressed {
+            settings.graphics.pause_on_focus_loss = !settings.graphics.pause_on_focus_loss;
+        }
+    }
+}
+
+fn handle_toggle_adaptive_quality_button(
+    query: Query<&Interaction, (Changed<Interaction>, With<ToggleAdaptiveQualityButton>)>,
+    mut settings: ResMut<Settings>,
+) {
+    for interaction in query.iter() {
+        if *interaction == Interaction::Pressed {
+            settings.graphics.adaptive_quality = !settings.graphics.adaptive_quality;
+        }
+    }
+}
+
+fn handle_ui_scale_buttons(
+    cycle: Query<&Interaction, (Changed<Interaction>, With<CycleUiScaleButton>)>,
+    scale_world: Query<&Interaction, (Changed<Interaction>, With<ToggleScaleWorldButton>)>,
+    mut settings: ResMut<Settings>,
+) {
+    for interaction in cycle.iter() {
+        if *interaction == Interaction::Pressed {
+            let current = settings.ui_scale();
+            settings.graphics.ui_scale = UI_SCALE_STEPS
+                .iter()
+                .copied()
+                .find(|step| *step > current)
+                .unwrap_or(UI_SCALE_STEPS[0]);
+        }
+    }
+    for interaction in scale_world.iter() {
+        if *interaction == Interaction::Pressed {
+            settings.graphics.scale_world = !settings.graphics.scale_world;
+        }
+    }
+}
+
+fn handle_colorblind_mode_button(
+    query: Query<&Interaction, (Changed<Interaction>, With<CycleColorblindModeButton>)>,
+    mut settings: ResMut<Settings>,
+) {
+    for interaction in query.iter() {
+        if *interaction == Interaction::Pressed {
+            settings.graphics.colorblind_mode = settings.graphics.colorblind_mode.next();
+        }
+    }
+}
+
+fn handle_reset_bindings_button(
+    query: Query<&Interaction, (Changed<Interaction>, With<ResetBindingsButton>)>,
+    mut state: ResMut<SettingsMenuState>,
+    mut bindings: ResMut<KeyBindings>,
+) {
+    for interaction in query.iter() {
+        if *interaction == Interaction::Pressed {
+            *bindings = KeyBindings::defaults();
+            state.capturing = None;
+        }
+    }
+}
+
+fn handle_close_settings_button(
+    query: Query<&Interaction, (Changed<Interaction>, With<CloseSettingsButton>)>,
+    mut state: ResMut<SettingsMenuState>,
+    mut edit: ResMut<ServerFieldEdit>,
+) {
+    for interaction in query.iter() {
+        if *interaction == Interaction::Pressed {
+            state.open = false;
+            state.capturing = None;
+            edit.field = None;
+        }
+    }
+}
+
+fn handle_edit_server_field_buttons(
+    query: Query<(&Interaction, &EditServerFieldButton), Changed<Interaction>>,
+    settings: Res<Settings>,
+    mut edit: ResMut<ServerFieldEdit>,
+) {
+    for (interaction, button) in query.iter() {
+        if *interaction == Interaction::Pressed {
+            edit.field = Some(button.0);
+            edit.buffer = button.0.value(&settings).to_string();
+        }
+    }
+}
+
+/// While a server field is being edited, typed characters go to it. Enter
+/// saves the value, Escape discards it.
+fn type_server_field(
+    mut keyboard_input: MessageReader<KeyboardInput>,
+    mut edit: ResMut<ServerFieldEdit>,
+    mut settings: ResMut<Settings>,
+    mut actions: ResMut<ActionState>,
+) {
+    let Some(field) = edit.field else {
+        keyboard_input.clear();
+        return;
+    };
+    actions.text_entry = true;
+
+    for input in keyboard_input.read() {
+        if !input.state.is_pressed() {
+            continue;
+        }
+        match &input.logical_key {
+            Key::Escape => {
+                edit.field = None;
+                edit.buffer.clear();
+                return;
+            }
+            Key::Enter => {
+                let value = std::mem::take(&mut edit.buffer).trim().to_string();
+                edit.field = None;
+                if !value.is_empty() {
+                    match field {
+                        ServerField::Host => settings.network.host = value,
+                        ServerField::Module => settings.network.module = value,
+                    }
+                }
+                return;
+            }
+            Key::Backspace => {
+                edit.buffer.pop();
+            }
+            Key::Character(text) => {
+                for c in text.chars() {
+                    if !c.is_control() && !c.is_whitespace() && edit.buffer.len() < MAX_SERVER_FIELD_LEN {
+                        edit.buffer.push(c);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Run the preflight check against the saved host and module
+fn handle_test_connection_button(
+    query: Query<&Interaction, (Changed<Interaction>, With<TestConnectionButton>)>,
+    settings: Res<Settings>,
+    mut test: ResMut<ConnectionTest>,
+) {
+    for interaction in query.iter() {
+        if *interaction != Interaction::Pressed || test.task.is_some() {
+            continue;
+        }
+        let host = settings.network.host.clone();
+        let module = settings.network.module.clone();
+        test.result = None;
+        test.task = Some(AsyncComputeTaskPool::get().spawn(async move { test_connection(&host, &module) }));
+    }
+}
+
+fn poll_connection_test(mut test: ResMut<ConnectionTest>) {
+    let Some(task) = test.task.as_mut() else { return };
+    if let Some(result) = check_ready(task) {
+        test.task = None;
+        test.result = Some(result);
+    }
+}
+
+/// Refresh the server field values and the test result in place
+#[allow(clippy::type_complexity)]
+fn update_server_texts(
+    settings: Res<Settings>,
+    edit: Res<ServerFieldEdit>,
+    test: Res<ConnectionTest>,
+    mut field_texts: Query<(&mut Text, &ServerFieldText)>,
+    mut status_texts: Query<(&mut Text, &mut TextColor), (With<ConnectionStatusText>, Without<ServerFieldText>)>,
+    added: Query<(), Added<ServerFieldText>>,
+) {
+    if !settings.is_changed() && !edit.is_changed() && !test.is_changed() && added.is_empty() {
+        return;
+    }
+    for (mut text, field_text) in field_texts.iter_mut() {
+        text.0 = server_field_text(field_text.0, &settings, &edit);
+    }
+    let (status, color) = connection_status_text(&test);
+    for (mut text, mut text_color) in status_texts.iter_mut() {
+        text.0 = status.clone();
+        text_color.0 = color;
+    }
+}
+
+/// Keys pressed while the panel is up (rebinding, typing a server) are for
+/// the panel, not the game underneath
+fn claim_keyboard(state: Res<SettingsMenuState>, mut actions: ResMut<ActionState>) {
+    if state.open {
+        actions.text_entry = true;
+    }
+}
+
+fn close_settings_panel(
+    mut commands: Commands,
+    mut state: ResMut<SettingsMenuState>,
+    mut edit: ResMut<ServerFieldEdit>,
+    panels: Query<Entity, With<SettingsPanel>>,
+) {
+    state.open = false;
+    state.capturing = None;
+    edit.field = None;
+    for entity in panels.iter() {
+        commands.entity(entity).despawn();
+    }
+}
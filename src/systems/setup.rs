@@ -2,11 +2,13 @@ use bevy::prelude::*;
 use bevy_ecs_tiled::prelude::*;
 use bevy_spacetimedb::*;
 use spacetimedb_sdk::Table;
-use crate::components::{Castle, FogTile, GameUI};
+use crate::components::{AnimationTimer, Castle, CastleHealthFill, DamageVignette, FogTile, GameUI, LastStandTint, NightTint};
+use crate::config::{AmbientConfig, StartingResourcesConfig};
 use crate::constants::{CASTLE_SIZE, MAP_HEIGHT, MAP_SCALE, MAP_WIDTH, SCALED_TILE_SIZE};
 use crate::map::tile_to_world;
 use crate::module_bindings::{Color as PlayerColor, DbConnection, MyUserTableAccess, RemoteModule};
-use crate::resources::{BlockedTiles, FogOfWar, StdbConfig};
+use crate::resources::{BlockedTiles, EndlessMode, FogOfWar, GameState, StdbConfig};
+use crate::systems::AnimationInfo;
 
 /// Type alias for cleaner SpacetimeDB resource access
 pub type SpacetimeDB<'a> = Res<'a, StdbConnection<DbConnection>>;
@@ -37,6 +39,21 @@ pub fn setup_camera(mut commands: Commands) {
 /// Connect to SpacetimeDB using the delayed connection feature
 /// This runs when entering InGame state and establishes the connection with optional token
 pub fn connect_to_spacetimedb(world: &mut World) {
+    // Back from a game we left: the connection is still open and rejoins
+    if world.contains_resource::<StdbConnection<DbConnection>>() {
+        return;
+    }
+
+    let fresh_identity = world
+        .get_resource::<StdbConfig>()
+        .is_some_and(|config| config.fresh_identity);
+    if fresh_identity {
+        // The saved token is left untouched for the next normal launch
+        info!("Connecting to SpacetimeDB with a new identity...");
+        connect_with_token::<DbConnection, RemoteModule>(world, None);
+        return;
+    }
+
     let token = world
         .get_resource::<StdbConfig>()
         .and_then(|config| config.token.clone());
@@ -50,6 +67,67 @@ pub fn connect_to_spacetimedb(world: &mut World) {
 
 }
 
+/// Hand out the starting resources of the chosen game mode from
+/// starting_resources.toml, keeping the defaults if it is missing or invalid
+pub fn apply_starting_resources(mut game_state: ResMut<GameState>, endless: Res<EndlessMode>) {
+    let config = StartingResourcesConfig::load().unwrap_or_else(|e| {
+        warn!("Using default starting resources: {}", e);
+        StartingResourcesConfig::default()
+    });
+    game_state.start_with(config.for_mode(endless.enabled));
+}
+
+/// Spawn the animated decorations from ambient.toml. They are only visual,
+/// so a missing or broken file just leaves the map static.
+pub fn setup_ambient_decorations(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let config = match AmbientConfig::load() {
+        Ok(config) => config,
+        Err(e) => {
+            warn!("No ambient decorations: {}", e);
+            return;
+        }
+    };
+
+    for decoration in &config.decorations {
+        if decoration.frame_count == 0 {
+            warn!("Skipping {}: frame_count must be at least 1", decoration.sprite_path);
+            continue;
+        }
+        // One texture and atlas layout shared by every tile of the decoration
+        let texture = asset_server.load(&decoration.sprite_path);
+        let frame_size = UVec2::from(decoration.frame_size);
+        let layout = asset_server.add(TextureAtlasLayout::from_grid(
+            frame_size,
+            decoration.frame_count as u32,
+            1,
+            None,
+            None,
+        ));
+        let scale = SCALED_TILE_SIZE / frame_size.x as f32;
+
+        for (i, &(tile_x, tile_y)) in decoration.tiles.iter().enumerate() {
+            let pos = tile_to_world(tile_x, tile_y);
+            commands.spawn((
+                Sprite::from_atlas_image(
+                    texture.clone(),
+                    TextureAtlas {
+                        layout: layout.clone(),
+                        // Offset the start so neighbours don't move in lockstep
+                        index: (i * 3) % decoration.frame_count,
+                    },
+                ),
+                Transform::from_xyz(pos.x, pos.y, 0.5).with_scale(Vec3::splat(scale)),
+                AnimationTimer {
+                    timer: Timer::from_seconds(decoration.frame_time, TimerMode::Repeating),
+                },
+                AnimationInfo {
+                    frame_count: decoration.frame_count,
+                },
+            ));
+        }
+    }
+}
+
 /// Setup game elements (runs when entering InGame state)
 pub fn setup_game(
     mut commands: Commands,
@@ -73,6 +151,74 @@ pub fn setup_game(
         Transform::from_xyz(400.0, 0.0, 1.0).with_scale(Vec3::splat(castle_scale)),
         Castle,
     ));
+
+    // Castle health bar (kept outside the castle hierarchy so it isn't scaled with the sprite)
+    let bar_width = SCALED_TILE_SIZE * 3.0;
+    let bar_y = CASTLE_SIZE.y * castle_scale / 2.0 + 8.0;
+    commands
+        .spawn((
+            Sprite {
+                color: Color::srgb(0.15, 0.15, 0.15),
+                custom_size: Some(Vec2::new(bar_width + 4.0, 10.0)),
+                ..default()
+            },
+            Transform::from_xyz(400.0, bar_y, 4.0),
+        ))
+        .with_children(|bar| {
+            bar.spawn((
+                Sprite {
+                    color: Color::srgb(0.0, 1.0, 0.0),
+                    custom_size: Some(Vec2::new(bar_width, 6.0)),
+                    ..default()
+                },
+                Transform::from_xyz(0.0, 0.0, 0.1),
+                CastleHealthFill { max_width: bar_width },
+            ));
+        });
+
+    // Red overlay flashed when the castle takes damage
+    let mut vignette_timer = Timer::from_seconds(0.4, TimerMode::Once);
+    vignette_timer.finish();
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            ..default()
+        },
+        BackgroundColor(Color::NONE),
+        GlobalZIndex(50),
+        Pickable::IGNORE,
+        DamageVignette { timer: vignette_timer },
+    ));
+
+    // Red tint held while the last stand slows time down
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            ..default()
+        },
+        BackgroundColor(Color::NONE),
+        GlobalZIndex(49),
+        Pickable::IGNORE,
+        LastStandTint,
+    ));
+
+    // Darkens with the day/night cycle, under the other overlays
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            ..default()
+        },
+        BackgroundColor(Color::NONE),
+        GlobalZIndex(48),
+        Pickable::IGNORE,
+        NightTint,
+    ));
 }
 
 pub fn setup_fog_of_war(
@@ -0,0 +1,169 @@
+use bevy::prelude::*;
+use spacetimedb_sdk::Table;
+
+use crate::components::{SyncedTower, Tower};
+use crate::events::EnemyKilled;
+use crate::module_bindings::player_stats_table::PlayerStatsTableAccess;
+use crate::module_bindings::record_stats_reducer::record_stats;
+use crate::module_bindings::user_table::UserTableAccess;
+use crate::resources::{AppState, GameState};
+use crate::systems::networking::SpacetimeDB;
+
+/// Seconds between `record_stats` calls, so kills aren't sent one by one
+const STATS_SYNC_INTERVAL: f32 = 1.0;
+
+/// What the local player did this game. Kills and gathered resources not yet
+/// reported to the server are kept apart until `sync_game_stats` sends them;
+/// towers and gold are counted by the server itself.
+#[derive(Resource, Default)]
+pub struct GameStats {
+    pub towers_built: u32,
+    /// Enemies killed by this player's towers
+    pub kills: u32,
+    pub wood_gathered: u32,
+    pub meat_gathered: u32,
+    unsynced_kills: u32,
+    unsynced_wood: u32,
+    unsynced_meat: u32,
+}
+
+impl GameStats {
+    pub fn gather_wood(&mut self, amount: u32) {
+        self.wood_gathered += amount;
+        self.unsynced_wood += amount;
+    }
+
+    pub fn gather_meat(&mut self, amount: u32) {
+        self.meat_gathered += amount;
+        self.unsynced_meat += amount;
+    }
+
+    fn has_unsynced(&self) -> bool {
+        self.unsynced_kills > 0 || self.unsynced_wood > 0 || self.unsynced_meat > 0
+    }
+}
+
+/// One player's line in the end-game breakdown
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatsRow {
+    pub name: String,
+    pub towers_built: u32,
+    pub kills: u32,
+    pub gold_earned: u32,
+    pub wood_gathered: u32,
+    pub meat_gathered: u32,
+}
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameStats>()
+            .add_systems(
+                Update,
+                (track_game_stats, sync_game_stats)
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(OnExit(AppState::InGame), flush_game_stats);
+    }
+}
+
+/// Count the towers the player builds and the kills their towers make;
+/// towers and kills of other players are counted on their own clients
+fn track_game_stats(
+    stdb: Option<SpacetimeDB>,
+    mut killed: MessageReader<EnemyKilled>,
+    towers: Query<(), (Added<Tower>, Without<SyncedTower>)>,
+    mut stats: ResMut<GameStats>,
+) {
+    let me = stdb.and_then(|stdb| stdb.try_identity());
+    let kills = killed
+        .read()
+        .filter(|kill| kill.tower_type_id.is_some() && (kill.tower_owner.is_none() || kill.tower_owner == me))
+        .count() as u32;
+    if kills > 0 {
+        stats.kills += kills;
+        stats.unsynced_kills += kills;
+    }
+    let built = towers.iter().count() as u32;
+    if built > 0 {
+        stats.towers_built += built;
+    }
+}
+
+fn sync_game_stats(
+    time: Res<Time>,
+    stdb: Option<SpacetimeDB>,
+    mut stats: ResMut<GameStats>,
+    mut since_sync: Local<f32>,
+) {
+    *since_sync += time.delta_secs();
+    if *since_sync < STATS_SYNC_INTERVAL || !stats.has_unsynced() {
+        return;
+    }
+    *since_sync = 0.0;
+    send_stats(stdb.as_ref(), &mut stats);
+}
+
+/// Send what is left when the game ends, so the breakdown is complete
+fn flush_game_stats(stdb: Option<SpacetimeDB>, mut stats: ResMut<GameStats>) {
+    if stats.has_unsynced() {
+        send_stats(stdb.as_ref(), &mut stats);
+    }
+}
+
+fn send_stats(stdb: Option<&SpacetimeDB>, stats: &mut GameStats) {
+    let kills = std::mem::take(&mut stats.unsynced_kills);
+    let wood = std::mem::take(&mut stats.unsynced_wood);
+    let meat = std::mem::take(&mut stats.unsynced_meat);
+    let Some(stdb) = stdb else { return };
+    if let Err(e) = stdb.reducers().record_stats(kills, wood, meat) {
+        warn!("Failed to send stats: {}", e);
+    }
+}
+
+/// Rows for the end-game breakdown, best killer first. Online it lists every
+/// online player from `player_stats`; the local player's row always uses the
+/// local counts, which are ahead of the server's.
+pub fn stats_breakdown(stdb: Option<&SpacetimeDB>, game_state: &GameState, stats: &GameStats) -> Vec<StatsRow> {
+    let local = |name: String| StatsRow {
+        name,
+        towers_built: stats.towers_built,
+        kills: stats.kills,
+        gold_earned: game_state.gold_earned.max(0) as u32,
+        wood_gathered: stats.wood_gathered,
+        meat_gathered: stats.meat_gathered,
+    };
+
+    let Some(stdb) = stdb else {
+        return vec![local("You".to_string())];
+    };
+    let me = stdb.try_identity();
+    let mut rows: Vec<StatsRow> = stdb
+        .db()
+        .user()
+        .iter()
+        .filter(|user| user.online && user.in_game)
+        .filter_map(|user| {
+            let name = user.name.clone().unwrap_or_else(|| "Anonymous".to_string());
+            if Some(user.identity) == me {
+                return Some(local(format!("{} (you)", name)));
+            }
+            let row = stdb.db().player_stats().identity().find(&user.identity)?;
+            Some(StatsRow {
+                name,
+                towers_built: row.towers_built,
+                kills: row.kills,
+                gold_earned: row.gold_earned,
+                wood_gathered: row.wood_gathered,
+                meat_gathered: row.meat_gathered,
+            })
+        })
+        .collect();
+    if rows.is_empty() {
+        rows.push(local("You".to_string()));
+    }
+    rows.sort_by_key(|row| std::cmp::Reverse(row.kills));
+    rows
+}
@@ -0,0 +1,147 @@
+use bevy::prelude::*;
+
+use crate::components::{AnimationTimer, Burn, BurnFlame, Enemy, Tower};
+use crate::config::BurnConfig;
+use crate::constants::{BURN_TICK_INTERVAL, SCALED_TILE_SIZE};
+use crate::events::EnemyKilled;
+use crate::resources::GameState;
+use crate::systems::enemy::award_kill;
+use crate::systems::networking::SpacetimeDB;
+use crate::systems::AnimationInfo;
+
+// Flame overlay (Fire_01.png is 8 frames of 64x64)
+pub const FLAME_SPRITE: &str = "Particle FX/Fire_01.png";
+const FLAME_FRAME_SIZE: UVec2 = UVec2::new(64, 64);
+const FLAME_FRAME_COUNT: usize = 8;
+
+/// Add a burn stack to `enemy_entity`, or set it on fire if it isn't burning yet
+pub fn apply_burn(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    texture_atlases: &mut ResMut<Assets<TextureAtlasLayout>>,
+    enemy_entity: Entity,
+    burn: Option<Mut<Burn>>,
+    config: BurnConfig,
+    source: Entity,
+) {
+    if let Some(mut burn) = burn {
+        add_burn_stack(&mut burn, config, source);
+        return;
+    }
+
+    let layout = TextureAtlasLayout::from_grid(FLAME_FRAME_SIZE, FLAME_FRAME_COUNT as u32, 1, None, None);
+    let flame = commands
+        .spawn((
+            Sprite {
+                image: asset_server.load(FLAME_SPRITE),
+                color: Color::srgba(1.0, 1.0, 1.0, 0.85),
+                texture_atlas: Some(TextureAtlas {
+                    layout: texture_atlases.add(layout),
+                    index: 0,
+                }),
+                ..default()
+            },
+            // Scaled in update_burns once the stack count is known
+            Transform::from_xyz(0.0, 0.0, 0.5).with_scale(Vec3::ZERO),
+            AnimationTimer {
+                timer: Timer::from_seconds(0.08, TimerMode::Repeating),
+            },
+            AnimationInfo {
+                frame_count: FLAME_FRAME_COUNT,
+            },
+            BurnFlame,
+        ))
+        .id();
+
+    // Another hit this frame may have set the enemy on fire already; its Burn
+    // only shows up once commands apply, so check again then and stack onto it
+    commands.queue_silenced(move |world: &mut World| {
+        if let Some(mut burn) = world.get_mut::<Burn>(enemy_entity) {
+            add_burn_stack(&mut burn, config, source);
+            world.despawn(flame);
+        } else if let Ok(mut enemy) = world.get_entity_mut(enemy_entity) {
+            enemy.add_child(flame).insert(Burn {
+                stacks: 1.min(config.max_stacks),
+                max_stacks: config.max_stacks,
+                dps_per_stack: config.dps_per_stack,
+                timer: Timer::from_seconds(config.duration, TimerMode::Once),
+                tick: Timer::from_seconds(BURN_TICK_INTERVAL, TimerMode::Repeating),
+                source,
+                flame,
+            });
+        } else {
+            world.despawn(flame);
+        }
+    });
+}
+
+fn add_burn_stack(burn: &mut Burn, config: BurnConfig, source: Entity) {
+    burn.stacks = (burn.stacks + 1).min(burn.max_stacks);
+    burn.max_stacks = burn.max_stacks.max(config.max_stacks);
+    burn.dps_per_stack = burn.dps_per_stack.max(config.dps_per_stack);
+    burn.timer = Timer::from_seconds(config.duration, TimerMode::Once);
+    burn.source = source;
+}
+
+/// Deal burn damage every tick, drop a stack each time the burn timer runs
+/// out and size the flame overlay to the remaining stacks
+pub fn update_burns(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut enemies: Query<(Entity, &Transform, &mut Enemy, &mut Burn)>,
+    mut flames: Query<&mut Transform, (With<BurnFlame>, Without<Enemy>)>,
+    mut towers: Query<&mut Tower>,
+    mut game_state: ResMut<GameState>,
+    mut killed: MessageWriter<EnemyKilled>,
+    stdb: Option<SpacetimeDB>,
+) {
+    let local_player = stdb.and_then(|stdb| stdb.try_identity());
+    for (entity, transform, mut enemy, mut burn) in enemies.iter_mut() {
+        if enemy.health <= 0.0 {
+            continue;
+        }
+
+        burn.tick.tick(time.delta());
+        let ticks = burn.tick.times_finished_this_tick();
+        if ticks > 0 {
+            // Burn is untyped fire damage: defense types don't reduce it, armor does
+            enemy.health -=
+                burn.dps_per_stack * burn.stacks as f32 * BURN_TICK_INTERVAL * ticks as f32 * enemy.damage_taken;
+
+            if enemy.health <= 0.0 {
+                award_kill(
+                    &enemy,
+                    towers.get_mut(burn.source).ok().as_deref_mut(),
+                    local_player,
+                    &mut game_state,
+                    &mut killed,
+                );
+                continue;
+            }
+        }
+
+        burn.timer.tick(time.delta());
+        if burn.timer.is_finished() {
+            burn.stacks = burn.stacks.saturating_sub(1);
+            burn.timer.reset();
+        }
+
+        if burn.stacks == 0 {
+            let flame = burn.flame;
+            commands.queue_silenced(move |world: &mut World| {
+                if let Ok(entity_mut) = world.get_entity_mut(flame) {
+                    entity_mut.despawn();
+                }
+            });
+            commands.entity(entity).try_remove::<Burn>();
+            continue;
+        }
+
+        // The flame is a child, so undo the enemy's scale to size it in world pixels
+        if let Ok(mut flame_transform) = flames.get_mut(burn.flame) {
+            let world_size = SCALED_TILE_SIZE * (0.6 + 0.2 * burn.stacks as f32);
+            let parent_scale = transform.scale.x.max(f32::EPSILON);
+            flame_transform.scale = Vec3::splat(world_size / (FLAME_FRAME_SIZE.x as f32 * parent_scale));
+        }
+    }
+}
@@ -1,11 +1,21 @@
 use bevy::ecs::relationship::RelatedSpawnerCommands;
 use bevy::prelude::*;
-use crate::components::{get_attack_type_icon, get_defense_type_icon, AttackType, DefenseType, GameUI};
-use crate::resources::GameState;
+use crate::components::{get_attack_type_icon, get_defense_type_icon, AttackType, DefenseType, GameUI, ResourceType};
+use crate::resources::{EconomyMode, GameRules, GameState, InfiniteResources, Palette};
+
+/// Gap between the window's top-left corner and the top bar
+const TOP_BAR_MARGIN: f32 = 10.0;
 
 #[derive(Component)]
 pub struct TopBar;
 
+/// Keeps a node `offset` pixels below the top bar's bottom edge. The bar
+/// wraps onto more rows when the window is narrow, pushing these down with it.
+#[derive(Component)]
+pub struct BelowTopBar {
+    pub offset: f32,
+}
+
 #[derive(Component)]
 pub struct LivesText;
 
@@ -18,6 +28,10 @@ pub struct WoodText;
 #[derive(Component)]
 pub struct MeatText;
 
+/// Warning shown while a leak streak is reducing gold income
+#[derive(Component)]
+pub struct LeakStreakText;
+
 #[derive(Component)]
 pub struct EffectivenessHint;
 
@@ -27,13 +41,17 @@ pub fn setup_top_bar(mut commands: Commands, asset_server: Res<AssetServer>) {
         .spawn((
             Node {
                 position_type: PositionType::Absolute,
-                top: Val::Px(10.0),
-                left: Val::Px(10.0),
+                top: Val::Px(TOP_BAR_MARGIN),
+                left: Val::Px(TOP_BAR_MARGIN),
+                right: Val::Px(TOP_BAR_MARGIN),
                 flex_direction: FlexDirection::Row,
+                flex_wrap: FlexWrap::Wrap,
                 align_items: AlignItems::Center,
                 column_gap: Val::Px(20.0),
+                row_gap: Val::Px(6.0),
                 ..default()
             },
+            Pickable::IGNORE,
             TopBar,
             GameUI,
         ))
@@ -62,6 +80,12 @@ pub fn setup_top_bar(mut commands: Commands, asset_server: Res<AssetServer>) {
                 "UI Elements/UI Elements/Icons/Defense_Icon.png",
                 LivesText,
             );
+            parent.spawn((
+                Text::default(),
+                TextFont { font_size: 20.0, ..default() },
+                TextColor(Color::srgb(1.0, 0.35, 0.3)),
+                LeakStreakText,
+            ));
         });
 }
 
@@ -100,9 +124,26 @@ fn spawn_stat_display(
         });
 }
 
+/// A resource amount, with its storage cap when the rules set one
+/// Shown instead of stockpiles in sandbox mode. Spelled out because the
+/// default font only covers ASCII, so an infinity sign would draw as a box.
+const INFINITE_STOCK_TEXT: &str = "INF";
+
+fn stock_text(amount: i32, cap: Option<i32>, infinite: InfiniteResources) -> String {
+    if infinite.0 {
+        return INFINITE_STOCK_TEXT.to_string();
+    }
+    match cap {
+        Some(cap) => format!("{}/{}", amount, cap),
+        None => amount.to_string(),
+    }
+}
+
 // Update function
 pub fn update_top_bar(
     game_state: Res<GameState>,
+    rules: Res<GameRules>,
+    infinite: Res<InfiniteResources>,
     mut lives_query: Query<&mut Text, (With<LivesText>, Without<GoldText>, Without<WoodText>, Without<MeatText>)>,
     mut gold_query: Query<&mut Text, (With<GoldText>, Without<LivesText>, Without<WoodText>, Without<MeatText>)>,
     mut wood_query: Query<&mut Text, (With<WoodText>, Without<LivesText>, Without<GoldText>, Without<MeatText>)>,
@@ -112,25 +153,71 @@ pub fn update_top_bar(
         text.0 = game_state.lives.to_string();
     }
     for mut text in gold_query.iter_mut() {
-        text.0 = game_state.gold.to_string();
+        let gold = stock_text(game_state.gold, rules.storage_cap(ResourceType::Gold), *infinite);
+        text.0 = match game_state.economy_mode {
+            EconomyMode::Shared => format!("{} (shared)", gold),
+            EconomyMode::Split => gold,
+        };
     }
     for mut text in wood_query.iter_mut() {
-        text.0 = game_state.wood.to_string();
+        text.0 = stock_text(game_state.wood, rules.storage_cap(ResourceType::Wood), *infinite);
     }
     for mut text in meat_query.iter_mut() {
-        text.0 = game_state.meat.to_string();
+        text.0 = stock_text(game_state.meat, rules.storage_cap(ResourceType::Meat), *infinite);
+    }
+}
+
+/// Move `BelowTopBar` nodes to follow the top bar's height, which changes
+/// when a resize makes it wrap or unwrap
+pub fn anchor_below_top_bar(
+    top_bar: Query<&ComputedNode, With<TopBar>>,
+    mut anchored: Query<(&mut Node, &BelowTopBar), Without<TopBar>>,
+) {
+    let Ok(bar) = top_bar.single() else { return };
+    let bottom = TOP_BAR_MARGIN + bar.size().y * bar.inverse_scale_factor();
+    for (mut node, below) in anchored.iter_mut() {
+        let top = Val::Px(bottom + below.offset);
+        if node.top != top {
+            node.top = top;
+        }
+    }
+}
+
+pub fn update_leak_streak_warning(
+    game_state: Res<GameState>,
+    mut query: Query<&mut Text, With<LeakStreakText>>,
+) {
+    if !game_state.is_changed() {
+        return;
+    }
+
+    let multiplier = game_state.gold_income_multiplier();
+    let warning = if multiplier < 1.0 {
+        format!(
+            "Leak streak x{}! Gold -{:.0}%",
+            game_state.leak_streak,
+            (1.0 - multiplier) * 100.0
+        )
+    } else {
+        String::new()
+    };
+
+    for mut text in query.iter_mut() {
+        if text.0 != warning {
+            text.0 = warning.clone();
+        }
     }
 }
 
 /// Setup the effectiveness matrix hint in the bottom left
-pub fn setup_effectiveness_hint(mut commands: Commands, asset_server: Res<AssetServer>) {
+pub fn setup_effectiveness_hint(mut commands: Commands, asset_server: Res<AssetServer>, palette: Res<Palette>) {
     let icon_size = 20.0;
     let cell_size = 28.0;
     let font_size = 11.0;
 
-    // Colors for effectiveness values
-    let strong_color = Color::srgb(0.3, 1.0, 0.3);   // Green for bonus
-    let weak_color = Color::srgb(1.0, 0.4, 0.4);     // Red for penalty
+    // Colors for effectiveness values; the +/- signs tell them apart too
+    let strong_color = palette.bonus;
+    let weak_color = palette.penalty;
     let neutral_color = Color::srgb(0.8, 0.8, 0.8); // Gray for neutral
 
     commands
@@ -1,15 +1,28 @@
 use bevy::prelude::*;
 use bevy::ecs::prelude::ChildSpawnerCommands;
 use bevy_spacetimedb::StdbConnection;
-use spacetimedb_sdk::Table;
-use crate::components::{get_attack_type_icon, get_damage_multiplier, AttackType, AnimationTimer, Enemy, HolyTowerEffect, Tower, TowerLevel, TowerUpgradeMenu, TowerUpgradeOption, TowerWheelMenu, TowerWheelOption, Projectile, UpgradeType, WorkerBuilding};
+use spacetimedb_sdk::{Identity, Table};
+use crate::combat::nearest_in_range;
+use crate::components::{get_attack_type_icon, AttackType, AnimationTimer, Ballistic, Enemy, GoldGenerator, GroundHazard, HitFlash, HolyBeam, HolyTowerEffect, PlacedAt, Tower, TowerBuffs, TowerCooldownBar, TowerLevel, ConfirmedTower, MoveTowerButton, PendingTower, TowerUpgradeMenu, TowerUpgradeOption, TowerWheelMenu, TowerWheelOption, TowerWheelTooltip, PlacementHighlight, Projectile, SyncedTower, Trail, UpgradeEffect, UpgradeType, Veterancy, VeterancyStar, Wall, WorkerBuilding};
+use crate::systems::audio::SoundEffect;
+use crate::systems::enemy::award_kill;
+use crate::systems::focus_fire::FocusTarget;
 use crate::systems::AnimationInfo;
-use crate::config::TowerType;
-use crate::constants::{ARROW_SIZE, EXPLORE_COST, EXPLORE_RADIUS, SCALED_TILE_SIZE, TOWER_SIZE};
-use crate::map::world_to_tile;
+use crate::systems::input::{ActionState, InputAction};
+use crate::config::{AuraEffect, HazardConfig, TowerType};
+use crate::events::{EnemyKilled, PlaySound};
+use crate::constants::{ARROW_SIZE, EXPLORE_COST, EXPLORE_RADIUS, FOG_TOWER_RANGE_PENALTY, MARKET_COST, MARKET_GOLD_AMOUNT, MARKET_GOLD_INTERVAL, NIGHT_TOWER_RANGE_PENALTY, SCALED_TILE_SIZE, TOWER_SIZE, TOWER_UNDO_GRACE_SECS, VETERANCY_DAMAGE_BONUS, VETERANCY_FIRE_RATE_BONUS};
+use crate::map::{tile_to_world, world_to_tile, PATH_CORNERS};
+use crate::systems::pathfinding::{compute_enemy_path, overlaps_enemy_path};
+use crate::systems::stats::GameStats;
+use crate::systems::ui::{spawn_floating_text, spawn_immune_text};
 use crate::module_bindings;
-use crate::module_bindings::{DbConnection, MyUserTableAccess, UserTableAccess};
-use crate::resources::{BlockedTiles, FogOfWar, GameState, HouseMenuState, RecruitMenuState, TowerConfigs, TowerUpgradeMenuState, TowerWheelState};
+use crate::module_bindings::move_tower_reducer::move_tower;
+use crate::module_bindings::place_tower_reducer::place_tower;
+use crate::module_bindings::place_wall_reducer::place_wall;
+use crate::module_bindings::undo_tower_placement_reducer::undo_tower_placement;
+use crate::module_bindings::{DbConnection, User, UserTableAccess};
+use crate::resources::{BlockedTiles, FogOfWar, GameRules, GameState, HouseMenuState, InfiniteResources, OverlaySettings, Palette, PathWaypoints, RecruitMenuState, SelectedTowers, TimeOfDay, TowerConfigs, TowerMoveState, TowerUpgradeMenuState, TowerWheelState, Weather, WeatherKind};
 
 //TODO Display for generated Types?!
 impl module_bindings::Color {
@@ -30,43 +43,90 @@ pub fn spawn_tower(
     position: Vec3,
     tower_type: &TowerType,
     stdb: Option<SpacetimeDB>,
-) {
+    owner: Option<Identity>,
+) -> Entity {
     // Tower is 128x256, we want it to fit exactly 1 tile (32x32 when scaled)
     // Scale factor = desired_size / actual_size
     let scale_x = SCALED_TILE_SIZE / TOWER_SIZE.x; // 32 / 128 = 0.25
     let scale_y = SCALED_TILE_SIZE / TOWER_SIZE.y; // 32 / 256 = 0.125
     let scale = scale_x.min(scale_y); // Use smaller to fit within 1 tile
 
-    let path = get_tower_sprite_path(tower_type, stdb.as_ref());
+    let path = get_tower_sprite_path(tower_type, stdb.as_ref(), owner);
     commands.spawn((
         Sprite::from_image(asset_server.load(path)),
         Transform::from_translation(position).with_scale(Vec3::splat(scale)),
         Tower {
             tower_type_id: tower_type.id.clone(),
-            range: tower_type.range,
-            damage: tower_type.damage,
-            fire_rate: tower_type.fire_rate,
+            base_range: tower_type.range,
+            base_damage: tower_type.damage,
+            base_fire_rate: tower_type.fire_rate,
+            effective_range: tower_type.range,
+            effective_damage: tower_type.damage,
+            effective_fire_rate: tower_type.fire_rate,
             cooldown: 0.0,
             projectile_sprite: tower_type.projectile_sprite.clone(),
             projectile_speed: tower_type.projectile_speed,
             attack_type: AttackType::from_str(&tower_type.attack_type),
+            leads_target: tower_type.leads_target,
+            ballistic: tower_type.ballistic,
+            splash_radius: tower_type.splash_radius,
+            hazard: tower_type.hazard,
+            burn_on_hit: tower_type.burn_on_hit,
+            aura: tower_type.aura,
+            trail: tower_type.trail,
+            kills: 0,
+            owner,
         },
         TowerLevel::default(),
-    ));
+        TowerBuffs::default(),
+        Veterancy::default(),
+    )).with_children(|parent| {
+        // Cooldown bar above the tower; counter-scaled so its size is in world pixels
+        let bar_width = SCALED_TILE_SIZE * 0.8;
+        parent
+            .spawn((
+                Sprite {
+                    color: Color::srgba(0.0, 0.0, 0.0, 0.6),
+                    custom_size: Some(Vec2::new(bar_width + 2.0, 5.0)),
+                    ..default()
+                },
+                Transform::from_xyz(0.0, TOWER_SIZE.y / 2.0 + 4.0 / scale, 1.0)
+                    .with_scale(Vec3::splat(1.0 / scale)),
+            ))
+            .with_children(|bar| {
+                bar.spawn((
+                    Sprite {
+                        color: Color::srgb(0.3, 0.8, 1.0),
+                        custom_size: Some(Vec2::new(bar_width, 3.0)),
+                        ..default()
+                    },
+                    Transform::from_xyz(0.0, 0.0, 0.1),
+                    TowerCooldownBar { max_width: bar_width },
+                ));
+            });
+    }).id()
+}
+
+/// Grid position a tower opened at `world_pos` is placed on
+pub fn snap_to_grid(world_pos: Vec2) -> Vec2 {
+    (world_pos / SCALED_TILE_SIZE).round() * SCALED_TILE_SIZE
 }
 
+const WALL_SPRITE: &str = "Terrain/Decorations/Rocks/Rock1.png";
+const MARKET_SPRITE: &str = "Decorations/Buildings/Blue Buildings/House2.png";
+const MARKET_SIZE: Vec2 = Vec2::new(128.0, 192.0);
+
 pub fn show_tower_wheel_menu(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    mouse_button: Res<ButtonInput<MouseButton>>,
-    windows: Query<&Window>,
-    camera: Query<(&Camera, &GlobalTransform)>,
+    actions: Res<ActionState>,
     mut wheel_state: ResMut<TowerWheelState>,
     tower_configs: Res<TowerConfigs>,
     fog: Res<FogOfWar>,
     recruit_menu_state: Res<RecruitMenuState>,
     house_menu_state: Res<HouseMenuState>,
     upgrade_menu_state: Res<TowerUpgradeMenuState>,
+    move_state: Res<TowerMoveState>,
     blocked_tiles: Res<BlockedTiles>,
     existing_menus: Query<Entity, With<TowerWheelMenu>>,
     existing_towers: Query<&Transform, With<Tower>>,
@@ -74,68 +134,162 @@ pub fn show_tower_wheel_menu(
     stdb: Option<SpacetimeDB>,
 ) {
     // Don't show if any menu is active
-    if mouse_button.just_pressed(MouseButton::Left)
+    if actions.just_pressed(InputAction::Select)
         && !wheel_state.active
         && !recruit_menu_state.active
         && !house_menu_state.active
         && !upgrade_menu_state.active
+        && move_state.moving.is_none()
     {
-        let Ok(window) = windows.single() else { return };
-        let Ok((camera, camera_transform)) = camera.single() else {
-            return;
-        };
+        if let Some(world_pos) = actions.pointer_world {
+            let (tile_x, tile_y) = world_to_tile(world_pos);
 
-        if let Some(cursor_pos) = window.cursor_position() {
-            if let Ok(world_pos) = camera.viewport_to_world_2d(camera_transform, cursor_pos) {
-                let (tile_x, tile_y) = world_to_tile(world_pos);
+            // Don't show tower wheel on castle (recruit menu handles that)
+            if blocked_tiles.is_castle(tile_x, tile_y) {
+                return;
+            }
 
-                // Don't show tower wheel on castle (recruit menu handles that)
-                if blocked_tiles.is_castle(tile_x, tile_y) {
+            // Road tiles only take walls; never on an existing wall or the spawn
+            let on_road = blocked_tiles.is_road(tile_x, tile_y);
+            if on_road
+                && (blocked_tiles.is_wall(tile_x, tile_y) || (tile_x, tile_y) == PATH_CORNERS[0])
+            {
+                return;
+            }
+
+            // Don't show tower wheel if clicking on existing tower (upgrade menu handles that)
+            for tower_transform in existing_towers.iter() {
+                let tower_pos = tower_transform.translation.truncate();
+                if world_pos.distance(tower_pos) < SCALED_TILE_SIZE / 2.0 {
                     return;
                 }
+            }
 
-                // Don't show tower wheel on road tiles
-                if blocked_tiles.is_road(tile_x, tile_y) {
+            // Don't show tower wheel if clicking on worker building (house menu handles that)
+            for building_transform in worker_buildings.iter() {
+                let building_pos = building_transform.translation.truncate();
+                if world_pos.distance(building_pos) < SCALED_TILE_SIZE / 2.0 {
                     return;
                 }
+            }
 
-                // Don't show tower wheel if clicking on existing tower (upgrade menu handles that)
-                for tower_transform in existing_towers.iter() {
-                    let tower_pos = tower_transform.translation.truncate();
-                    if world_pos.distance(tower_pos) < SCALED_TILE_SIZE / 2.0 {
-                        return;
+            // Clean up any existing menus
+            for entity in existing_menus.iter() {
+                commands.queue_silenced(move |world: &mut World| {
+                    if let Ok(entity_mut) = world.get_entity_mut(entity) {
+                        entity_mut.despawn();
                     }
-                }
+                });
+            }
 
-                // Don't show tower wheel if clicking on worker building (house menu handles that)
-                for building_transform in worker_buildings.iter() {
-                    let building_pos = building_transform.translation.truncate();
-                    if world_pos.distance(building_pos) < SCALED_TILE_SIZE / 2.0 {
-                        return;
-                    }
-                }
+            // Store the world position where we want to place the tower
+            wheel_state.active = true;
+            wheel_state.position = world_pos;
+            wheel_state.tapped_open = false;
 
-                // Clean up any existing menus
-                for entity in existing_menus.iter() {
-                    commands.queue_silenced(move |world: &mut World| {
-                        if let Ok(entity_mut) = world.get_entity_mut(entity) {
-                            entity_mut.despawn();
-                        }
-                    });
-                }
+            // Check if clicked tile is in fog
+            let is_in_fog = !fog.is_explored(tile_x, tile_y);
 
-                // Store the world position where we want to place the tower
-                wheel_state.active = true;
-                wheel_state.position = world_pos;
+            // Load paper background texture
+            let paper_texture = asset_server.load("UI Elements/UI Elements/Papers/SpecialPaper.png");
+
+            if is_in_fog {
+                // Show explore option only
+                let circle_entity = commands
+                    .spawn((
+                        Sprite {
+                            image: paper_texture.clone(),
+                            custom_size: Some(Vec2::splat(70.0)),
+                            ..default()
+                        },
+                        Transform::from_xyz(world_pos.x, world_pos.y - 60.0, 10.0),
+                        TowerWheelMenu,
+                        TowerWheelOption {
+                            tower_type_id: "_explore".to_string(),
+                        },
+                    ))
+                    .id();
 
-                // Check if clicked tile is in fog
-                let is_in_fog = !fog.is_explored(tile_x, tile_y);
+                // Add explore text
+                let name_entity = commands
+                    .spawn((
+                        Text2d::new("Explore"),
+                        TextFont {
+                            font_size: 14.0,
+                            ..default()
+                        },
+                        TextColor(Color::srgb(1.0, 1.0, 1.0)),
+                        Transform::from_xyz(0.0, 0.0, 0.1),
+                    ))
+                    .id();
+                commands.entity(circle_entity).add_child(name_entity);
+
+                // Add cost label
+                let cost_entity = commands
+                    .spawn((
+                        Text2d::new(format!("{}g", EXPLORE_COST)),
+                        TextFont {
+                            font_size: 12.0,
+                            ..default()
+                        },
+                        TextColor(Color::srgb(1.0, 1.0, 0.0)),
+                        Transform::from_xyz(0.0, -20.0, 0.1),
+                    ))
+                    .id();
+                commands.entity(circle_entity).add_child(cost_entity);
+            } else if on_road {
+                // Show wall option only
+                let circle_entity = commands
+                    .spawn((
+                        Sprite {
+                            image: paper_texture.clone(),
+                            custom_size: Some(Vec2::splat(70.0)),
+                            ..default()
+                        },
+                        Transform::from_xyz(world_pos.x, world_pos.y - 60.0, 10.0),
+                        TowerWheelMenu,
+                        TowerWheelOption {
+                            tower_type_id: "_wall".to_string(),
+                        },
+                    ))
+                    .id();
+
+                let icon_entity = commands
+                    .spawn((
+                        Sprite {
+                            image: asset_server.load(WALL_SPRITE),
+                            custom_size: Some(Vec2::splat(32.0)),
+                            ..default()
+                        },
+                        Transform::from_xyz(0.0, 5.0, 0.1),
+                    ))
+                    .id();
+                commands.entity(circle_entity).add_child(icon_entity);
+
+                let cost_entity = commands
+                    .spawn((
+                        Text2d::new(format!("Wall {}g", tower_configs.wall_cost)),
+                        TextFont {
+                            font_size: 12.0,
+                            ..default()
+                        },
+                        TextColor(Color::srgb(1.0, 1.0, 0.0)),
+                        Transform::from_xyz(0.0, -22.0, 0.1),
+                    ))
+                    .id();
+                commands.entity(circle_entity).add_child(cost_entity);
+            } else {
+                // Show tower options, with the market in the last slot
+                let num_towers = tower_configs.towers.len();
+                let num_options = num_towers + 1;
+                let radius = 80.0; // Distance from center to each option
 
-                // Load paper background texture
-                let paper_texture = asset_server.load("UI Elements/UI Elements/Papers/SpecialPaper.png");
+                for (i, tower_type) in tower_configs.towers.iter().enumerate() {
+                    let angle = (i as f32 / num_options as f32) * std::f32::consts::TAU;
+                    let offset_x = angle.cos() * radius;
+                    let offset_y = angle.sin() * radius;
 
-                if is_in_fog {
-                    // Show explore option only
+                    // Create background with paper texture
                     let circle_entity = commands
                         .spawn((
                             Sprite {
@@ -143,155 +297,159 @@ pub fn show_tower_wheel_menu(
                                 custom_size: Some(Vec2::splat(70.0)),
                                 ..default()
                             },
-                            Transform::from_xyz(world_pos.x, world_pos.y - 60.0, 10.0),
+                            Transform::from_xyz(
+                                world_pos.x + offset_x,
+                                world_pos.y + offset_y,
+                                10.0,
+                            ),
                             TowerWheelMenu,
                             TowerWheelOption {
-                                tower_type_id: "_explore".to_string(),
+                                tower_type_id: tower_type.id.clone(),
                             },
                         ))
                         .id();
 
-                    // Add explore text
+                    // Add tower sprite on top
+                    let scale = 40.0 / TOWER_SIZE.x.max(TOWER_SIZE.y);
+                    let sprite_entity = commands
+                        .spawn((
+                            Sprite::from_image(asset_server.load(get_tower_sprite_path(
+                                tower_type,
+                                stdb.as_ref(),
+                                stdb.as_ref().and_then(|stdb| stdb.try_identity()),
+                            ))),
+                            Transform::from_xyz(0.0, 0.0, 0.1).with_scale(Vec3::splat(scale)),
+                        ))
+                        .id();
+
+                    commands.entity(circle_entity).add_child(sprite_entity);
+
+                    // Add damage type badge (similar to wave_manager_ui defense type badge)
+                    let attack_type = AttackType::from_str(&tower_type.attack_type);
+                    let badge_entity = commands
+                        .spawn((
+                            Sprite {
+                                image: asset_server.load(get_attack_type_icon(attack_type)),
+                                custom_size: Some(Vec2::splat(18.0)),
+                                ..default()
+                            },
+                            // Position at bottom-right corner, slightly outside
+                            Transform::from_xyz(22.0, -22.0, 0.2),
+                        ))
+                        .id();
+
+                    commands.entity(circle_entity).add_child(badge_entity);
+
+                    // Add tower name below
                     let name_entity = commands
                         .spawn((
-                            Text2d::new("Explore"),
+                            Text2d::new(&tower_type.name),
                             TextFont {
-                                font_size: 14.0,
+                                font_size: 12.0,
                                 ..default()
                             },
                             TextColor(Color::srgb(1.0, 1.0, 1.0)),
-                            Transform::from_xyz(0.0, 0.0, 0.1),
+                            Transform::from_xyz(0.0, -40.0, 0.1),
+                            TowerWheelMenu,
                         ))
                         .id();
                     commands.entity(circle_entity).add_child(name_entity);
 
-                    // Add cost label
+                    // Add cost label above sprite
                     let cost_entity = commands
                         .spawn((
-                            Text2d::new(format!("{}g", EXPLORE_COST)),
+                            Text2d::new(format!("{}g", tower_type.cost)),
                             TextFont {
-                                font_size: 12.0,
+                                font_size: 14.0,
                                 ..default()
                             },
                             TextColor(Color::srgb(1.0, 1.0, 0.0)),
-                            Transform::from_xyz(0.0, -20.0, 0.1),
+                            Transform::from_xyz(0.0, 35.0, 0.1),
+                            TowerWheelMenu,
                         ))
                         .id();
                     commands.entity(circle_entity).add_child(cost_entity);
-                } else {
-                    // Show tower options (existing code)
-                    let num_towers = tower_configs.towers.len();
-                    let radius = 80.0; // Distance from center to each option
-
-                    for (i, tower_type) in tower_configs.towers.iter().enumerate() {
-                        let angle = (i as f32 / num_towers as f32) * std::f32::consts::TAU;
-                        let offset_x = angle.cos() * radius;
-                        let offset_y = angle.sin() * radius;
-
-                        // Create background with paper texture
-                        let circle_entity = commands
-                            .spawn((
-                                Sprite {
-                                    image: paper_texture.clone(),
-                                    custom_size: Some(Vec2::splat(70.0)),
-                                    ..default()
-                                },
-                                Transform::from_xyz(
-                                    world_pos.x + offset_x,
-                                    world_pos.y + offset_y,
-                                    10.0,
-                                ),
-                                TowerWheelMenu,
-                                TowerWheelOption {
-                                    tower_type_id: tower_type.id.clone(),
-                                },
-                            ))
-                            .id();
-
-                        // Add tower sprite on top
-                        let scale = 40.0 / TOWER_SIZE.x.max(TOWER_SIZE.y);
-                        let sprite_entity = commands
-                            .spawn((
-                                Sprite::from_image(asset_server.load(get_tower_sprite_path(tower_type, stdb.as_ref()))),
-                                Transform::from_xyz(0.0, 0.0, 0.1).with_scale(Vec3::splat(scale)),
-                            ))
-                            .id();
-
-                        commands.entity(circle_entity).add_child(sprite_entity);
-
-                        // Add damage type badge (similar to wave_manager_ui defense type badge)
-                        let attack_type = AttackType::from_str(&tower_type.attack_type);
-                        let badge_entity = commands
-                            .spawn((
-                                Sprite {
-                                    image: asset_server.load(get_attack_type_icon(attack_type)),
-                                    custom_size: Some(Vec2::splat(18.0)),
-                                    ..default()
-                                },
-                                // Position at bottom-right corner, slightly outside
-                                Transform::from_xyz(22.0, -22.0, 0.2),
-                            ))
-                            .id();
-
-                        commands.entity(circle_entity).add_child(badge_entity);
-
-                        // Add tower name below
-                        let name_entity = commands
-                            .spawn((
-                                Text2d::new(&tower_type.name),
-                                TextFont {
-                                    font_size: 12.0,
-                                    ..default()
-                                },
-                                TextColor(Color::srgb(1.0, 1.0, 1.0)),
-                                Transform::from_xyz(0.0, -40.0, 0.1),
-                                TowerWheelMenu,
-                            ))
-                            .id();
-                        commands.entity(circle_entity).add_child(name_entity);
-
-                        // Add cost label above sprite
-                        let cost_entity = commands
-                            .spawn((
-                                Text2d::new(format!("{}g", tower_type.cost)),
-                                TextFont {
-                                    font_size: 14.0,
-                                    ..default()
-                                },
-                                TextColor(Color::srgb(1.0, 1.0, 0.0)),
-                                Transform::from_xyz(0.0, 35.0, 0.1),
-                                TowerWheelMenu,
-                            ))
-                            .id();
-                        commands.entity(circle_entity).add_child(cost_entity);
-                    }
                 }
 
-                // Add center indicator
-                commands.spawn((
-                    Sprite {
-                        color: Color::srgba(1.0, 1.0, 1.0, 0.5),
-                        custom_size: Some(Vec2::splat(10.0)),
-                        ..default()
-                    },
-                    Transform::from_xyz(world_pos.x, world_pos.y, 10.0),
-                    TowerWheelMenu,
-                ));
+                let angle = (num_towers as f32 / num_options as f32) * std::f32::consts::TAU;
+                let circle_entity = commands
+                    .spawn((
+                        Sprite {
+                            image: paper_texture.clone(),
+                            custom_size: Some(Vec2::splat(70.0)),
+                            ..default()
+                        },
+                        Transform::from_xyz(
+                            world_pos.x + angle.cos() * radius,
+                            world_pos.y + angle.sin() * radius,
+                            10.0,
+                        ),
+                        TowerWheelMenu,
+                        TowerWheelOption {
+                            tower_type_id: "_market".to_string(),
+                        },
+                    ))
+                    .id();
+
+                let scale = 40.0 / MARKET_SIZE.x.max(MARKET_SIZE.y);
+                let sprite_entity = commands
+                    .spawn((
+                        Sprite::from_image(asset_server.load(MARKET_SPRITE)),
+                        Transform::from_xyz(0.0, 0.0, 0.1).with_scale(Vec3::splat(scale)),
+                    ))
+                    .id();
+                commands.entity(circle_entity).add_child(sprite_entity);
+
+                let name_entity = commands
+                    .spawn((
+                        Text2d::new("Market"),
+                        TextFont {
+                            font_size: 12.0,
+                            ..default()
+                        },
+                        TextColor(Color::srgb(1.0, 1.0, 1.0)),
+                        Transform::from_xyz(0.0, -40.0, 0.1),
+                        TowerWheelMenu,
+                    ))
+                    .id();
+                commands.entity(circle_entity).add_child(name_entity);
+
+                let cost_entity = commands
+                    .spawn((
+                        Text2d::new(format!("{}g", MARKET_COST)),
+                        TextFont {
+                            font_size: 14.0,
+                            ..default()
+                        },
+                        TextColor(Color::srgb(1.0, 1.0, 0.0)),
+                        Transform::from_xyz(0.0, 35.0, 0.1),
+                        TowerWheelMenu,
+                    ))
+                    .id();
+                commands.entity(circle_entity).add_child(cost_entity);
             }
+
+            // Add center indicator
+            commands.spawn((
+                Sprite {
+                    color: Color::srgba(1.0, 1.0, 1.0, 0.5),
+                    custom_size: Some(Vec2::splat(10.0)),
+                    ..default()
+                },
+                Transform::from_xyz(world_pos.x, world_pos.y, 10.0),
+                TowerWheelMenu,
+            ));
         }
     }
 }
 
 pub fn hide_tower_wheel_menu(
     mut commands: Commands,
-    mouse_button: Res<ButtonInput<MouseButton>>,
+    actions: Res<ActionState>,
     mut wheel_state: ResMut<TowerWheelState>,
     menu_entities: Query<Entity, With<TowerWheelMenu>>,
 ) {
-    if (mouse_button.just_pressed(MouseButton::Right)
-        || mouse_button.just_pressed(MouseButton::Middle))
-        && wheel_state.active
-    {
+    if actions.just_pressed(InputAction::Cancel) && wheel_state.active {
         // Clean up menu
         for entity in menu_entities.iter() {
             commands.queue_silenced(move |world: &mut World| {
@@ -307,136 +465,623 @@ pub fn hide_tower_wheel_menu(
 pub fn handle_tower_selection(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    mouse_button: Res<ButtonInput<MouseButton>>,
-    windows: Query<&Window>,
-    camera: Query<(&Camera, &GlobalTransform)>,
+    actions: Res<ActionState>,
     mut wheel_state: ResMut<TowerWheelState>,
     mut game_state: ResMut<GameState>,
     tower_configs: Res<TowerConfigs>,
     mut fog: ResMut<FogOfWar>,
+    mut blocked_tiles: ResMut<BlockedTiles>,
+    mut waypoints: ResMut<PathWaypoints>,
     menu_options: Query<(&Transform, &TowerWheelOption), With<TowerWheelMenu>>,
     menu_entities: Query<Entity, With<TowerWheelMenu>>,
+    existing_towers: Query<&Transform, Or<(With<Tower>, With<GoldGenerator>)>>,
     stdb: Option<SpacetimeDB>,
+    infinite: Res<InfiniteResources>,
+    time: Res<Time>,
 ) {
-    if mouse_button.just_released(MouseButton::Left) && wheel_state.active {
-        let Ok(window) = windows.single() else { return };
-        let Ok((camera, camera_transform)) = camera.single() else {
-            return;
-        };
-
-        if let Some(cursor_pos) = window.cursor_position() {
-            if let Ok(mouse_world_pos) = camera.viewport_to_world_2d(camera_transform, cursor_pos) {
-                // Check which option is closest to the mouse
-                let mut closest_option: Option<(&TowerWheelOption, f32)> = None;
-
-                for (transform, option) in menu_options.iter() {
-                    let distance = transform.translation.truncate().distance(mouse_world_pos);
-                    if distance < 40.0 {
-                        // Within click range
-                        if let Some((_, closest_dist)) = closest_option {
-                            if distance < closest_dist {
-                                closest_option = Some((option, distance));
-                            }
-                        } else {
+    if actions.just_released(InputAction::Select) && wheel_state.active {
+        if let Some(mouse_world_pos) = actions.pointer_world {
+            // Check which option is closest to the mouse
+            let mut closest_option: Option<(&TowerWheelOption, f32)> = None;
+
+            for (transform, option) in menu_options.iter() {
+                let distance = transform.translation.truncate().distance(mouse_world_pos);
+                if distance < 40.0 {
+                    // Within click range
+                    if let Some((_, closest_dist)) = closest_option {
+                        if distance < closest_dist {
                             closest_option = Some((option, distance));
                         }
+                    } else {
+                        closest_option = Some((option, distance));
                     }
                 }
+            }
+
+            // Without hover, a quick tap opens the wheel and leaves it open
+            // so the next tap can pick an option
+            if closest_option.is_none()
+                && !actions.source.has_hover()
+                && !wheel_state.tapped_open
+                && mouse_world_pos.distance(wheel_state.position) < 40.0
+            {
+                wheel_state.tapped_open = true;
+                return;
+            }
 
-                // If an option was selected
-                if let Some((option, _)) = closest_option {
-                    // Handle explore option
-                    if option.tower_type_id == "_explore" {
-                        if game_state.gold >= EXPLORE_COST {
-                            let (tile_x, tile_y) = world_to_tile(wheel_state.position);
-                            fog.explore_rect(tile_x, tile_y, EXPLORE_RADIUS);
-                            game_state.gold -= EXPLORE_COST;
+            // If an option was selected
+            if let Some((option, _)) = closest_option {
+                // Handle explore option
+                if option.tower_type_id == "_explore" {
+                    if infinite.affords(game_state.gold, EXPLORE_COST) {
+                        let (tile_x, tile_y) = world_to_tile(wheel_state.position);
+                        fog.explore_rect(tile_x, tile_y, EXPLORE_RADIUS);
+                        game_state.spend_gold(infinite.charge(EXPLORE_COST));
+                    }
+                } else if option.tower_type_id == "_wall" {
+                    let cost = tower_configs.wall_cost;
+                    if infinite.affords(game_state.gold, cost) {
+                        let tile = world_to_tile(wheel_state.position);
+                        // Reject walls that would leave enemies no way to the castle
+                        match (compute_enemy_path(&blocked_tiles, Some(tile)), stdb.as_ref()) {
+                            (None, _) => info!("Can't build a wall there: it would block the path"),
+                            // Every player's enemies must go the same way, so online walls
+                            // only go up from the server's `wall` table (see `sync_walls`)
+                            (Some(_), Some(stdb)) => match stdb.reducers().place_wall(tile.0, tile.1) {
+                                Ok(()) => {
+                                    game_state.expect_server_gold(-cost);
+                                    game_state.gold -= cost;
+                                }
+                                Err(e) => error!("Failed to send place_wall: {}", e),
+                            },
+                            (Some(path), None) => {
+                                spawn_wall(&mut commands, &asset_server, tile);
+                                blocked_tiles.walls.insert(tile);
+                                waypoints.points = path;
+                                game_state.spend_gold(infinite.charge(cost));
+                            }
                         }
-                    } else if let Some(tower_type) = tower_configs
-                        .towers
-                        .iter()
-                        .find(|t| t.id == option.tower_type_id)
-                    {
-                        // Snap to tile grid
-                        let snapped_x =
-                            (wheel_state.position.x / SCALED_TILE_SIZE).round() * SCALED_TILE_SIZE;
-                        let snapped_y =
-                            (wheel_state.position.y / SCALED_TILE_SIZE).round() * SCALED_TILE_SIZE;
-                        let snapped_pos = Vec3::new(snapped_x, snapped_y, 1.0);
-
-                        // Check if tile is explored (not in fog)
-                        let (tile_x, tile_y) = world_to_tile(Vec2::new(snapped_x, snapped_y));
-                        let is_explored = fog.is_explored(tile_x, tile_y);
-
-                        if game_state.gold >= tower_type.cost && is_explored {
-                            spawn_tower(&mut commands, &asset_server, snapped_pos, tower_type, stdb);
-                            game_state.gold -= tower_type.cost;
+                    }
+                } else if option.tower_type_id == "_market" {
+                    let snapped = snap_to_grid(wheel_state.position);
+                    let buildable = is_buildable(
+                        snapped,
+                        &fog,
+                        &blocked_tiles,
+                        &waypoints,
+                        existing_towers.iter().map(|t| t.translation.truncate()),
+                    );
+                    if !buildable {
+                        info!("Can't build a market there: the tile is blocked or on the enemy path");
+                    } else if infinite.affords(game_state.gold, MARKET_COST) {
+                        spawn_market(&mut commands, &asset_server, snapped);
+                        game_state.spend_gold(infinite.charge(MARKET_COST));
+                    }
+                } else if let Some(tower_type) = tower_configs
+                    .towers
+                    .iter()
+                    .find(|t| t.id == option.tower_type_id)
+                {
+                    let snapped = snap_to_grid(wheel_state.position);
+                    let snapped_pos = snapped.extend(1.0);
+
+                    // Explored, off the road and clear of the enemy path
+                    let buildable = is_buildable(
+                        snapped,
+                        &fog,
+                        &blocked_tiles,
+                        &waypoints,
+                        existing_towers.iter().map(|t| t.translation.truncate()),
+                    );
+
+                    if !buildable {
+                        info!("Can't build a tower there: the tile is blocked or on the enemy path");
+                    } else if infinite.affords(game_state.gold, tower_type.cost) {
+                        // Local gold is a prediction; the server charges the real balance
+                        // and confirms the tower, which stays pending until then
+                        if let Some(stdb) = stdb.as_ref()
+                            && let Err(e) = stdb.reducers().place_tower(tower_type.id.clone(), snapped.x, snapped.y)
+                        {
+                            error!("Failed to send place_tower: {}", e);
+                        } else {
+                            let online = stdb.is_some();
+                            if online {
+                                game_state.expect_server_gold(-tower_type.cost);
+                            }
+                            let owner = stdb.as_ref().and_then(|stdb| stdb.try_identity());
+                            // Sandbox mode is offline only; the server always charges
+                            let paid = if online { tower_type.cost } else { infinite.charge(tower_type.cost) };
+                            let tower = spawn_tower(&mut commands, &asset_server, snapped_pos, tower_type, stdb, owner);
+                            commands.entity(tower).insert(PlacedAt {
+                                secs: time.elapsed_secs(),
+                                paid,
+                            });
+                            if online {
+                                commands.entity(tower).insert(PendingTower::default());
+                            }
+                            game_state.gold -= paid;
                         }
                     }
                 }
+            }
 
-                // Clean up menu
-                for entity in menu_entities.iter() {
-                    commands.queue_silenced(move |world: &mut World| {
-                        if let Ok(entity_mut) = world.get_entity_mut(entity) {
-                            entity_mut.despawn();
-                        }
-                    });
-                }
-                wheel_state.active = false;
+            // Clean up menu
+            for entity in menu_entities.iter() {
+                commands.queue_silenced(move |world: &mut World| {
+                    if let Ok(entity_mut) = world.get_entity_mut(entity) {
+                        entity_mut.despawn();
+                    }
+                });
+            }
+            wheel_state.active = false;
+        }
+    }
+}
+
+/// Undo takes back the local player's latest tower if it was placed less
+/// than `TOWER_UNDO_GRACE_SECS` ago, refunding everything it cost. Online
+/// the server checks the same window before refunding the real balance.
+#[allow(clippy::too_many_arguments)]
+pub fn undo_last_tower_placement(
+    mut commands: Commands,
+    actions: Res<ActionState>,
+    time: Res<Time>,
+    stdb: Option<SpacetimeDB>,
+    mut game_state: ResMut<GameState>,
+    mut stats: ResMut<GameStats>,
+    mut upgrade_menu: ResMut<TowerUpgradeMenuState>,
+    mut selected: ResMut<SelectedTowers>,
+    upgrade_menu_entities: Query<Entity, With<TowerUpgradeMenu>>,
+    towers: Query<(Entity, &Transform, &PlacedAt)>,
+) {
+    if !actions.just_pressed(InputAction::UndoPlacement) {
+        return;
+    }
+    let now = time.elapsed_secs();
+    let Some((entity, transform, placed)) = towers
+        .iter()
+        .filter(|(_, _, placed)| now - placed.secs <= TOWER_UNDO_GRACE_SECS)
+        .max_by(|(_, _, a), (_, _, b)| a.secs.total_cmp(&b.secs))
+    else {
+        info!("No tower placed in the last {:.0}s to undo", TOWER_UNDO_GRACE_SECS);
+        return;
+    };
+
+    if let Some(stdb) = stdb.as_ref() {
+        match stdb.reducers().undo_tower_placement() {
+            Ok(()) => game_state.expect_server_gold(placed.paid),
+            Err(e) => {
+                error!("Failed to send undo_tower_placement: {}", e);
+                return;
+            }
+        }
+    }
+    game_state.gold += placed.paid;
+    stats.towers_built = stats.towers_built.saturating_sub(1);
+    if placed.paid > 0 {
+        spawn_floating_text(
+            &mut commands,
+            transform.translation.truncate(),
+            format!("+{}g", placed.paid),
+            Color::srgb(1.0, 0.85, 0.2),
+        );
+    }
+
+    selected.towers.remove(&entity);
+    if upgrade_menu.selected_tower == Some(entity) {
+        for menu in upgrade_menu_entities.iter() {
+            commands.entity(menu).despawn();
+        }
+        upgrade_menu.active = false;
+        upgrade_menu.selected_tower = None;
+    }
+    commands.entity(entity).despawn();
+}
+
+/// Whether a tower can stand at the snapped grid position `snapped`
+pub fn is_buildable(
+    snapped: Vec2,
+    fog: &FogOfWar,
+    blocked_tiles: &BlockedTiles,
+    waypoints: &PathWaypoints,
+    mut other_towers: impl Iterator<Item = Vec2>,
+) -> bool {
+    let (tile_x, tile_y) = world_to_tile(snapped);
+    fog.is_explored(tile_x, tile_y)
+        && !blocked_tiles.is_blocked(tile_x, tile_y)
+        && !overlaps_enemy_path(snapped, waypoints)
+        && !other_towers.any(|pos| pos.distance(snapped) < SCALED_TILE_SIZE / 2.0)
+}
+
+/// Highlight the tile a tower would land on: the wheel's snapped position while
+/// it is open, the pointer while moving a tower, otherwise the tile under a
+/// hovering mouse. Tinted with the palette's invalid color when the tile
+/// can't take a tower.
+pub fn update_placement_highlight(
+    mut commands: Commands,
+    actions: Res<ActionState>,
+    wheel_state: Res<TowerWheelState>,
+    recruit_menu_state: Res<RecruitMenuState>,
+    house_menu_state: Res<HouseMenuState>,
+    upgrade_menu_state: Res<TowerUpgradeMenuState>,
+    fog: Res<FogOfWar>,
+    blocked_tiles: Res<BlockedTiles>,
+    move_state: Res<TowerMoveState>,
+    waypoints: Res<PathWaypoints>,
+    palette: Res<Palette>,
+    towers: Query<(Entity, &Transform), (Or<(With<Tower>, With<GoldGenerator>)>, Without<PlacementHighlight>)>,
+    mut highlight: Query<(&mut Transform, &mut Sprite, &mut Visibility), With<PlacementHighlight>>,
+) {
+    let target = if wheel_state.active {
+        Some(wheel_state.position)
+    } else if move_state.moving.is_some()
+        || (actions.source.has_hover()
+            && !recruit_menu_state.active
+            && !house_menu_state.active
+            && !upgrade_menu_state.active)
+    {
+        actions.pointer_world
+    } else {
+        None
+    };
+
+    let Ok((mut transform, mut sprite, mut visibility)) = highlight.single_mut() else {
+        commands.spawn((
+            Sprite {
+                color: palette.placement_valid,
+                custom_size: Some(Vec2::splat(SCALED_TILE_SIZE)),
+                ..default()
+            },
+            Transform::from_xyz(0.0, 0.0, 0.95),
+            Visibility::Hidden,
+            PlacementHighlight,
+        ));
+        return;
+    };
+
+    let Some(target) = target else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+
+    let (tile_x, tile_y) = world_to_tile(target);
+    let (position, valid) = if wheel_state.active && blocked_tiles.is_road(tile_x, tile_y) {
+        // Walls sit on the road tile itself rather than the tower grid
+        (tile_to_world(tile_x, tile_y), fog.is_explored(tile_x, tile_y))
+    } else {
+        let snapped = snap_to_grid(target);
+        let valid = is_buildable(
+            snapped,
+            &fog,
+            &blocked_tiles,
+            &waypoints,
+            towers
+                .iter()
+                .filter(|(entity, _)| Some(*entity) != move_state.moving)
+                .map(|(_, t)| t.translation.truncate()),
+        );
+        (snapped, valid)
+    };
+
+    transform.translation.x = position.x;
+    transform.translation.y = position.y;
+    sprite.color = if valid { palette.placement_valid } else { palette.placement_invalid };
+    *visibility = Visibility::Visible;
+}
+
+/// The wheel option closest to `pointer`, if any is within reach
+fn hovered_wheel_option<'a>(
+    pointer: Vec2,
+    options: impl Iterator<Item = (&'a Transform, &'a TowerWheelOption)>,
+) -> Option<(&'a Transform, &'a TowerWheelOption)> {
+    options
+        .filter(|(transform, _)| transform.translation.truncate().distance(pointer) < 40.0)
+        .min_by(|(a, _), (b, _)| {
+            let da = a.translation.truncate().distance(pointer);
+            let db = b.translation.truncate().distance(pointer);
+            da.total_cmp(&db)
+        })
+}
+
+/// Share of a new tower's range that other towers already cover before the
+/// placement preview warns about it
+const RANGE_OVERLAP_WARNING_THRESHOLD: f32 = 0.6;
+/// Range ring of the tower being placed
+const RANGE_PREVIEW_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.4);
+/// Range ring of the tower being placed, and of the towers it overlaps, when
+/// most of its range is already covered
+const RANGE_OVERLAP_WARNING_COLOR: Color = Color::srgba(1.0, 0.45, 0.2, 0.8);
+/// Samples per axis when estimating how much of a range is already covered
+const RANGE_OVERLAP_SAMPLES: i32 = 16;
+
+/// Fraction (0.0 - 1.0) of the circle at `center` with radius `range` that
+/// lies within at least one of the `others` (position, range) circles
+pub fn range_overlap_fraction(center: Vec2, range: f32, others: &[(Vec2, f32)]) -> f32 {
+    if range <= 0.0 || others.is_empty() {
+        return 0.0;
+    }
+
+    let step = 2.0 * range / RANGE_OVERLAP_SAMPLES as f32;
+    let mut inside = 0;
+    let mut covered = 0;
+    for ix in 0..RANGE_OVERLAP_SAMPLES {
+        for iy in 0..RANGE_OVERLAP_SAMPLES {
+            let offset = Vec2::new(ix as f32 + 0.5, iy as f32 + 0.5) * step - Vec2::splat(range);
+            if offset.length() > range {
+                continue;
+            }
+            inside += 1;
+            let point = center + offset;
+            if others.iter().any(|(position, other_range)| point.distance(*position) <= *other_range) {
+                covered += 1;
             }
         }
     }
+
+    if inside == 0 {
+        0.0
+    } else {
+        covered as f32 / inside as f32
+    }
+}
+
+/// Preview the range of the tower about to be placed (the hovered wheel
+/// option, or the tower being moved) and warn when most of it overlaps the
+/// ranges of towers already standing. Advisory only: placement is unaffected.
+pub fn draw_range_overlap_warning(
+    mut gizmos: Gizmos,
+    actions: Res<ActionState>,
+    overlays: Res<OverlaySettings>,
+    wheel_state: Res<TowerWheelState>,
+    move_state: Res<TowerMoveState>,
+    tower_configs: Res<TowerConfigs>,
+    menu_options: Query<(&Transform, &TowerWheelOption), With<TowerWheelMenu>>,
+    towers: Query<(Entity, &Transform, &Tower)>,
+) {
+    if !overlays.range_overlap_warning {
+        return;
+    }
+    let Some(pointer) = actions.pointer_world else { return };
+
+    let candidate = if wheel_state.active {
+        hovered_wheel_option(pointer, menu_options.iter())
+            .and_then(|(_, option)| tower_configs.towers.iter().find(|t| t.id == option.tower_type_id))
+            .map(|tower_type| (snap_to_grid(wheel_state.position), tower_type.range))
+    } else {
+        move_state
+            .moving
+            .and_then(|entity| towers.get(entity).ok())
+            .map(|(_, _, tower)| (snap_to_grid(pointer), tower.effective_range))
+    };
+    let Some((center, range)) = candidate else { return };
+
+    // Only towers whose range reaches the new one's can overlap it
+    let others: Vec<(Vec2, f32)> = towers
+        .iter()
+        .filter(|(entity, _, _)| Some(*entity) != move_state.moving)
+        .map(|(_, transform, tower)| (transform.translation.truncate(), tower.effective_range))
+        .filter(|(position, other_range)| position.distance(center) < range + other_range)
+        .collect();
+
+    if range_overlap_fraction(center, range, &others) < RANGE_OVERLAP_WARNING_THRESHOLD {
+        gizmos.circle_2d(center, range, RANGE_PREVIEW_COLOR);
+        return;
+    }
+
+    gizmos.circle_2d(center, range, RANGE_OVERLAP_WARNING_COLOR);
+    for (position, other_range) in others {
+        gizmos.circle_2d(position, other_range, RANGE_OVERLAP_WARNING_COLOR);
+    }
+}
+
+/// How long a touch / gamepad press must rest on a wheel option before its tooltip shows
+const TOOLTIP_HOLD_SECS: f32 = 0.4;
+
+/// Show the description of the wheel option under the pointer: on hover with a
+/// mouse, or after tap-and-hold with touch / gamepad
+pub fn update_tower_wheel_tooltip(
+    mut commands: Commands,
+    actions: Res<ActionState>,
+    wheel_state: Res<TowerWheelState>,
+    tower_configs: Res<TowerConfigs>,
+    menu_options: Query<(&Transform, &TowerWheelOption), With<TowerWheelMenu>>,
+    tooltips: Query<(Entity, &TowerWheelTooltip)>,
+) {
+    let wants_tooltip = actions.source.has_hover()
+        || actions.select_hold_secs >= TOOLTIP_HOLD_SECS;
+
+    let hovered = match (wheel_state.active && wants_tooltip, actions.pointer_world) {
+        (true, Some(pointer)) => hovered_wheel_option(pointer, menu_options.iter()),
+        _ => None,
+    };
+
+    // Keep the current tooltip if it already describes the hovered option
+    let mut up_to_date = false;
+    for (entity, tooltip) in tooltips.iter() {
+        if hovered.is_some_and(|(_, option)| option.tower_type_id == tooltip.tower_type_id) {
+            up_to_date = true;
+        } else {
+            commands.queue_silenced(move |world: &mut World| {
+                if let Ok(entity_mut) = world.get_entity_mut(entity) {
+                    entity_mut.despawn();
+                }
+            });
+        }
+    }
+
+    let Some((transform, option)) = hovered else { return };
+    if up_to_date {
+        return;
+    }
+
+    let text = if option.tower_type_id == "_explore" {
+        format!("Explore\nReveal the surrounding area\nCost: {}g", EXPLORE_COST)
+    } else if option.tower_type_id == "_wall" {
+        format!("Wall\nBlocks the road; enemies go around it\nCost: {}g", tower_configs.wall_cost)
+    } else if option.tower_type_id == "_market" {
+        format!(
+            "Market\nEarns {}g every {:.0}s without workers\nCost: {}g",
+            MARKET_GOLD_AMOUNT, MARKET_GOLD_INTERVAL, MARKET_COST
+        )
+    } else if let Some(tower_type) = tower_configs
+        .towers
+        .iter()
+        .find(|t| t.id == option.tower_type_id)
+    {
+        format!(
+            "{}\n{}\nDMG {:.0}  RNG {:.0}  {}",
+            tower_type.name,
+            tower_type.description,
+            tower_type.damage,
+            tower_type.range,
+            tower_type.attack_type
+        )
+    } else {
+        return;
+    };
+
+    let position = transform.translation.truncate() + Vec2::new(0.0, 70.0);
+    commands.spawn((
+        Text2d::new(text),
+        TextFont {
+            font_size: 12.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        TextLayout::new_with_justify(Justify::Center),
+        TextBackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.8)),
+        Transform::from_xyz(position.x, position.y, 12.0),
+        TowerWheelMenu,
+        TowerWheelTooltip {
+            tower_type_id: option.tower_type_id.clone(),
+        },
+    ));
+}
+
+pub fn spawn_wall(commands: &mut Commands, asset_server: &AssetServer, (tile_x, tile_y): (i32, i32)) {
+    let position = tile_to_world(tile_x, tile_y);
+    commands.spawn((
+        Sprite {
+            image: asset_server.load(WALL_SPRITE),
+            custom_size: Some(Vec2::splat(SCALED_TILE_SIZE)),
+            ..default()
+        },
+        Transform::from_xyz(position.x, position.y, 0.9),
+        Wall,
+    ));
+}
+
+fn spawn_market(commands: &mut Commands, asset_server: &AssetServer, position: Vec2) {
+    let scale = SCALED_TILE_SIZE * 1.5 / MARKET_SIZE.y;
+    commands.spawn((
+        Sprite::from_image(asset_server.load(MARKET_SPRITE)),
+        Transform::from_xyz(position.x, position.y + SCALED_TILE_SIZE * 0.25, 1.0).with_scale(Vec3::splat(scale)),
+        GoldGenerator::new(MARKET_GOLD_INTERVAL, MARKET_GOLD_AMOUNT),
+    ));
+}
+
+/// Pay out market gold. It goes through `earn_gold` like any other income,
+/// so online it lands in the builder's own gold, or the shared pool.
+pub fn generate_gold(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut game_state: ResMut<GameState>,
+    mut generators: Query<(&Transform, &mut GoldGenerator)>,
+) {
+    for (transform, mut generator) in generators.iter_mut() {
+        generator.timer.tick(time.delta());
+        if !generator.timer.just_finished() {
+            continue;
+        }
+        game_state.earn_gold(generator.amount);
+        spawn_floating_text(
+            &mut commands,
+            transform.translation.truncate(),
+            format!("+{}g", generator.amount),
+            Color::srgb(1.0, 0.85, 0.2),
+        );
+    }
 }
 
 // Holy tower heal effect constants
 const HEAL_FRAME_SIZE: UVec2 = UVec2::new(192, 192);
 const HEAL_FRAME_COUNT: usize = 11;
 const HEAL_ANIMATION_DURATION: f32 = 1.1; // 11 frames at 0.1s each
+const HOLY_BEAM_COLOR: Color = Color::srgba(1.0, 0.92, 0.55, 0.85);
+const HOLY_BEAM_WIDTH: f32 = 4.0;
+const HOLY_BEAM_DURATION: f32 = 0.15;
+/// Tint of the sparkle over a freshly upgraded tower
+const UPGRADE_EFFECT_COLOR: Color = Color::srgb(0.6, 0.95, 1.0);
+/// Apex of a lobbed shot as a fraction of the distance it covers
+const BALLISTIC_APEX_RATIO: f32 = 0.35;
+/// Point-blank lobs still take this long, so the arc stays visible
+const MIN_BALLISTIC_FLIGHT_TIME: f32 = 0.3;
 
 pub fn tower_shooting(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
-    mut towers: Query<(&Transform, &mut Tower)>,
+    mut towers: Query<(Entity, &Transform, &mut Tower)>,
     mut enemies: Query<(Entity, &Transform, &mut Enemy), Without<Tower>>,
     mut game_state: ResMut<GameState>,
+    waypoints: Res<PathWaypoints>,
     time: Res<Time>,
+    mut killed: MessageWriter<EnemyKilled>,
+    mut sounds: MessageWriter<PlaySound>,
+    focus: Res<FocusTarget>,
     stdb: Option<SpacetimeDB>,
 ) {
-    for (tower_transform, mut tower) in towers.iter_mut() {
+    let local_player = stdb.and_then(|stdb| stdb.try_identity());
+    for (tower_entity, tower_transform, mut tower) in towers.iter_mut() {
+        // Support towers only buff their neighbours
+        if tower.aura.is_some() {
+            continue;
+        }
         tower.cooldown -= time.delta_secs();
 
         if tower.cooldown <= 0.0 {
-            // Find closest enemy in range
-            let mut closest_enemy: Option<(Entity, f32)> = None;
-
-            for (enemy_entity, enemy_transform, _) in enemies.iter() {
-                let distance = tower_transform
-                    .translation
-                    .distance(enemy_transform.translation);
-
-                if distance <= tower.range {
-                    if let Some((_, closest_dist)) = closest_enemy {
-                        if distance < closest_dist {
-                            closest_enemy = Some((enemy_entity, distance));
-                        }
-                    } else {
-                        closest_enemy = Some((enemy_entity, distance));
-                    }
-                }
-            }
+            // The player's focus target first, if this tower can reach and hurt it.
+            // Enemies killed earlier this frame linger until cleanup; skip them.
+            let tower_pos = tower_transform.translation.truncate();
+            let focused_enemy = focus.0.filter(|target| {
+                enemies.get(*target).is_ok_and(|(_, enemy_transform, enemy)| {
+                    enemy.health > 0.0
+                        && enemy.damage_multiplier(tower.attack_type) > 0.0
+                        && enemy_transform.translation.truncate().distance(tower_pos) <= tower.effective_range
+                })
+            });
+            // Otherwise the closest enemy in range
+            let closest_enemy = focused_enemy.or_else(|| {
+                nearest_in_range(
+                    tower_pos.into(),
+                    tower.effective_range,
+                    enemies
+                        .iter()
+                        .filter(|(_, _, enemy)| enemy.health > 0.0)
+                        .map(|(enemy_entity, enemy_transform, _)| (enemy_entity, enemy_transform.translation.truncate().into())),
+                )
+            });
 
             // Handle attack based on tower type
-            if let Some((target_entity, _)) = closest_enemy {
+            if let Some(target_entity) = closest_enemy {
                 // Holy tower: instant damage with holy effect on enemy
                 if tower.tower_type_id == "holy" {
                     // Deal instant damage to target
-                    if let Ok((_enemy_entity, enemy_transform, mut enemy)) = enemies.get_mut(target_entity) {
-                        let multiplier = get_damage_multiplier(tower.attack_type, enemy.defense_type);
-                        let final_damage = tower.damage * multiplier;
-                        enemy.health -= final_damage;
+                    if let Ok((enemy_entity, enemy_transform, mut enemy)) = enemies.get_mut(target_entity) {
+                        let was_alive = enemy.health > 0.0;
+                        let multiplier = enemy.damage_multiplier(tower.attack_type);
+                        if multiplier > 0.0 {
+                            enemy.health -= tower.effective_damage * multiplier;
+                            commands.entity(enemy_entity).try_insert(HitFlash::default());
+                        } else {
+                            spawn_immune_text(&mut commands, enemy_transform.translation.truncate());
+                        }
 
+                        spawn_holy_beam(
+                            &mut commands,
+                            tower_transform.translation.truncate(),
+                            enemy_transform.translation.truncate(),
+                        );
                         // Spawn holy effect at enemy position
                         spawn_holy_tower_effect(
                             &mut commands,
@@ -446,34 +1091,224 @@ pub fn tower_shooting(
                         );
 
                         // Check if enemy died from instant damage
-                        if enemy.health <= 0.0 {
-                            game_state.gold += enemy.gold_reward;
-                            game_state.score += enemy.gold_reward;
+                        if was_alive && enemy.health <= 0.0 {
+                            award_kill(&enemy, Some(&mut *tower), local_player, &mut game_state, &mut killed);
                         }
                     }
+                } else if let Some(hazard) = tower.hazard {
+                    // Area-denial towers: drop a burning patch where the target stands
+                    if let Ok((_, enemy_transform, _)) = enemies.get(target_entity) {
+                        spawn_ground_hazard(
+                            &mut commands,
+                            &asset_server,
+                            &mut texture_atlases,
+                            enemy_transform.translation.truncate(),
+                            tower_entity,
+                            &tower,
+                            hazard,
+                        );
+                    }
                 } else {
                     // Regular towers: spawn projectile
                     let projectile_scale = (SCALED_TILE_SIZE * 0.5) / ARROW_SIZE.x;
+                    let origin = tower_transform.translation.truncate();
+
+                    // Leading and lobbing towers aim at where the enemy will be when the projectile arrives
+                    let aim_point = if tower.leads_target || tower.ballistic {
+                        enemies.get(target_entity).ok().map(|(_, enemy_transform, enemy)| {
+                            let enemy_pos = enemy_transform.translation.truncate();
+                            let velocity = enemy_velocity(enemy, enemy_pos, &waypoints);
+                            intercept_point(origin, enemy_pos, velocity, tower.projectile_speed).unwrap_or(enemy_pos)
+                        })
+                    } else {
+                        None
+                    };
+                    let aim_direction = aim_point.map(|point| (point - origin).normalize_or_zero());
+                    let direction = aim_direction.unwrap_or(Vec2::X);
 
-                    commands.spawn((
+                    let mut projectile = commands.spawn((
                         Sprite::from_image(asset_server.load(&tower.projectile_sprite)),
                         Transform::from_translation(tower_transform.translation)
-                            .with_scale(Vec3::splat(projectile_scale)),
+                            .with_scale(Vec3::splat(projectile_scale))
+                            .with_rotation(Quat::from_rotation_z(direction.y.atan2(direction.x))),
                         Projectile {
-                            damage: tower.damage,
+                            damage: tower.effective_damage,
                             speed: tower.projectile_speed,
                             target: target_entity,
                             attack_type: tower.attack_type,
+                            homing: aim_direction.is_none(),
+                            direction,
+                            remaining_range: tower.effective_range * 1.5,
+                            source: tower_entity,
+                            burn: tower.burn_on_hit,
                         },
                     ));
+                    if let Some(impact) = aim_point.filter(|_| tower.ballistic) {
+                        let distance = origin.distance(impact);
+                        projectile.insert(Ballistic {
+                            start: origin,
+                            impact,
+                            elapsed: 0.0,
+                            flight_time: (distance / tower.projectile_speed).max(MIN_BALLISTIC_FLIGHT_TIME),
+                            apex_height: distance * BALLISTIC_APEX_RATIO,
+                            splash_radius: tower.splash_radius,
+                        });
+                    }
+                    if let Some(config) = tower.trail {
+                        projectile.insert(Trail {
+                            config,
+                            last_point: origin,
+                        });
+                    }
+                    sounds.write(PlaySound {
+                        effect: SoundEffect::TowerFire,
+                        position: Some(origin),
+                    });
                 }
 
-                tower.cooldown = tower.fire_rate;
+                tower.cooldown = tower.effective_fire_rate;
+            }
+        }
+    }
+}
+
+// Ground hazard decal (Fire_01.png is 8 frames of 64x64)
+const HAZARD_FRAME_SIZE: UVec2 = UVec2::new(64, 64);
+const HAZARD_FRAME_COUNT: usize = 8;
+
+fn spawn_ground_hazard(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    texture_atlases: &mut ResMut<Assets<TextureAtlasLayout>>,
+    position: Vec2,
+    tower_entity: Entity,
+    tower: &Tower,
+    hazard: HazardConfig,
+) {
+    let layout = TextureAtlasLayout::from_grid(HAZARD_FRAME_SIZE, HAZARD_FRAME_COUNT as u32, 1, None, None);
+    let scale = hazard.radius * 2.0 / HAZARD_FRAME_SIZE.x as f32;
+
+    commands.spawn((
+        Sprite {
+            image: asset_server.load(&tower.projectile_sprite),
+            color: Color::srgba(1.0, 1.0, 1.0, 0.8),
+            texture_atlas: Some(TextureAtlas {
+                layout: texture_atlases.add(layout),
+                index: 0,
+            }),
+            ..default()
+        },
+        // Just above the map, below enemies
+        Transform::from_translation(position.extend(0.9)).with_scale(Vec3::splat(scale)),
+        AnimationTimer {
+            timer: Timer::from_seconds(0.1, TimerMode::Repeating),
+        },
+        AnimationInfo {
+            frame_count: HAZARD_FRAME_COUNT,
+        },
+        GroundHazard {
+            radius: hazard.radius,
+            dps: tower.effective_damage,
+            attack_type: tower.attack_type,
+            lifetime: Timer::from_seconds(hazard.lifetime, TimerMode::Once),
+            source: tower_entity,
+        },
+    ));
+}
+
+/// Damage enemies standing in ground hazards and remove expired hazards
+pub fn update_ground_hazards(
+    mut commands: Commands,
+    mut hazards: Query<(Entity, &Transform, &mut GroundHazard, &mut Sprite)>,
+    mut enemies: Query<(&Transform, &mut Enemy), Without<GroundHazard>>,
+    mut towers: Query<&mut Tower>,
+    mut game_state: ResMut<GameState>,
+    time: Res<Time>,
+    mut killed: MessageWriter<EnemyKilled>,
+    stdb: Option<SpacetimeDB>,
+) {
+    let local_player = stdb.and_then(|stdb| stdb.try_identity());
+    for (hazard_entity, hazard_transform, mut hazard, mut sprite) in hazards.iter_mut() {
+        hazard.lifetime.tick(time.delta());
+
+        for (enemy_transform, mut enemy) in enemies.iter_mut() {
+            if enemy.health <= 0.0 {
+                continue;
             }
+            let distance = hazard_transform.translation.truncate().distance(enemy_transform.translation.truncate());
+            if distance <= hazard.radius {
+                // Immune enemies walk through unharmed; no text, it would repeat every frame
+                let multiplier = enemy.damage_multiplier(hazard.attack_type);
+                enemy.health -= hazard.dps * multiplier * time.delta_secs();
+
+                if enemy.health <= 0.0 {
+                    award_kill(
+                        &enemy,
+                        towers.get_mut(hazard.source).ok().as_deref_mut(),
+                        local_player,
+                        &mut game_state,
+                        &mut killed,
+                    );
+                }
+            }
+        }
+
+        // Fade out over the last half second
+        let remaining = hazard.lifetime.remaining_secs();
+        sprite.color = Color::srgba(1.0, 1.0, 1.0, 0.8 * (remaining / 0.5).min(1.0));
+
+        if hazard.lifetime.is_finished() {
+            commands.queue_silenced(move |world: &mut World| {
+                if let Ok(entity_mut) = world.get_entity_mut(hazard_entity) {
+                    entity_mut.despawn();
+                }
+            });
         }
     }
 }
 
+/// Current velocity of an enemy walking the path towards its next waypoint
+fn enemy_velocity(enemy: &Enemy, position: Vec2, waypoints: &PathWaypoints) -> Vec2 {
+    waypoints
+        .points
+        .get(enemy.current_waypoint)
+        .map(|target| (*target - position).normalize_or_zero() * enemy.speed)
+        .unwrap_or(Vec2::ZERO)
+}
+
+/// Point where a projectile fired from `origin` at `speed` meets a target moving at
+/// constant `velocity`, or None if the projectile can never catch it
+fn intercept_point(origin: Vec2, target: Vec2, velocity: Vec2, speed: f32) -> Option<Vec2> {
+    let to_target = target - origin;
+    // Solve |to_target + velocity * t| = speed * t for the smallest positive t
+    let a = velocity.length_squared() - speed * speed;
+    let b = 2.0 * to_target.dot(velocity);
+    let c = to_target.length_squared();
+
+    let t = if a.abs() < f32::EPSILON {
+        if b.abs() < f32::EPSILON {
+            return None;
+        }
+        -c / b
+    } else {
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let sqrt_d = discriminant.sqrt();
+        let t1 = (-b - sqrt_d) / (2.0 * a);
+        let t2 = (-b + sqrt_d) / (2.0 * a);
+        match (t1 > 0.0, t2 > 0.0) {
+            (true, true) => t1.min(t2),
+            (true, false) => t1,
+            (false, true) => t2,
+            (false, false) => return None,
+        }
+    };
+
+    (t > 0.0).then(|| target + velocity * t)
+}
+
 /// Spawn holy smite effect on the enemy (gold-tinted heal effect)
 fn spawn_holy_tower_effect(
     commands: &mut Commands,
@@ -518,6 +1353,48 @@ fn spawn_holy_tower_effect(
     ));
 }
 
+/// Stretch a beam of light from the tower to the struck enemy, so the
+/// otherwise instant attack is seen to connect
+fn spawn_holy_beam(commands: &mut Commands, tower_position: Vec2, enemy_position: Vec2) {
+    let offset = enemy_position - tower_position;
+    commands.spawn((
+        Sprite {
+            color: HOLY_BEAM_COLOR,
+            custom_size: Some(Vec2::new(offset.length(), HOLY_BEAM_WIDTH)),
+            ..default()
+        },
+        Transform::from_translation(((tower_position + enemy_position) / 2.0).extend(5.0))
+            .with_rotation(Quat::from_rotation_z(offset.y.atan2(offset.x))),
+        HolyBeam {
+            lifetime: Timer::from_seconds(HOLY_BEAM_DURATION, TimerMode::Once),
+        },
+    ));
+}
+
+/// Fade holy beams out, then remove them
+pub fn update_holy_beams(
+    mut commands: Commands,
+    mut beams: Query<(Entity, &mut HolyBeam, &mut Sprite, &Visibility)>,
+    time: Res<Time>,
+) {
+    for (entity, mut beam, mut sprite, visibility) in beams.iter_mut() {
+        beam.lifetime.tick(time.delta());
+
+        if beam.lifetime.is_finished() {
+            commands.queue_silenced(move |world: &mut World| {
+                if let Ok(entity_mut) = world.get_entity_mut(entity) {
+                    entity_mut.despawn();
+                }
+            });
+            continue;
+        }
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+        sprite.color.set_alpha(HOLY_BEAM_COLOR.alpha() * (1.0 - beam.lifetime.fraction()));
+    }
+}
+
 /// Update and cleanup holy tower visual effects
 pub fn update_holy_tower_effects(
     mut commands: Commands,
@@ -537,15 +1414,188 @@ pub fn update_holy_tower_effects(
     }
 }
 
-fn get_user_color(stdb: Option<&SpacetimeDB>) -> module_bindings::Color {
-    stdb
-        .and_then(|stdb| stdb.db().my_user().iter().next().map(|user| user.color))
+const VETERANCY_STAR_COLOR: Color = Color::srgb(1.0, 0.85, 0.2);
+
+/// Promote towers whose kills reached the next veterancy rank. Each rank
+/// adds a small damage and fire rate bonus and a star above the tower.
+pub fn update_tower_veterancy(
+    mut commands: Commands,
+    mut towers: Query<(Entity, &Transform, &mut Tower, &mut Veterancy), Changed<Tower>>,
+) {
+    for (entity, transform, mut tower, mut veterancy) in towers.iter_mut() {
+        let rank = Veterancy::rank_for_kills(tower.kills);
+        if rank <= veterancy.rank {
+            continue;
+        }
+
+        for _ in veterancy.rank..rank {
+            tower.base_damage *= 1.0 + VETERANCY_DAMAGE_BONUS;
+            tower.base_fire_rate *= 1.0 - VETERANCY_FIRE_RATE_BONUS;
+        }
+
+        // Counter-scaled so the stars are sized in world pixels, above the cooldown bar
+        let scale = transform.scale.x;
+        let star_y = TOWER_SIZE.y / 2.0 + 11.0 / scale;
+        commands.entity(entity).with_children(|parent| {
+            for index in veterancy.rank..rank {
+                let x = (index as f32 - 1.0) * 7.0 / scale;
+                parent.spawn((
+                    Sprite {
+                        color: VETERANCY_STAR_COLOR,
+                        custom_size: Some(Vec2::splat(5.0)),
+                        ..default()
+                    },
+                    Transform::from_xyz(x, star_y, 1.0)
+                        .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4))
+                        .with_scale(Vec3::splat(1.0 / scale)),
+                    VeterancyStar,
+                ));
+            }
+        });
+
+        info!("{} tower reached veteran rank {} ({} kills)", tower.tower_type_id, rank, tower.kills);
+        veterancy.rank = rank;
+    }
+}
+
+/// Tint of towers boosted by a support tower's aura
+const AURA_BUFF_TINT: Color = Color::srgb(1.0, 0.92, 0.65);
+/// Ring drawn around support towers at their aura radius
+const AURA_RING_COLOR: Color = Color::srgba(1.0, 0.85, 0.3, 0.5);
+/// Gather the aura bonuses covering each tower into its `TowerBuffs`.
+/// Auras don't stack: each bonus comes from the strongest support tower in range.
+/// With night or weather penalties on, every tower also loses range at
+/// night or in heavy fog.
+pub fn apply_tower_auras(
+    rules: Res<GameRules>,
+    time_of_day: Res<TimeOfDay>,
+    weather: Res<Weather>,
+    mut towers: Query<(Entity, &Transform, &Tower, &mut TowerBuffs, &mut Sprite)>,
+) {
+    let mut range_penalty = 0.0;
+    if rules.night_penalties && time_of_day.is_night() {
+        range_penalty += NIGHT_TOWER_RANGE_PENALTY;
+    }
+    if rules.weather_penalties && weather.kind == WeatherKind::Fog {
+        range_penalty += FOG_TOWER_RANGE_PENALTY;
+    }
+
+    let supports: Vec<(Entity, Vec2, f32, AuraEffect)> = towers
+        .iter()
+        .filter_map(|(entity, transform, tower, ..)| {
+            tower.aura.map(|aura| (entity, transform.translation.truncate(), tower.effective_range, aura))
+        })
+        .collect();
+
+    for (entity, transform, _, mut buffs, mut sprite) in towers.iter_mut() {
+        let position = transform.translation.truncate();
+        let mut next = TowerBuffs {
+            range_bonus: -range_penalty,
+            ..default()
+        };
+        let mut buffed = false;
+        for (support, support_position, radius, aura) in &supports {
+            if *support != entity && position.distance(*support_position) <= *radius {
+                next.damage_bonus = next.damage_bonus.max(aura.damage_bonus);
+                next.fire_rate_bonus = next.fire_rate_bonus.max(aura.fire_rate_bonus);
+                buffed = true;
+            }
+        }
+        buffs.set_if_neq(next);
+
+        let tint = if buffed { AURA_BUFF_TINT } else { Color::WHITE };
+        if sprite.color != tint {
+            sprite.color = tint;
+        }
+    }
+}
+
+/// Derive every tower's effective stats from its base stats and buffs.
+/// Only real changes are written so `Changed<Tower>` stays meaningful.
+pub fn update_effective_tower_stats(mut towers: Query<(&mut Tower, &TowerBuffs)>) {
+    for (mut tower, buffs) in towers.iter_mut() {
+        let stats = tower.buffed_stats(buffs);
+        if stats != (tower.effective_damage, tower.effective_range, tower.effective_fire_rate) {
+            (tower.effective_damage, tower.effective_range, tower.effective_fire_rate) = stats;
+        }
+    }
+}
+
+/// Outline the area each support tower buffs
+pub fn draw_tower_auras(mut gizmos: Gizmos, towers: Query<(&Transform, &Tower)>) {
+    for (transform, tower) in towers.iter() {
+        if tower.aura.is_some() {
+            gizmos.circle_2d(transform.translation.truncate(), tower.effective_range, AURA_RING_COLOR);
+        }
+    }
+}
+
+/// Fill each tower's cooldown bar with `1 - cooldown / fire_rate`
+pub fn update_tower_cooldown_bars(
+    overlays: Res<OverlaySettings>,
+    towers: Query<(&Tower, &Children)>,
+    mut backgrounds: Query<(&mut Visibility, &Children), Without<TowerCooldownBar>>,
+    mut fills: Query<(&mut Transform, &mut Sprite, &TowerCooldownBar)>,
+) {
+    for (tower, tower_children) in towers.iter() {
+        for child in tower_children.iter() {
+            let Ok((mut visibility, bar_children)) = backgrounds.get_mut(child) else {
+                continue;
+            };
+            *visibility = if overlays.show_cooldowns {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+
+            let readiness = if tower.effective_fire_rate > 0.0 {
+                (1.0 - tower.cooldown / tower.effective_fire_rate).clamp(0.0, 1.0)
+            } else {
+                1.0
+            };
+
+            for fill_child in bar_children.iter() {
+                if let Ok((mut transform, mut sprite, bar)) = fills.get_mut(fill_child) {
+                    if let Some(ref mut size) = sprite.custom_size {
+                        size.x = bar.max_width * readiness;
+                        transform.translation.x = -(bar.max_width - size.x) / 2.0;
+                    }
+                    // Dim while reloading, bright once ready to fire
+                    sprite.color = if readiness >= 1.0 {
+                        Color::srgb(0.3, 0.8, 1.0)
+                    } else {
+                        Color::srgba(0.3, 0.5, 0.6, 0.7)
+                    };
+                }
+            }
+        }
+    }
+}
+
+/// Toggle optional overlays (tower cooldown bars)
+pub fn toggle_overlays(actions: Res<ActionState>, mut overlays: ResMut<OverlaySettings>) {
+    if actions.just_pressed(InputAction::ToggleOverlays) {
+        overlays.show_cooldowns = !overlays.show_cooldowns;
+    }
+}
+
+/// Color of `owner` among `users`, falling back to blue offline or for
+/// unknown players
+fn owner_color(users: &[User], owner: Option<Identity>) -> module_bindings::Color {
+    owner
+        .and_then(|owner| users.iter().find(|user| user.identity == owner))
+        .map(|user| user.color)
         .unwrap_or(module_bindings::Color::Blue)
 }
 
-fn get_tower_sprite_path(tower_type: &TowerType, stdb: Option<&SpacetimeDB>) -> String {
-    let color = get_user_color(stdb);
-    tower_type.sprite_path.replace("Blue", color.as_str())
+/// Sprite of `tower_type` in the color of the player who owns it
+fn tower_sprite_path(tower_type: &TowerType, users: &[User], owner: Option<Identity>) -> String {
+    tower_type.sprite_path.replace("Blue", owner_color(users, owner).as_str())
+}
+
+fn get_tower_sprite_path(tower_type: &TowerType, stdb: Option<&SpacetimeDB>, owner: Option<Identity>) -> String {
+    let users: Vec<User> = stdb.map(|stdb| stdb.db().user().iter().collect()).unwrap_or_default();
+    tower_sprite_path(tower_type, &users, owner)
 }
 
 // ==================== Tower Upgrade Menu Systems ====================
@@ -558,30 +1608,30 @@ const UPGRADE_FIRE_RATE_COST: i32 = 35;
 pub fn show_tower_upgrade_menu(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    mouse_button: Res<ButtonInput<MouseButton>>,
-    windows: Query<&Window>,
-    camera: Query<(&Camera, &GlobalTransform)>,
+    actions: Res<ActionState>,
     mut upgrade_menu_state: ResMut<TowerUpgradeMenuState>,
     wheel_state: Res<TowerWheelState>,
     recruit_menu_state: Res<RecruitMenuState>,
     house_menu_state: Res<HouseMenuState>,
-    towers: Query<(Entity, &Transform, &Tower, &TowerLevel)>,
+    move_state: Res<TowerMoveState>,
+    selected: Res<SelectedTowers>,
+    // Other players' towers are theirs to upgrade
+    towers: Query<(Entity, &Transform, &Tower, &TowerLevel), Without<SyncedTower>>,
     existing_menus: Query<Entity, With<TowerUpgradeMenu>>,
+    tower_configs: Res<TowerConfigs>,
 ) {
     // Don't show if any other menu is active
-    if !mouse_button.just_pressed(MouseButton::Left)
+    if !actions.just_pressed(InputAction::Select)
         || upgrade_menu_state.active
         || wheel_state.active
         || recruit_menu_state.active
         || house_menu_state.active
+        || move_state.moving.is_some()
     {
         return;
     }
 
-    let Ok(window) = windows.single() else { return };
-    let Ok((camera, camera_transform)) = camera.single() else { return };
-    let Some(cursor_pos) = window.cursor_position() else { return };
-    let Ok(world_pos) = camera.viewport_to_world_2d(camera_transform, cursor_pos) else { return };
+    let Some(world_pos) = actions.pointer_world else { return };
 
     // Check if clicked on a tower
     for (tower_entity, tower_transform, tower, tower_level) in towers.iter() {
@@ -592,9 +1642,16 @@ pub fn show_tower_upgrade_menu(
                 commands.entity(entity).despawn();
             }
 
+            // Clicking a box-selected tower upgrades the whole selection
+            let group_size = if selected.towers.contains(&tower_entity) {
+                selected.towers.len()
+            } else {
+                1
+            };
+
             upgrade_menu_state.active = true;
             upgrade_menu_state.selected_tower = Some(tower_entity);
-            spawn_tower_upgrade_menu(&mut commands, &asset_server, tower, tower_level);
+            spawn_tower_upgrade_menu(&mut commands, &asset_server, tower, tower_level, group_size, tower_configs.move_cost);
             return;
         }
     }
@@ -605,7 +1662,15 @@ fn spawn_tower_upgrade_menu(
     asset_server: &Res<AssetServer>,
     tower: &Tower,
     tower_level: &TowerLevel,
+    group_size: usize,
+    move_cost: i32,
 ) {
+    let group_size = group_size as i32;
+    let title = if group_size > 1 {
+        format!("Upgrade {} towers", group_size)
+    } else {
+        format!("Upgrade {}", tower.tower_type_id.to_uppercase())
+    };
     let wood_icon = asset_server.load("Terrain/Resources/Wood/Wood Resource/Wood Resource.png");
 
     // Main menu container (centered overlay)
@@ -638,7 +1703,7 @@ fn spawn_tower_upgrade_menu(
                 .with_children(|panel| {
                     // Title
                     panel.spawn((
-                        Text::new(format!("Upgrade {}", tower.tower_type_id.to_uppercase())),
+                        Text::new(title),
                         TextFont {
                             font_size: 24.0,
                             ..default()
@@ -649,8 +1714,12 @@ fn spawn_tower_upgrade_menu(
                     // Current stats display
                     panel.spawn((
                         Text::new(format!(
-                            "DMG: {:.0}  RNG: {:.0}  SPD: {:.1}s",
-                            tower.damage, tower.range, tower.fire_rate
+                            "DMG: {:.0}  RNG: {:.0}  SPD: {:.1}s\nKills: {}  Veteran rank: {}",
+                            tower.effective_damage,
+                            tower.effective_range,
+                            tower.effective_fire_rate,
+                            tower.kills,
+                            Veterancy::rank_for_kills(tower.kills)
                         )),
                         TextFont {
                             font_size: 12.0,
@@ -676,6 +1745,7 @@ fn spawn_tower_upgrade_menu(
                                 format!("Lv.{}", tower_level.damage_level),
                                 UpgradeType::Damage,
                                 UPGRADE_DAMAGE_COST,
+                                group_size,
                             );
 
                             // Range upgrade
@@ -687,6 +1757,7 @@ fn spawn_tower_upgrade_menu(
                                 format!("Lv.{}", tower_level.range_level),
                                 UpgradeType::Range,
                                 UPGRADE_RANGE_COST,
+                                group_size,
                             );
 
                             // Fire Rate upgrade
@@ -698,9 +1769,34 @@ fn spawn_tower_upgrade_menu(
                                 format!("Lv.{}", tower_level.fire_rate_level),
                                 UpgradeType::FireRate,
                                 UPGRADE_FIRE_RATE_COST,
+                                group_size,
                             );
                         });
 
+                    if group_size == 1 {
+                        panel
+                            .spawn((
+                                Node {
+                                    padding: UiRect::axes(Val::Px(12.0), Val::Px(6.0)),
+                                    ..default()
+                                },
+                                BackgroundColor(Color::srgba(0.35, 0.3, 0.15, 1.0)),
+                                BorderRadius::all(Val::Px(4.0)),
+                                MoveTowerButton,
+                                Button,
+                            ))
+                            .with_children(|button| {
+                                button.spawn((
+                                    Text::new(format!("Move ({}g)", move_cost)),
+                                    TextFont {
+                                        font_size: 12.0,
+                                        ..default()
+                                    },
+                                    TextColor(Color::srgb(1.0, 1.0, 0.0)),
+                                ));
+                            });
+                    }
+
                     // Close hint
                     panel.spawn((
                         Text::new("Right-click or ESC to close"),
@@ -714,6 +1810,9 @@ fn spawn_tower_upgrade_menu(
         });
 }
 
+/// Card for one upgrade, priced at `cost` wood for each of the `group_size`
+/// towers it applies to
+#[allow(clippy::too_many_arguments)]
 fn spawn_upgrade_card(
     parent: &mut ChildSpawnerCommands,
     wood_icon: &Handle<Image>,
@@ -722,6 +1821,7 @@ fn spawn_upgrade_card(
     level: String,
     upgrade_type: UpgradeType,
     cost: i32,
+    group_size: i32,
 ) {
     parent
         .spawn((
@@ -803,7 +1903,7 @@ fn spawn_upgrade_card(
                     })
                     .with_children(|cost_row: &mut ChildSpawnerCommands| {
                         cost_row.spawn((
-                            Text::new(format!("{}", cost)),
+                            Text::new(format!("{}", cost * group_size)),
                             TextFont {
                                 font_size: 10.0,
                                 ..default()
@@ -827,8 +1927,7 @@ fn spawn_upgrade_card(
 /// Hide upgrade menu on right-click or escape
 pub fn hide_tower_upgrade_menu(
     mut commands: Commands,
-    mouse_button: Res<ButtonInput<MouseButton>>,
-    keyboard: Res<ButtonInput<KeyCode>>,
+    actions: Res<ActionState>,
     mut menu_state: ResMut<TowerUpgradeMenuState>,
     menu_entities: Query<Entity, With<TowerUpgradeMenu>>,
 ) {
@@ -836,7 +1935,7 @@ pub fn hide_tower_upgrade_menu(
         return;
     }
 
-    if mouse_button.just_pressed(MouseButton::Right) || keyboard.just_pressed(KeyCode::Escape) {
+    if actions.just_pressed(InputAction::Cancel) {
         for entity in menu_entities.iter() {
             commands.entity(entity).despawn();
         }
@@ -854,57 +1953,314 @@ pub fn handle_tower_upgrade(
     >,
     mut game_state: ResMut<GameState>,
     mut menu_state: ResMut<TowerUpgradeMenuState>,
+    selected: Res<SelectedTowers>,
     menu_entities: Query<Entity, With<TowerUpgradeMenu>>,
     mut towers: Query<(&mut Tower, &mut TowerLevel)>,
+    infinite: Res<InfiniteResources>,
 ) {
     for (interaction, option) in interaction_query.iter_mut() {
-        if *interaction == Interaction::Pressed {
-            if game_state.wood >= option.wood_cost {
-                if let Some(tower_entity) = menu_state.selected_tower {
-                    if let Ok((mut tower, mut tower_level)) = towers.get_mut(tower_entity) {
-                        game_state.wood -= option.wood_cost;
-
-                        match option.upgrade_type {
-                            UpgradeType::Damage => {
-                                tower.damage *= 1.25;
-                                tower_level.damage_level += 1;
-                                info!(
-                                    "Tower damage upgraded to {:.0} (level {})",
-                                    tower.damage, tower_level.damage_level
-                                );
-                            }
-                            UpgradeType::Range => {
-                                tower.range *= 1.20;
-                                tower_level.range_level += 1;
-                                info!(
-                                    "Tower range upgraded to {:.0} (level {})",
-                                    tower.range, tower_level.range_level
-                                );
-                            }
-                            UpgradeType::FireRate => {
-                                tower.fire_rate *= 0.80;
-                                tower_level.fire_rate_level += 1;
-                                info!(
-                                    "Tower fire rate upgraded to {:.2}s (level {})",
-                                    tower.fire_rate, tower_level.fire_rate_level
-                                );
-                            }
-                        }
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let Some(tower_entity) = menu_state.selected_tower else { continue };
 
-                        // Close menu after successful upgrade
-                        for entity in menu_entities.iter() {
-                            commands.entity(entity).despawn();
-                        }
-                        menu_state.active = false;
-                        menu_state.selected_tower = None;
-                    }
-                }
-            } else {
-                info!(
-                    "Not enough wood to upgrade. Need {}, have {}",
-                    option.wood_cost, game_state.wood
-                );
+        // Towers sold or undone since the menu opened are neither upgraded nor paid for
+        let targets: Vec<Entity> = if selected.towers.contains(&tower_entity) {
+            selected.towers.iter().copied().filter(|entity| towers.contains(*entity)).collect()
+        } else {
+            vec![tower_entity]
+        };
+        let cost = option.wood_cost * targets.len() as i32;
+        if !infinite.affords(game_state.wood, cost) {
+            info!("Not enough wood to upgrade. Need {}, have {}", cost, game_state.wood);
+            continue;
+        }
+
+        let mut upgraded = 0;
+        for entity in targets {
+            if let Ok((mut tower, mut tower_level)) = towers.get_mut(entity) {
+                apply_tower_upgrade(&mut tower, &mut tower_level, option.upgrade_type);
+                upgraded += 1;
+            }
+        }
+
+        if upgraded > 0 {
+            game_state.wood -= infinite.charge(option.wood_cost * upgraded);
+
+            // Close menu after successful upgrade
+            for entity in menu_entities.iter() {
+                commands.entity(entity).despawn();
+            }
+            menu_state.active = false;
+            menu_state.selected_tower = None;
+        }
+    }
+}
+
+fn apply_tower_upgrade(tower: &mut Tower, tower_level: &mut TowerLevel, upgrade_type: UpgradeType) {
+    match upgrade_type {
+        UpgradeType::Damage => {
+            tower.base_damage *= 1.25;
+            tower_level.damage_level += 1;
+            info!(
+                "Tower damage upgraded to {:.0} (level {})",
+                tower.base_damage, tower_level.damage_level
+            );
+        }
+        UpgradeType::Range => {
+            tower.base_range *= 1.20;
+            tower_level.range_level += 1;
+            info!(
+                "Tower range upgraded to {:.0} (level {})",
+                tower.base_range, tower_level.range_level
+            );
+        }
+        UpgradeType::FireRate => {
+            tower.base_fire_rate *= 0.80;
+            tower_level.fire_rate_level += 1;
+            info!(
+                "Tower fire rate upgraded to {:.2}s (level {})",
+                tower.base_fire_rate, tower_level.fire_rate_level
+            );
+        }
+    }
+}
+
+/// Sound and sparkle for every tower whose level went up, whichever way the
+/// upgrade was applied. Towers just placed start at level 0 and are skipped.
+pub fn celebrate_tower_upgrades(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut texture_atlases: ResMut<Assets<TextureAtlasLayout>>,
+    towers: Query<(&Transform, Ref<TowerLevel>)>,
+    mut sounds: MessageWriter<PlaySound>,
+) {
+    for (transform, level) in towers.iter() {
+        if !level.is_changed() || level.is_added() {
+            continue;
+        }
+        let position = transform.translation.truncate();
+        let layout = TextureAtlasLayout::from_grid(HEAL_FRAME_SIZE, HEAL_FRAME_COUNT as u32, 1, None, None);
+        commands.spawn((
+            Sprite {
+                image: asset_server.load("Units/Yellow Units/Monk/Heal_Effect.png"),
+                color: UPGRADE_EFFECT_COLOR,
+                texture_atlas: Some(TextureAtlas {
+                    layout: texture_atlases.add(layout),
+                    index: 0,
+                }),
+                ..default()
+            },
+            Transform::from_translation(position.extend(5.0))
+                .with_scale(Vec3::splat(SCALED_TILE_SIZE * 2.0 / HEAL_FRAME_SIZE.x as f32)),
+            AnimationTimer {
+                timer: Timer::from_seconds(0.1, TimerMode::Repeating),
+            },
+            AnimationInfo {
+                frame_count: HEAL_FRAME_COUNT,
+            },
+            UpgradeEffect {
+                lifetime: Timer::from_seconds(HEAL_ANIMATION_DURATION, TimerMode::Once),
+            },
+        ));
+        spawn_floating_text(&mut commands, position, "LEVEL UP".to_string(), UPGRADE_EFFECT_COLOR);
+        sounds.write(PlaySound {
+            effect: SoundEffect::Upgrade,
+            position: Some(position),
+        });
+    }
+}
+
+/// Remove upgrade sparkles once their animation has played
+pub fn update_upgrade_effects(
+    mut commands: Commands,
+    mut effects: Query<(Entity, &mut UpgradeEffect)>,
+    time: Res<Time>,
+) {
+    for (entity, mut effect) in effects.iter_mut() {
+        effect.lifetime.tick(time.delta());
+        if effect.lifetime.is_finished() {
+            commands.entity(entity).try_despawn();
+        }
+    }
+}
+
+/// Start relocating the tower shown in the upgrade menu
+pub fn handle_move_tower_button(
+    mut commands: Commands,
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<MoveTowerButton>)>,
+    game_state: Res<GameState>,
+    mut menu_state: ResMut<TowerUpgradeMenuState>,
+    mut move_state: ResMut<TowerMoveState>,
+    menu_entities: Query<Entity, With<TowerUpgradeMenu>>,
+    infinite: Res<InfiniteResources>,
+    tower_configs: Res<TowerConfigs>,
+) {
+    for interaction in interaction_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        if !infinite.affords(game_state.gold, tower_configs.move_cost) {
+            info!(
+                "Not enough gold to move tower. Need {}, have {}",
+                tower_configs.move_cost, game_state.gold
+            );
+            continue;
+        }
+
+        move_state.moving = menu_state.selected_tower;
+        for entity in menu_entities.iter() {
+            commands.entity(entity).despawn();
+        }
+        menu_state.active = false;
+        menu_state.selected_tower = None;
+    }
+}
+
+/// While moving a tower, the next click on a buildable tile relocates it for
+/// `TowerConfigs::move_cost` gold. Placing on release keeps the click from also
+/// opening a menu on the tower's new tile. Online the move goes through
+/// `move_tower` and the tower follows its row once the server accepts it.
+#[allow(clippy::too_many_arguments)]
+pub fn place_moving_tower(
+    actions: Res<ActionState>,
+    stdb: Option<SpacetimeDB>,
+    mut move_state: ResMut<TowerMoveState>,
+    mut game_state: ResMut<GameState>,
+    fog: Res<FogOfWar>,
+    blocked_tiles: Res<BlockedTiles>,
+    waypoints: Res<PathWaypoints>,
+    infinite: Res<InfiniteResources>,
+    tower_configs: Res<TowerConfigs>,
+    mut towers: Query<(Entity, &mut Transform), With<Tower>>,
+    rows: Query<&ConfirmedTower>,
+    // Set once a press starts after the Move button, so releasing the
+    // button click itself doesn't place the tower
+    mut armed: Local<bool>,
+) {
+    let Some(moving) = move_state.moving else {
+        *armed = false;
+        return;
+    };
+
+    if actions.just_pressed(InputAction::Cancel) || !towers.contains(moving) {
+        move_state.moving = None;
+        return;
+    }
+
+    if actions.just_pressed(InputAction::Select) {
+        *armed = true;
+    }
+    if !*armed || !actions.just_released(InputAction::Select) {
+        return;
+    }
+    *armed = false;
+    let Some(world_pos) = actions.pointer_world else { return };
+
+    let snapped = snap_to_grid(world_pos);
+    let other_towers: Vec<Vec2> = towers
+        .iter()
+        .filter(|(entity, _)| *entity != moving)
+        .map(|(_, transform)| transform.translation.truncate())
+        .collect();
+    if !is_buildable(snapped, &fog, &blocked_tiles, &waypoints, other_towers.into_iter()) {
+        info!("Can't move tower there");
+        return;
+    }
+    let cost = tower_configs.move_cost;
+    if !infinite.affords(game_state.gold, cost) {
+        info!("Not enough gold to move tower. Need {}, have {}", cost, game_state.gold);
+        move_state.moving = None;
+        return;
+    }
+
+    if let Some(stdb) = stdb {
+        let Ok(row) = rows.get(moving) else {
+            info!("Can't move a tower the server hasn't confirmed yet");
+            move_state.moving = None;
+            return;
+        };
+        match stdb.reducers().move_tower(row.id, snapped.x, snapped.y) {
+            Ok(()) => {
+                game_state.expect_server_gold(-cost);
+                game_state.gold -= cost;
             }
+            Err(e) => error!("Failed to send move_tower: {}", e),
+        }
+    } else if let Ok((_, mut transform)) = towers.get_mut(moving) {
+        transform.translation.x = snapped.x;
+        transform.translation.y = snapped.y;
+        game_state.spend_gold(infinite.charge(cost));
+    }
+    move_state.moving = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(byte: u8, color: module_bindings::Color) -> User {
+        User {
+            identity: Identity::from_byte_array([byte; 32]),
+            name: None,
+            color,
+            online: true,
+            in_game: true,
+            typing_until: None,
+        }
+    }
+
+    fn archer() -> TowerType {
+        TowerType {
+            id: "archer".to_string(),
+            name: "Archer".to_string(),
+            sprite_path: "Decorations/Buildings/Blue Buildings/Archery.png".to_string(),
+            cost: 50,
+            range: 150.0,
+            damage: 10.0,
+            fire_rate: 1.0,
+            projectile_speed: 400.0,
+            ..Default::default()
         }
     }
+
+    #[test]
+    fn towers_use_their_owners_color() {
+        let me = user(1, module_bindings::Color::Blue);
+        let friend = user(2, module_bindings::Color::Purple);
+        let users = [me.clone(), friend.clone()];
+
+        let mine = tower_sprite_path(&archer(), &users, Some(me.identity));
+        let theirs = tower_sprite_path(&archer(), &users, Some(friend.identity));
+        assert_eq!(mine, "Decorations/Buildings/Blue Buildings/Archery.png");
+        assert_eq!(theirs, "Decorations/Buildings/Purple Buildings/Archery.png");
+        assert_ne!(mine, theirs);
+    }
+
+    #[test]
+    fn unknown_owners_fall_back_to_blue() {
+        let users = [user(2, module_bindings::Color::Yellow)];
+        let stranger = Some(Identity::from_byte_array([9; 32]));
+        assert_eq!(tower_sprite_path(&archer(), &users, stranger), "Decorations/Buildings/Blue Buildings/Archery.png");
+        assert_eq!(tower_sprite_path(&archer(), &users, None), "Decorations/Buildings/Blue Buildings/Archery.png");
+    }
+
+    #[test]
+    fn stationary_targets_are_aimed_at_directly() {
+        let aim = intercept_point(Vec2::ZERO, Vec2::new(100.0, 0.0), Vec2::ZERO, 200.0);
+        assert_eq!(aim, Some(Vec2::new(100.0, 0.0)));
+    }
+
+    #[test]
+    fn moving_targets_are_led() {
+        // Crossing at 40 with a projectile at 50: they meet after 10 seconds
+        let aim = intercept_point(Vec2::ZERO, Vec2::new(300.0, 0.0), Vec2::new(0.0, 40.0), 50.0).unwrap();
+        assert!(aim.distance(Vec2::new(300.0, 400.0)) < 0.01);
+    }
+
+    #[test]
+    fn targets_outrunning_the_projectile_cannot_be_hit() {
+        let aim = intercept_point(Vec2::ZERO, Vec2::new(100.0, 0.0), Vec2::new(300.0, 0.0), 200.0);
+        assert_eq!(aim, None);
+    }
 }
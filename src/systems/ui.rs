@@ -1,19 +1,36 @@
 use bevy::prelude::*;
 
-use crate::components::{AnimationTimer, Enemy, GameUI, HealthBar, HealthBarFill};
+use crate::components::{AnimationTimer, BaseTint, CastleHealthFill, CombatLogPanel, CombatLogText, DamageVignette, Enemy, FloatingText, GameUI, HealthBar, HealthBarFill, HitFlash, SkipTutorialButton, TowerLevel, TutorialPanel, TutorialText, WorkerBuilding};
+use crate::config::WaveModifier;
 use crate::constants::SCALED_TILE_SIZE;
-use crate::resources::GameState;
+use crate::events::{EnemyKilled, EnemyReachedEnd};
+use crate::resources::{CombatLog, FogOfWar, GameState, Palette, ScreenShake, TowerConfigs, TowerWheelState, Tutorial, TutorialStep, WaveConfigs};
+use crate::systems::input::{ActionState, InputAction};
+use crate::systems::menu::{spawn_nine_slice_button, ButtonStyle};
+use crate::systems::top_bar::BelowTopBar;
+use crate::systems::wave_manager_ui::WaveManager;
 use crate::systems::AnimationInfo;
+use crate::user_settings::Settings;
 
+/// Combat log lines visible at once; the panel always shows the newest
+const COMBAT_LOG_VISIBLE_LINES: usize = 12;
+
+/// Health bars only show once an enemy is hurt, so a fresh wave marches in uncluttered
 pub fn update_health_bars(
+    palette: Res<Palette>,
     enemies: Query<(&Enemy, &Children)>,
-    health_bars: Query<(&HealthBar, &Children)>,
+    mut health_bars: Query<(&HealthBar, &Children, &mut Visibility)>,
     mut fills: Query<(&mut Transform, &mut Sprite, &HealthBarFill)>,
 ) {
     for (enemy, enemy_children) in enemies.iter() {
         for child in enemy_children.iter() {
-            if let Ok((health_bar, bar_children)) = health_bars.get(child) {
+            if let Ok((health_bar, bar_children, mut visibility)) = health_bars.get_mut(child) {
                 let health_percent = (enemy.health / health_bar.max_health).max(0.0).min(1.0);
+                visibility.set_if_neq(if health_percent >= 1.0 {
+                    Visibility::Hidden
+                } else {
+                    Visibility::Inherited
+                });
 
                 for fill_child in bar_children.iter() {
                     if let Ok((mut fill_transform, mut fill_sprite, health_bar_fill)) = fills.get_mut(fill_child) {
@@ -24,13 +41,7 @@ pub fn update_health_bars(
                             fill_transform.translation.x = -offset;
                         }
 
-                        fill_sprite.color = if health_percent > 0.6 {
-                            Color::srgb(0.0, 1.0, 0.0)
-                        } else if health_percent > 0.3 {
-                            Color::srgb(1.0, 1.0, 0.0)
-                        } else {
-                            Color::srgb(1.0, 0.0, 0.0)
-                        };
+                        fill_sprite.color = palette.health(health_percent);
                     }
                 }
             }
@@ -52,3 +63,368 @@ pub fn animate_sprites(
         }
     }
 }
+
+/// Fade the red hit tint on damaged enemies back to their base tint
+pub fn update_hit_flash(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut HitFlash, &mut Sprite, Option<&BaseTint>)>,
+) {
+    for (entity, mut flash, mut sprite, base_tint) in query.iter_mut() {
+        flash.timer.tick(time.delta());
+        let base = base_tint.map_or(Color::WHITE, |tint| tint.0).to_srgba();
+
+        if flash.timer.is_finished() {
+            sprite.color = base.into();
+            commands.entity(entity).try_remove::<HitFlash>();
+        } else {
+            // Full red at the hit, whatever the tint, easing back into it
+            let t = flash.timer.fraction();
+            let red = base.red + (1.0 - base.red) * (1.0 - t);
+            sprite.color = Color::srgba(red, base.green * t, base.blue * t, base.alpha);
+        }
+    }
+}
+
+/// Resize the castle health bar and flash the screen red when lives drop
+pub fn update_castle_health(
+    game_state: Res<GameState>,
+    palette: Res<Palette>,
+    mut shake: ResMut<ScreenShake>,
+    mut last_lives: Local<Option<i32>>,
+    mut fills: Query<(&mut Transform, &mut Sprite, &CastleHealthFill)>,
+    mut vignettes: Query<&mut DamageVignette>,
+) {
+    if !game_state.is_changed() {
+        return;
+    }
+
+    // React to the lives value rather than the damage source so it also works for synced state
+    if let Some(previous) = *last_lives {
+        if game_state.lives < previous {
+            for mut vignette in vignettes.iter_mut() {
+                vignette.timer.reset();
+            }
+            shake.add_trauma(0.5);
+        }
+    }
+    *last_lives = Some(game_state.lives);
+
+    let health_percent = (game_state.lives as f32 / game_state.max_lives.max(1) as f32).clamp(0.0, 1.0);
+    for (mut transform, mut sprite, fill) in fills.iter_mut() {
+        if let Some(ref mut size) = sprite.custom_size {
+            size.x = fill.max_width * health_percent;
+            transform.translation.x = -(fill.max_width - size.x) / 2.0;
+        }
+
+        sprite.color = palette.health(health_percent);
+    }
+}
+
+/// Fade out the red damage overlay
+pub fn update_damage_vignette(
+    time: Res<Time>,
+    mut query: Query<(&mut DamageVignette, &mut BackgroundColor)>,
+) {
+    for (mut vignette, mut background) in query.iter_mut() {
+        vignette.timer.tick(time.delta());
+        let alpha = 0.35 * (1.0 - vignette.timer.fraction());
+        background.0 = Color::srgba(0.8, 0.0, 0.0, alpha);
+    }
+}
+
+/// Spawn a short-lived text at `position` that rises and fades out
+pub fn spawn_floating_text(commands: &mut Commands, position: Vec2, text: String, color: Color) {
+    commands.spawn((
+        Text2d::new(text),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        TextColor(color),
+        Transform::from_xyz(position.x, position.y + SCALED_TILE_SIZE * 0.5, 15.0),
+        FloatingText {
+            lifetime: Timer::from_seconds(1.2, TimerMode::Once),
+            velocity: Vec2::new(0.0, 40.0),
+        },
+    ));
+}
+
+/// "IMMUNE" popup for a hit that an enemy's immunity cancelled
+pub fn spawn_immune_text(commands: &mut Commands, position: Vec2) {
+    spawn_floating_text(commands, position, "IMMUNE".to_string(), Color::srgb(0.7, 0.7, 0.75));
+}
+
+pub fn update_floating_text(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut FloatingText, &mut Transform, &mut TextColor, &Visibility)>,
+) {
+    for (entity, mut floating, mut transform, mut color, visibility) in query.iter_mut() {
+        floating.lifetime.tick(time.delta());
+        if floating.lifetime.is_finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        // Culled off screen
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+        transform.translation += (floating.velocity * time.delta_secs()).extend(0.0);
+        color.0.set_alpha(1.0 - floating.lifetime.fraction());
+    }
+}
+
+/// Spawn the combat log panel in the bottom right, hidden until toggled
+pub fn setup_combat_log(mut commands: Commands, log: Res<CombatLog>) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(10.0),
+                right: Val::Px(10.0),
+                width: Val::Px(300.0),
+                max_width: Val::Percent(40.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+            BorderRadius::all(Val::Px(6.0)),
+            if log.visible { Visibility::Inherited } else { Visibility::Hidden },
+            CombatLogPanel,
+            GameUI,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(combat_log_text(&log)),
+                TextFont {
+                    font_size: 12.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.9, 0.9, 0.9)),
+                CombatLogText,
+            ));
+        });
+}
+
+/// Turn kills, leaks and wave starts into combat log lines
+#[allow(clippy::too_many_arguments)]
+pub fn record_combat_events(
+    mut log: ResMut<CombatLog>,
+    mut killed: MessageReader<EnemyKilled>,
+    mut reached_end: MessageReader<EnemyReachedEnd>,
+    wave_manager: Res<WaveManager>,
+    game_state: Res<GameState>,
+    tower_configs: Res<TowerConfigs>,
+    wave_configs: Res<WaveConfigs>,
+    mut wave_was_active: Local<bool>,
+) {
+    if wave_manager.wave_active && !*wave_was_active {
+        let modifiers: Vec<&str> = usize::try_from(game_state.wave - 1)
+            .ok()
+            .and_then(|i| wave_configs.waves.get(i))
+            .map(|wave| {
+                WaveModifier::ALL
+                    .into_iter()
+                    .filter(|m| wave.modifiers.contains(m))
+                    .map(|m| m.label())
+                    .collect()
+            })
+            .unwrap_or_default();
+        if modifiers.is_empty() {
+            log.push(format!("Wave {} started", game_state.wave));
+        } else {
+            log.push(format!("Wave {} started: {}", game_state.wave, modifiers.join(", ")));
+        }
+    }
+    *wave_was_active = wave_manager.wave_active;
+
+    for kill in killed.read() {
+        let tower_name = kill.tower_type_id.as_ref().map(|id| {
+            tower_configs
+                .towers
+                .iter()
+                .find(|tower| &tower.id == id)
+                .map_or(id.clone(), |tower| tower.name.clone())
+        });
+        log.push(match tower_name {
+            Some(tower) => format!("{} killed {} (+{}g)", tower, kill.enemy, kill.gold_reward),
+            None => format!("{} died (+{}g)", kill.enemy, kill.gold_reward),
+        });
+    }
+
+    for leak in reached_end.read() {
+        let mut line = format!("{} hit the castle for {} damage", leak.enemy, leak.damage);
+        if leak.gold_stolen > 0 {
+            line.push_str(&format!(", stole {}g", leak.gold_stolen));
+        }
+        log.push(line);
+    }
+}
+
+pub fn toggle_combat_log(actions: Res<ActionState>, mut log: ResMut<CombatLog>) {
+    if actions.just_pressed(InputAction::ToggleCombatLog) {
+        log.visible = !log.visible;
+    }
+}
+
+pub fn update_combat_log_panel(
+    log: Res<CombatLog>,
+    mut panels: Query<&mut Visibility, With<CombatLogPanel>>,
+    mut texts: Query<&mut Text, With<CombatLogText>>,
+) {
+    if !log.is_changed() {
+        return;
+    }
+    let visibility = if log.visible { Visibility::Inherited } else { Visibility::Hidden };
+    for mut panel_visibility in panels.iter_mut() {
+        panel_visibility.set_if_neq(visibility);
+    }
+    // Hidden panels skip the text rebuild; it catches up when shown
+    if !log.visible {
+        return;
+    }
+    let text = combat_log_text(&log);
+    for mut log_text in texts.iter_mut() {
+        log_text.0 = text.clone();
+    }
+}
+
+fn combat_log_text(log: &CombatLog) -> String {
+    let skip = log.lines.len().saturating_sub(COMBAT_LOG_VISIBLE_LINES);
+    let lines: Vec<&str> = log.lines.iter().skip(skip).map(String::as_str).collect();
+    if lines.is_empty() {
+        "Combat log".to_string()
+    } else {
+        lines.join("\n")
+    }
+}
+
+/// Show the first-run tutorial unless it was finished or skipped before
+pub fn setup_tutorial(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    settings: Res<Settings>,
+    mut tutorial: ResMut<Tutorial>,
+) {
+    if settings.tutorial.completed {
+        tutorial.step = TutorialStep::Done;
+        return;
+    }
+    *tutorial = Tutorial::default();
+
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(80.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            Pickable::IGNORE,
+            BelowTopBar { offset: 38.0 },
+            TutorialPanel,
+            GameUI,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    Node {
+                        flex_direction: FlexDirection::Row,
+                        align_items: AlignItems::Center,
+                        column_gap: Val::Px(12.0),
+                        max_width: Val::Px(560.0),
+                        padding: UiRect::all(Val::Px(10.0)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(0.05, 0.05, 0.15, 0.85)),
+                    BorderRadius::all(Val::Px(6.0)),
+                ))
+                .with_children(|panel| {
+                    panel.spawn((
+                        Text::new(tutorial.step.instructions()),
+                        TextFont {
+                            font_size: 16.0,
+                            ..default()
+                        },
+                        TextColor(Color::WHITE),
+                        TutorialText,
+                    ));
+                    spawn_nine_slice_button(
+                        panel,
+                        &asset_server,
+                        ButtonStyle::SmallRedSquare,
+                        "SKIP",
+                        SkipTutorialButton,
+                    );
+                });
+        });
+}
+
+/// Move to the next tutorial step once the player has done what the current
+/// one asks, measured by how far its progress measure grew since it started
+#[allow(clippy::too_many_arguments)]
+pub fn update_tutorial(
+    mut commands: Commands,
+    mut tutorial: ResMut<Tutorial>,
+    mut settings: ResMut<Settings>,
+    wheel_state: Res<TowerWheelState>,
+    fog: Res<FogOfWar>,
+    buildings: Query<&WorkerBuilding>,
+    tower_levels: Query<&TowerLevel>,
+    mut texts: Query<&mut Text, With<TutorialText>>,
+    panels: Query<Entity, With<TutorialPanel>>,
+) {
+    if tutorial.step == TutorialStep::Done {
+        return;
+    }
+
+    let progress = |step: TutorialStep| -> u32 {
+        match step {
+            TutorialStep::OpenWheel => wheel_state.active as u32,
+            TutorialStep::Explore => fog.explored.iter().flatten().filter(|explored| **explored).count() as u32,
+            TutorialStep::BuildWorker => buildings.iter().map(|b| b.worker_capacity.max(0) as u32).sum(),
+            TutorialStep::UpgradeTower => tower_levels
+                .iter()
+                .map(|l| (l.damage_level + l.range_level + l.fire_rate_level).max(0) as u32)
+                .sum(),
+            TutorialStep::Done => 0,
+        }
+    };
+    if progress(tutorial.step) <= tutorial.progress {
+        return;
+    }
+
+    let step = tutorial.step.next();
+    tutorial.step = step;
+    tutorial.progress = progress(step);
+    if step == TutorialStep::Done {
+        settings.tutorial.completed = true;
+        for entity in panels.iter() {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+    for mut text in texts.iter_mut() {
+        text.0 = step.instructions().to_string();
+    }
+}
+
+pub fn handle_skip_tutorial_button(
+    mut commands: Commands,
+    query: Query<&Interaction, (Changed<Interaction>, With<SkipTutorialButton>)>,
+    mut tutorial: ResMut<Tutorial>,
+    mut settings: ResMut<Settings>,
+    panels: Query<Entity, With<TutorialPanel>>,
+) {
+    for interaction in query.iter() {
+        if *interaction == Interaction::Pressed {
+            tutorial.step = TutorialStep::Done;
+            settings.tutorial.completed = true;
+            for entity in panels.iter() {
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+}
@@ -1,9 +1,19 @@
 use bevy::prelude::*;
+use bevy_spacetimedb::ReadUpdateMessage;
+use spacetimedb_sdk::Table;
 use crate::components::{get_defense_type_icon, AnimationTimer, DefenseType, Enemy};
-use crate::config::{UnitSpawn, UnitType, UnitsConfig, Wave, WavesConfig};
-use crate::resources::{AppState, EnemySpawner, GameState, PathWaypoints, WaveConfigs};
+use crate::config::{UnitSpawn, UnitType, UnitsConfig, Wave, WaveModifier, WavesConfig, BOUNTY_GOLD_MULTIPLIER};
+use crate::endless::generate_seeded_wave;
+use crate::resources::{AppState, EndlessMode, EnemySpawner, GameState, PathWaypoints, WaveConfigs};
 use crate::resources::AppState::InGame;
+use crate::module_bindings::request_start_wave_reducer::request_start_wave;
+use crate::module_bindings::withdraw_wave_vote_reducer::withdraw_wave_vote;
+use crate::module_bindings::{UserTableAccess, WaveStart, WaveVoteTableAccess};
+use crate::systems::menu::{spawn_nine_slice_button, ButtonStyle};
+use crate::systems::networking::SpacetimeDB;
+use crate::systems::top_bar::BelowTopBar;
 use crate::systems::AnimationInfo;
+use crate::user_settings::{GameplaySettings, Settings};
 // ============================================================================
 // Components
 // ============================================================================
@@ -16,6 +26,14 @@ pub struct WaveEnemyItem;
 
 #[derive(Component)]
 pub struct WaveTimerText;
+
+/// Button that votes to start the next wave now
+#[derive(Component)]
+pub struct WaveReadyButton;
+
+/// "2/3 ready" count of players who voted to start the wave
+#[derive(Component)]
+pub struct WaveVoteText;
 // ============================================================================
 // Resources
 // ============================================================================
@@ -29,9 +47,16 @@ pub struct WaveManager {
 
 impl Default for WaveManager {
     fn default() -> Self {
+        Self::from_settings(&GameplaySettings::default())
+    }
+}
+
+impl WaveManager {
+    /// Build phase before the first wave, with `settings`' prep times
+    pub fn from_settings(settings: &GameplaySettings) -> Self {
         Self {
-            preparation_time: 30.0, // 15 seconds between waves
-            current_prep_time: 15.0,
+            preparation_time: settings.wave_prep_secs.max(0.0),
+            current_prep_time: settings.first_wave_prep_secs.max(0.0),
             wave_active: false,
         }
     }
@@ -50,8 +75,10 @@ pub fn setup_wave_panel(mut commands: Commands) {
             left: Val::Px(20.0),
             flex_direction: FlexDirection::Column,
             width: Val::Px(280.0),
+            max_width: Val::Percent(40.0),
             ..default()
         },
+        BelowTopBar { offset: 0.0 },
         WavePanel,
     ));
 }
@@ -172,6 +199,29 @@ fn spawn_wave_banner(
                         TextColor(Color::srgb(0.4, 0.25, 0.1)), // Dark brown
                     ));
 
+                    if wave.bounty {
+                        content.spawn((
+                            Text::new(format!("Bounty Wave - x{} gold", BOUNTY_GOLD_MULTIPLIER)),
+                            TextFont {
+                                font_size: 20.0,
+                                ..default()
+                            },
+                            TextColor(Color::srgb(0.75, 0.55, 0.0)), // Gold
+                        ));
+                    }
+
+                    // Wave affixes, one line each
+                    for modifier in WaveModifier::ALL.into_iter().filter(|m| wave.modifiers.contains(m)) {
+                        content.spawn((
+                            Text::new(format!("{} - {}", modifier.label(), modifier.description())),
+                            TextFont {
+                                font_size: 18.0,
+                                ..default()
+                            },
+                            TextColor(Color::srgb(0.6, 0.1, 0.1)), // Dark red
+                        ));
+                    }
+
                     // Enemy list
                     for spawn in &wave.spawns {
                         if let Some(unit) = wave_configs
@@ -182,6 +232,34 @@ fn spawn_wave_banner(
                             spawn_enemy_row(content, unit, spawn, asset_server);
                         }
                     }
+
+                    if !wave_manager.wave_active {
+                        content.spawn((
+                            Text::new(prep_timer_text(wave_manager, wave.wave_number <= 1)),
+                            TextFont {
+                                font_size: 18.0,
+                                ..default()
+                            },
+                            TextColor(Color::srgb(0.4, 0.25, 0.1)),
+                            WaveTimerText,
+                        ));
+                        content.spawn((
+                            Text::new(""),
+                            TextFont {
+                                font_size: 16.0,
+                                ..default()
+                            },
+                            TextColor(Color::srgb(0.4, 0.25, 0.1)),
+                            WaveVoteText,
+                        ));
+                        spawn_nine_slice_button(
+                            content,
+                            asset_server,
+                            ButtonStyle::SmallBlueRound,
+                            "READY",
+                            WaveReadyButton,
+                        );
+                    }
                 });
         });
 }
@@ -280,22 +358,37 @@ fn spawn_enemy_row(
 /// Update the timer text every frame
 pub fn update_wave_timer(
     wave_manager: Res<WaveManager>,
+    game_state: Option<Res<GameState>>,
     mut timer_query: Query<&mut Text, With<WaveTimerText>>,
 ) {
     if wave_manager.wave_active {
         return;
     }
 
+    let first_wave = game_state.is_some_and(|game_state| game_state.wave <= 1);
     for mut text in timer_query.iter_mut() {
-        text.0 = format!("Time: {}s", wave_manager.current_prep_time.ceil() as i32);
+        text.0 = prep_timer_text(&wave_manager, first_wave);
     }
 }
 
-/// Countdown the wave timer and start wave automatically
+/// Countdown shown under the next wave while the players build
+fn prep_timer_text(wave_manager: &WaveManager, first_wave: bool) -> String {
+    let seconds = wave_manager.current_prep_time.max(0.0).ceil() as i32;
+    if first_wave {
+        format!("Build time: {}s", seconds)
+    } else {
+        format!("Next wave in: {}s", seconds)
+    }
+}
+
+/// Countdown the wave timer and start wave automatically. Online, our vote
+/// for this wave is withdrawn so it doesn't count towards the next one.
 pub fn countdown_wave_timer(
     time: Res<Time>,
     mut wave_manager: ResMut<WaveManager>,
+    mut game_state: ResMut<GameState>,
     spawner: Option<ResMut<EnemySpawner>>,
+    stdb: Option<SpacetimeDB>,
 ) {
     if wave_manager.wave_active {
         return;
@@ -308,17 +401,87 @@ pub fn countdown_wave_timer(
     wave_manager.current_prep_time -= time.delta_secs();
 
     if wave_manager.current_prep_time <= 0.0 {
-        start_wave(&mut wave_manager, &mut spawner);
+        start_wave(&mut wave_manager, &mut spawner, &mut game_state);
+        if let Some(stdb) = stdb
+            && let Err(e) = stdb.reducers().withdraw_wave_vote()
+        {
+            error!("Failed to withdraw wave vote: {}", e);
+        }
     }
 }
 
 /// Start the wave (called by timer or button)
-fn start_wave(wave_manager: &mut WaveManager, spawner: &mut EnemySpawner) {
+fn start_wave(wave_manager: &mut WaveManager, spawner: &mut EnemySpawner, game_state: &mut GameState) {
     wave_manager.wave_active = true;
+    game_state.leaks_this_wave = 0;
     wave_manager.current_prep_time = 0.0;
     spawner.timer.reset(); // Start spawning immediately
 }
 
+/// Vote to start the wave; in single player it starts right away
+pub fn handle_wave_ready_button(
+    query: Query<&Interaction, (Changed<Interaction>, With<WaveReadyButton>)>,
+    stdb: Option<SpacetimeDB>,
+    mut wave_manager: ResMut<WaveManager>,
+    mut game_state: ResMut<GameState>,
+    spawner: Option<ResMut<EnemySpawner>>,
+) {
+    if wave_manager.wave_active || !query.iter().any(|i| *i == Interaction::Pressed) {
+        return;
+    }
+
+    if let Some(stdb) = stdb {
+        if let Err(e) = stdb.reducers().request_start_wave() {
+            error!("Failed to vote for wave start: {}", e);
+        }
+    } else if let Some(mut spawner) = spawner {
+        start_wave(&mut wave_manager, &mut spawner, &mut game_state);
+    }
+}
+
+/// Show how many players in the game are ready for the next wave
+pub fn update_wave_vote_text(stdb: Option<SpacetimeDB>, mut texts: Query<&mut Text, With<WaveVoteText>>) {
+    let Some(stdb) = stdb else { return };
+
+    let playing: Vec<_> = stdb
+        .db()
+        .user()
+        .iter()
+        .filter(|u| u.online && u.in_game)
+        .map(|u| u.identity)
+        .collect();
+    let ready = stdb
+        .db()
+        .wave_vote()
+        .iter()
+        .filter(|vote| playing.contains(&vote.voter))
+        .count();
+    for mut text in texts.iter_mut() {
+        text.0 = format!("{}/{} ready", ready, playing.len());
+    }
+}
+
+/// Start the wave when the server reports that every player voted. Only a
+/// bumped count does: the row also arrives as an insert whenever the
+/// subscription is applied, which must not start a wave on joining or
+/// reconnecting.
+pub fn on_wave_start_voted(
+    updated: Option<ReadUpdateMessage<WaveStart>>,
+    mut wave_manager: ResMut<WaveManager>,
+    mut game_state: ResMut<GameState>,
+    spawner: Option<ResMut<EnemySpawner>>,
+) {
+    let passed = updated.map_or(0, |mut messages| {
+        messages.read().filter(|msg| msg.new.count != msg.old.count).count()
+    });
+    if passed == 0 || wave_manager.wave_active {
+        return;
+    }
+    if let Some(mut spawner) = spawner {
+        start_wave(&mut wave_manager, &mut spawner, &mut game_state);
+    }
+}
+
 /// Reset wave manager when wave completes (called from spawn_enemies)
 pub fn check_wave_completion(
     spawner: Option<Res<EnemySpawner>>,
@@ -339,6 +502,50 @@ pub fn check_wave_completion(
     }
 }
 
+/// Every game opens with a build phase, the first wave waiting for the
+/// first-wave prep time or the READY button
+pub fn start_prep_phase(settings: Res<Settings>, mut wave_manager: ResMut<WaveManager>) {
+    *wave_manager = WaveManager::from_settings(&settings.gameplay);
+}
+
+/// Swap the authored waves for generated ones when an endless run starts
+pub fn start_endless_run(
+    endless: Res<EndlessMode>,
+    game_state: Res<GameState>,
+    mut wave_configs: ResMut<WaveConfigs>,
+    mut spawner: ResMut<EnemySpawner>,
+) {
+    if !endless.enabled {
+        return;
+    }
+    info!("Starting endless mode with seed {}", endless.seed);
+    let wave_configs = &mut *wave_configs;
+    wave_configs.waves = (1..=game_state.wave.max(1))
+        .map(|wave_number| generate_seeded_wave(endless.seed, wave_number, &wave_configs.units))
+        .collect();
+    if let Some(wave) = wave_configs.waves.last() {
+        *spawner = EnemySpawner::from_wave_config(wave);
+    }
+}
+
+/// Keep one generated wave past the current one, so the spawner and the
+/// wave preview always find the next wave in endless mode
+pub fn extend_endless_waves(
+    endless: Res<EndlessMode>,
+    game_state: Res<GameState>,
+    mut wave_configs: ResMut<WaveConfigs>,
+) {
+    if !endless.enabled {
+        return;
+    }
+    let wanted = game_state.wave.max(0) as usize + 1;
+    while wave_configs.waves.len() < wanted {
+        let wave_number = wave_configs.waves.len() as i32 + 1;
+        let wave = generate_seeded_wave(endless.seed, wave_number, &wave_configs.units);
+        wave_configs.waves.push(wave);
+    }
+}
+
 
 // ============================================================================
 // Plugin
@@ -351,11 +558,17 @@ impl Plugin for WaveManagerPlugin {
         app
             .init_resource::<WaveManager>()
             .add_systems(Startup, setup_wave_panel)
+            .add_systems(OnEnter(AppState::InGame), (start_prep_phase, start_endless_run))
             .add_systems(Update, (
                 update_wave_panel,
                 update_wave_timer,
-                countdown_wave_timer,
+                // Not on the menus, or the first build phase runs out there
+                countdown_wave_timer.run_if(in_state(AppState::InGame)),
                 check_wave_completion,
+                handle_wave_ready_button,
+                update_wave_vote_text,
+                on_wave_start_voted,
+                extend_endless_waves.run_if(in_state(AppState::InGame)),
             ));
     }
 }
\ No newline at end of file
@@ -0,0 +1,166 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::components::{GameUI, RainDrop, WeatherFog};
+use crate::constants::OFFLINE_WEATHER_INTERVAL;
+use crate::module_bindings::weather_table::WeatherTableAccess;
+use crate::module_bindings::WeatherKind as ServerWeatherKind;
+use crate::resources::{AppState, Weather, WeatherKind};
+use crate::systems::networking::SpacetimeDB;
+
+/// Row id of the server's single `weather` row
+const WEATHER_ID: u32 = 0;
+const RAIN_DROPS_PER_SECOND: f32 = 60.0;
+const RAIN_COLOR: Color = Color::srgba(0.7, 0.8, 1.0, 0.45);
+/// Fog overlay opacity at full density
+const FOG_ALPHA: f32 = 0.45;
+/// Fog density change per second when fog rolls in or lifts
+const FOG_FADE_SPEED: f32 = 0.25;
+
+pub struct WeatherPlugin;
+
+impl Plugin for WeatherPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Weather>()
+            .add_systems(OnEnter(AppState::InGame), setup_weather_overlay)
+            .add_systems(
+                Update,
+                (sync_weather, (update_rain, update_weather_fog))
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(OnExit(AppState::InGame), clear_weather);
+    }
+}
+
+fn setup_weather_overlay(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            ..default()
+        },
+        BackgroundColor(Color::NONE),
+        GlobalZIndex(47),
+        Pickable::IGNORE,
+        WeatherFog,
+        GameUI,
+    ));
+}
+
+/// Follow the server's weather, or roll it locally every
+/// `OFFLINE_WEATHER_INTERVAL` with the same odds when offline
+fn sync_weather(
+    time: Res<Time>,
+    stdb: Option<SpacetimeDB>,
+    mut weather: ResMut<Weather>,
+    mut offline_timer: Local<f32>,
+) {
+    let server_weather = stdb.and_then(|stdb| stdb.db().weather().id().find(&WEATHER_ID));
+    let kind = match server_weather {
+        Some(row) => match row.kind {
+            ServerWeatherKind::Clear => WeatherKind::Clear,
+            ServerWeatherKind::Rain => WeatherKind::Rain,
+            ServerWeatherKind::Fog => WeatherKind::Fog,
+        },
+        None => {
+            *offline_timer += time.delta_secs();
+            if *offline_timer < OFFLINE_WEATHER_INTERVAL {
+                return;
+            }
+            *offline_timer = 0.0;
+            match rand::thread_rng().gen_range(0..100) {
+                0..60 => WeatherKind::Clear,
+                60..85 => WeatherKind::Rain,
+                _ => WeatherKind::Fog,
+            }
+        }
+    };
+
+    if weather.kind != kind {
+        info!("Weather changes to {:?}", kind);
+        weather.kind = kind;
+    }
+}
+
+/// Spawn streaks while it rains and let them fall off the bottom of the
+/// screen; drops already falling finish when the rain stops
+fn update_rain(
+    mut commands: Commands,
+    time: Res<Time>,
+    weather: Res<Weather>,
+    mut spawn_budget: Local<f32>,
+    mut drops: Query<(Entity, &mut RainDrop, &mut Node)>,
+) {
+    let dt = time.delta_secs();
+    for (entity, mut drop, mut node) in drops.iter_mut() {
+        drop.y += drop.speed * dt;
+        // Slight slant, as if blown by the wind
+        drop.x -= drop.speed * 0.15 * dt;
+        if drop.y > 105.0 {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        node.top = Val::Percent(drop.y);
+        node.left = Val::Percent(drop.x);
+    }
+
+    if weather.kind != WeatherKind::Rain {
+        *spawn_budget = 0.0;
+        return;
+    }
+    *spawn_budget += RAIN_DROPS_PER_SECOND * dt;
+    let mut rng = rand::thread_rng();
+    while *spawn_budget >= 1.0 {
+        *spawn_budget -= 1.0;
+        let x = rng.gen_range(0.0..115.0);
+        let y = rng.gen_range(-10.0..0.0);
+        commands.spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(x),
+                top: Val::Percent(y),
+                width: Val::Px(2.0),
+                height: Val::Px(rng.gen_range(10.0..18.0)),
+                ..default()
+            },
+            BackgroundColor(RAIN_COLOR),
+            GlobalZIndex(46),
+            Pickable::IGNORE,
+            RainDrop {
+                x,
+                y,
+                speed: rng.gen_range(80.0..120.0),
+            },
+            GameUI,
+        ));
+    }
+}
+
+/// Ease the fog overlay in and out
+fn update_weather_fog(
+    time: Res<Time>,
+    mut weather: ResMut<Weather>,
+    mut overlays: Query<&mut BackgroundColor, With<WeatherFog>>,
+) {
+    let target = if weather.kind == WeatherKind::Fog { 1.0 } else { 0.0 };
+    let step = FOG_FADE_SPEED * time.delta_secs();
+    let density = weather.fog_density + (target - weather.fog_density).clamp(-step, step);
+    if density == weather.fog_density {
+        return;
+    }
+    weather.fog_density = density;
+
+    let tint = Color::srgba(0.75, 0.78, 0.8, FOG_ALPHA * density);
+    for mut background in overlays.iter_mut() {
+        background.0 = tint;
+    }
+}
+
+fn clear_weather(mut commands: Commands, mut weather: ResMut<Weather>, drops: Query<Entity, With<RainDrop>>) {
+    weather.fog_density = 0.0;
+    for entity in drops.iter() {
+        commands.entity(entity).despawn();
+    }
+}
@@ -4,14 +4,18 @@ use bevy_spacetimedb::*;
 use spacetimedb_sdk::Table;
 
 use crate::components::{
-    AnimationTimer, BuildWorkerOption, Depleted, HarvestTimer, HouseMenu, ResourceNode,
-    ResourceType, Worker, WorkerBuilding, WorkerState, WorkerTarget,
+    AnimationTimer, BuildWorkerOption, CarryLabel, ClearRallyButton, Depleted, Enemy, HarvestTimer, HouseMenu,
+    RallyFlag, ResourceNode, ResourceType, SetRallyButton, UpgradeCarryOption, UpgradeHouseOption, Worker, WorkerBuilding,
+    WorkerCapButton, WorkerCapText, WorkerState, WorkerTarget,
 };
 use crate::constants::SCALED_TILE_SIZE;
 use crate::map::tile_to_world;
 use crate::module_bindings::{Color as PlayerColor, DbConnection, MyUserTableAccess};
-use crate::resources::{GameState, HouseMenuState, RecruitMenuState, TowerUpgradeMenuState, TowerWheelState};
+use crate::resources::{GameRules, GameState, HouseMenuState, InfiniteResources, RallyPoint, RecruitMenuState, TowerMoveState, TowerUpgradeMenuState, TowerWheelState, WorkerAllocation};
 use crate::systems::AnimationInfo;
+use crate::systems::stats::GameStats;
+use crate::systems::input::{ActionState, InputAction};
+use crate::systems::ui::spawn_floating_text;
 
 /// Type alias for cleaner SpacetimeDB resource access
 pub type SpacetimeDB<'a> = Res<'a, StdbConnection<DbConnection>>;
@@ -20,10 +24,62 @@ const PAWN_FRAME_SIZE: UVec2 = UVec2::new(192, 192);
 const WORKER_SPEED: f32 = 30.0;
 const HARVEST_TIME: f32 = 5.0;
 const ARRIVAL_DISTANCE: f32 = 16.0;
+/// Enemies closer than this send a worker running home
+const WORKER_THREAT_RADIUS: f32 = 80.0;
+/// Enemies closer than this kill a worker
+const WORKER_KILL_DISTANCE: f32 = 14.0;
+/// Fleeing workers run this much faster than they walk
+const FLEE_SPEED_MULTIPLIER: f32 = 1.6;
+pub const RALLY_FLAG_SPRITE: &str = "Decorations/Buildings/Blue Buildings/Test/Flag.png";
+/// Carry capacity the house menu can raise workers to
+const MAX_CARRY_CAPACITY: i32 = 5;
+/// Gold per unit of the current capacity for the next carry upgrade
+const CARRY_UPGRADE_COST_PER_UNIT: i32 = 40;
 
 // Asset sizes for proper scaling
 const HOUSE_SIZE: Vec2 = Vec2::new(128.0, 128.0);
 
+/// One tier of the worker house
+pub struct HouseTier {
+    /// Seconds between free workers
+    pub spawn_interval: f32,
+    /// Free workers the house trains on its own, on top of bought ones
+    pub worker_capacity: i32,
+    /// Cost to reach this tier from the one below
+    pub gold_cost: i32,
+    pub wood_cost: i32,
+    /// House sprite in the player's color folder
+    pub sprite: &'static str,
+}
+
+pub const HOUSE_TIERS: [HouseTier; 3] = [
+    HouseTier {
+        spawn_interval: 3.0,
+        worker_capacity: 1,
+        gold_cost: 0,
+        wood_cost: 0,
+        sprite: "House1.png",
+    },
+    HouseTier {
+        spawn_interval: 2.0,
+        worker_capacity: 2,
+        gold_cost: 100,
+        wood_cost: 20,
+        sprite: "House2.png",
+    },
+    HouseTier {
+        spawn_interval: 1.0,
+        worker_capacity: 4,
+        gold_cost: 250,
+        wood_cost: 50,
+        sprite: "House3.png",
+    },
+];
+
+pub fn house_sprite_path(color_dir: &str, level: usize) -> String {
+    format!("Decorations/Buildings/{} Buildings/{}", color_dir, HOUSE_TIERS[level - 1].sprite)
+}
+
 /// Get the color directory name for asset paths
 fn get_color_dir(color: PlayerColor) -> &'static str {
     match color {
@@ -53,7 +109,7 @@ pub fn setup_resource_gathering(
 
     // Spawn worker building (House1) close to castle (castle is at ~tile 27, 10)
     let building_pos = tile_to_world(27, 6);
-    let building_path = format!("Decorations/Buildings/{} Buildings/House1.png", color_dir);
+    let building_path = house_sprite_path(color_dir, 1);
     // Scale house like towers: use min of x/y scale to fit in 1 tile
     let house_scale_x = SCALED_TILE_SIZE / HOUSE_SIZE.x;
     let house_scale_y = SCALED_TILE_SIZE / HOUSE_SIZE.y;
@@ -64,9 +120,11 @@ pub fn setup_resource_gathering(
             Transform::from_xyz(building_pos.x, building_pos.y, 1.0)
                 .with_scale(Vec3::splat(house_scale)),
             WorkerBuilding {
-                spawn_timer: Timer::from_seconds(3.0, TimerMode::Repeating),
-                worker_capacity: 1,
+                spawn_timer: Timer::from_seconds(HOUSE_TIERS[0].spawn_interval, TimerMode::Repeating),
+                worker_capacity: HOUSE_TIERS[0].worker_capacity,
                 current_workers: 0,
+                carry_capacity: 1,
+                building_level: 1,
             },
         ))
         .id();
@@ -177,11 +235,80 @@ pub fn setup_resource_gathering(
     }
 }
 
+fn spawn_worker(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    color_dir: &str,
+    home_building: Entity,
+    position: Vec2,
+    carry_capacity: i32,
+    rally: Option<Vec2>,
+) {
+    // Load pawn idle sprite sheet with dynamic color
+    let texture_path = format!("Units/{} Units/Pawn/Pawn_Idle.png", color_dir);
+    let texture = asset_server.load(&texture_path);
+    let layout = TextureAtlasLayout::from_grid(PAWN_FRAME_SIZE, 8, 1, None, None);
+    let texture_atlas_layout = asset_server.add(layout);
+
+    // Scale pawns same as enemies: SCALED_TILE_SIZE / frame_size
+    let pawn_scale = SCALED_TILE_SIZE / PAWN_FRAME_SIZE.x as f32;
+
+    let mut worker = commands.spawn((
+        Sprite::from_atlas_image(
+            texture,
+            TextureAtlas {
+                layout: texture_atlas_layout,
+                index: 0,
+            },
+        ),
+        Transform::from_xyz(position.x, position.y, 2.0).with_scale(Vec3::splat(pawn_scale)),
+        Worker {
+            speed: WORKER_SPEED,
+            home_building,
+            current_resource: None,
+            carry_capacity,
+            carrying: 0,
+        },
+        WorkerState::Idle,
+        AnimationTimer {
+            timer: Timer::from_seconds(0.15, TimerMode::Repeating),
+        },
+        AnimationInfo {
+            frame_count: 6,
+        },
+    ));
+    worker.with_children(|parent| {
+        // Counter-scaled so the text size is in world pixels
+        parent.spawn((
+            Text2d::new(""),
+            TextFont {
+                font_size: 10.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+            Transform::from_xyz(0.0, PAWN_FRAME_SIZE.y as f32 * 0.3, 1.0)
+                .with_scale(Vec3::splat(1.0 / pawn_scale)),
+            Visibility::Hidden,
+            CarryLabel,
+        ));
+    });
+    if let Some(rally) = rally {
+        worker.insert((
+            WorkerTarget {
+                target_entity: None,
+                target_position: rally,
+            },
+            WorkerState::MovingToRally,
+        ));
+    }
+}
+
 /// Spawn workers from building
 pub fn spawn_workers(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     time: Res<Time>,
+    rally: Res<RallyPoint>,
     mut buildings: Query<(Entity, &mut WorkerBuilding, &Transform)>,
     stdb: Option<SpacetimeDB>,
 ) {
@@ -193,73 +320,57 @@ pub fn spawn_workers(
 
         if building.spawn_timer.just_finished() && building.current_workers < building.worker_capacity {
             let spawn_pos = building_transform.translation.truncate();
-
-            // Load pawn idle sprite sheet with dynamic color
-            let texture_path = format!("Units/{} Units/Pawn/Pawn_Idle.png", color_dir);
-            let texture = asset_server.load(&texture_path);
-            let layout = TextureAtlasLayout::from_grid(PAWN_FRAME_SIZE, 8, 1, None, None);
-            let texture_atlas_layout = asset_server.add(layout);
-
-            // Scale pawns same as enemies: SCALED_TILE_SIZE / frame_size
-            let pawn_scale = SCALED_TILE_SIZE / PAWN_FRAME_SIZE.x as f32;
-
-            commands.spawn((
-                Sprite::from_atlas_image(
-                    texture,
-                    TextureAtlas {
-                        layout: texture_atlas_layout,
-                        index: 0,
-                    },
-                ),
-                Transform::from_xyz(spawn_pos.x, spawn_pos.y, 2.0)
-                    .with_scale(Vec3::splat(pawn_scale)),
-                Worker {
-                    speed: WORKER_SPEED,
-                    home_building: building_entity,
-                    current_resource: None,
-                },
-                WorkerState::Idle,
-                AnimationTimer {
-                    timer: Timer::from_seconds(0.15, TimerMode::Repeating),
-                },
-                AnimationInfo {
-                    frame_count: 6,
-                },
-            ));
+            spawn_worker(
+                &mut commands,
+                &asset_server,
+                color_dir,
+                building_entity,
+                spawn_pos,
+                building.carry_capacity,
+                rally.position,
+            );
 
             building.current_workers += 1;
         }
     }
 }
 
-/// Assign idle workers to nearby resource nodes
+/// Assign idle workers to the nearest resource node whose resource hasn't
+/// reached its worker cap. Workers stay idle when every open resource is used up.
 pub fn worker_find_resource(
     mut commands: Commands,
-    mut workers: Query<(Entity, &Transform, &mut Worker), (With<WorkerState>, Without<WorkerTarget>)>,
-    worker_states: Query<&WorkerState>,
+    allocation: Res<WorkerAllocation>,
+    mut workers: Query<(Entity, &Transform, &mut Worker, &WorkerState, Has<WorkerTarget>)>,
     resources: Query<(Entity, &Transform, &ResourceNode), Without<Depleted>>,
 ) {
-    for (worker_entity, worker_transform, mut worker) in workers.iter_mut() {
-        let state = worker_states.get(worker_entity).unwrap();
-        if *state != WorkerState::Idle {
+    let mut assigned: Vec<ResourceType> = workers
+        .iter()
+        .filter_map(|(_, _, worker, _, _)| worker.current_resource)
+        .collect();
+
+    for (worker_entity, worker_transform, mut worker, state, has_target) in workers.iter_mut() {
+        if *state != WorkerState::Idle || has_target {
             continue;
         }
 
-        // Find nearest undepleted resource
+        // Find nearest undepleted resource with room for another worker
         let worker_pos = worker_transform.translation.truncate();
-        let mut nearest: Option<(Entity, Vec2, f32, ResourceType)> = None;
-
-        for (res_entity, res_transform, resource) in resources.iter() {
-            let res_pos = res_transform.translation.truncate();
-            let dist = worker_pos.distance(res_pos);
-
-            if nearest.is_none() || dist < nearest.unwrap().2 {
-                nearest = Some((res_entity, res_pos, dist, resource.resource_type));
-            }
-        }
+        let has_room = |resource_type: ResourceType| {
+            let working = assigned.iter().filter(|t| **t == resource_type).count() as i32;
+            allocation.cap(resource_type).is_none_or(|cap| working < cap)
+        };
+        let nearest = resources
+            .iter()
+            .filter(|(_, _, resource)| has_room(resource.resource_type))
+            .map(|(res_entity, res_transform, resource)| {
+                let res_pos = res_transform.translation.truncate();
+                (res_entity, res_pos, worker_pos.distance(res_pos), resource.resource_type)
+            })
+            .min_by(|a, b| a.2.total_cmp(&b.2));
 
         if let Some((target_entity, target_pos, _, resource_type)) = nearest {
             worker.current_resource = Some(resource_type);
+            assigned.push(resource_type);
             commands.entity(worker_entity).insert((
                 WorkerTarget {
                     target_entity: Some(target_entity),
@@ -271,13 +382,110 @@ pub fn worker_find_resource(
     }
 }
 
+/// When a cap is lowered, send workers still walking to a resource over its
+/// cap back to idle so they are reassigned. Workers already harvesting or
+/// carrying a load finish their trip first.
+pub fn enforce_worker_caps(
+    mut commands: Commands,
+    allocation: Res<WorkerAllocation>,
+    mut workers: Query<(Entity, &mut Worker, &mut WorkerState)>,
+) {
+    if !allocation.is_changed() {
+        return;
+    }
+    for resource_type in ResourceType::ALL {
+        let Some(cap) = allocation.cap(resource_type) else { continue };
+        let mut excess = workers
+            .iter()
+            .filter(|(_, worker, _)| worker.current_resource == Some(resource_type))
+            .count() as i32
+            - cap;
+
+        for (entity, mut worker, mut state) in workers.iter_mut() {
+            if excess <= 0 {
+                break;
+            }
+            if worker.current_resource != Some(resource_type) || *state != WorkerState::MovingToResource {
+                continue;
+            }
+            worker.current_resource = None;
+            *state = WorkerState::Idle;
+            commands.entity(entity).remove::<WorkerTarget>();
+            excess -= 1;
+        }
+    }
+}
+
+/// With `GameRules::worker_danger`, enemies kill workers they come close to
+/// and workers that see one coming drop what they carry and run home. A
+/// killed worker frees its place in the house so a new one can be trained.
+pub fn worker_danger(
+    mut commands: Commands,
+    rules: Res<GameRules>,
+    enemies: Query<&Transform, With<Enemy>>,
+    mut workers: Query<(Entity, &Transform, &mut Worker, &mut WorkerState)>,
+    mut buildings: Query<(&Transform, &mut WorkerBuilding)>,
+) {
+    if !rules.worker_danger || enemies.is_empty() {
+        return;
+    }
+
+    for (worker_entity, worker_transform, mut worker, mut state) in workers.iter_mut() {
+        let worker_pos = worker_transform.translation.truncate();
+        let Some(nearest) = enemies
+            .iter()
+            .map(|enemy| enemy.translation.truncate().distance(worker_pos))
+            .min_by(f32::total_cmp)
+        else {
+            continue;
+        };
+
+        if nearest < WORKER_KILL_DISTANCE {
+            if let Ok((_, mut building)) = buildings.get_mut(worker.home_building) {
+                building.current_workers = (building.current_workers - 1).max(0);
+            }
+            commands.queue_silenced(move |world: &mut World| {
+                if let Ok(entity_mut) = world.get_entity_mut(worker_entity) {
+                    entity_mut.despawn();
+                }
+            });
+            spawn_floating_text(&mut commands, worker_pos, "Worker lost!".to_string(), Color::srgb(1.0, 0.3, 0.3));
+            continue;
+        }
+
+        if nearest >= WORKER_THREAT_RADIUS || *state == WorkerState::Fleeing {
+            continue;
+        }
+        let Ok((home_transform, _)) = buildings.get(worker.home_building) else { continue };
+        let home = home_transform.translation.truncate();
+        // Already home; nothing to run to
+        if worker_pos.distance(home) < ARRIVAL_DISTANCE {
+            continue;
+        }
+
+        worker.current_resource = None;
+        worker.carrying = 0;
+        *state = WorkerState::Fleeing;
+        commands.entity(worker_entity).remove::<HarvestTimer>().insert(WorkerTarget {
+            target_entity: Some(worker.home_building),
+            target_position: home,
+        });
+    }
+}
+
 /// Move workers toward their target
 pub fn worker_movement(
     time: Res<Time>,
     mut workers: Query<(&Worker, &WorkerState, &WorkerTarget, &mut Transform, &mut Sprite)>,
 ) {
     for (worker, state, target, mut transform, mut sprite) in workers.iter_mut() {
-        if *state != WorkerState::MovingToResource && *state != WorkerState::ReturningWithResource {
+        if !matches!(
+            *state,
+            WorkerState::MovingToRally
+                | WorkerState::MovingToResource
+                | WorkerState::ReturningWithResource
+                | WorkerState::Fleeing
+        ) {
             continue;
         }
 
@@ -285,7 +493,12 @@ pub fn worker_movement(
         let direction = (target.target_position - current_pos).normalize_or_zero();
 
         // Move toward target
-        let movement = direction * worker.speed * time.delta_secs();
+        let speed = if *state == WorkerState::Fleeing {
+            worker.speed * FLEE_SPEED_MULTIPLIER
+        } else {
+            worker.speed
+        };
+        let movement = direction * speed * time.delta_secs();
         transform.translation.x += movement.x;
         transform.translation.y += movement.y;
 
@@ -300,6 +513,8 @@ pub fn worker_movement(
 pub fn worker_arrive_check(
     mut commands: Commands,
     mut game_state: ResMut<GameState>,
+    mut stats: ResMut<GameStats>,
+    rules: Res<GameRules>,
     buildings: Query<&Transform, With<WorkerBuilding>>,
     mut workers: Query<(Entity, &Transform, &mut WorkerState, &WorkerTarget, &mut Worker)>,
 ) {
@@ -318,6 +533,14 @@ pub fn worker_arrive_check(
                         .insert(HarvestTimer(Timer::from_seconds(HARVEST_TIME, TimerMode::Once)));
                 }
             }
+            // At the rally point; from here it looks for work as usual.
+            // Fled workers wait at home the same way until they are sent out again.
+            WorkerState::MovingToRally | WorkerState::Fleeing
+                if worker_pos.distance(target.target_position) < ARRIVAL_DISTANCE =>
+            {
+                commands.entity(worker_entity).remove::<WorkerTarget>();
+                *state = WorkerState::Idle;
+            }
             WorkerState::ReturningWithResource => {
                 // Check distance to the ACTUAL building, not target.target_position
                 // (target might not be updated yet due to deferred commands)
@@ -326,14 +549,39 @@ pub fn worker_arrive_check(
                     let dist_to_building = worker_pos.distance(building_pos);
 
                     if dist_to_building < ARRIVAL_DISTANCE {
-                        // Deposit resource and go idle
-                        match worker.current_resource {
-                            Some(ResourceType::Wood) => game_state.wood += 1,
-                            Some(ResourceType::Gold) => game_state.gold += 5,
-                            Some(ResourceType::Meat) => game_state.meat += 1,
-                            None => {}
+                        // Deposit resource and go idle; what doesn't fit in storage is lost
+                        if let Some(resource) = worker.current_resource {
+                            let (stock, amount) = match resource {
+                                ResourceType::Wood => (game_state.wood, worker.carrying),
+                                ResourceType::Gold => (game_state.gold, 5 * worker.carrying),
+                                ResourceType::Meat => (game_state.meat, worker.carrying),
+                            };
+                            let stored = match rules.storage_cap(resource) {
+                                Some(cap) => amount.min(cap - stock).max(0),
+                                None => amount,
+                            };
+                            if stored < amount {
+                                spawn_floating_text(
+                                    &mut commands,
+                                    building_pos,
+                                    format!("{} storage full!", resource.label()),
+                                    Color::srgb(1.0, 0.5, 0.2),
+                                );
+                            }
+                            match resource {
+                                ResourceType::Wood => {
+                                    game_state.wood += stored;
+                                    stats.gather_wood(stored as u32);
+                                }
+                                ResourceType::Gold => game_state.earn_gold(stored),
+                                ResourceType::Meat => {
+                                    game_state.meat += stored;
+                                    stats.gather_meat(stored as u32);
+                                }
+                            }
                         }
                         worker.current_resource = None;
+                        worker.carrying = 0;
                         commands.entity(worker_entity).remove::<WorkerTarget>();
                         *state = WorkerState::Idle;
                     }
@@ -355,11 +603,11 @@ pub fn worker_harvest(
         &mut WorkerState,
         &mut HarvestTimer,
         &WorkerTarget,
-        &Worker,
+        &mut Worker,
     )>,
     mut resources: Query<(Entity, &mut ResourceNode, &mut Sprite)>,
 ) {
-    for (worker_entity, mut state, mut harvest_timer, target, worker) in workers.iter_mut() {
+    for (worker_entity, mut state, mut harvest_timer, target, mut worker) in workers.iter_mut() {
         if *state != WorkerState::Harvesting {
             continue;
         }
@@ -373,6 +621,13 @@ pub fn worker_harvest(
                     resources.get_mut(target_entity)
                 {
                     resource.remaining -= 1;
+                    worker.carrying += 1;
+
+                    // Keep gathering from the same node until the load is full
+                    if resource.remaining > 0 && worker.carrying < worker.carry_capacity {
+                        harvest_timer.0.reset();
+                        continue;
+                    }
 
                     if resource.remaining <= 0 {
                         // Resource depleted - handle based on type
@@ -429,6 +684,7 @@ pub fn worker_sprite_update(
         // Returns (sprite_name, frame_count)
         let (sprite_name, frame_count) = match (state, worker.current_resource) {
             (WorkerState::Idle, _) => ("Pawn_Idle.png", 8),
+            (WorkerState::MovingToRally | WorkerState::Fleeing, _) => ("Pawn_Run.png", 8),
             // Moving to resource - use tool based on resource type
             (WorkerState::MovingToResource, Some(ResourceType::Wood)) => ("Pawn_Run Axe.png", 6),
             (WorkerState::MovingToResource, Some(ResourceType::Gold)) => ("Pawn_Run Pickaxe.png", 6),
@@ -461,6 +717,22 @@ pub fn worker_sprite_update(
     }
 }
 
+/// Show "xN" over workers heading home with more than one unit
+pub fn update_carry_labels(
+    workers: Query<(&WorkerState, &Worker, &Children), Changed<WorkerState>>,
+    mut labels: Query<(&mut Text2d, &mut Visibility), With<CarryLabel>>,
+) {
+    for (state, worker, children) in workers.iter() {
+        let show = *state == WorkerState::ReturningWithResource && worker.carrying > 1;
+        for child in children.iter() {
+            if let Ok((mut text, mut visibility)) = labels.get_mut(child) {
+                text.0 = format!("x{}", worker.carrying);
+                *visibility = if show { Visibility::Inherited } else { Visibility::Hidden };
+            }
+        }
+    }
+}
+
 // Separate system to animate frames
 pub fn animate_worker_sprites(
     time: Res<Time>,
@@ -481,36 +753,81 @@ pub fn animate_worker_sprites(
 
 const WORKER_GOLD_COST: i32 = 50;
 
+fn spawn_worker_cap_button(row: &mut ChildSpawnerCommands, resource: ResourceType, delta: i32) {
+    row.spawn((
+        Node {
+            width: Val::Px(22.0),
+            height: Val::Px(22.0),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.15, 0.4, 0.3, 1.0)),
+        BorderRadius::all(Val::Px(4.0)),
+        WorkerCapButton { resource, delta },
+        Button,
+    ))
+    .with_children(|button: &mut ChildSpawnerCommands| {
+        button.spawn((
+            Text::new(if delta < 0 { "-" } else { "+" }),
+            TextFont { font_size: 14.0, ..default() },
+            TextColor(Color::WHITE),
+        ));
+    });
+}
+
+fn spawn_rally_button(row: &mut ChildSpawnerCommands, label: &str, marker: impl Component) {
+    row.spawn((
+        Node {
+            padding: UiRect::axes(Val::Px(12.0), Val::Px(6.0)),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.15, 0.4, 0.3, 1.0)),
+        BorderRadius::all(Val::Px(4.0)),
+        marker,
+        Button,
+    ))
+    .with_children(|button: &mut ChildSpawnerCommands| {
+        button.spawn((
+            Text::new(label),
+            TextFont { font_size: 12.0, ..default() },
+            TextColor(Color::WHITE),
+        ));
+    });
+}
+
+/// Gold for raising the carry capacity from `capacity`
+fn carry_upgrade_cost(capacity: i32) -> i32 {
+    CARRY_UPGRADE_COST_PER_UNIT * capacity
+}
+
 /// Show house menu when clicking on the worker building
 pub fn show_house_menu(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
-    mouse_button: Res<ButtonInput<MouseButton>>,
-    windows: Query<&Window>,
-    camera: Query<(&Camera, &GlobalTransform)>,
+    actions: Res<ActionState>,
     mut house_menu_state: ResMut<HouseMenuState>,
     recruit_menu_state: Res<RecruitMenuState>,
     tower_wheel_state: Res<TowerWheelState>,
     upgrade_menu_state: Res<TowerUpgradeMenuState>,
-    buildings: Query<&Transform, With<WorkerBuilding>>,
+    move_state: Res<TowerMoveState>,
+    buildings: Query<(&Transform, &WorkerBuilding)>,
     existing_menus: Query<Entity, With<HouseMenu>>,
     stdb: Option<SpacetimeDB>,
 ) {
-    if !mouse_button.just_pressed(MouseButton::Left)
+    if !actions.just_pressed(InputAction::Select)
         || house_menu_state.active
         || recruit_menu_state.active
         || tower_wheel_state.active
         || upgrade_menu_state.active
+        || move_state.moving.is_some()
     {
         return;
     }
 
-    let Ok(window) = windows.single() else { return };
-    let Ok((camera, camera_transform)) = camera.single() else { return };
-    let Some(cursor_pos) = window.cursor_position() else { return };
-    let Ok(world_pos) = camera.viewport_to_world_2d(camera_transform, cursor_pos) else { return };
+    let Some(world_pos) = actions.pointer_world else { return };
 
-    for building_transform in buildings.iter() {
+    for (building_transform, building) in buildings.iter() {
         let building_pos = building_transform.translation.truncate();
         if world_pos.distance(building_pos) < SCALED_TILE_SIZE / 2.0 {
             for entity in existing_menus.iter() {
@@ -518,13 +835,19 @@ pub fn show_house_menu(
             }
             house_menu_state.active = true;
             let player_color = get_player_color(&stdb);
-            spawn_house_menu(&mut commands, &asset_server, player_color);
+            spawn_house_menu(&mut commands, &asset_server, player_color, building);
             return;
         }
     }
 }
 
-fn spawn_house_menu(commands: &mut Commands, asset_server: &Res<AssetServer>, player_color: PlayerColor) {
+fn spawn_house_menu(
+    commands: &mut Commands,
+    asset_server: &Res<AssetServer>,
+    player_color: PlayerColor,
+    building: &WorkerBuilding,
+) {
+    let carry_capacity = building.carry_capacity;
     let gold_icon = asset_server.load("UI Elements/UI Elements/Icons/Gold_Icon.png");
     let color_dir = get_color_dir(player_color);
     let pawn_icon: Handle<Image> = asset_server.load(format!("Units/{} Units/Pawn/Pawn_Avatar.png", color_dir));
@@ -630,6 +953,220 @@ fn spawn_house_menu(commands: &mut Commands, asset_server: &Res<AssetServer>, pl
                             });
                         });
 
+                    // Worker caps per resource; the texts are filled in by `update_worker_cap_texts`
+                    panel
+                        .spawn((
+                            Node {
+                                flex_direction: FlexDirection::Column,
+                                align_items: AlignItems::Center,
+                                padding: UiRect::all(Val::Px(15.0)),
+                                row_gap: Val::Px(6.0),
+                                ..default()
+                            },
+                            BackgroundColor(Color::srgba(0.2, 0.35, 0.45, 0.9)),
+                            BorderRadius::all(Val::Px(8.0)),
+                        ))
+                        .with_children(|card: &mut ChildSpawnerCommands| {
+                            card.spawn((
+                                Text::new("ASSIGNMENT"),
+                                TextFont { font_size: 14.0, ..default() },
+                                TextColor(Color::WHITE),
+                            ));
+
+                            for resource in ResourceType::ALL {
+                                card.spawn(Node {
+                                    flex_direction: FlexDirection::Row,
+                                    align_items: AlignItems::Center,
+                                    column_gap: Val::Px(8.0),
+                                    ..default()
+                                })
+                                .with_children(|row: &mut ChildSpawnerCommands| {
+                                    spawn_worker_cap_button(row, resource, -1);
+                                    row.spawn((
+                                        Text::new(""),
+                                        TextFont { font_size: 12.0, ..default() },
+                                        TextColor(Color::WHITE),
+                                        Node { width: Val::Px(130.0), ..default() },
+                                        WorkerCapText(resource),
+                                    ));
+                                    spawn_worker_cap_button(row, resource, 1);
+                                });
+                            }
+                        });
+
+                    // Carry capacity upgrade for every worker of the house
+                    panel
+                        .spawn((
+                            Node {
+                                flex_direction: FlexDirection::Column,
+                                align_items: AlignItems::Center,
+                                padding: UiRect::all(Val::Px(15.0)),
+                                row_gap: Val::Px(8.0),
+                                ..default()
+                            },
+                            BackgroundColor(Color::srgba(0.2, 0.35, 0.45, 0.9)),
+                            BorderRadius::all(Val::Px(8.0)),
+                        ))
+                        .with_children(|card: &mut ChildSpawnerCommands| {
+                            card.spawn((
+                                Text::new(format!("CARRY {}/{}", carry_capacity, MAX_CARRY_CAPACITY)),
+                                TextFont { font_size: 14.0, ..default() },
+                                TextColor(Color::WHITE),
+                            ));
+
+                            if carry_capacity >= MAX_CARRY_CAPACITY {
+                                card.spawn((
+                                    Text::new("Max"),
+                                    TextFont { font_size: 12.0, ..default() },
+                                    TextColor(Color::srgba(0.7, 0.7, 0.7, 1.0)),
+                                ));
+                                return;
+                            }
+
+                            let cost = carry_upgrade_cost(carry_capacity);
+                            card.spawn((
+                                Node {
+                                    flex_direction: FlexDirection::Column,
+                                    align_items: AlignItems::Center,
+                                    padding: UiRect::axes(Val::Px(12.0), Val::Px(6.0)),
+                                    ..default()
+                                },
+                                BackgroundColor(Color::srgba(0.15, 0.4, 0.3, 1.0)),
+                                BorderRadius::all(Val::Px(4.0)),
+                                UpgradeCarryOption { gold_cost: cost },
+                                Button,
+                            ))
+                            .with_children(|button: &mut ChildSpawnerCommands| {
+                                button.spawn((
+                                    Text::new("Upgrade"),
+                                    TextFont { font_size: 12.0, ..default() },
+                                    TextColor(Color::WHITE),
+                                ));
+
+                                button
+                                    .spawn(Node {
+                                        flex_direction: FlexDirection::Row,
+                                        align_items: AlignItems::Center,
+                                        column_gap: Val::Px(4.0),
+                                        ..default()
+                                    })
+                                    .with_children(|cost_row: &mut ChildSpawnerCommands| {
+                                        cost_row.spawn((
+                                            Text::new(format!("{}", cost)),
+                                            TextFont { font_size: 11.0, ..default() },
+                                            TextColor(Color::srgb(1.0, 0.85, 0.0)),
+                                        ));
+                                        cost_row.spawn((
+                                            ImageNode::new(gold_icon.clone()),
+                                            Node { width: Val::Px(16.0), height: Val::Px(16.0), ..default() },
+                                        ));
+                                    });
+                            });
+                        });
+
+                    // House tier: next tier's stats and its price
+                    panel
+                        .spawn((
+                            Node {
+                                flex_direction: FlexDirection::Column,
+                                align_items: AlignItems::Center,
+                                padding: UiRect::all(Val::Px(15.0)),
+                                row_gap: Val::Px(8.0),
+                                ..default()
+                            },
+                            BackgroundColor(Color::srgba(0.2, 0.35, 0.45, 0.9)),
+                            BorderRadius::all(Val::Px(8.0)),
+                        ))
+                        .with_children(|card: &mut ChildSpawnerCommands| {
+                            let level = building.building_level;
+                            card.spawn((
+                                Text::new(format!("HOUSE LEVEL {}/{}", level, HOUSE_TIERS.len())),
+                                TextFont { font_size: 14.0, ..default() },
+                                TextColor(Color::WHITE),
+                            ));
+
+                            let Some(next) = HOUSE_TIERS.get(level) else {
+                                card.spawn((
+                                    Text::new("Max"),
+                                    TextFont { font_size: 12.0, ..default() },
+                                    TextColor(Color::srgba(0.7, 0.7, 0.7, 1.0)),
+                                ));
+                                return;
+                            };
+
+                            let current = &HOUSE_TIERS[level - 1];
+                            card.spawn((
+                                Text::new(format!(
+                                    "Worker every {:.0}s -> {:.0}s\nFree workers {} -> {}",
+                                    current.spawn_interval,
+                                    next.spawn_interval,
+                                    current.worker_capacity,
+                                    next.worker_capacity
+                                )),
+                                TextFont { font_size: 11.0, ..default() },
+                                TextColor(Color::srgba(0.85, 0.85, 0.85, 1.0)),
+                                TextLayout::new_with_justify(Justify::Center),
+                            ));
+
+                            card.spawn((
+                                Node {
+                                    flex_direction: FlexDirection::Column,
+                                    align_items: AlignItems::Center,
+                                    padding: UiRect::axes(Val::Px(12.0), Val::Px(6.0)),
+                                    ..default()
+                                },
+                                BackgroundColor(Color::srgba(0.15, 0.4, 0.3, 1.0)),
+                                BorderRadius::all(Val::Px(4.0)),
+                                UpgradeHouseOption {
+                                    gold_cost: next.gold_cost,
+                                    wood_cost: next.wood_cost,
+                                },
+                                Button,
+                            ))
+                            .with_children(|button: &mut ChildSpawnerCommands| {
+                                button.spawn((
+                                    Text::new("Upgrade"),
+                                    TextFont { font_size: 12.0, ..default() },
+                                    TextColor(Color::WHITE),
+                                ));
+
+                                button
+                                    .spawn(Node {
+                                        flex_direction: FlexDirection::Row,
+                                        align_items: AlignItems::Center,
+                                        column_gap: Val::Px(4.0),
+                                        ..default()
+                                    })
+                                    .with_children(|cost_row: &mut ChildSpawnerCommands| {
+                                        cost_row.spawn((
+                                            Text::new(format!("{}", next.gold_cost)),
+                                            TextFont { font_size: 11.0, ..default() },
+                                            TextColor(Color::srgb(1.0, 0.85, 0.0)),
+                                        ));
+                                        cost_row.spawn((
+                                            ImageNode::new(gold_icon.clone()),
+                                            Node { width: Val::Px(16.0), height: Val::Px(16.0), ..default() },
+                                        ));
+                                        cost_row.spawn((
+                                            Text::new(format!("{} wood", next.wood_cost)),
+                                            TextFont { font_size: 11.0, ..default() },
+                                            TextColor(Color::srgb(0.8, 0.6, 0.35)),
+                                        ));
+                                    });
+                            });
+                        });
+
+                    panel
+                        .spawn(Node {
+                            flex_direction: FlexDirection::Row,
+                            column_gap: Val::Px(8.0),
+                            ..default()
+                        })
+                        .with_children(|row: &mut ChildSpawnerCommands| {
+                            spawn_rally_button(row, "Set Rally Point", SetRallyButton);
+                            spawn_rally_button(row, "Clear", ClearRallyButton);
+                        });
+
                     panel.spawn((
                         Text::new("Right-click or ESC to close"),
                         TextFont { font_size: 12.0, ..default() },
@@ -641,19 +1178,19 @@ fn spawn_house_menu(commands: &mut Commands, asset_server: &Res<AssetServer>, pl
 
 pub fn hide_house_menu(
     mut commands: Commands,
-    mouse_button: Res<ButtonInput<MouseButton>>,
-    keyboard: Res<ButtonInput<KeyCode>>,
+    actions: Res<ActionState>,
     mut menu_state: ResMut<HouseMenuState>,
     menu_entities: Query<Entity, With<HouseMenu>>,
 ) {
     if !menu_state.active {
         return;
     }
-    if mouse_button.just_pressed(MouseButton::Right) || keyboard.just_pressed(KeyCode::Escape) {
+    if actions.just_pressed(InputAction::Cancel) {
         for entity in menu_entities.iter() {
             commands.entity(entity).despawn();
         }
         menu_state.active = false;
+        menu_state.placing_rally = false;
     }
 }
 
@@ -664,33 +1201,28 @@ pub fn handle_build_worker(
     mut game_state: ResMut<GameState>,
     mut menu_state: ResMut<HouseMenuState>,
     menu_entities: Query<Entity, With<HouseMenu>>,
+    rally: Res<RallyPoint>,
     mut buildings: Query<(Entity, &mut WorkerBuilding, &Transform)>,
     stdb: Option<SpacetimeDB>,
+    infinite: Res<InfiniteResources>,
 ) {
     for (interaction, option) in interaction_query.iter_mut() {
         if *interaction == Interaction::Pressed {
-            if game_state.gold >= option.gold_cost {
-                game_state.gold -= option.gold_cost;
+            if infinite.affords(game_state.gold, option.gold_cost) {
+                game_state.spend_gold(infinite.charge(option.gold_cost));
 
                 if let Some((building_entity, mut building, building_transform)) = buildings.iter_mut().next() {
                     let spawn_pos = building_transform.translation.truncate();
-                    let color = get_player_color(&stdb);
-                    let color_dir = get_color_dir(color);
-
-                    let texture_path = format!("Units/{} Units/Pawn/Pawn_Idle.png", color_dir);
-                    let texture = asset_server.load(&texture_path);
-                    let layout = TextureAtlasLayout::from_grid(PAWN_FRAME_SIZE, 8, 1, None, None);
-                    let texture_atlas_layout = asset_server.add(layout);
-                    let pawn_scale = SCALED_TILE_SIZE / PAWN_FRAME_SIZE.x as f32;
-
-                    commands.spawn((
-                        Sprite::from_atlas_image(texture, TextureAtlas { layout: texture_atlas_layout, index: 0 }),
-                        Transform::from_xyz(spawn_pos.x, spawn_pos.y, 2.0).with_scale(Vec3::splat(pawn_scale)),
-                        Worker { speed: WORKER_SPEED, home_building: building_entity, current_resource: None },
-                        WorkerState::Idle,
-                        AnimationTimer { timer: Timer::from_seconds(0.15, TimerMode::Repeating) },
-                        AnimationInfo { frame_count: 6 },
-                    ));
+                    let color_dir = get_color_dir(get_player_color(&stdb));
+                    spawn_worker(
+                        &mut commands,
+                        &asset_server,
+                        color_dir,
+                        building_entity,
+                        spawn_pos,
+                        building.carry_capacity,
+                        rally.position,
+                    );
 
                     building.current_workers += 1;
                     building.worker_capacity += 1;
@@ -704,4 +1236,194 @@ pub fn handle_build_worker(
             }
         }
     }
-}
\ No newline at end of file
+}
+
+pub fn handle_upgrade_carry(
+    mut commands: Commands,
+    interaction_query: Query<(&Interaction, &UpgradeCarryOption), (Changed<Interaction>, With<Button>)>,
+    mut game_state: ResMut<GameState>,
+    mut menu_state: ResMut<HouseMenuState>,
+    menu_entities: Query<Entity, With<HouseMenu>>,
+    mut buildings: Query<(Entity, &mut WorkerBuilding)>,
+    mut workers: Query<&mut Worker>,
+    infinite: Res<InfiniteResources>,
+) {
+    for (interaction, option) in interaction_query.iter() {
+        if *interaction != Interaction::Pressed || !infinite.affords(game_state.gold, option.gold_cost) {
+            continue;
+        }
+        let Some((building_entity, mut building)) = buildings.iter_mut().next() else { continue };
+        if building.carry_capacity >= MAX_CARRY_CAPACITY {
+            continue;
+        }
+
+        game_state.spend_gold(infinite.charge(option.gold_cost));
+        building.carry_capacity += 1;
+        for mut worker in workers.iter_mut().filter(|worker| worker.home_building == building_entity) {
+            worker.carry_capacity = building.carry_capacity;
+        }
+        info!("Worker carry capacity raised to {} for {} gold", building.carry_capacity, option.gold_cost);
+
+        for entity in menu_entities.iter() {
+            commands.entity(entity).despawn();
+        }
+        menu_state.active = false;
+    }
+}
+
+/// Raise the house a tier: faster free workers, more of them, and the
+/// bigger house sprite. Bought workers keep their slots on top.
+pub fn handle_upgrade_house(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    interaction_query: Query<(&Interaction, &UpgradeHouseOption), (Changed<Interaction>, With<Button>)>,
+    mut game_state: ResMut<GameState>,
+    mut menu_state: ResMut<HouseMenuState>,
+    menu_entities: Query<Entity, With<HouseMenu>>,
+    mut buildings: Query<(&mut WorkerBuilding, &mut Sprite)>,
+    stdb: Option<SpacetimeDB>,
+    infinite: Res<InfiniteResources>,
+) {
+    for (interaction, option) in interaction_query.iter() {
+        if *interaction != Interaction::Pressed
+            || !infinite.affords(game_state.gold, option.gold_cost)
+            || !infinite.affords(game_state.wood, option.wood_cost)
+        {
+            continue;
+        }
+        let Some((mut building, mut sprite)) = buildings.iter_mut().next() else { continue };
+        let Some(next) = HOUSE_TIERS.get(building.building_level) else { continue };
+        let current = &HOUSE_TIERS[building.building_level - 1];
+
+        game_state.spend_gold(infinite.charge(option.gold_cost));
+        game_state.wood -= infinite.charge(option.wood_cost);
+        building.worker_capacity += next.worker_capacity - current.worker_capacity;
+        building
+            .spawn_timer
+            .set_duration(std::time::Duration::from_secs_f32(next.spawn_interval));
+        building.building_level += 1;
+        let color_dir = get_color_dir(get_player_color(&stdb));
+        sprite.image = asset_server.load(house_sprite_path(color_dir, building.building_level));
+        info!(
+            "Worker house raised to level {} for {} gold and {} wood",
+            building.building_level, option.gold_cost, option.wood_cost
+        );
+
+        for entity in menu_entities.iter() {
+            commands.entity(entity).despawn();
+        }
+        menu_state.active = false;
+    }
+}
+
+pub fn handle_worker_cap_buttons(
+    interaction_query: Query<(&Interaction, &WorkerCapButton), (Changed<Interaction>, With<Button>)>,
+    workers: Query<(), With<Worker>>,
+    mut allocation: ResMut<WorkerAllocation>,
+) {
+    let total = workers.iter().count() as i32;
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            allocation.adjust(button.resource, button.delta, total);
+        }
+    }
+}
+
+/// "Wood: 2 / 3" per resource, or a note when its nodes are used up
+pub fn update_worker_cap_texts(
+    allocation: Res<WorkerAllocation>,
+    workers: Query<&Worker>,
+    resources: Query<&ResourceNode, Without<Depleted>>,
+    mut texts: Query<(&mut Text, &WorkerCapText)>,
+) {
+    for (mut text, cap_text) in texts.iter_mut() {
+        let resource = cap_text.0;
+        let working = workers.iter().filter(|w| w.current_resource == Some(resource)).count();
+        let cap = allocation.cap(resource).map_or("any".to_string(), |cap| cap.to_string());
+        let line = if resources.iter().any(|node| node.resource_type == resource) {
+            format!("{}: {} / {}", resource.label(), working, cap)
+        } else {
+            format!("{}: none left", resource.label())
+        };
+        if text.0 != line {
+            text.0 = line;
+        }
+    }
+}
+
+
+#[allow(clippy::type_complexity)]
+pub fn handle_rally_buttons(
+    mut commands: Commands,
+    set_buttons: Query<&Interaction, (Changed<Interaction>, With<SetRallyButton>)>,
+    clear_buttons: Query<&Interaction, (Changed<Interaction>, With<ClearRallyButton>)>,
+    mut menu_state: ResMut<HouseMenuState>,
+    mut rally: ResMut<RallyPoint>,
+    menu_entities: Query<Entity, With<HouseMenu>>,
+) {
+    if clear_buttons.iter().any(|interaction| *interaction == Interaction::Pressed) {
+        rally.position = None;
+    }
+    if set_buttons.iter().any(|interaction| *interaction == Interaction::Pressed) {
+        // The menu stays active until the point is placed
+        for entity in menu_entities.iter() {
+            commands.entity(entity).despawn();
+        }
+        menu_state.placing_rally = true;
+    }
+}
+
+/// While placing the rally point, the next click sets it. Placing on release,
+/// as when moving a tower, keeps the click from reaching anything else.
+pub fn place_rally_point(
+    actions: Res<ActionState>,
+    mut menu_state: ResMut<HouseMenuState>,
+    mut rally: ResMut<RallyPoint>,
+    // Set once a press starts after the button, so releasing the button
+    // click itself doesn't place the point
+    mut armed: Local<bool>,
+) {
+    if !menu_state.placing_rally {
+        *armed = false;
+        return;
+    }
+    if actions.just_pressed(InputAction::Select) {
+        *armed = true;
+    }
+    if !*armed || !actions.just_released(InputAction::Select) {
+        return;
+    }
+    *armed = false;
+    let Some(world_pos) = actions.pointer_world else { return };
+
+    rally.position = Some(world_pos);
+    menu_state.placing_rally = false;
+    menu_state.active = false;
+}
+
+/// Keep the flag on the rally point
+pub fn sync_rally_flag(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    rally: Res<RallyPoint>,
+    flags: Query<Entity, With<RallyFlag>>,
+) {
+    if !rally.is_changed() {
+        return;
+    }
+    for entity in flags.iter() {
+        commands.entity(entity).despawn();
+    }
+    if let Some(position) = rally.position {
+        commands.spawn((
+            Sprite {
+                image: asset_server.load(RALLY_FLAG_SPRITE),
+                custom_size: Some(Vec2::new(SCALED_TILE_SIZE * 0.5, SCALED_TILE_SIZE * 0.9)),
+                ..default()
+            },
+            // Anchor the pole at the clicked spot
+            Transform::from_xyz(position.x, position.y + SCALED_TILE_SIZE * 0.45, 2.0),
+            RallyFlag,
+        ));
+    }
+}
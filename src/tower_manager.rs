@@ -1,5 +1,5 @@
 mod config;
-use config::{TowerType, TowersConfig};
+use config::{get_damage_multiplier, AttackType, DefenseType, TowerType, TowersConfig};
 
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
@@ -18,11 +18,15 @@ use ratatui::{
 use ratatui_image::StatefulImage;
 use ratatui_image::picker::Picker;
 use ratatui_image::protocol::StatefulProtocol;
+use std::fmt::Write as _;
 use std::{
     fs,
     io::{self, stdout},
 };
 
+/// Markdown balance report written with `r`
+const REPORT_PATH: &str = "tower_balance_report.md";
+
 enum SelectedPanel {
     Towers,
     TowerDetails,
@@ -79,6 +83,9 @@ impl TowerField {
 
 struct App {
     towers: Vec<TowerType>,
+    /// Kept from `towers.toml` so saving writes them back unchanged
+    move_cost: i32,
+    wall_cost: i32,
     selected_panel: SelectedPanel,
     tower_list_state: ListState,
     current_tower: Option<TowerType>,
@@ -86,6 +93,8 @@ struct App {
     selected_field: TowerField,
     editing: bool,
     edit_buffer: String,
+    /// Tower number typed after ':', jumped to on Enter
+    jump_buffer: Option<String>,
     picker: Picker,
     tower_image: Option<StatefulProtocol>,
     projectile_image: Option<StatefulProtocol>,
@@ -99,14 +108,17 @@ impl App {
 
         let mut app = Self {
             towers: towers_config.towers,
+            move_cost: towers_config.move_cost,
+            wall_cost: towers_config.wall_cost,
             selected_panel: SelectedPanel::Towers,
             tower_list_state: ListState::default(),
             current_tower: None,
-            status_message: "q:quit | w:save | a:new tower | x:delete | Tab:switch | ↑/↓:navigate | Enter:edit/save"
+            status_message: "q:quit | w:save | r:report | a:new tower | x:delete | Tab:switch | ↑/↓:navigate | :N:jump | Enter:edit/save"
                 .to_string(),
             selected_field: TowerField::Id,
             editing: false,
             edit_buffer: String::new(),
+            jump_buffer: None,
             picker: Picker::from_query_stdio().unwrap_or(Picker::halfblocks()),
             tower_image: None,
             projectile_image: None,
@@ -173,9 +185,95 @@ impl App {
         self.load_selected_tower_image();
     }
 
+    /// Select the tower with the typed 1-based number
+    fn confirm_jump(&mut self) {
+        let Some(buffer) = self.jump_buffer.take() else { return };
+        match buffer.parse::<usize>() {
+            Ok(number) if number >= 1 && number <= self.towers.len() => {
+                let i = number - 1;
+                self.tower_list_state.select(Some(i));
+                self.current_tower = Some(self.towers[i].clone());
+                self.load_selected_tower_image();
+                self.status_message = format!("Jumped to tower {}", number);
+            }
+            _ => {
+                self.status_message = format!("No tower {} (1-{})", buffer, self.towers.len());
+            }
+        }
+    }
+
+    /// Write DPS and cost-efficiency of every tower, with DPS against each
+    /// defense type from the damage matrix, to `REPORT_PATH`
+    fn write_report(&mut self) -> io::Result<()> {
+        let mut report = String::from("# Tower Balance Report\n\n");
+        report.push_str("DPS is damage per second against a single target in range. ");
+        report.push_str("Support towers buff others and deal no damage.\n\n");
+        report.push_str("| Tower | Attack | Cost | Range | DPS | vs Armor | vs Agility | vs Mystical | DPS per 100g |\n");
+        report.push_str("|---|---|---|---|---|---|---|---|---|\n");
+
+        for tower in &self.towers {
+            let dps = if tower.aura.is_some() {
+                None
+            } else if tower.hazard.is_some() {
+                // Hazard damage is already per second
+                Some(tower.damage)
+            } else if tower.fire_rate > 0.0 {
+                Some(tower.damage / tower.fire_rate)
+            } else {
+                None
+            };
+            let attack = AttackType::from_str(&tower.attack_type);
+
+            let _ = write!(
+                report,
+                "| {} | {} | {} | {:.0} |",
+                tower.name, tower.attack_type, tower.cost, tower.range
+            );
+            match dps {
+                Some(dps) => {
+                    let _ = write!(report, " {:.1} |", dps);
+                    for defense in DefenseType::ALL {
+                        let _ = write!(report, " {:.1} |", dps * get_damage_multiplier(attack, defense));
+                    }
+                    if tower.cost > 0 {
+                        let _ = write!(report, " {:.2} |", dps * 100.0 / tower.cost as f32);
+                    } else {
+                        report.push_str(" - |");
+                    }
+                }
+                None => report.push_str(" support | - | - | - | - |"),
+            }
+            report.push('\n');
+        }
+
+        let burning: Vec<&TowerType> = self.towers.iter().filter(|t| t.burn_on_hit.is_some()).collect();
+        if !burning.is_empty() {
+            report.push_str("\n## Burn\n\n");
+            for tower in burning {
+                if let Some(burn) = tower.burn_on_hit {
+                    let _ = writeln!(
+                        report,
+                        "- {}: up to {:.1} extra DPS ({} stacks of {:.1}, {:.1}s each)",
+                        tower.name,
+                        burn.dps_per_stack * burn.max_stacks as f32,
+                        burn.max_stacks,
+                        burn.dps_per_stack,
+                        burn.duration
+                    );
+                }
+            }
+        }
+
+        fs::write(REPORT_PATH, report)?;
+        self.status_message = format!("✓ Balance report written to {}", REPORT_PATH);
+        Ok(())
+    }
+
     fn save(&mut self) -> io::Result<()> {
         // Save towers config
         let towers_config = TowersConfig {
+            move_cost: self.move_cost,
+            wall_cost: self.wall_cost,
             towers: self.towers.clone(),
         };
         let towers_toml = toml::to_string_pretty(&towers_config)
@@ -199,7 +297,7 @@ impl App {
             projectile_sprite: "Units/Blue Units/Archer/Arrow.png".to_string(),
             projectile_speed: 300.0,
             description: "A new tower".to_string(),
-            attack_type: "pierce".to_string(),
+            ..Default::default()
         };
         self.towers.push(new_tower.clone());
         self.tower_list_state.select(Some(self.towers.len() - 1));
@@ -639,7 +737,11 @@ fn render_tower_details(f: &mut Frame, app: &mut App, area: Rect) {
 }
 
 fn render_status_bar(f: &mut Frame, app: &App, area: Rect) {
-    let status = Paragraph::new(app.status_message.clone())
+    let text = match &app.jump_buffer {
+        Some(buffer) => format!(":{}  (tower number, Enter to jump, Esc to cancel)", buffer),
+        None => app.status_message.clone(),
+    };
+    let status = Paragraph::new(text)
         .block(Block::default().borders(Borders::ALL))
         .style(Style::default().fg(Color::Green));
     f.render_widget(status, area);
@@ -685,7 +787,17 @@ where
 
         if let Event::Key(key) = event::read()? {
             // Handle editing mode separately
-            if app.editing {
+            if let Some(buffer) = app.jump_buffer.as_mut() {
+                match key.code {
+                    KeyCode::Enter => app.confirm_jump(),
+                    KeyCode::Esc => app.jump_buffer = None,
+                    KeyCode::Backspace => {
+                        buffer.pop();
+                    }
+                    KeyCode::Char(c) if c.is_ascii_digit() => buffer.push(c),
+                    _ => {}
+                }
+            } else if app.editing {
                 match key.code {
                     KeyCode::Enter => {
                         app.confirm_edit();
@@ -705,9 +817,15 @@ where
                 // Normal mode input handling
                 match key.code {
                     KeyCode::Char('q') => return Ok(()),
+                    KeyCode::Char(':') => app.jump_buffer = Some(String::new()),
                     KeyCode::Char('w') => {
                         app.save()?;
                     }
+                    KeyCode::Char('r') => {
+                        if let Err(e) = app.write_report() {
+                            app.status_message = format!("Failed to write report: {}", e);
+                        }
+                    }
                     KeyCode::Char('a') => {
                         app.add_new_tower();
                     }
@@ -0,0 +1,398 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use bevy::window::{PresentMode, PrimaryWindow, WindowMode};
+use serde::{Deserialize, Serialize};
+
+use crate::resources::{OverlaySettings, Palette, ScreenShake};
+use crate::systems::input::KeyBindings;
+
+/// Folder created under the platform config directory
+const SETTINGS_DIR: &str = "tower-defense-with-friends";
+const SETTINGS_FILE: &str = "settings.toml";
+/// Interface scales offered in the settings panel, cycled in this order
+pub const UI_SCALE_STEPS: [f32; 6] = [0.75, 1.0, 1.25, 1.5, 2.0, 2.5];
+
+/// User preferences persisted to `settings.toml` in the platform config
+/// directory (e.g. `~/.config/tower-defense-with-friends/` on Linux).
+/// Runtime resources such as `KeyBindings` are built from it at startup and
+/// written back whenever they change.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Settings {
+    pub audio: AudioSettings,
+    pub graphics: GraphicsSettings,
+    pub input: InputSettings,
+    pub network: NetworkSettings,
+    pub tutorial: TutorialSettings,
+    pub achievements: AchievementSettings,
+    pub gameplay: GameplaySettings,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioSettings {
+    /// 0.0 - 1.0
+    pub master_volume: f32,
+    /// 0.0 - 1.0
+    pub music_volume: f32,
+    /// 0.0 - 1.0
+    pub sfx_volume: f32,
+    pub muted: bool,
+    /// Pan and fade combat sounds by where they happen relative to the camera
+    pub spatial: bool,
+    /// Silence the game while its window is in the background
+    pub mute_on_focus_loss: bool,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            master_volume: 1.0,
+            music_volume: 0.7,
+            sfx_volume: 1.0,
+            muted: false,
+            spatial: true,
+            mute_on_focus_loss: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GraphicsSettings {
+    /// Windowed resolution in logical pixels
+    pub resolution: [u32; 2],
+    pub fullscreen: bool,
+    pub vsync: bool,
+    pub screen_shake: bool,
+    /// 0.0 - 1.0
+    pub screen_shake_intensity: f32,
+    pub show_cooldowns: bool,
+    /// Slow time down briefly when lives are critical
+    pub last_stand_slow_motion: bool,
+    /// Warn when a new tower's range mostly overlaps existing towers
+    pub range_overlap_warning: bool,
+    /// Pause offline games while the window is in the background. Online
+    /// games keep running since the other players share them.
+    pub pause_on_focus_loss: bool,
+    /// Drop non-essential visuals while the frame rate is low
+    pub adaptive_quality: bool,
+    /// Smoothed FPS below which visuals are reduced
+    pub reduce_quality_below_fps: f32,
+    /// Smoothed FPS above which they come back; kept above the other
+    /// threshold so quality doesn't flicker between the two
+    pub restore_quality_above_fps: f32,
+    /// Multiplies every UI size, text included, on top of the display's
+    /// own scale factor. For high-DPI screens where the interface is tiny.
+    pub ui_scale: f32,
+    /// Zoom the map in by `ui_scale` as well
+    pub scale_world: bool,
+    /// Palette for health bars, placement and type hints
+    pub colorblind_mode: ColorblindMode,
+}
+
+/// Color vision deficiency the `Palette` is adjusted for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorblindMode {
+    #[default]
+    Off,
+    Deuteranopia,
+    Protanopia,
+    Tritanopia,
+}
+
+impl ColorblindMode {
+    pub const ALL: [ColorblindMode; 4] = [
+        ColorblindMode::Off,
+        ColorblindMode::Deuteranopia,
+        ColorblindMode::Protanopia,
+        ColorblindMode::Tritanopia,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ColorblindMode::Off => "Off",
+            ColorblindMode::Deuteranopia => "Deuteranopia",
+            ColorblindMode::Protanopia => "Protanopia",
+            ColorblindMode::Tritanopia => "Tritanopia",
+        }
+    }
+
+    /// The mode after this one, wrapping around
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|mode| *mode == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+impl Default for GraphicsSettings {
+    fn default() -> Self {
+        Self {
+            resolution: [1024, 768],
+            fullscreen: false,
+            vsync: true,
+            screen_shake: true,
+            screen_shake_intensity: 1.0,
+            show_cooldowns: true,
+            last_stand_slow_motion: true,
+            range_overlap_warning: true,
+            pause_on_focus_loss: true,
+            adaptive_quality: true,
+            reduce_quality_below_fps: 30.0,
+            restore_quality_above_fps: 45.0,
+            ui_scale: 1.0,
+            scale_world: false,
+            colorblind_mode: ColorblindMode::Off,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputSettings {
+    /// Action id -> key name, see `KeyBindings::to_names`
+    pub key_bindings: HashMap<String, String>,
+}
+
+impl Default for InputSettings {
+    fn default() -> Self {
+        Self {
+            key_bindings: KeyBindings::defaults().to_names(),
+        }
+    }
+}
+
+/// SpacetimeDB server the client connects to. Read once at startup, so
+/// changes apply on the next launch. `SPACETIMEDB_URI` and
+/// `SPACETIMEDB_MODULE` override these values.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkSettings {
+    pub host: String,
+    pub module: String,
+}
+
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        Self {
+            host: "http://127.0.0.1:3000".to_string(),
+            module: "td-mmo".to_string(),
+        }
+    }
+}
+
+/// First-run tutorial progress
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct TutorialSettings {
+    /// Finished or skipped; the tutorial isn't shown again
+    pub completed: bool,
+}
+
+/// Unlocked achievements and the lifetime counters they track
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct AchievementSettings {
+    /// Ids from `ACHIEVEMENTS`
+    pub unlocked: Vec<String>,
+    /// Enemies killed over all games
+    pub total_kills: u64,
+}
+
+/// Pacing of the build phases between waves
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameplaySettings {
+    /// Seconds to build before the first wave starts on its own
+    pub first_wave_prep_secs: f32,
+    /// Seconds between the end of a wave and the start of the next
+    pub wave_prep_secs: f32,
+}
+
+impl Default for GameplaySettings {
+    fn default() -> Self {
+        Self {
+            first_wave_prep_secs: 45.0,
+            wave_prep_secs: 30.0,
+        }
+    }
+}
+
+/// `file_name` under the game's platform config directory, or in the working
+/// directory if the platform has none
+pub fn config_file_path(file_name: &str) -> PathBuf {
+    dirs::config_dir()
+        .map(|dir| dir.join(SETTINGS_DIR).join(file_name))
+        .unwrap_or_else(|| PathBuf::from(file_name))
+}
+
+impl Settings {
+    pub fn path() -> PathBuf {
+        config_file_path(SETTINGS_FILE)
+    }
+
+    pub fn load() -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let content = std::fs::read_to_string(Self::path())?;
+        Ok(toml::from_str(&content)?)
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let path = Self::path();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Load the saved settings. A missing or unreadable file is replaced with
+    /// the defaults so the next launch starts from a valid file.
+    pub fn load_or_default() -> Self {
+        match Self::load() {
+            Ok(settings) => settings,
+            Err(e) => {
+                let path = Self::path();
+                if path.exists() {
+                    warn!("Failed to load {}: {}. Using default settings", path.display(), e);
+                }
+                let settings = Self::default();
+                if let Err(e) = settings.save() {
+                    error!("Failed to write {}: {}", path.display(), e);
+                }
+                settings
+            }
+        }
+    }
+
+    pub fn window_mode(&self) -> WindowMode {
+        if self.graphics.fullscreen {
+            WindowMode::BorderlessFullscreen(MonitorSelection::Current)
+        } else {
+            WindowMode::Windowed
+        }
+    }
+
+    /// `ui_scale` kept within the offered steps, in case the file was edited by hand
+    pub fn ui_scale(&self) -> f32 {
+        self.graphics.ui_scale.clamp(UI_SCALE_STEPS[0], UI_SCALE_STEPS[UI_SCALE_STEPS.len() - 1])
+    }
+
+    pub fn present_mode(&self) -> PresentMode {
+        if self.graphics.vsync {
+            PresentMode::AutoVsync
+        } else {
+            PresentMode::AutoNoVsync
+        }
+    }
+}
+
+/// Loads `Settings` and seeds the runtime resources it controls.
+/// Must be added before `BevyPlugin` so the window is created with them.
+/// The resolution only applies at startup so manual window resizes stick.
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        let settings = Settings::load_or_default();
+
+        app.insert_resource(KeyBindings::from_names(&settings.input.key_bindings))
+            .insert_resource(OverlaySettings {
+                show_cooldowns: settings.graphics.show_cooldowns,
+                range_overlap_warning: settings.graphics.range_overlap_warning,
+            })
+            .insert_resource(ScreenShake {
+                enabled: settings.graphics.screen_shake,
+                intensity: settings.graphics.screen_shake_intensity,
+                ..default()
+            })
+            .insert_resource(UiScale(settings.ui_scale()))
+            .insert_resource(Palette::new(settings.graphics.colorblind_mode))
+            .insert_resource(settings)
+            .add_systems(
+                Update,
+                (
+                    collect_settings,
+                    (apply_window_settings, apply_ui_scale, apply_palette, save_settings)
+                        .chain()
+                        .run_if(resource_changed::<Settings>)
+                        .after(collect_settings),
+                ),
+            );
+    }
+}
+
+/// Copy runtime changes (rebinding, overlay toggles...) back into `Settings`
+fn collect_settings(
+    mut settings: ResMut<Settings>,
+    bindings: Res<KeyBindings>,
+    overlays: Res<OverlaySettings>,
+    shake: Res<ScreenShake>,
+) {
+    if !bindings.is_changed() && !overlays.is_changed() && !shake.is_changed() {
+        return;
+    }
+
+    let mut next = settings.clone();
+    next.input.key_bindings = bindings.to_names();
+    next.graphics.show_cooldowns = overlays.show_cooldowns;
+    next.graphics.range_overlap_warning = overlays.range_overlap_warning;
+    next.graphics.screen_shake = shake.enabled;
+    next.graphics.screen_shake_intensity = shake.intensity;
+    settings.set_if_neq(next);
+}
+
+fn apply_window_settings(
+    settings: Res<Settings>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let Ok(mut window) = windows.single_mut() else { return };
+
+    let mode = settings.window_mode();
+    if window.mode != mode {
+        window.mode = mode;
+    }
+    let present_mode = settings.present_mode();
+    if window.present_mode != present_mode {
+        window.present_mode = present_mode;
+    }
+}
+
+/// Resize the interface, and the map when `scale_world` is on. Changing the
+/// scale resets the map zoom to match it.
+fn apply_ui_scale(
+    settings: Res<Settings>,
+    mut ui_scale: ResMut<UiScale>,
+    mut cameras: Query<&mut Transform, With<Camera2d>>,
+    mut last_world_scale: Local<Option<f32>>,
+) {
+    let scale = settings.ui_scale();
+    if ui_scale.0 != scale {
+        ui_scale.0 = scale;
+    }
+
+    let world_scale = if settings.graphics.scale_world { scale } else { 1.0 };
+    if *last_world_scale == Some(world_scale) {
+        return;
+    }
+    *last_world_scale = Some(world_scale);
+    for mut transform in cameras.iter_mut() {
+        transform.scale = Vec3::new(1.0 / world_scale, 1.0 / world_scale, 1.0);
+    }
+}
+
+fn apply_palette(settings: Res<Settings>, mut palette: ResMut<Palette>) {
+    palette.set_if_neq(Palette::new(settings.graphics.colorblind_mode));
+}
+
+fn save_settings(settings: Res<Settings>) {
+    // The file was just read (or rewritten) when the resource was inserted
+    if settings.is_added() {
+        return;
+    }
+    if let Err(e) = settings.save() {
+        error!("Failed to save settings: {}", e);
+    }
+}
@@ -1,8 +1,14 @@
 mod config;
 mod animation;
+mod combat;
+mod simulation;
 
 use animation::Animation;
-use config::{UnitSpawn, UnitType, UnitsConfig, Wave, WavesConfig};
+use config::{
+    find_content_issues, get_damage_multiplier, schedule_wave, AttackType, DefenseType, TowersConfig, UnitSpawn, UnitType,
+    UnitsConfig, Wave, WaveModifier, WavesConfig, BOUNTY_GOLD_MULTIPLIER, WAVES_FILE,
+};
+use simulation::{default_layout, simulate_wave};
 
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, poll},
@@ -18,6 +24,7 @@ use ratatui::{
     widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
 };
 use ratatui_image::{picker::Picker, StatefulImage};
+use std::fmt::Write as _;
 use std::{
     fs,
     io::{self, stdout},
@@ -26,6 +33,37 @@ use std::{
 };
 use std::io::empty;
 use ratatui_image::protocol::StatefulProtocol;
+use serde::{Deserialize, Serialize};
+
+/// Markdown balance report written with `r`
+const REPORT_PATH: &str = "wave_balance_report.md";
+
+/// Preview-only settings of the editor itself, kept out of `waves.toml`
+const PREVIEW_SETTINGS_PATH: &str = "wave_manager_preview.toml";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct PreviewSettings {
+    /// Server-side difficulty multiplier applied to displayed HP, so the
+    /// numbers match what players face in game. Unset shows config HP.
+    difficulty: Option<f32>,
+}
+
+impl PreviewSettings {
+    fn load() -> Self {
+        fs::read_to_string(PREVIEW_SETTINGS_PATH)
+            .ok()
+            .and_then(|contents| toml::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let contents =
+            toml::to_string_pretty(self).map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        fs::write(PREVIEW_SETTINGS_PATH, contents)
+    }
+}
+
 // === APP STATE ===
 
 enum SelectedPanel {
@@ -96,7 +134,10 @@ enum UnitField {
     BaseSpeed,
     DamageToBase,
     GoldReward,
+    GoldSteal,
     DefenseType,
+    ImmuneTo,
+    Tint,
     FrameCount,
     FrameSize,
 }
@@ -112,7 +153,10 @@ impl UnitField {
             UnitField::BaseSpeed,
             UnitField::DamageToBase,
             UnitField::GoldReward,
+            UnitField::GoldSteal,
             UnitField::DefenseType,
+            UnitField::ImmuneTo,
+            UnitField::Tint,
             UnitField::FrameCount,
             UnitField::FrameSize,
         ]
@@ -138,6 +182,8 @@ impl UnitField {
 struct App {
     units: Vec<UnitType>,
     waves: Vec<Wave>,
+    /// Wave files loaded from `waves/`, empty when using `waves.toml`
+    wave_files: Vec<String>,
     selected_panel: SelectedPanel,
     wave_list_state: ListState,
     unit_list_state: ListState,
@@ -148,7 +194,12 @@ struct App {
     selected_spawn_field: SpawnField,
     selected_unit_field: UnitField,
     editing: bool,
+    /// The edit buffer holds the preview difficulty rather than a field
+    editing_difficulty: bool,
     edit_buffer: String,
+    /// Wave or unit number typed after ':', jumped to on Enter
+    jump_buffer: Option<String>,
+    preview: PreviewSettings,
     picker: Picker,
     unit_animation: Option<Animation>,
     unit_avatar: Option<StatefulProtocol>,
@@ -165,18 +216,22 @@ impl App {
         let mut app = Self {
             units: units_config.units,
             waves: waves_config.waves,
+            wave_files: waves_config.files,
             selected_panel: SelectedPanel::Waves,
             wave_list_state: ListState::default(),
             unit_list_state: ListState::default(),
             current_wave: None,
             current_unit: None,
-            status_message: "q:quit | w:save | a:new | x:delete | Tab:switch | ↑/↓:navigate | Enter:edit"
+            status_message: "q:quit | w:save | r:report | v:validate | a:new | x:delete | Tab:switch | ↑/↓:navigate | :N:jump | Enter:edit"
                 .to_string(),
             selected_field: WaveDetailField::SpawnInterval,
             selected_spawn_field: SpawnField::UnitType,
             selected_unit_field: UnitField::Id,
             editing: false,
+            editing_difficulty: false,
             edit_buffer: String::new(),
+            jump_buffer: None,
+            preview: PreviewSettings::load(),
             picker: Picker::from_query_stdio().unwrap_or(Picker::halfblocks()),
             unit_animation: None,
             unit_avatar: None,
@@ -263,6 +318,40 @@ impl App {
         self.load_selected_unit_avatar();
     }
 
+    /// Select the typed wave number in the wave panels, or the typed
+    /// 1-based unit number in the units panel
+    fn confirm_jump(&mut self) {
+        let Some(buffer) = self.jump_buffer.take() else { return };
+        let Ok(number) = buffer.parse::<usize>() else {
+            self.status_message = "Type a number to jump to".to_string();
+            return;
+        };
+
+        if matches!(self.selected_panel, SelectedPanel::Units) {
+            if number == 0 || number > self.units.len() {
+                self.status_message = format!("No unit {} (1-{})", number, self.units.len());
+                return;
+            }
+            let i = number - 1;
+            self.unit_list_state.select(Some(i));
+            self.current_unit = Some(self.units[i].clone());
+            self.selected_unit_field = UnitField::Id;
+            self.load_selected_unit_animation();
+            self.load_selected_unit_avatar();
+            self.status_message = format!("Jumped to unit {}", number);
+        } else {
+            let Some(i) = self.waves.iter().position(|wave| wave.wave_number as usize == number) else {
+                self.status_message = format!("No wave {}", number);
+                return;
+            };
+            self.wave_list_state.select(Some(i));
+            self.current_wave = Some(self.waves[i].clone());
+            // The previous wave's spawn row may not exist in this one
+            self.selected_field = WaveDetailField::SpawnInterval;
+            self.status_message = format!("Jumped to wave {}", number);
+        }
+    }
+
     fn load_selected_unit_avatar(&mut self) {
         if let Some(idx) = self.unit_list_state.selected() {
             if let Some(unit) = self.units.get(idx) {
@@ -314,13 +403,148 @@ impl App {
         }
     }
 
+    /// Write total HP and threat of every wave, and each unit's effective HP
+    /// against every attack type from the damage matrix, to `REPORT_PATH`
+    fn write_report(&mut self) -> io::Result<()> {
+        let difficulty = self.preview.difficulty.unwrap_or(1.0);
+        let mut report = String::from("# Wave Balance Report\n\n");
+        if self.preview.difficulty.is_some() {
+            let _ = writeln!(report, "HP includes a preview difficulty of x{}.\n", difficulty);
+        }
+
+        report.push_str("## Waves\n\n");
+        report.push_str("Threat is the castle damage dealt if every enemy leaks.\n\n");
+        report.push_str("| Wave | Enemies | Total HP | Threat | Gold | Last spawn | Bounty |\n");
+        report.push_str("|---|---|---|---|---|---|---|\n");
+        for wave in &self.waves {
+            let schedule = schedule_wave(wave, &self.units);
+            let total_health: f32 = schedule.iter().map(|event| event.health * difficulty).sum();
+            let threat: i32 = schedule
+                .iter()
+                .filter_map(|event| self.units.iter().find(|u| u.id == event.unit_id))
+                .map(|unit| unit.damage_to_base)
+                .sum();
+            let gold: i32 = schedule.iter().map(|event| event.gold_reward).sum();
+            let last_spawn = schedule.last().map_or(0.0, |event| event.spawn_time);
+            let _ = writeln!(
+                report,
+                "| {} | {} | {:.0} | {} | {} | {:.1}s | {} |",
+                wave.wave_number,
+                schedule.len(),
+                total_health,
+                threat,
+                gold,
+                last_spawn,
+                if wave.bounty { "yes" } else { "no" }
+            );
+        }
+
+        report.push_str("\n## Units\n\n");
+        report.push_str("Effective HP is the raw damage needed to kill the unit with each attack type.\n\n");
+        report.push_str("| Unit | Defense | HP | vs Blunt | vs Pierce | vs Divine | Speed | Castle dmg |\n");
+        report.push_str("|---|---|---|---|---|---|---|---|\n");
+        for unit in &self.units {
+            let health = unit.base_health * difficulty;
+            let defense = DefenseType::from_str(&unit.defense_type);
+            let immune_to = unit.immune_to.as_deref().map(AttackType::from_str);
+            let _ = write!(report, "| {} | {} | {:.0} |", unit.name, unit.defense_type, health);
+            for attack in AttackType::ALL {
+                if immune_to == Some(attack) {
+                    report.push_str(" immune |");
+                } else {
+                    let _ = write!(report, " {:.0} |", health / get_damage_multiplier(attack, defense));
+                }
+            }
+            let _ = writeln!(report, " {:.0} | {} |", unit.base_speed, unit.damage_to_base);
+        }
+
+        let issues = find_content_issues(&self.units, &self.waves);
+        if !issues.is_empty() {
+            report.push_str("\n## Content Issues\n\n");
+            for unit_id in &issues.unused_units {
+                let _ = writeln!(report, "- Unit `{}` is not used by any wave", unit_id);
+            }
+            for wave_number in &issues.empty_waves {
+                let _ = writeln!(report, "- Wave {} spawns nothing", wave_number);
+            }
+            for (wave_number, unit_id) in &issues.unknown_units {
+                let _ = writeln!(report, "- Wave {} spawns unknown unit `{}`", wave_number, unit_id);
+            }
+        }
+
+        fs::write(REPORT_PATH, report)?;
+        self.status_message = format!("✓ Balance report written to {}", REPORT_PATH);
+        Ok(())
+    }
+
+    /// Play the selected wave against one of each tower from `towers.toml`
+    /// in the default layout and show the outcome in the status bar
+    fn simulate_current_wave(&mut self) {
+        let Some(wave) = &self.current_wave else {
+            self.status_message = "No wave selected".to_string();
+            return;
+        };
+        let towers = match TowersConfig::load() {
+            Ok(config) => config.towers,
+            Err(e) => {
+                self.status_message = format!("Failed to load towers.toml: {}", e);
+                return;
+            }
+        };
+
+        let layout = default_layout(&towers);
+        let result = simulate_wave(wave, &self.units, &layout, self.preview.difficulty.unwrap_or(1.0));
+        self.status_message = format!(
+            "Sim wave {} vs {} towers: {} with {}/{} lives | {}/{} killed, {} leaked | +{} gold | {:.0}s",
+            wave.wave_number,
+            result.towers,
+            if result.survived() { "base survives" } else { "base falls" },
+            result.lives_left,
+            result.max_lives,
+            result.killed,
+            result.enemies,
+            result.leaked,
+            result.gold_earned,
+            result.duration
+        );
+    }
+
+    /// Summarize unused units, empty waves and unknown unit references in
+    /// the status bar
+    fn validate_content(&mut self) {
+        let issues = find_content_issues(&self.units, &self.waves);
+        if issues.is_empty() {
+            self.status_message = "✓ No content issues".to_string();
+            return;
+        }
+
+        let mut parts = Vec::new();
+        if !issues.unused_units.is_empty() {
+            parts.push(format!("unused units: {}", issues.unused_units.join(", ")));
+        }
+        if !issues.empty_waves.is_empty() {
+            let waves: Vec<String> = issues.empty_waves.iter().map(|n| n.to_string()).collect();
+            parts.push(format!("empty waves: {}", waves.join(", ")));
+        }
+        if !issues.unknown_units.is_empty() {
+            let refs: Vec<String> = issues
+                .unknown_units
+                .iter()
+                .map(|(wave_number, unit_id)| format!("{} in wave {}", unit_id, wave_number))
+                .collect();
+            parts.push(format!("unknown units: {}", refs.join(", ")));
+        }
+        self.status_message = format!("⚠ {}", parts.join(" | "));
+    }
+
     fn save(&mut self) -> io::Result<()> {
         let waves_config = WavesConfig {
             waves: self.waves.clone(),
+            files: self.wave_files.clone(),
         };
-        let waves_toml = toml::to_string_pretty(&waves_config)
+        waves_config
+            .save()
             .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
-        fs::write("waves.toml", waves_toml)?;
 
         let units_config = UnitsConfig {
             units: self.units.clone(),
@@ -341,8 +565,11 @@ impl App {
             spawns: vec![UnitSpawn {
                 unit_id: "warrior".to_string(),
                 count: 5,
-                health_multiplier: 1.0,
+                ..Default::default()
             }],
+            // New waves join the file of the last wave
+            source: self.waves.last().and_then(|wave| wave.source.clone()),
+            ..Default::default()
         };
         self.waves.push(new_wave.clone());
         self.wave_list_state.select(Some(self.waves.len() - 1));
@@ -350,6 +577,32 @@ impl App {
         self.status_message = format!("Added Wave {}", new_wave_number);
     }
 
+    fn toggle_bounty_for_current_wave(&mut self) {
+        if let Some(idx) = self.wave_list_state.selected() {
+            if let Some(wave) = self.waves.get_mut(idx) {
+                wave.bounty = !wave.bounty;
+                self.status_message = if wave.bounty {
+                    format!("Wave {} is now a bounty wave", wave.wave_number)
+                } else {
+                    format!("Wave {} is no longer a bounty wave", wave.wave_number)
+                };
+                self.current_wave = Some(wave.clone());
+            }
+        }
+    }
+
+    fn toggle_modifier_for_current_wave(&mut self, modifier: WaveModifier) {
+        let Some(wave) = self.wave_list_state.selected().and_then(|idx| self.waves.get_mut(idx)) else { return };
+        if let Some(pos) = wave.modifiers.iter().position(|m| *m == modifier) {
+            wave.modifiers.remove(pos);
+            self.status_message = format!("Wave {} is no longer {}", wave.wave_number, modifier.label());
+        } else {
+            wave.modifiers.push(modifier);
+            self.status_message = format!("Wave {} is now {}", wave.wave_number, modifier.label());
+        }
+        self.current_wave = Some(wave.clone());
+    }
+
     fn delete_current_wave(&mut self) {
         if let Some(idx) = self.wave_list_state.selected() {
             if !self.waves.is_empty() {
@@ -378,6 +631,7 @@ impl App {
                     .unwrap_or("warrior".to_string()),
                 count: 5,
                 health_multiplier: 1.0,
+                linked: false,
             };
             self.waves[idx].spawns.push(new_spawn);
             self.current_wave = Some(self.waves[idx].clone());
@@ -455,6 +709,19 @@ impl App {
         }
     }
 
+    fn toggle_link_for_current_spawn(&mut self) {
+        let Some(wave_idx) = self.wave_list_state.selected() else { return };
+        let Some(spawn_idx) = self.selected_field.spawn_index() else { return };
+        let Some(spawn) = self.waves[wave_idx].spawns.get_mut(spawn_idx) else { return };
+        spawn.linked = !spawn.linked;
+        self.status_message = if spawn.linked {
+            format!("{} group now shares damage", spawn.unit_id)
+        } else {
+            format!("{} group no longer shares damage", spawn.unit_id)
+        };
+        self.current_wave = Some(self.waves[wave_idx].clone());
+    }
+
     fn cycle_unit_for_current_spawn(&mut self) {
         if let Some(wave_idx) = self.wave_list_state.selected() {
             if let Some(spawn_idx) = self.selected_field.spawn_index() {
@@ -562,8 +829,54 @@ impl App {
         self.edit_buffer.clear();
     }
 
+    fn start_editing_difficulty(&mut self) {
+        self.editing = true;
+        self.editing_difficulty = true;
+        self.edit_buffer = self
+            .preview
+            .difficulty
+            .map(|difficulty| difficulty.to_string())
+            .unwrap_or_default();
+        self.status_message =
+            "Preview difficulty (empty to clear, Enter to save, Esc to cancel)".to_string();
+    }
+
+    fn confirm_difficulty_edit(&mut self) {
+        let input = self.edit_buffer.trim();
+        let difficulty = if input.is_empty() {
+            None
+        } else {
+            match input.parse::<f32>() {
+                Ok(value) if value > 0.0 => Some(value),
+                _ => {
+                    self.status_message = "Difficulty must be a positive number".to_string();
+                    self.edit_buffer.clear();
+                    return;
+                }
+            }
+        };
+
+        self.preview.difficulty = difficulty;
+        self.editing = false;
+        self.editing_difficulty = false;
+        self.edit_buffer.clear();
+        self.status_message = match self.preview.save() {
+            Ok(()) => match difficulty {
+                Some(value) => format!("Previewing HP at difficulty x{}", value),
+                None => "Preview difficulty cleared".to_string(),
+            },
+            Err(e) => format!("Failed to save preview settings: {}", e),
+        };
+    }
+
+    /// HP a unit spawns with in game, including the preview difficulty
+    fn displayed_health(&self, base_health: f32, health_multiplier: f32) -> f32 {
+        base_health * health_multiplier * self.preview.difficulty.unwrap_or(1.0)
+    }
+
     fn cancel_edit(&mut self) {
         self.editing = false;
+        self.editing_difficulty = false;
         self.edit_buffer.clear();
         self.status_message = "Edit cancelled".to_string();
     }
@@ -577,7 +890,30 @@ impl App {
         self.selected_unit_field = self.selected_unit_field.prev();
     }
 
+    /// Cycle the immunity of the selected unit: none -> blunt -> pierce -> divine -> none
+    fn cycle_unit_immunity(&mut self) {
+        if let Some(unit_idx) = self.unit_list_state.selected() {
+            let unit = &mut self.units[unit_idx];
+            unit.immune_to = match unit.immune_to.as_deref() {
+                None => Some("blunt".to_string()),
+                Some("blunt") => Some("pierce".to_string()),
+                Some("pierce") => Some("divine".to_string()),
+                _ => None,
+            };
+            self.status_message = format!(
+                "Immunity set to {}",
+                unit.immune_to.as_deref().unwrap_or("none")
+            );
+            self.current_unit = Some(unit.clone());
+        }
+    }
+
     fn start_editing_unit(&mut self) {
+        // A fixed set of values, so Enter cycles instead of opening the editor
+        if self.selected_unit_field == UnitField::ImmuneTo {
+            self.cycle_unit_immunity();
+            return;
+        }
         if let Some(unit) = &self.current_unit {
             self.editing = true;
             self.edit_buffer = match self.selected_unit_field {
@@ -589,7 +925,10 @@ impl App {
                 UnitField::BaseSpeed => unit.base_speed.to_string(),
                 UnitField::DamageToBase => unit.damage_to_base.to_string(),
                 UnitField::GoldReward => unit.gold_reward.to_string(),
+                UnitField::GoldSteal => unit.gold_steal.to_string(),
                 UnitField::DefenseType => unit.defense_type.clone(),
+                UnitField::ImmuneTo => unit.immune_to.clone().unwrap_or_default(),
+                UnitField::Tint => unit.tint.map(format_tint).unwrap_or_default(),
                 UnitField::FrameCount => unit.frame_count.to_string(),
                 UnitField::FrameSize => format!("{}x{}", unit.frame_size[0], unit.frame_size[1]),
             };
@@ -666,6 +1005,15 @@ impl App {
                         Err("Invalid number".to_string())
                     }
                 }
+                UnitField::GoldSteal => {
+                    match self.edit_buffer.parse::<i32>() {
+                        Ok(value) if value >= 0 => {
+                            self.units[unit_idx].gold_steal = value;
+                            Ok(format!("Gold steal set to {}", value))
+                        }
+                        _ => Err("Invalid number (must be 0 or more)".to_string()),
+                    }
+                }
                 UnitField::DefenseType => {
                     let valid_types = ["armor", "agility", "mystical"];
                     let input = self.edit_buffer.to_lowercase();
@@ -676,6 +1024,18 @@ impl App {
                         Err("Invalid defense type (use: armor, agility, mystical)".to_string())
                     }
                 }
+                // Cycled in cycle_unit_immunity, never edited as text
+                UnitField::ImmuneTo => Ok("Immunity unchanged".to_string()),
+                UnitField::Tint => match parse_tint(&self.edit_buffer) {
+                    Ok(tint) => {
+                        self.units[unit_idx].tint = tint;
+                        Ok(match tint {
+                            Some(tint) => format!("Tint set to {}", format_tint(tint)),
+                            None => "Tint cleared".to_string(),
+                        })
+                    }
+                    Err(msg) => Err(msg),
+                },
                 UnitField::FrameCount => {
                     if let Ok(value) = self.edit_buffer.parse::<usize>() {
                         self.units[unit_idx].frame_count = value;
@@ -728,7 +1088,7 @@ impl App {
             gold_reward: 10,
             frame_count: 6,
             frame_size: [192, 192],
-            defense_type: "armor".to_string(),
+            ..Default::default()
         };
         self.units.push(new_unit.clone());
         self.unit_list_state.select(Some(self.units.len() - 1));
@@ -821,6 +1181,40 @@ fn render_unit_avatar(f: &mut Frame, app: &mut App, area: Rect) {
         f.render_widget(placeholder, inner_area);
     }
 }
+/// File name of a wave's source, without the `waves/` directory
+/// Tint as the editor shows it, "r,g,b,a"
+fn format_tint([r, g, b, a]: [f32; 4]) -> String {
+    format!("{},{},{},{}", r, g, b, a)
+}
+
+/// Parse "r,g,b" or "r,g,b,a" with channels from 0 to 1; empty clears the tint
+fn parse_tint(input: &str) -> Result<Option<[f32; 4]>, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Ok(None);
+    }
+    let channels: Vec<f32> = input
+        .split(',')
+        .map(|part| part.trim().parse::<f32>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| "Invalid tint (use r,g,b or r,g,b,a)".to_string())?;
+    if !channels.iter().all(|channel| (0.0..=1.0).contains(channel)) {
+        return Err("Tint channels must be between 0 and 1".to_string());
+    }
+    match channels[..] {
+        [r, g, b] => Ok(Some([r, g, b, 1.0])),
+        [r, g, b, a] => Ok(Some([r, g, b, a])),
+        _ => Err("Invalid tint (use r,g,b or r,g,b,a)".to_string()),
+    }
+}
+
+fn source_file_name(source: &str) -> &str {
+    Path::new(source)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(source)
+}
+
 fn render_waves_list(f: &mut Frame, app: &mut App, area: Rect) {
     let items: Vec<ListItem> = app
         .waves
@@ -839,6 +1233,25 @@ fn render_waves_list(f: &mut Frame, app: &mut App, area: Rect) {
                     format!("{} units", total_units),
                     Style::default().fg(Color::Yellow),
                 ),
+                Span::styled(
+                    if wave.bounty { " [Bounty]" } else { "" },
+                    Style::default().fg(Color::LightYellow),
+                ),
+                Span::styled(
+                    WaveModifier::ALL
+                        .into_iter()
+                        .filter(|m| wave.modifiers.contains(m))
+                        .map(|m| format!(" [{}]", m.label()))
+                        .collect::<String>(),
+                    Style::default().fg(Color::LightRed),
+                ),
+                Span::styled(
+                    wave.source
+                        .as_deref()
+                        .map(|source| format!(" ({})", source_file_name(source)))
+                        .unwrap_or_default(),
+                    Style::default().fg(Color::DarkGray),
+                ),
             ]);
             ListItem::new(content)
         })
@@ -881,6 +1294,10 @@ fn render_wave_details(f: &mut Frame, app: &App, area: Rect) {
             Span::styled("Wave Number: ", Style::default().fg(Color::Cyan)),
             Span::raw(wave.wave_number.to_string()),
         ])];
+        lines.push(Line::from(vec![
+            Span::styled("   Source: ", Style::default().fg(Color::Cyan)),
+            Span::raw(wave.source.clone().unwrap_or_else(|| WAVES_FILE.to_string())),
+        ]));
 
         let is_interval_selected =
             matches!(app.selected_field, WaveDetailField::SpawnInterval) && is_selected;
@@ -904,6 +1321,56 @@ fn render_wave_details(f: &mut Frame, app: &App, area: Rect) {
         }
         lines.push(Line::from(interval_spans));
 
+        let schedule = schedule_wave(wave, &app.units);
+        let last_spawn = schedule.last().map_or(0.0, |event| event.spawn_time);
+        lines.push(Line::from(vec![
+            Span::styled("   Schedule: ", Style::default().fg(Color::Cyan)),
+            Span::raw(format!("{} enemies, last spawns at {:.1}s", schedule.len(), last_spawn)),
+        ]));
+        let mut difficulty_spans =
+            vec![Span::styled("   Difficulty: ", Style::default().fg(Color::Cyan))];
+        if app.editing_difficulty {
+            difficulty_spans.push(Span::styled(
+                app.edit_buffer.clone(),
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ));
+        } else {
+            difficulty_spans.push(Span::raw(match app.preview.difficulty {
+                Some(difficulty) => format!("x{} preview, HP scaled (d to edit)", difficulty),
+                None => "config HP (d to preview)".to_string(),
+            }));
+        }
+        lines.push(Line::from(difficulty_spans));
+        let modifiers: Vec<String> = WaveModifier::ALL
+            .into_iter()
+            .filter(|m| wave.modifiers.contains(m))
+            .map(|m| format!("{} ({})", m.label(), m.description()))
+            .collect();
+        let toggle_hint = WaveModifier::ALL
+            .iter()
+            .enumerate()
+            .map(|(i, m)| format!("{}:{}", i + 1, m.label()))
+            .collect::<Vec<_>>()
+            .join(" ");
+        lines.push(Line::from(vec![
+            Span::styled("   Modifiers: ", Style::default().fg(Color::Cyan)),
+            Span::raw(if modifiers.is_empty() {
+                format!("none ({} to toggle)", toggle_hint)
+            } else {
+                format!("{} ({} to toggle)", modifiers.join(", "), toggle_hint)
+            }),
+        ]));
+        lines.push(Line::from(vec![
+            Span::styled("   Bounty: ", Style::default().fg(Color::Cyan)),
+            Span::raw(if wave.bounty {
+                format!("yes, x{} gold (b to toggle)", BOUNTY_GOLD_MULTIPLIER)
+            } else {
+                "no (b to toggle)".to_string()
+            }),
+        ]));
+
         lines.push(Line::from(""));
         lines.push(Line::from(Span::styled(
             "Spawns (↑/↓:navigate spawns | ←/→:navigate fields):",
@@ -914,7 +1381,7 @@ fn render_wave_details(f: &mut Frame, app: &App, area: Rect) {
 
         for (idx, spawn) in wave.spawns.iter().enumerate() {
             if let Some(unit) = app.units.iter().find(|u| u.id == spawn.unit_id) {
-                let health = unit.base_health * spawn.health_multiplier;
+                let health = app.displayed_health(unit.base_health, spawn.health_multiplier);
                 let is_spawn_selected = matches!(app.selected_field, WaveDetailField::Spawn(i) if i == idx)
                     && is_selected;
 
@@ -992,6 +1459,13 @@ fn render_wave_details(f: &mut Frame, app: &App, area: Rect) {
                 }
                 lines.push(Line::from(health_spans));
 
+                if spawn.linked {
+                    lines.push(Line::from(vec![
+                        Span::raw("   Linked: "),
+                        Span::styled("shares damage", Style::default().fg(Color::Magenta)),
+                    ]));
+                }
+
                 lines.push(Line::from(vec![
                     Span::raw("   Speed: "),
                     Span::styled(
@@ -1010,7 +1484,7 @@ fn render_wave_details(f: &mut Frame, app: &App, area: Rect) {
         let title = if app.editing {
             "Wave Details (Editing - Enter to save, Esc to cancel)"
         } else {
-            "Wave Details (Enter:edit | Space:cycle unit | Insert:add | Delete:remove)"
+            "Wave Details (Enter:edit | Space:cycle unit | k:link | Insert:add | Delete:remove | s:simulate)"
         };
 
         let paragraph = Paragraph::new(lines)
@@ -1125,7 +1599,17 @@ fn render_units_list(f: &mut Frame, app: &mut App, area: Rect) {
             make_field_line(UnitField::BaseSpeed, "Speed: ".to_string(), format!("{:.0}", unit.base_speed), Color::Cyan),
             make_field_line(UnitField::DamageToBase, "Damage: ".to_string(), unit.damage_to_base.to_string(), Color::Magenta),
             make_field_line(UnitField::GoldReward, "Gold: ".to_string(), unit.gold_reward.to_string(), Color::Yellow),
+            make_field_line(UnitField::GoldSteal, "Steals: ".to_string(), unit.gold_steal.to_string(), Color::LightYellow),
             make_field_line(UnitField::DefenseType, "Defense: ".to_string(), unit.defense_type.clone(), Color::LightBlue),
+            make_field_line(UnitField::ImmuneTo, "Immune: ".to_string(), unit.immune_to.clone().unwrap_or_else(|| "none (Enter to cycle)".to_string()), Color::LightRed),
+            make_field_line(
+                UnitField::Tint,
+                "Tint: ".to_string(),
+                unit.tint.map(format_tint).unwrap_or_else(|| "none (r,g,b[,a])".to_string()),
+                unit.tint.map_or(Color::White, |[r, g, b, _]| {
+                    Color::Rgb((r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8)
+                }),
+            ),
             Line::from(""),
             make_field_line(UnitField::FrameCount, "Frames: ".to_string(), unit.frame_count.to_string(), Color::Blue),
             make_field_line(UnitField::FrameSize, "Size: ".to_string(), format!("{}x{}", unit.frame_size[0], unit.frame_size[1]), Color::Blue),
@@ -1161,7 +1645,14 @@ fn render_units_list(f: &mut Frame, app: &mut App, area: Rect) {
 }
 
 fn render_status_bar(f: &mut Frame, app: &App, area: Rect) {
-    let status = Paragraph::new(app.status_message.clone())
+    let text = match &app.jump_buffer {
+        Some(buffer) if matches!(app.selected_panel, SelectedPanel::Units) => {
+            format!(":{}  (unit number, Enter to jump, Esc to cancel)", buffer)
+        }
+        Some(buffer) => format!(":{}  (wave number, Enter to jump, Esc to cancel)", buffer),
+        None => app.status_message.clone(),
+    };
+    let status = Paragraph::new(text)
         .block(Block::default().borders(Borders::ALL))
         .style(Style::default().fg(Color::Green));
     f.render_widget(status, area);
@@ -1206,12 +1697,26 @@ where
 
         if poll(Duration::from_millis(16))? {
             if let Event::Key(key) = event::read()? {
-                if app.editing {
+                if let Some(buffer) = app.jump_buffer.as_mut() {
+                    match key.code {
+                        KeyCode::Enter => app.confirm_jump(),
+                        KeyCode::Esc => app.jump_buffer = None,
+                        KeyCode::Backspace => {
+                            buffer.pop();
+                        }
+                        KeyCode::Char(c) if c.is_ascii_digit() => buffer.push(c),
+                        _ => {}
+                    }
+                } else if app.editing {
                     match key.code {
                         KeyCode::Enter => {
-                            match app.selected_panel {
-                                SelectedPanel::Units => app.confirm_unit_edit(),
-                                _ => app.confirm_edit(),
+                            if app.editing_difficulty {
+                                app.confirm_difficulty_edit();
+                            } else {
+                                match app.selected_panel {
+                                    SelectedPanel::Units => app.confirm_unit_edit(),
+                                    _ => app.confirm_edit(),
+                                }
                             }
                         }
                         KeyCode::Esc => app.cancel_edit(),
@@ -1225,11 +1730,17 @@ where
                     }
                 } else {
                     // Reset status message to help text on navigation
-                    app.status_message = "q:quit | w:save | a:new | x:delete | Tab:switch | ↑/↓:navigate | Enter:edit".to_string();
+                    app.status_message = "q:quit | w:save | r:report | v:validate | a:new | x:delete | Tab:switch | ↑/↓:navigate | :N:jump | Enter:edit".to_string();
 
                     match key.code {
                         KeyCode::Char('q') => return Ok(()),
+                        KeyCode::Char(':') => app.jump_buffer = Some(String::new()),
                         KeyCode::Char('w') => app.save()?,
+                        KeyCode::Char('r') => {
+                            if let Err(e) = app.write_report() {
+                                app.status_message = format!("Failed to write report: {}", e);
+                            }
+                        }
                         KeyCode::Char('a') => {
                             match app.selected_panel {
                                 SelectedPanel::Waves => app.add_new_wave(),
@@ -1244,6 +1755,26 @@ where
                                 _ => {}
                             }
                         }
+                        KeyCode::Char('b') => {
+                            if matches!(app.selected_panel, SelectedPanel::Waves | SelectedPanel::WaveDetails) {
+                                app.toggle_bounty_for_current_wave();
+                            }
+                        }
+                        KeyCode::Char(digit @ '1'..='9') => {
+                            let modifier = digit.to_digit(10).and_then(|d| WaveModifier::ALL.get(d as usize - 1));
+                            if let Some(modifier) = modifier
+                                && matches!(app.selected_panel, SelectedPanel::Waves | SelectedPanel::WaveDetails)
+                            {
+                                app.toggle_modifier_for_current_wave(*modifier);
+                            }
+                        }
+                        KeyCode::Char('d') => app.start_editing_difficulty(),
+                        KeyCode::Char('v') => app.validate_content(),
+                        KeyCode::Char('s') => {
+                            if matches!(app.selected_panel, SelectedPanel::Waves | SelectedPanel::WaveDetails) {
+                                app.simulate_current_wave();
+                            }
+                        }
                         KeyCode::Insert => {
                             if matches!(app.selected_panel, SelectedPanel::WaveDetails) {
                                 app.add_spawn_to_current_wave();
@@ -1259,6 +1790,11 @@ where
                                 app.cycle_unit_for_current_spawn();
                             }
                         }
+                        KeyCode::Char('k') => {
+                            if matches!(app.selected_panel, SelectedPanel::WaveDetails) {
+                                app.toggle_link_for_current_spawn();
+                            }
+                        }
                         KeyCode::Enter => {
                             match app.selected_panel {
                                 SelectedPanel::WaveDetails => app.start_editing(),